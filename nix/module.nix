@@ -124,6 +124,7 @@ in
       type = lib.types.enum [
         "mixed"
         "individual-tar-gz"
+        "individual-gz"
       ];
       default = "individual-tar-gz";
       description = "Navigation output format.";
@@ -133,6 +134,7 @@ in
       type = lib.types.enum [
         "rinex"
         "hatanaka"
+        "individual-gz"
       ];
       default = "rinex";
       description = "Observation output format.";
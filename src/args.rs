@@ -8,16 +8,96 @@ pub enum NmeaLogFormat {
     Both,
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum Durability {
+    None,
+    Flush,
+    Fsync,
+}
+
+// Serial line framing beyond the baud rate; the defaults are 8N1 without flow control.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum SerialParity {
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum SerialStopBits {
+    #[value(name = "1")]
+    One,
+    #[value(name = "2")]
+    Two,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum SerialFlowControl {
+    None,
+    Software,
+    Hardware,
+}
+
+// What the input reader does when the ring buffer to the file writer is full.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum OverflowPolicy {
+    DropOld,
+    DropNew,
+    Block,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
 pub enum NavOutputFormat {
     Mixed,
     IndividualTarGz,
+    IndividualGz,
+}
+
+// External tool that turns merged UBX into RINEX (see `Converter` in commands/convert.rs).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ConverterBackend {
+    Convbin,
+}
+
+// Product naming: RINEX 3 long names, or RINEX 2.11 short names (`ssssdddh.yyt`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum RinexStyle {
+    Long,
+    Short,
+}
+
+// Placement of hourly UBX files: all in data_dir, or under data_dir/<YYYYMMDD>/.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum DataLayout {
+    Flat,
+    Daily,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
 pub enum ObsOutputFormat {
     Rinex,
     Hatanaka,
+    // One observation file per constellation (`_GO`, `_RO`, ...), each compressed.
+    IndividualGz,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum ProductCompression {
+    Gzip,
+    Zstd,
+    None,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, ValueEnum)]
+pub enum UploadMethod {
+    Rsync,
+    Http,
 }
 
 // CLI root definition. This is the single entrypoint for all supported modes.
@@ -27,6 +107,21 @@ pub enum ObsOutputFormat {
 pub struct Cli {
     #[command(subcommand)]
     pub command: AppCommand,
+    /// Only print warnings and errors
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "GNSS2TEC_QUIET",
+        conflicts_with = "verbose"
+    )]
+    pub quiet: bool,
+    /// More detail: -v for verbose messages, -vv also prints every converter command line
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    pub verbose: u8,
+    /// Load KEY=VALUE lines into the environment first (variables already set win)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub env_file: Option<PathBuf>,
 }
 
 // Subcommands map directly to one module each under src/commands/.
@@ -38,40 +133,207 @@ pub enum AppCommand {
     Convert(ConvertArgs),
     /// Run logger continuously and convert closed UTC hours in a background worker
     Run(RunArgs),
+    /// Check serial access, converters, directories, config, and locks, then report
+    Doctor(DoctorArgs),
+    /// Upload pending products from the archive upload queue and verify their checksums
+    Upload(UploadArgs),
+    /// Report logging freshness, unconverted hours, newest product, and free space
+    Status(StatusArgs),
+    /// Print the effective configuration of a subcommand (file, env, and flags merged) as TOML
+    PrintConfig(PrintConfigArgs),
+    /// List the `!UBX` commands accepted in ubx.dat with their argument counts
+    ListCommands,
+    /// Parse a ubx.dat file and report each `!UBX` line without opening the receiver
+    CheckConfig(CheckConfigArgs),
+    /// Regenerate RINEX from archived `.ubx.gz` hours (from --archive-ubx) in a --from/--to range
+    Reprocess(ReprocessArgs),
+    /// Check archived products against day manifests and checksum sidecars, and test every compressed file
+    VerifyArchive(VerifyArchiveArgs),
+}
+
+// `print-config <subcommand> [options]` parses the options exactly like the real subcommand would.
+#[derive(Args, Debug)]
+pub struct PrintConfigArgs {
+    #[command(subcommand)]
+    pub target: ConfigTarget,
+}
+
+// The large argument structs are boxed so the enum stays the size of its small variants.
+#[derive(Subcommand, Debug)]
+pub enum ConfigTarget {
+    Log(Box<LogArgs>),
+    Convert(Box<ConvertArgs>),
+    Run(Box<RunArgs>),
+    Doctor(DoctorArgs),
+    Upload(UploadArgs),
+    Status(StatusArgs),
 }
 
 // Logging-only configuration. This mirrors the old ubx_log.sh behavior.
 #[derive(Args, Debug, Clone)]
 pub struct LogArgs {
+    /// TOML file supplying option values (CLI flags and env vars take precedence)
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Status output on stderr: text lines or one JSON object per event
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+    /// Poll UBX-MON-VER, print the receiver's software/hardware versions, and exit
+    #[arg(long, default_value_t = false)]
+    pub probe_receiver: bool,
+    /// Receiver device path, or `auto` to pick the one USB serial port matching --usb-vid/--usb-pid
     #[arg(long, default_value = "/dev/ttyACM0")]
     pub serial_port: String,
+    /// USB vendor ID matched by --serial-port auto (hex; default u-blox)
+    #[arg(long, default_value = "0x1546", value_parser = parse_usb_id)]
+    pub usb_vid: u16,
+    /// USB product ID matched by --serial-port auto (hex; any product when unset)
+    #[arg(long, value_parser = parse_usb_id)]
+    pub usb_pid: Option<u16>,
+    /// Alternative GNSS input, e.g. tcp://host:port (overrides --serial-port)
+    #[arg(long)]
+    pub input: Option<String>,
+    /// Command run before the receiver input is opened (e.g. usbreset); a failure aborts startup
+    #[arg(long)]
+    pub pre_open_command: Option<String>,
+    /// Command run on shutdown after the receiver input is closed; a failure is only a warning
+    #[arg(long)]
+    pub post_close_command: Option<String>,
+    /// Also send every logged byte live to tcp://host:port (reconnects on its own)
+    #[arg(long)]
+    pub forward: Option<String>,
+    /// Most bytes held for a slow or absent --forward target before the oldest are dropped
+    #[arg(long, default_value_t = 1024 * 1024)]
+    pub forward_buffer_bytes: usize,
+    /// Replay a captured UBX/NMEA stream from this file instead of a receiver; stops at EOF
+    #[arg(long, conflicts_with = "input")]
+    pub input_file: Option<PathBuf>,
+    /// Pace --input-file by its `<file>.times` capture timestamps
+    #[arg(long, default_value_t = false, requires = "input_file")]
+    pub replay_realtime: bool,
+    #[arg(long, default_value_t = 2_000)]
+    pub reconnect_delay_ms: u64,
+    /// Upper bound for the doubling reconnect delay
+    #[arg(long, default_value_t = 60)]
+    pub reconnect_max_backoff_secs: u64,
     #[arg(long, default_value_t = 115_200)]
     pub baud_rate: u32,
+    /// Serial data bits per character (7 or 8; UBX needs 8)
+    #[arg(long, default_value_t = 8, value_parser = clap::value_parser!(u8).range(7..=8))]
+    pub data_bits: u8,
+    #[arg(long, value_enum, default_value_t = SerialParity::None)]
+    pub parity: SerialParity,
+    #[arg(long, value_enum, default_value_t = SerialStopBits::One)]
+    pub stop_bits: SerialStopBits,
+    #[arg(long, value_enum, default_value_t = SerialFlowControl::None)]
+    pub flow_control: SerialFlowControl,
     #[arg(long, default_value_t = 250)]
     pub read_timeout_ms: u64,
     #[arg(long, default_value_t = 8_192)]
     pub read_buffer_bytes: usize,
+    /// Warn and grow the read buffer when reads are this many ms apart (0 disables)
+    #[arg(long, default_value_t = 1_000)]
+    pub read_gap_warn_ms: u64,
+    /// Unix socket that answers every connection with one JSON health snapshot
+    #[arg(long)]
+    pub health_socket: Option<PathBuf>,
+    /// Bytes buffered between the input reader and the file writer
+    #[arg(long, default_value_t = 16_777_216)]
+    pub ring_buffer_bytes: usize,
+    /// When the ring buffer is full: drop-old, drop-new, or block (stop reading until it drains)
+    #[arg(long, value_enum, default_value_t = OverflowPolicy::Block)]
+    pub overflow: OverflowPolicy,
     #[arg(long, default_value_t = 5)]
     pub flush_interval_secs: u64,
     #[arg(long, default_value_t = 5)]
     pub stats_interval_secs: u64,
+    /// Also append each stats interval as a CSV row (timestamp,total_bytes,bps,frames,bad_frames,port)
+    #[arg(long)]
+    pub stats_file: Option<PathBuf>,
     #[arg(long, default_value_t = 30)]
     pub nmea_log_interval_secs: u64,
+    /// Log the host clock's offset from receiver UTC (UBX-NAV-TIMEUTC) every N seconds (0 disables)
+    #[arg(long, default_value_t = 0)]
+    pub log_time_drift_secs: u64,
+    /// Hold hour rotation while the host clock is more than N seconds off GNSS time (0 disables)
+    #[arg(long, default_value_t = 0)]
+    pub max_clock_skew_secs: u64,
+    /// Report clock skew beyond --max-clock-skew-secs but rotate anyway (deliberately offset hosts)
+    #[arg(long, default_value_t = false)]
+    pub allow_clock_skew: bool,
+    #[arg(long, default_value_t = 0)]
+    pub stall_timeout_secs: u64,
+    #[arg(long, default_value_t = false)]
+    pub stall_resend_config: bool,
+    /// Warn when nothing decodes as UBX or NMEA this many seconds after the first byte (0 disables)
+    #[arg(long, default_value_t = 10)]
+    pub link_check_secs: u64,
     #[arg(long, value_enum, default_value_t = NmeaLogFormat::Plain)]
     pub nmea_log_format: NmeaLogFormat,
+    /// Track watched NMEA sentences per talker (GPGSV, GLGSV, ...) instead of per type
+    #[arg(long, default_value_t = false)]
+    pub nmea_per_talker: bool,
+    /// Alert when an NMEA fix is this many meters from the running mean position (0 disables)
+    #[arg(long, default_value_t = 0.0)]
+    pub position_alert_meters: f64,
+    /// Seconds a deviation must last before it becomes the new reference position
+    #[arg(long, default_value_t = 600)]
+    pub position_alert_reset_secs: u64,
     #[arg(long, default_value_t = 50)]
     pub command_gap_ms: u64,
+    #[arg(long, default_value_t = 2_000)]
+    pub reset_settle_ms: u64,
+    /// After sending the config, fail unless a valid UBX frame arrives within this many ms (0 skips)
+    #[arg(long, default_value_t = 0)]
+    pub verify_ubx_ms: u64,
     #[arg(long, default_value = "/etc/gnss2tec-logger/ubx.dat")]
     pub config_file: PathBuf,
     #[arg(long, default_value = "/var/lib/gnss2tec-logger/data")]
     pub data_dir: PathBuf,
+    /// UBX file placement: flat (all in --data-dir) or daily (<data-dir>/<YYYYMMDD>/)
+    #[arg(long, value_enum, default_value_t = DataLayout::Flat)]
+    pub data_layout: DataLayout,
     #[arg(long, default_value = "/var/lib/gnss2tec-logger/ubx_log.lock")]
     pub lock_file: PathBuf,
+    #[arg(long, default_value_t = 0)]
+    pub stale_max_age_hours: u32,
+    #[arg(long, default_value_t = false)]
+    pub quarantine_stale: bool,
+    #[arg(long, default_value_t = false)]
+    pub compress_logs: bool,
+    /// Append to one `<YYYYMMDD_HH>.ubx` file per hour so restarts resume the same file
+    #[arg(long, default_value_t = false, conflicts_with = "compress_logs")]
+    pub single_file_per_hour: bool,
+    /// Rotate hourly at the first UBX epoch boundary (RXM-RAWX / NAV-EOE) after the hour changes
+    #[arg(long, default_value_t = false)]
+    pub rotate_on_epoch: bool,
+    /// With --rotate-on-epoch, rotate on the wall clock if no epoch arrives within N seconds
+    #[arg(long, default_value_t = 10)]
+    pub rotate_on_epoch_grace_secs: u64,
+    /// Only log these UBX messages, e.g. "RXM-RAWX,RXM-SFRBX" or hex "0x02-0x15" (default: everything)
+    #[arg(long)]
+    pub log_classes: Option<String>,
+    /// Periodic write durability: none (no periodic flush), flush, or fsync (sync_data to disk)
+    #[arg(long, value_enum, default_value_t = Durability::Flush)]
+    pub durability: Durability,
+    #[arg(long, default_value_t = 0)]
+    pub min_free_bytes: u64,
+    /// Skip the per-device lock, so another instance may open the same receiver
+    #[arg(long, default_value_t = false)]
+    pub no_device_lock: bool,
+    #[arg(long, default_value = "/var/lib/gnss2tec-logger")]
+    pub device_lock_dir: PathBuf,
 }
 
 // Conversion configuration. This mirrors convert.sh while keeping paths configurable.
 #[derive(Args, Debug, Clone)]
 pub struct ConvertArgs {
+    /// TOML file supplying option values (CLI flags and env vars take precedence)
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Status output on stderr: text lines or one JSON object per event
+    #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
     #[arg(long, default_value = "NJIT")]
     pub station: String,
     #[arg(long, default_value = "USA")]
@@ -80,54 +342,295 @@ pub struct ConvertArgs {
     pub receiver_type: String,
     #[arg(long, default_value = "TOPGNSS AN-105L")]
     pub antenna_type: String,
+    /// APPROX POSITION XYZ as ECEF meters "x,y,z" (default: convbin derives it)
+    #[arg(long, conflicts_with = "approx_llh")]
+    pub approx_xyz: Option<String>,
+    /// APPROX POSITION XYZ from WGS84 "lat,lon,height" (degrees, degrees, meters)
+    #[arg(long)]
+    pub approx_llh: Option<String>,
     #[arg(long, default_value = "H. Kim/NJIT")]
     pub observer: String,
     #[arg(long, default_value_t = 1)]
     pub shift_hours: u32,
     #[arg(long, default_value_t = 3)]
     pub max_days_back: u32,
+    /// Skip UBX files modified within this many seconds (still being written by `log`)
+    #[arg(long, default_value_t = 30)]
+    pub min_file_age_secs: u64,
+    /// Minutes an hour must be over before it is converted (the current hour never is)
+    #[arg(long, default_value_t = 0)]
+    pub min_complete_minutes: u32,
+    /// First UTC hour (YYYYMMDD_HH) of an explicit range; replaces the --shift-hours window
+    #[arg(long, requires = "to")]
+    pub from: Option<String>,
+    /// Last UTC hour (YYYYMMDD_HH) of the --from range, inclusive
+    #[arg(long, requires = "from")]
+    pub to: Option<String>,
+    /// Allow a --from/--to range longer than 31 days
+    #[arg(long, default_value_t = false)]
+    pub allow_large_range: bool,
+    #[arg(long, default_value_t = 1)]
+    pub convert_jobs: usize,
     #[arg(long, default_value = "/var/lib/gnss2tec-logger/data")]
     pub data_dir: PathBuf,
+    /// UBX file placement: flat (all in --data-dir) or daily (<data-dir>/<YYYYMMDD>/)
+    #[arg(long, value_enum, default_value_t = DataLayout::Flat)]
+    pub data_layout: DataLayout,
     #[arg(long, default_value = "/var/lib/gnss2tec-logger/archive")]
     pub archive_dir: PathBuf,
     #[arg(long, default_value = "/var/lib/gnss2tec-logger/convert.lock")]
     pub lock_file: PathBuf,
+    /// Seconds to wait for --lock-file held by another conversion (0 fails immediately)
+    #[arg(long, default_value_t = 0)]
+    pub lock_wait_secs: u64,
+    /// Seconds a single convbin/rnx2crx run may take before it is killed (0 waits forever)
+    #[arg(long, default_value_t = 0)]
+    pub convert_timeout_secs: u64,
+    /// UBX to RINEX backend
+    #[arg(long, value_enum, default_value_t = ConverterBackend::Convbin)]
+    pub converter: ConverterBackend,
     #[arg(long, default_value = "/usr/lib/gnss2tec-logger/bin/convbin")]
     pub convbin_path: PathBuf,
     #[arg(long, default_value = "/usr/lib/gnss2tec-logger/bin/rnx2crx")]
     pub rnx2crx_path: PathBuf,
+    #[arg(long)]
+    pub convert_sandbox: Option<String>,
+    #[arg(long, allow_hyphen_values = true)]
+    pub convbin_extra_args: Option<String>,
     #[arg(long, value_enum, default_value_t = NavOutputFormat::IndividualTarGz)]
     pub nav_output_format: NavOutputFormat,
     #[arg(long, value_enum, default_value_t = ObsOutputFormat::Rinex)]
     pub obs_output_format: ObsOutputFormat,
+    /// Shorthand for --obs-output-format hatanaka (rnx2crx, archived as .crx.gz)
+    #[arg(long, default_value_t = false)]
+    pub hatanaka: bool,
+    /// Compression for archived products: gzip (.gz), zstd (.zst), or none
+    #[arg(long, value_enum, default_value_t = ProductCompression::Gzip)]
+    pub compression: ProductCompression,
+    /// Shorthand for --compression none (archive plain .rnx/.crx and NAVSET .tar)
+    #[arg(long, default_value_t = false)]
+    pub no_compress_products: bool,
     #[arg(long, default_value_t = 1)]
     pub obs_sampling_secs: u32,
+    /// ubx.dat whose CFG-RATE is checked against --obs-sampling-secs (run passes its own)
+    #[arg(long)]
+    pub config_file: Option<PathBuf>,
+    /// RINEX version convbin writes (3.02, 3.03, 3.04, or 3.05)
+    #[arg(long, default_value = "3.04")]
+    pub rinex_version: String,
+    /// `short` writes RINEX 2.11 with short names such as NJIT001a.24o (ignores --rinex-version)
+    #[arg(long, value_enum, default_value_t = RinexStyle::Long)]
+    pub rinex_style: RinexStyle,
+    #[arg(
+        long,
+        default_value = "{station}00{country}_R_{year}{doy}{hour}_01H_{sampling}_{type}"
+    )]
+    pub obs_name_template: String,
+    #[arg(
+        long,
+        default_value = "{station}00{country}_R_{year}{doy}{hour}_01H_{type}"
+    )]
+    pub nav_name_template: String,
+    #[arg(long, default_value = "GRECJS")]
+    pub constellations: String,
+    /// Systems dropped from observation output only (e.g. "RCJS"); NAV keeps --constellations
+    #[arg(long)]
+    pub exclude_systems: Option<String>,
     #[arg(long, default_value_t = false)]
     pub output_ionex: bool,
     #[arg(long, default_value_t = false)]
     pub skip_nav: bool,
     #[arg(long, default_value_t = false)]
     pub keep_ubx: bool,
+    /// Gzip each converted hour's merged UBX into archive/<year>/<doy>/ and delete the originals
+    #[arg(long, default_value_t = false)]
+    pub archive_ubx: bool,
+    #[arg(long, default_value_t = false)]
+    pub tec_sidecar: bool,
+    /// Archive a `<prefix>.qc.json` per hour: epoch count, observation completeness, satellites
+    #[arg(long, default_value_t = false)]
+    pub qc: bool,
+    #[arg(long, default_value_t = false)]
+    pub write_checksums: bool,
+    /// Keep archive/<year>/<doy>/manifest.json listing every archived hour and its products
+    #[arg(long, default_value_t = false)]
+    pub day_manifest: bool,
+    #[arg(long, default_value_t = false)]
+    pub annotate_gaps: bool,
+    /// Do not pass the target hour to convbin as -ts/-te (epochs outside it may leak in)
+    #[arg(long, default_value_t = false)]
+    pub no_obs_window: bool,
+    #[arg(long, default_value_t = 10)]
+    pub gap_threshold_secs: u32,
+    /// Do not archive an hour whose observation file has fewer epochs than this (0 disables)
+    #[arg(long, default_value_t = 0)]
+    pub min_obs_epochs: u32,
+    /// Move products rejected by --min-obs-epochs here (<dir>/<year>/<doy>/) instead of dropping them
+    #[arg(long)]
+    pub reject_dir: Option<PathBuf>,
+    #[arg(long, default_value = "utc")]
+    pub archive_timezone: String,
+    /// Archive under archive/<station>/<year>/<doy>/ instead of archive/<year>/<doy>/
+    #[arg(long, default_value_t = false)]
+    pub archive_station_prefix: bool,
+    /// Archive observation products under archive/<year>/<doy>/<subdir>/ (unset keeps them in the day directory)
+    #[arg(long)]
+    pub obs_archive_subdir: Option<PathBuf>,
+    /// Archive navigation products under archive/<year>/<doy>/<subdir>/ (unset keeps them in the day directory)
+    #[arg(long)]
+    pub nav_archive_subdir: Option<PathBuf>,
+    #[arg(long, default_value_t = 0)]
+    pub archive_retention_days: u32,
+    /// Command run after each hour is archived, with GNSS2TEC_YEAR/DOY/HOUR/ARCHIVE_DIR/PRODUCTS set
+    #[arg(long)]
+    pub post_hook: Option<String>,
+    #[arg(long)]
+    pub rsync_target: Option<String>,
+    #[arg(long, default_value = "rsync")]
+    pub rsync_path: PathBuf,
+    #[arg(long, default_value_t = false)]
+    pub rsync_delete_after_upload: bool,
+    #[arg(long, default_value_t = false)]
+    pub upload_queue: bool,
+    #[arg(long, default_value_t = false)]
+    pub daily: bool,
+    #[arg(long, default_value_t = false)]
+    pub keep_hourly: bool,
+    #[arg(long, default_value = "gfzrnx")]
+    pub gfzrnx_path: PathBuf,
 }
 
 // Combined runtime mode config.
 // In this mode, conversion is event-driven and executed by a background worker after hour rollover.
 #[derive(Args, Debug, Clone)]
 pub struct RunArgs {
+    /// TOML file supplying option values (CLI flags and env vars take precedence)
+    #[arg(long, env = "GNSS2TEC_CONFIG_TOML")]
+    pub config: Option<PathBuf>,
+    /// Status output on stderr: text lines or one JSON object per event
+    #[arg(
+        long,
+        env = "GNSS2TEC_LOG_FORMAT",
+        value_enum,
+        default_value_t = LogFormat::Text
+    )]
+    pub log_format: LogFormat,
+    /// Receiver device path, or `auto` to pick the one USB serial port matching --usb-vid/--usb-pid
     #[arg(long, env = "GNSS2TEC_SERIAL_PORT", default_value = "/dev/ttyACM0")]
     pub serial_port: String,
+    /// USB vendor ID matched by --serial-port auto (hex; default u-blox)
+    #[arg(
+        long,
+        env = "GNSS2TEC_USB_VID",
+        default_value = "0x1546",
+        value_parser = parse_usb_id
+    )]
+    pub usb_vid: u16,
+    /// USB product ID matched by --serial-port auto (hex; any product when unset)
+    #[arg(long, env = "GNSS2TEC_USB_PID", value_parser = parse_usb_id)]
+    pub usb_pid: Option<u16>,
+    /// Alternative GNSS input, e.g. tcp://host:port (overrides --serial-port)
+    #[arg(long, env = "GNSS2TEC_INPUT")]
+    pub input: Option<String>,
+    /// Command run before the receiver input is opened (e.g. usbreset); a failure aborts startup
+    #[arg(long, env = "GNSS2TEC_PRE_OPEN_COMMAND")]
+    pub pre_open_command: Option<String>,
+    /// Command run on shutdown after the receiver input is closed; a failure is only a warning
+    #[arg(long, env = "GNSS2TEC_POST_CLOSE_COMMAND")]
+    pub post_close_command: Option<String>,
+    /// Also send every logged byte live to tcp://host:port (reconnects on its own)
+    #[arg(long, env = "GNSS2TEC_FORWARD")]
+    pub forward: Option<String>,
+    /// Most bytes held for a slow or absent --forward target before the oldest are dropped
+    #[arg(long, env = "GNSS2TEC_FORWARD_BUFFER_BYTES", default_value_t = 1024 * 1024)]
+    pub forward_buffer_bytes: usize,
+    /// Replay a captured UBX/NMEA stream from this file instead of a receiver; stops at EOF
+    #[arg(long, env = "GNSS2TEC_INPUT_FILE", conflicts_with = "input")]
+    pub input_file: Option<PathBuf>,
+    /// Pace --input-file by its `<file>.times` capture timestamps
+    #[arg(
+        long,
+        env = "GNSS2TEC_REPLAY_REALTIME",
+        default_value_t = false,
+        requires = "input_file"
+    )]
+    pub replay_realtime: bool,
+    #[arg(long, env = "GNSS2TEC_RECONNECT_DELAY_MS", default_value_t = 2_000)]
+    pub reconnect_delay_ms: u64,
+    /// Upper bound for the doubling reconnect delay
+    #[arg(
+        long,
+        env = "GNSS2TEC_RECONNECT_MAX_BACKOFF_SECS",
+        default_value_t = 60
+    )]
+    pub reconnect_max_backoff_secs: u64,
     #[arg(long, env = "GNSS2TEC_BAUD_RATE", default_value_t = 115_200)]
     pub baud_rate: u32,
+    /// Serial data bits per character (7 or 8; UBX needs 8)
+    #[arg(
+        long,
+        env = "GNSS2TEC_DATA_BITS",
+        default_value_t = 8,
+        value_parser = clap::value_parser!(u8).range(7..=8)
+    )]
+    pub data_bits: u8,
+    #[arg(long, env = "GNSS2TEC_PARITY", value_enum, default_value_t = SerialParity::None)]
+    pub parity: SerialParity,
+    #[arg(long, env = "GNSS2TEC_STOP_BITS", value_enum, default_value_t = SerialStopBits::One)]
+    pub stop_bits: SerialStopBits,
+    #[arg(
+        long,
+        env = "GNSS2TEC_FLOW_CONTROL",
+        value_enum,
+        default_value_t = SerialFlowControl::None
+    )]
+    pub flow_control: SerialFlowControl,
     #[arg(long, env = "GNSS2TEC_READ_TIMEOUT_MS", default_value_t = 250)]
     pub read_timeout_ms: u64,
     #[arg(long, env = "GNSS2TEC_READ_BUFFER_BYTES", default_value_t = 8_192)]
     pub read_buffer_bytes: usize,
+    /// Warn and grow the read buffer when reads are this many ms apart (0 disables)
+    #[arg(long, env = "GNSS2TEC_READ_GAP_WARN_MS", default_value_t = 1_000)]
+    pub read_gap_warn_ms: u64,
+    /// Unix socket that answers every connection with one JSON health snapshot
+    #[arg(long, env = "GNSS2TEC_HEALTH_SOCKET")]
+    pub health_socket: Option<PathBuf>,
+    /// Bytes buffered between the input reader and the file writer
+    #[arg(long, env = "GNSS2TEC_RING_BUFFER_BYTES", default_value_t = 16_777_216)]
+    pub ring_buffer_bytes: usize,
+    /// When the ring buffer is full: drop-old, drop-new, or block (stop reading until it drains)
+    #[arg(
+        long,
+        env = "GNSS2TEC_OVERFLOW",
+        value_enum,
+        default_value_t = OverflowPolicy::Block
+    )]
+    pub overflow: OverflowPolicy,
     #[arg(long, env = "GNSS2TEC_FLUSH_INTERVAL_SECS", default_value_t = 5)]
     pub flush_interval_secs: u64,
     #[arg(long, env = "GNSS2TEC_STATS_INTERVAL_SECS", default_value_t = 5)]
     pub stats_interval_secs: u64,
+    /// Also append each stats interval as a CSV row (timestamp,total_bytes,bps,frames,bad_frames,port)
+    #[arg(long, env = "GNSS2TEC_STATS_FILE")]
+    pub stats_file: Option<PathBuf>,
     #[arg(long, env = "GNSS2TEC_NMEA_LOG_INTERVAL_SECS", default_value_t = 30)]
     pub nmea_log_interval_secs: u64,
+    /// Log the host clock's offset from receiver UTC (UBX-NAV-TIMEUTC) every N seconds (0 disables)
+    #[arg(long, env = "GNSS2TEC_LOG_TIME_DRIFT_SECS", default_value_t = 0)]
+    pub log_time_drift_secs: u64,
+    /// Hold hour rotation while the host clock is more than N seconds off GNSS time (0 disables)
+    #[arg(long, env = "GNSS2TEC_MAX_CLOCK_SKEW_SECS", default_value_t = 0)]
+    pub max_clock_skew_secs: u64,
+    /// Report clock skew beyond --max-clock-skew-secs but rotate anyway (deliberately offset hosts)
+    #[arg(long, env = "GNSS2TEC_ALLOW_CLOCK_SKEW", default_value_t = false)]
+    pub allow_clock_skew: bool,
+    #[arg(long, env = "GNSS2TEC_STALL_TIMEOUT_SECS", default_value_t = 0)]
+    pub stall_timeout_secs: u64,
+    #[arg(long, env = "GNSS2TEC_STALL_RESEND_CONFIG", default_value_t = false)]
+    pub stall_resend_config: bool,
+    /// Warn when nothing decodes as UBX or NMEA this many seconds after the first byte (0 disables)
+    #[arg(long, env = "GNSS2TEC_LINK_CHECK_SECS", default_value_t = 10)]
+    pub link_check_secs: u64,
     #[arg(
         long,
         env = "GNSS2TEC_NMEA_LOG_FORMAT",
@@ -135,8 +638,29 @@ pub struct RunArgs {
         default_value_t = NmeaLogFormat::Plain
     )]
     pub nmea_log_format: NmeaLogFormat,
+    /// Track watched NMEA sentences per talker (GPGSV, GLGSV, ...) instead of per type
+    #[arg(long, env = "GNSS2TEC_NMEA_PER_TALKER", default_value_t = false)]
+    pub nmea_per_talker: bool,
+    /// Alert when an NMEA fix is this many meters from the running mean position (0 disables)
+    #[arg(long, env = "GNSS2TEC_POSITION_ALERT_METERS", default_value_t = 0.0)]
+    pub position_alert_meters: f64,
+    /// Seconds a deviation must last before it becomes the new reference position
+    #[arg(
+        long,
+        env = "GNSS2TEC_POSITION_ALERT_RESET_SECS",
+        default_value_t = 600
+    )]
+    pub position_alert_reset_secs: u64,
     #[arg(long, env = "GNSS2TEC_COMMAND_GAP_MS", default_value_t = 50)]
     pub command_gap_ms: u64,
+    #[arg(long, env = "GNSS2TEC_RESET_SETTLE_MS", default_value_t = 2_000)]
+    pub reset_settle_ms: u64,
+    /// Userspace buffer in front of the hourly UBX file, in bytes (0 writes every chunk directly)
+    #[arg(long, env = "GNSS2TEC_WRITE_BUFFER_BYTES", default_value_t = 0)]
+    pub write_buffer_bytes: usize,
+    /// After sending the config, fail unless a valid UBX frame arrives within this many ms (0 skips)
+    #[arg(long, env = "GNSS2TEC_VERIFY_UBX_MS", default_value_t = 0)]
+    pub verify_ubx_ms: u64,
     #[arg(
         long,
         env = "GNSS2TEC_CONFIG_FILE",
@@ -149,6 +673,55 @@ pub struct RunArgs {
         default_value = "/var/lib/gnss2tec-logger/data"
     )]
     pub data_dir: PathBuf,
+    /// UBX file placement: flat (all in --data-dir) or daily (<data-dir>/<YYYYMMDD>/)
+    #[arg(long, env = "GNSS2TEC_DATA_LAYOUT", value_enum, default_value_t = DataLayout::Flat)]
+    pub data_layout: DataLayout,
+    #[arg(long, env = "GNSS2TEC_STALE_MAX_AGE_HOURS", default_value_t = 0)]
+    pub stale_max_age_hours: u32,
+    #[arg(long, env = "GNSS2TEC_QUARANTINE_STALE", default_value_t = false)]
+    pub quarantine_stale: bool,
+    #[arg(long, env = "GNSS2TEC_COMPRESS_LOGS", default_value_t = false)]
+    pub compress_logs: bool,
+    /// Append to one `<YYYYMMDD_HH>.ubx` file per hour so restarts resume the same file
+    #[arg(
+        long,
+        env = "GNSS2TEC_SINGLE_FILE_PER_HOUR",
+        default_value_t = false,
+        conflicts_with = "compress_logs"
+    )]
+    pub single_file_per_hour: bool,
+    /// Rotate hourly at the first UBX epoch boundary (RXM-RAWX / NAV-EOE) after the hour changes
+    #[arg(long, env = "GNSS2TEC_ROTATE_ON_EPOCH", default_value_t = false)]
+    pub rotate_on_epoch: bool,
+    /// With --rotate-on-epoch, rotate on the wall clock if no epoch arrives within N seconds
+    #[arg(
+        long,
+        env = "GNSS2TEC_ROTATE_ON_EPOCH_GRACE_SECS",
+        default_value_t = 10
+    )]
+    pub rotate_on_epoch_grace_secs: u64,
+    /// Only log these UBX messages, e.g. "RXM-RAWX,RXM-SFRBX" or hex "0x02-0x15" (default: everything)
+    #[arg(long, env = "GNSS2TEC_LOG_CLASSES")]
+    pub log_classes: Option<String>,
+    /// Periodic write durability: none (no periodic flush), flush, or fsync (sync_data to disk)
+    #[arg(
+        long,
+        env = "GNSS2TEC_DURABILITY",
+        value_enum,
+        default_value_t = Durability::Flush
+    )]
+    pub durability: Durability,
+    #[arg(long, env = "GNSS2TEC_MIN_FREE_BYTES", default_value_t = 0)]
+    pub min_free_bytes: u64,
+    /// Skip the per-device lock, so another instance may open the same receiver
+    #[arg(long, env = "GNSS2TEC_NO_DEVICE_LOCK", default_value_t = false)]
+    pub no_device_lock: bool,
+    #[arg(
+        long,
+        env = "GNSS2TEC_DEVICE_LOCK_DIR",
+        default_value = "/var/lib/gnss2tec-logger"
+    )]
+    pub device_lock_dir: PathBuf,
     #[arg(long, env = "GNSS2TEC_STATION", default_value = "NJIT")]
     pub station: String,
     #[arg(long, env = "GNSS2TEC_COUNTRY", default_value = "USA")]
@@ -161,6 +734,12 @@ pub struct RunArgs {
     pub receiver_type: String,
     #[arg(long, env = "GNSS2TEC_ANTENNA_TYPE", default_value = "TOPGNSS AN-105L")]
     pub antenna_type: String,
+    /// APPROX POSITION XYZ as ECEF meters "x,y,z" (default: convbin derives it)
+    #[arg(long, env = "GNSS2TEC_APPROX_XYZ", conflicts_with = "approx_llh")]
+    pub approx_xyz: Option<String>,
+    /// APPROX POSITION XYZ from WGS84 "lat,lon,height" (degrees, degrees, meters)
+    #[arg(long, env = "GNSS2TEC_APPROX_LLH")]
+    pub approx_llh: Option<String>,
     #[arg(long, env = "GNSS2TEC_OBSERVER", default_value = "H. Kim/NJIT")]
     pub observer: String,
     #[arg(long, env = "GNSS2TEC_SHIFT_HOURS", default_value_t = 1)]
@@ -173,6 +752,20 @@ pub struct RunArgs {
         default_value = "/var/lib/gnss2tec-logger/archive"
     )]
     pub archive_dir: PathBuf,
+    /// Seconds to wait for the conversion lock held by another conversion (0 fails immediately)
+    #[arg(long, env = "GNSS2TEC_LOCK_WAIT_SECS", default_value_t = 0)]
+    pub lock_wait_secs: u64,
+    /// Seconds a single convbin/rnx2crx run may take before it is killed (0 waits forever)
+    #[arg(long, env = "GNSS2TEC_CONVERT_TIMEOUT_SECS", default_value_t = 0)]
+    pub convert_timeout_secs: u64,
+    /// UBX to RINEX backend
+    #[arg(
+        long,
+        env = "GNSS2TEC_CONVERTER",
+        value_enum,
+        default_value_t = ConverterBackend::Convbin
+    )]
+    pub converter: ConverterBackend,
     #[arg(
         long,
         env = "GNSS2TEC_CONVBIN_PATH",
@@ -185,6 +778,10 @@ pub struct RunArgs {
         default_value = "/usr/lib/gnss2tec-logger/bin/rnx2crx"
     )]
     pub rnx2crx_path: PathBuf,
+    #[arg(long, env = "GNSS2TEC_CONVERT_SANDBOX")]
+    pub convert_sandbox: Option<String>,
+    #[arg(long, env = "GNSS2TEC_CONVBIN_EXTRA_ARGS", allow_hyphen_values = true)]
+    pub convbin_extra_args: Option<String>,
     #[arg(
         long,
         env = "GNSS2TEC_NAV_OUTPUT_FORMAT",
@@ -199,40 +796,524 @@ pub struct RunArgs {
         default_value_t = ObsOutputFormat::Rinex
     )]
     pub obs_output_format: ObsOutputFormat,
+    /// Shorthand for --obs-output-format hatanaka (rnx2crx, archived as .crx.gz)
+    #[arg(long, env = "GNSS2TEC_HATANAKA", default_value_t = false)]
+    pub hatanaka: bool,
+    /// Compression for archived products: gzip (.gz), zstd (.zst), or none
+    #[arg(
+        long,
+        env = "GNSS2TEC_COMPRESSION",
+        value_enum,
+        default_value_t = ProductCompression::Gzip
+    )]
+    pub compression: ProductCompression,
+    /// Shorthand for --compression none (archive plain .rnx/.crx and NAVSET .tar)
+    #[arg(long, env = "GNSS2TEC_NO_COMPRESS_PRODUCTS", default_value_t = false)]
+    pub no_compress_products: bool,
     #[arg(long, env = "GNSS2TEC_OBS_SAMPLING_SECS", default_value_t = 1)]
     pub obs_sampling_secs: u32,
+    /// RINEX version convbin writes (3.02, 3.03, 3.04, or 3.05)
+    #[arg(long, env = "GNSS2TEC_RINEX_VERSION", default_value = "3.04")]
+    pub rinex_version: String,
+    /// `short` writes RINEX 2.11 with short names such as NJIT001a.24o (ignores --rinex-version)
+    #[arg(
+        long,
+        env = "GNSS2TEC_RINEX_STYLE",
+        value_enum,
+        default_value_t = RinexStyle::Long
+    )]
+    pub rinex_style: RinexStyle,
+    #[arg(
+        long,
+        env = "GNSS2TEC_OBS_NAME_TEMPLATE",
+        default_value = "{station}00{country}_R_{year}{doy}{hour}_01H_{sampling}_{type}"
+    )]
+    pub obs_name_template: String,
+    #[arg(
+        long,
+        env = "GNSS2TEC_NAV_NAME_TEMPLATE",
+        default_value = "{station}00{country}_R_{year}{doy}{hour}_01H_{type}"
+    )]
+    pub nav_name_template: String,
+    #[arg(long, env = "GNSS2TEC_CONSTELLATIONS", default_value = "GRECJS")]
+    pub constellations: String,
+    /// Systems dropped from observation output only (e.g. "RCJS"); NAV keeps --constellations
+    #[arg(long, env = "GNSS2TEC_EXCLUDE_SYSTEMS")]
+    pub exclude_systems: Option<String>,
     #[arg(long, env = "GNSS2TEC_OUTPUT_IONEX", default_value_t = false)]
     pub output_ionex: bool,
     #[arg(long, env = "GNSS2TEC_SKIP_NAV", default_value_t = false)]
     pub skip_nav: bool,
     #[arg(long, env = "GNSS2TEC_KEEP_UBX", default_value_t = false)]
     pub keep_ubx: bool,
+    /// Gzip each converted hour's merged UBX into archive/<year>/<doy>/ and delete the originals
+    #[arg(long, env = "GNSS2TEC_ARCHIVE_UBX", default_value_t = false)]
+    pub archive_ubx: bool,
+    #[arg(long, env = "GNSS2TEC_TEC_SIDECAR", default_value_t = false)]
+    pub tec_sidecar: bool,
+    /// Archive a `<prefix>.qc.json` per hour: epoch count, observation completeness, satellites
+    #[arg(long, env = "GNSS2TEC_QC", default_value_t = false)]
+    pub qc: bool,
+    #[arg(long, env = "GNSS2TEC_WRITE_CHECKSUMS", default_value_t = false)]
+    pub write_checksums: bool,
+    /// Keep archive/<year>/<doy>/manifest.json listing every archived hour and its products
+    #[arg(long, env = "GNSS2TEC_DAY_MANIFEST", default_value_t = false)]
+    pub day_manifest: bool,
+    #[arg(long, env = "GNSS2TEC_ANNOTATE_GAPS", default_value_t = false)]
+    pub annotate_gaps: bool,
+    /// Do not pass the target hour to convbin as -ts/-te (epochs outside it may leak in)
+    #[arg(long, env = "GNSS2TEC_NO_OBS_WINDOW", default_value_t = false)]
+    pub no_obs_window: bool,
+    #[arg(long, env = "GNSS2TEC_GAP_THRESHOLD_SECS", default_value_t = 10)]
+    pub gap_threshold_secs: u32,
+    /// Do not archive an hour whose observation file has fewer epochs than this (0 disables)
+    #[arg(long, env = "GNSS2TEC_MIN_OBS_EPOCHS", default_value_t = 0)]
+    pub min_obs_epochs: u32,
+    /// Move products rejected by --min-obs-epochs here (<dir>/<year>/<doy>/) instead of dropping them
+    #[arg(long, env = "GNSS2TEC_REJECT_DIR")]
+    pub reject_dir: Option<PathBuf>,
+    #[arg(long, env = "GNSS2TEC_ARCHIVE_TIMEZONE", default_value = "utc")]
+    pub archive_timezone: String,
+    /// Archive under archive/<station>/<year>/<doy>/ instead of archive/<year>/<doy>/
+    #[arg(long, env = "GNSS2TEC_ARCHIVE_STATION_PREFIX", default_value_t = false)]
+    pub archive_station_prefix: bool,
+    /// Archive observation products under archive/<year>/<doy>/<subdir>/ (unset keeps them in the day directory)
+    #[arg(long, env = "GNSS2TEC_OBS_ARCHIVE_SUBDIR")]
+    pub obs_archive_subdir: Option<PathBuf>,
+    /// Archive navigation products under archive/<year>/<doy>/<subdir>/ (unset keeps them in the day directory)
+    #[arg(long, env = "GNSS2TEC_NAV_ARCHIVE_SUBDIR")]
+    pub nav_archive_subdir: Option<PathBuf>,
+    #[arg(long, env = "GNSS2TEC_ARCHIVE_RETENTION_DAYS", default_value_t = 0)]
+    pub archive_retention_days: u32,
+    /// Command run after each hour is archived, with GNSS2TEC_YEAR/DOY/HOUR/ARCHIVE_DIR/PRODUCTS set
+    #[arg(long, env = "GNSS2TEC_POST_HOOK")]
+    pub post_hook: Option<String>,
+    #[arg(long, env = "GNSS2TEC_RSYNC_TARGET")]
+    pub rsync_target: Option<String>,
+    #[arg(long, env = "GNSS2TEC_RSYNC_PATH", default_value = "rsync")]
+    pub rsync_path: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_RSYNC_DELETE_AFTER_UPLOAD",
+        default_value_t = false
+    )]
+    pub rsync_delete_after_upload: bool,
+    #[arg(long, env = "GNSS2TEC_UPLOAD_QUEUE", default_value_t = false)]
+    pub upload_queue: bool,
+    #[arg(long, env = "GNSS2TEC_DAILY", default_value_t = false)]
+    pub daily: bool,
+    #[arg(long, env = "GNSS2TEC_KEEP_HOURLY", default_value_t = false)]
+    pub keep_hourly: bool,
+    #[arg(long, env = "GNSS2TEC_GFZRNX_PATH", default_value = "gfzrnx")]
+    pub gfzrnx_path: PathBuf,
     #[arg(long = "no-convert-on-start", action = ArgAction::SetFalse, default_value_t = true)]
     pub convert_on_start: bool,
+    /// Convert the catch-up window and exit without opening the GNSS input
+    #[arg(long, env = "GNSS2TEC_CATCHUP_ONLY", default_value_t = false)]
+    pub catchup_only: bool,
+    #[arg(long, env = "GNSS2TEC_CONVERT_MAX_RETRIES", default_value_t = 3)]
+    pub convert_max_retries: u32,
+    #[arg(long, env = "GNSS2TEC_CONVERT_RETRY_DELAY_SECS", default_value_t = 60)]
+    pub convert_retry_delay_secs: u64,
+}
+
+// Environment check configuration. Reads the same GNSS2TEC_* variables as `run`, so the
+// packaged runtime.env can be sourced to check exactly what the service will use.
+#[derive(Args, Debug, Clone)]
+pub struct DoctorArgs {
+    /// TOML file supplying option values (CLI flags and env vars take precedence)
+    #[arg(long, env = "GNSS2TEC_CONFIG_TOML")]
+    pub config: Option<PathBuf>,
+    /// Receiver device path, or `auto` to pick the one USB serial port matching --usb-vid/--usb-pid
+    #[arg(long, env = "GNSS2TEC_SERIAL_PORT", default_value = "/dev/ttyACM0")]
+    pub serial_port: String,
+    /// USB vendor ID matched by --serial-port auto (hex; default u-blox)
+    #[arg(
+        long,
+        env = "GNSS2TEC_USB_VID",
+        default_value = "0x1546",
+        value_parser = parse_usb_id
+    )]
+    pub usb_vid: u16,
+    /// USB product ID matched by --serial-port auto (hex; any product when unset)
+    #[arg(long, env = "GNSS2TEC_USB_PID", value_parser = parse_usb_id)]
+    pub usb_pid: Option<u16>,
+    #[arg(long, env = "GNSS2TEC_BAUD_RATE", default_value_t = 115_200)]
+    pub baud_rate: u32,
+    /// Serial data bits per character (7 or 8; UBX needs 8)
+    #[arg(
+        long,
+        env = "GNSS2TEC_DATA_BITS",
+        default_value_t = 8,
+        value_parser = clap::value_parser!(u8).range(7..=8)
+    )]
+    pub data_bits: u8,
+    #[arg(long, env = "GNSS2TEC_PARITY", value_enum, default_value_t = SerialParity::None)]
+    pub parity: SerialParity,
+    #[arg(long, env = "GNSS2TEC_STOP_BITS", value_enum, default_value_t = SerialStopBits::One)]
+    pub stop_bits: SerialStopBits,
+    #[arg(
+        long,
+        env = "GNSS2TEC_FLOW_CONTROL",
+        value_enum,
+        default_value_t = SerialFlowControl::None
+    )]
+    pub flow_control: SerialFlowControl,
+    #[arg(long, env = "GNSS2TEC_READ_TIMEOUT_MS", default_value_t = 250)]
+    pub read_timeout_ms: u64,
+    #[arg(
+        long,
+        env = "GNSS2TEC_CONFIG_FILE",
+        default_value = "/etc/gnss2tec-logger/ubx.dat"
+    )]
+    pub config_file: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_DATA_DIR",
+        default_value = "/var/lib/gnss2tec-logger/data"
+    )]
+    pub data_dir: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_ARCHIVE_DIR",
+        default_value = "/var/lib/gnss2tec-logger/archive"
+    )]
+    pub archive_dir: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_CONVBIN_PATH",
+        default_value = "/usr/lib/gnss2tec-logger/bin/convbin"
+    )]
+    pub convbin_path: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_RNX2CRX_PATH",
+        default_value = "/usr/lib/gnss2tec-logger/bin/rnx2crx"
+    )]
+    pub rnx2crx_path: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_OBS_OUTPUT_FORMAT",
+        value_enum,
+        default_value_t = ObsOutputFormat::Rinex
+    )]
+    pub obs_output_format: ObsOutputFormat,
+    /// Shorthand for --obs-output-format hatanaka (rnx2crx, archived as .crx.gz)
+    #[arg(long, env = "GNSS2TEC_HATANAKA", default_value_t = false)]
+    pub hatanaka: bool,
+    #[arg(long, default_value = "/var/lib/gnss2tec-logger/ubx_log.lock")]
+    pub log_lock_file: PathBuf,
+    #[arg(long, default_value = "/var/lib/gnss2tec-logger/convert.lock")]
+    pub convert_lock_file: PathBuf,
+    /// Skip the per-device lock, so another instance may open the same receiver
+    #[arg(long, default_value_t = false)]
+    pub no_device_lock: bool,
+    #[arg(
+        long,
+        env = "GNSS2TEC_DEVICE_LOCK_DIR",
+        default_value = "/var/lib/gnss2tec-logger"
+    )]
+    pub device_lock_dir: PathBuf,
+}
+
+// Upload configuration for draining `<archive_dir>/upload-queue.jsonl`.
+// Intended to run from a timer or by hand, independently of the logger process.
+#[derive(Args, Debug, Clone)]
+pub struct UploadArgs {
+    /// TOML file supplying option values (CLI flags and env vars take precedence)
+    #[arg(long, env = "GNSS2TEC_CONFIG_TOML")]
+    pub config: Option<PathBuf>,
+    /// Status output on stderr: text lines or one JSON object per event
+    #[arg(
+        long,
+        env = "GNSS2TEC_LOG_FORMAT",
+        value_enum,
+        default_value_t = LogFormat::Text
+    )]
+    pub log_format: LogFormat,
+    #[arg(
+        long,
+        env = "GNSS2TEC_ARCHIVE_DIR",
+        default_value = "/var/lib/gnss2tec-logger/archive"
+    )]
+    pub archive_dir: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_UPLOAD_METHOD",
+        value_enum,
+        default_value_t = UploadMethod::Rsync
+    )]
+    pub upload_method: UploadMethod,
+    /// rsync destination (user@host:path) or HTTP base URL objects are PUT under
+    #[arg(long, env = "GNSS2TEC_UPLOAD_TARGET")]
+    pub upload_target: String,
+    #[arg(long, env = "GNSS2TEC_RSYNC_PATH", default_value = "rsync")]
+    pub rsync_path: PathBuf,
+    #[arg(long, env = "GNSS2TEC_CURL_PATH", default_value = "curl")]
+    pub curl_path: PathBuf,
+}
+
+// Station health snapshot for scripts and health checks.
+#[derive(Args, Debug, Clone)]
+pub struct StatusArgs {
+    /// TOML file supplying option values (CLI flags and env vars take precedence)
+    #[arg(long, env = "GNSS2TEC_CONFIG_TOML")]
+    pub config: Option<PathBuf>,
+    #[arg(
+        long,
+        env = "GNSS2TEC_DATA_DIR",
+        default_value = "/var/lib/gnss2tec-logger/data"
+    )]
+    pub data_dir: PathBuf,
+    #[arg(
+        long,
+        env = "GNSS2TEC_ARCHIVE_DIR",
+        default_value = "/var/lib/gnss2tec-logger/archive"
+    )]
+    pub archive_dir: PathBuf,
+    /// Look for products under archive/<station>/ (matches `run --archive-station-prefix`)
+    #[arg(long, env = "GNSS2TEC_ARCHIVE_STATION_PREFIX", default_value_t = false)]
+    pub archive_station_prefix: bool,
+    #[arg(long, env = "GNSS2TEC_STATION", default_value = "NJIT")]
+    pub station: String,
+    /// Exit nonzero when the newest UBX file is older than this (0 disables the check)
+    #[arg(long, env = "GNSS2TEC_STATUS_STALE_SECS", default_value_t = 300)]
+    pub stale_secs: u64,
+}
+
+// Archive integrity check before shipping products to a data center.
+#[derive(Args, Debug, Clone)]
+pub struct VerifyArchiveArgs {
+    /// TOML file supplying option values (CLI flags and env vars take precedence)
+    #[arg(long, env = "GNSS2TEC_CONFIG_TOML")]
+    pub config: Option<PathBuf>,
+    #[arg(
+        long,
+        env = "GNSS2TEC_ARCHIVE_DIR",
+        default_value = "/var/lib/gnss2tec-logger/archive"
+    )]
+    pub archive_dir: PathBuf,
+    /// Look for products under archive/<station>/ (matches `run --archive-station-prefix`)
+    #[arg(long, env = "GNSS2TEC_ARCHIVE_STATION_PREFIX", default_value_t = false)]
+    pub archive_station_prefix: bool,
+    #[arg(long, env = "GNSS2TEC_STATION", default_value = "NJIT")]
+    pub station: String,
+}
+
+// Config file dry run. Reads GNSS2TEC_CONFIG_FILE like `run`, so the file the service would
+// load is checked by default.
+#[derive(Args, Debug, Clone)]
+pub struct CheckConfigArgs {
+    #[arg(
+        long,
+        env = "GNSS2TEC_CONFIG_FILE",
+        default_value = "/etc/gnss2tec-logger/ubx.dat"
+    )]
+    pub config_file: PathBuf,
+}
+
+// `reprocess`: the usual conversion options plus where the regenerated products go.
+#[derive(Args, Debug)]
+pub struct ReprocessArgs {
+    #[command(flatten)]
+    pub convert: ConvertArgs,
+    /// Archive regenerated products under this directory instead of --archive-dir
+    #[arg(long, conflicts_with = "replace")]
+    pub output_dir: Option<PathBuf>,
+    /// Overwrite the original products in --archive-dir (required without --output-dir)
+    #[arg(long, default_value_t = false)]
+    pub replace: bool,
+}
+
+impl AppCommand {
+    // Status output format of the selected subcommand (doctor, status, print-config,
+    // list-commands, check-config, and verify-archive always use text).
+    pub fn log_format(&self) -> LogFormat {
+        match self {
+            Self::Log(args) => args.log_format,
+            Self::Convert(args) => args.log_format,
+            Self::Run(args) => args.log_format,
+            Self::Upload(args) => args.log_format,
+            Self::Reprocess(args) => args.convert.log_format,
+            Self::Doctor(_)
+            | Self::Status(_)
+            | Self::PrintConfig(_)
+            | Self::ListCommands
+            | Self::CheckConfig(_)
+            | Self::VerifyArchive(_) => LogFormat::Text,
+        }
+    }
+}
+
+// Parse a USB vendor/product ID as hex, with or without `0x` (`1546`, `0x01a9`).
+fn parse_usb_id(value: &str) -> Result<u16, String> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    u16::from_str_radix(digits, 16)
+        .map_err(|_| format!("expected a hex USB ID such as 0x1546, got {value:?}"))
+}
+
+impl ConvertArgs {
+    // Product compression after applying the `--no-compress-products` shorthand.
+    pub fn effective_compression(&self) -> ProductCompression {
+        if self.no_compress_products {
+            ProductCompression::None
+        } else {
+            self.compression
+        }
+    }
+
+    // Observation output format after applying the `--hatanaka` shorthand.
+    pub fn effective_obs_output_format(&self) -> ObsOutputFormat {
+        if self.hatanaka {
+            ObsOutputFormat::Hatanaka
+        } else {
+            self.obs_output_format
+        }
+    }
 }
 
 impl RunArgs {
+    // Build LogArgs from the shared fields so run-mode reuses the logger's writer loop.
+    pub fn to_log_args(&self) -> LogArgs {
+        LogArgs {
+            config: self.config.clone(),
+            log_format: self.log_format,
+            probe_receiver: false,
+            serial_port: self.serial_port.clone(),
+            usb_vid: self.usb_vid,
+            usb_pid: self.usb_pid,
+            input: self.input.clone(),
+            pre_open_command: self.pre_open_command.clone(),
+            post_close_command: self.post_close_command.clone(),
+            forward: self.forward.clone(),
+            forward_buffer_bytes: self.forward_buffer_bytes,
+            input_file: self.input_file.clone(),
+            replay_realtime: self.replay_realtime,
+            reconnect_delay_ms: self.reconnect_delay_ms,
+            reconnect_max_backoff_secs: self.reconnect_max_backoff_secs,
+            baud_rate: self.baud_rate,
+            data_bits: self.data_bits,
+            parity: self.parity,
+            stop_bits: self.stop_bits,
+            flow_control: self.flow_control,
+            read_timeout_ms: self.read_timeout_ms,
+            read_buffer_bytes: self.read_buffer_bytes,
+            read_gap_warn_ms: self.read_gap_warn_ms,
+            health_socket: self.health_socket.clone(),
+            ring_buffer_bytes: self.ring_buffer_bytes,
+            overflow: self.overflow,
+            flush_interval_secs: self.flush_interval_secs,
+            stats_interval_secs: self.stats_interval_secs,
+            stats_file: self.stats_file.clone(),
+            nmea_log_interval_secs: self.nmea_log_interval_secs,
+            log_time_drift_secs: self.log_time_drift_secs,
+            max_clock_skew_secs: self.max_clock_skew_secs,
+            allow_clock_skew: self.allow_clock_skew,
+            stall_timeout_secs: self.stall_timeout_secs,
+            stall_resend_config: self.stall_resend_config,
+            link_check_secs: self.link_check_secs,
+            nmea_log_format: self.nmea_log_format,
+            nmea_per_talker: self.nmea_per_talker,
+            position_alert_meters: self.position_alert_meters,
+            position_alert_reset_secs: self.position_alert_reset_secs,
+            command_gap_ms: self.command_gap_ms,
+            reset_settle_ms: self.reset_settle_ms,
+            verify_ubx_ms: self.verify_ubx_ms,
+            config_file: self.config_file.clone(),
+            data_dir: self.data_dir.clone(),
+            data_layout: self.data_layout,
+            // Run mode takes no logger lock; the writer loop never reads this.
+            lock_file: PathBuf::from("/var/lib/gnss2tec-logger/ubx_log.lock"),
+            stale_max_age_hours: self.stale_max_age_hours,
+            quarantine_stale: self.quarantine_stale,
+            compress_logs: self.compress_logs,
+            single_file_per_hour: self.single_file_per_hour,
+            rotate_on_epoch: self.rotate_on_epoch,
+            rotate_on_epoch_grace_secs: self.rotate_on_epoch_grace_secs,
+            log_classes: self.log_classes.clone(),
+            durability: self.durability,
+            min_free_bytes: self.min_free_bytes,
+            no_device_lock: self.no_device_lock,
+            device_lock_dir: self.device_lock_dir.clone(),
+        }
+    }
+
     // Build ConvertArgs from the shared fields so run-mode reuses conversion helpers.
     pub fn to_convert_args(&self) -> ConvertArgs {
         ConvertArgs {
+            config: self.config.clone(),
             station: self.station.clone(),
             country: self.country.clone(),
             receiver_type: self.receiver_type.clone(),
             antenna_type: self.antenna_type.clone(),
+            approx_xyz: self.approx_xyz.clone(),
+            approx_llh: self.approx_llh.clone(),
             observer: self.observer.clone(),
             shift_hours: self.shift_hours,
             max_days_back: self.max_days_back,
+            // Run mode only converts hours its own logger has already closed.
+            min_file_age_secs: 0,
+            // Closed hours are handed over at rotation; they are complete by construction.
+            min_complete_minutes: 0,
+            from: None,
+            to: None,
+            allow_large_range: false,
+            // Run mode converts from a single background worker, one hour at a time.
+            convert_jobs: 1,
             data_dir: self.data_dir.clone(),
+            data_layout: self.data_layout,
             archive_dir: self.archive_dir.clone(),
             lock_file: PathBuf::from("/var/lib/gnss2tec-logger/convert.lock"),
+            lock_wait_secs: self.lock_wait_secs,
+            convert_timeout_secs: self.convert_timeout_secs,
+            converter: self.converter,
             convbin_path: self.convbin_path.clone(),
             rnx2crx_path: self.rnx2crx_path.clone(),
+            convert_sandbox: self.convert_sandbox.clone(),
+            convbin_extra_args: self.convbin_extra_args.clone(),
             nav_output_format: self.nav_output_format,
             obs_output_format: self.obs_output_format,
+            hatanaka: self.hatanaka,
+            log_format: self.log_format,
+            compression: self.compression,
+            no_compress_products: self.no_compress_products,
             obs_sampling_secs: self.obs_sampling_secs,
+            config_file: Some(self.config_file.clone()),
+            rinex_version: self.rinex_version.clone(),
+            rinex_style: self.rinex_style,
+            obs_name_template: self.obs_name_template.clone(),
+            nav_name_template: self.nav_name_template.clone(),
+            constellations: self.constellations.clone(),
+            exclude_systems: self.exclude_systems.clone(),
             output_ionex: self.output_ionex,
             skip_nav: self.skip_nav,
             keep_ubx: self.keep_ubx,
+            archive_ubx: self.archive_ubx,
+            tec_sidecar: self.tec_sidecar,
+            qc: self.qc,
+            write_checksums: self.write_checksums,
+            day_manifest: self.day_manifest,
+            annotate_gaps: self.annotate_gaps,
+            no_obs_window: self.no_obs_window,
+            gap_threshold_secs: self.gap_threshold_secs,
+            min_obs_epochs: self.min_obs_epochs,
+            reject_dir: self.reject_dir.clone(),
+            obs_archive_subdir: self.obs_archive_subdir.clone(),
+            nav_archive_subdir: self.nav_archive_subdir.clone(),
+            archive_timezone: self.archive_timezone.clone(),
+            archive_station_prefix: self.archive_station_prefix,
+            archive_retention_days: self.archive_retention_days,
+            post_hook: self.post_hook.clone(),
+            rsync_target: self.rsync_target.clone(),
+            rsync_path: self.rsync_path.clone(),
+            rsync_delete_after_upload: self.rsync_delete_after_upload,
+            upload_queue: self.upload_queue,
+            daily: self.daily,
+            keep_hourly: self.keep_hourly,
+            gfzrnx_path: self.gfzrnx_path.clone(),
         }
     }
 }
@@ -0,0 +1,94 @@
+use crate::args::CheckConfigArgs;
+use crate::commands::log::parse_ubx_config_lines;
+use crate::shared::ubx;
+use anyhow::{Result, bail};
+
+// Public check-config entrypoint.
+// Parses the config file exactly as `log` would and prints one PASS/FAIL line per `!UBX`
+// command, without touching the serial port. Fails when any line is rejected.
+pub fn run_check_config(args: CheckConfigArgs) -> Result<()> {
+    let entries = parse_ubx_config_lines(&args.config_file)?;
+    let path = args.config_file.display();
+    let mut failures = 0usize;
+
+    for entry in &entries {
+        match &entry.packet {
+            Ok(packet) => {
+                let (class, id) = ubx::packet_class_id(packet).unwrap_or_default();
+                println!(
+                    "PASS  {path}:{} {}: class 0x{class:02X} id 0x{id:02X}, {} bytes",
+                    entry.line,
+                    entry.command,
+                    packet.len()
+                );
+            }
+            Err(err) => {
+                failures += 1;
+                println!("FAIL  {path}:{} {}: {err:#}", entry.line, entry.command);
+            }
+        }
+    }
+
+    if entries.is_empty() {
+        bail!("no UBX commands found in {path}");
+    }
+    if failures > 0 {
+        bail!("{failures} of {} UBX config line(s) failed", entries.len());
+    }
+    println!("All {} UBX config line(s) parsed", entries.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn mixed_config_reports_each_line_and_fails_overall() {
+        let path =
+            std::env::temp_dir().join(format!("gnss2tec-check-config-{}.dat", std::process::id()));
+        fs::write(
+            &path,
+            "# comments and blank lines are skipped\n\
+             \n\
+             !UBX CFG-MSG 240 0 0 1 0 1 0 0\n\
+             !UBX CFG-RATE 1000 1\n\
+             !UBX CFG-RATE 1000 1 1\n\
+             !UBX CFG-PRT 1 0 0\n\
+             !UBX CFG-MSG\n",
+        )
+        .unwrap();
+
+        let entries = parse_ubx_config_lines(&path).unwrap();
+        let summary: Vec<(usize, &str, bool)> = entries
+            .iter()
+            .map(|entry| (entry.line, entry.command.as_str(), entry.packet.is_ok()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (3, "CFG-MSG", true),
+                (4, "CFG-RATE", false),
+                (5, "CFG-RATE", true),
+                (6, "CFG-PRT", false),
+                (7, "CFG-MSG", false),
+            ]
+        );
+
+        let err = run_check_config(CheckConfigArgs {
+            config_file: path.clone(),
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "3 of 5 UBX config line(s) failed");
+
+        fs::write(&path, "!UBX CFG-RATE 1000 1 1\n").unwrap();
+        assert!(
+            run_check_config(CheckConfigArgs {
+                config_file: path.clone(),
+            })
+            .is_ok()
+        );
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,93 +1,366 @@
-use crate::args::{ConvertArgs, NavOutputFormat, ObsOutputFormat};
+use crate::args::{
+    ConvertArgs, ConverterBackend, DataLayout, NavOutputFormat, ObsOutputFormat,
+    ProductCompression, RinexStyle,
+};
+use crate::commands::log::{configured_measurement_rate, parse_ubx_config};
+use crate::shared::checksum::{sha256_file, write_sha256_sidecar};
+use crate::shared::day_manifest::{
+    record_archived_hour, remove_archived_files, remove_archived_hour,
+};
+use crate::shared::error::{PipelineError, PipelineResultExt};
+use crate::shared::event::{EventKind, Level, emit_event, log_at};
 use crate::shared::lock::LockGuard;
+use crate::shared::logfile::{is_compressed_log_name, log_day_dir, log_file_stem};
+use crate::shared::rinex_obs::{
+    ObsQc, find_epoch_gaps, insert_header_comments, scan_obs_epochs, summarize_obs,
+};
+use crate::shared::shell::split_command_line;
+use crate::shared::ubx::{Frame, FrameScanner};
+use crate::shared::upload_queue::{STATUS_DONE, UploadQueue};
 use anyhow::{Context, Result, anyhow, bail};
-use chrono::{DateTime, Datelike, Duration as ChronoDuration, Timelike, Utc};
+use chrono::{
+    DateTime, Datelike, Duration as ChronoDuration, FixedOffset, Local, NaiveDate, NaiveDateTime,
+    Timelike, Utc,
+};
 use flate2::Compression;
+use flate2::read::MultiGzDecoder;
 use flate2::write::GzEncoder;
 use ionex::prelude::{
     Duration as IonexDuration, Epoch as IonexEpoch, Header as IonexHeader, IONEX, Key as IonexKey,
     Linspace as IonexLinspace, Record as IonexRecord, TEC as IonexTec,
 };
 use rinex::Rinex;
+use serde_json::json;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::ffi::{OsStr, OsString};
 use std::fs::{self, File};
-use std::io::{self, BufReader, BufWriter, Write};
-use std::path::{Path, PathBuf};
-use std::process::Command;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Output, Stdio};
 use std::str::FromStr;
-use std::time::SystemTime;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use tar::Builder;
 
 // Public convert command entrypoint.
 // This scans recent UTC hours, runs conversion, and archives hourly outputs.
-pub fn run_convert(args: ConvertArgs) -> Result<()> {
+pub fn run_convert(args: ConvertArgs) -> Result<(), PipelineError> {
     // Prepare output folders and enforce single-instance conversion.
-    fs::create_dir_all(&args.data_dir).with_context(|| {
-        format!(
-            "creating data directory failed: {}",
-            args.data_dir.display()
-        )
-    })?;
-    fs::create_dir_all(&args.archive_dir).with_context(|| {
-        format!(
-            "creating archive directory failed: {}",
-            args.archive_dir.display()
-        )
-    })?;
-    let _lock = LockGuard::acquire(&args.lock_file)?;
-
-    let total_hours = i64::from(args.max_days_back) * 24;
-    let processed_hours = convert_recent_hours(&args, total_hours)?;
-    eprintln!("Conversion complete; processed {} hour(s)", processed_hours);
+    fs::create_dir_all(&args.data_dir)
+        .with_context(|| {
+            format!(
+                "creating data directory failed: {}",
+                args.data_dir.display()
+            )
+        })
+        .or_pipeline(PipelineError::Storage)?;
+    fs::create_dir_all(&args.archive_dir)
+        .with_context(|| {
+            format!(
+                "creating archive directory failed: {}",
+                args.archive_dir.display()
+            )
+        })
+        .or_pipeline(PipelineError::Storage)?;
+    // No Ctrl-C handler here: the default action ends the process, so the wait never needs
+    // to be cancelled from inside.
+    let _lock = LockGuard::acquire_with_timeout(
+        &args.lock_file,
+        Duration::from_secs(args.lock_wait_secs),
+        &AtomicBool::new(true),
+    )
+    .or_pipeline(PipelineError::Lock)?;
+
+    let processed_hours = convert_requested_hours(&args)?;
+    emit_event(
+        EventKind::ConvertFinish,
+        &format!("Conversion complete; processed {} hour(s)", processed_hours),
+    );
+    let merged_days = merge_completed_days(&args).or_pipeline(PipelineError::Archive)?;
+    if args.daily {
+        emit_event(
+            EventKind::ConvertFinish,
+            &format!("Daily merge complete; merged {} day(s)", merged_days),
+        );
+    }
+    prune_expired_archive_days(
+        &archive_root(&args),
+        &args.archive_timezone,
+        args.archive_retention_days,
+    )
+    .or_pipeline(PipelineError::Archive)?;
     Ok(())
 }
 
-// Convert a recent UTC time window.
-// This helper is shared by `convert` command and `run` startup catch-up logic.
-pub(crate) fn convert_recent_hours(args: &ConvertArgs, total_hours: i64) -> Result<u32> {
-    if total_hours <= 0 {
-        bail!("max_days_back must be greater than zero");
+// Longest --from/--to range accepted without --allow-large-range.
+const MAX_EXPLICIT_RANGE_HOURS: i64 = 31 * 24;
+// How often a converter run under --convert-timeout-secs is checked for exit.
+const COMMAND_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Convert the hours selected for the `convert` command: the explicit `--from`/`--to` range
+// when given, otherwise the recent window anchored on now minus `--shift-hours`.
+fn convert_requested_hours(args: &ConvertArgs) -> Result<u32, PipelineError> {
+    let now = Utc::now();
+    let candidates = match (&args.from, &args.to) {
+        (Some(from), Some(to)) => explicit_hour_range(from, to, args.allow_large_range)
+            .or_pipeline(PipelineError::InvalidConfig)?,
+        _ => {
+            let total_hours = i64::from(args.max_days_back) * 24;
+            if total_hours <= 0 {
+                return Err(PipelineError::InvalidConfig(
+                    "max_days_back must be greater than zero".into(),
+                ));
+            }
+            // Anchor on previous full UTC hour by default (shift_hours), then walk backwards.
+            let anchor = floor_to_hour(now - ChronoDuration::hours(i64::from(args.shift_hours)));
+            (0..total_hours)
+                .map(|offset| anchor - ChronoDuration::hours(offset))
+                .collect()
+        }
+    };
+
+    ensure_converter_available(args).or_pipeline(PipelineError::ConverterMissing)?;
+
+    // Whatever the window says, an hour that has not ended (plus --min-complete-minutes) waits.
+    let (hours, pending): (Vec<DateTime<Utc>>, Vec<DateTime<Utc>>) = candidates
+        .into_iter()
+        .partition(|dt| is_hour_complete(*dt, now, args.min_complete_minutes));
+    if !pending.is_empty() {
+        emit_event(
+            EventKind::Info,
+            &format!(
+                "Skipping {} hour(s) that ended less than {} minute(s) ago (including the in-progress hour)",
+                pending.len(),
+                args.min_complete_minutes
+            ),
+        );
+    }
+
+    let started = Instant::now();
+    let jobs = args.convert_jobs.max(1);
+    if jobs == 1 {
+        let mut processed_hours = 0_u32;
+        for dt in hours {
+            let converted =
+                convert_hour_utc(args, dt).map_err(|err| PipelineError::ConversionFailed {
+                    hours: dt.format("%Y-%m-%d %H:00").to_string(),
+                    source: err.into(),
+                })?;
+            if converted {
+                processed_hours += 1;
+            }
+        }
+        emit_event(
+            EventKind::ConvertFinish,
+            &format!(
+                "Converted {} hour(s) in {:.1}s",
+                processed_hours,
+                started.elapsed().as_secs_f64()
+            ),
+        );
+        return Ok(processed_hours);
     }
 
-    ensure_converter_available(args)?;
+    convert_hours_parallel(args, &hours, jobs, started)
+}
+
+// Every UTC hour from `from` through `to` inclusive, both given as YYYYMMDD_HH.
+pub(crate) fn explicit_hour_range(
+    from: &str,
+    to: &str,
+    allow_large: bool,
+) -> Result<Vec<DateTime<Utc>>> {
+    let first = parse_hour_arg(from, "--from")?;
+    let last = parse_hour_arg(to, "--to")?;
+    if first > last {
+        bail!("--from {from} is after --to {to}");
+    }
+    let count = (last - first).num_hours() + 1;
+    if count > MAX_EXPLICIT_RANGE_HOURS && !allow_large {
+        bail!(
+            "--from {from} --to {to} covers {count} hours (more than {} days); pass --allow-large-range to convert it anyway",
+            MAX_EXPLICIT_RANGE_HOURS / 24
+        );
+    }
+    Ok((0..count)
+        .map(|offset| first + ChronoDuration::hours(offset))
+        .collect())
+}
 
-    // Anchor on previous full UTC hour by default (shift_hours), then walk backwards.
-    let anchor = floor_to_hour(Utc::now() - ChronoDuration::hours(i64::from(args.shift_hours)));
+fn parse_hour_arg(value: &str, flag: &str) -> Result<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(&format!("{value}0000"), "%Y%m%d_%H%M%S")
+        .map(|naive| naive.and_utc())
+        .with_context(|| format!("{flag} must be a UTC hour as YYYYMMDD_HH, got {value:?}"))
+}
 
-    let mut processed_hours = 0_u32;
-    for offset in 0..total_hours {
-        let dt = anchor - ChronoDuration::hours(offset);
-        if convert_hour_utc(args, dt)? {
-            processed_hours += 1;
+// Run up to `jobs` hour conversions at once from a shared work list.
+// Each hour already converts in its own workspace and archives under distinct product names,
+// so the archive move step does not contend; failures are collected and reported at the end.
+fn convert_hours_parallel(
+    args: &ConvertArgs,
+    hours: &[DateTime<Utc>],
+    jobs: usize,
+    started: Instant,
+) -> Result<u32, PipelineError> {
+    let next = AtomicUsize::new(0);
+    let processed = AtomicU32::new(0);
+    let failures: Mutex<Vec<(DateTime<Utc>, anyhow::Error)>> = Mutex::new(Vec::new());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs.min(hours.len()) {
+            scope.spawn(|| {
+                loop {
+                    let idx = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(&dt) = hours.get(idx) else {
+                        break;
+                    };
+                    match convert_hour_utc(args, dt) {
+                        Ok(true) => {
+                            processed.fetch_add(1, Ordering::SeqCst);
+                        }
+                        Ok(false) => {}
+                        Err(err) => {
+                            emit_event(
+                                EventKind::ConvertFailed,
+                                &format!(
+                                    "Hour conversion failed for {}: {err:#}",
+                                    dt.format("%Y-%m-%d %H:00")
+                                ),
+                            );
+                            failures
+                                .lock()
+                                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                                .push((dt, err));
+                        }
+                    }
+                }
+            });
         }
+    });
+
+    let processed_hours = processed.into_inner();
+    let mut failures = failures
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    failures.sort_by_key(|(dt, _)| *dt);
+    emit_event(
+        EventKind::ConvertFinish,
+        &format!(
+            "Converted {} hour(s) with {} job(s) in {:.1}s; {} hour(s) failed",
+            processed_hours,
+            jobs,
+            started.elapsed().as_secs_f64(),
+            failures.len()
+        ),
+    );
+    if failures.is_empty() {
+        return Ok(processed_hours);
+    }
+
+    for (dt, err) in &failures {
+        emit_event(
+            EventKind::ConvertFailed,
+            &format!("  failed {}: {err:#}", dt.format("%Y-%m-%d %H:00")),
+        );
     }
+    Err(PipelineError::ConversionFailed {
+        hours: failures
+            .iter()
+            .map(|(dt, _)| dt.format("%Y-%m-%d %H:00").to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+        source: format!("{} hour conversion(s) failed", failures.len()).into(),
+    })
+}
 
-    Ok(processed_hours)
+// Whether any UBX file (closed or still active) exists for the UTC hour; used to skip empty
+// hours before they are queued.
+pub(crate) fn hour_has_ubx_files(
+    data_dir: &Path,
+    layout: DataLayout,
+    dt: DateTime<Utc>,
+) -> Result<bool> {
+    let hour_dir = log_day_dir(data_dir, layout, &dt.format("%Y%m%d").to_string());
+    let prefix = dt.format("%Y%m%d_%H").to_string();
+    let (files, active) = list_hour_ubx_files(&hour_dir, &prefix, Duration::ZERO)?;
+    Ok(!files.is_empty() || !active.is_empty())
 }
 
 // Convert one specific UTC hour if input UBX files are present.
 pub(crate) fn convert_hour_utc(args: &ConvertArgs, dt: DateTime<Utc>) -> Result<bool> {
+    convert_hour_with(args, converter_for(args), dt)
+}
+
+// `convert_hour_utc` with an explicit backend (the run-mode worker passes its own).
+pub(crate) fn convert_hour_with(
+    args: &ConvertArgs,
+    converter: &dyn Converter,
+    dt: DateTime<Utc>,
+) -> Result<bool> {
     let prefix = dt.format("%Y%m%d_%H").to_string();
-    let ubx_files = list_hour_ubx_files(&args.data_dir, &prefix)?;
+    let min_file_age = Duration::from_secs(args.min_file_age_secs);
+    let hour_dir = log_day_dir(
+        &args.data_dir,
+        args.data_layout,
+        &dt.format("%Y%m%d").to_string(),
+    );
+    let (ubx_files, active_files) = list_hour_ubx_files(&hour_dir, &prefix, min_file_age)?;
+    if !active_files.is_empty() {
+        // Converting the closed part now would archive a partial hour and a second product later.
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "Deferring UTC hour {}: {} UBX file(s) modified within --min-file-age-secs {} (still being written?): {}",
+                dt.format("%Y-%m-%d %H:00"),
+                active_files.len(),
+                args.min_file_age_secs,
+                active_files
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        );
+        return Ok(false);
+    }
     if ubx_files.is_empty() {
         return Ok(false);
     }
 
-    eprintln!(
-        "Processing UTC hour {} with {} UBX file(s)",
-        dt.format("%Y-%m-%d %H:00"),
-        ubx_files.len()
+    emit_event(
+        EventKind::ConvertStart,
+        &format!(
+            "Processing UTC hour {} with {} UBX file(s)",
+            dt.format("%Y-%m-%d %H:00"),
+            ubx_files.len()
+        ),
     );
 
-    process_hour(args, dt, &ubx_files)?;
+    process_hour(args, converter, dt, &ubx_files)?;
     Ok(true)
 }
 
+// Convert already-archived UBX inputs for `reprocess` through the normal hour pipeline.
+pub(crate) fn reprocess_hour(
+    args: &ConvertArgs,
+    converter: &dyn Converter,
+    dt: DateTime<Utc>,
+    ubx_files: &[PathBuf],
+) -> Result<()> {
+    process_hour(args, converter, dt, ubx_files)
+}
+
 // Convert one UTC hour of UBX files into OBS (+optional NAV) and archive.
-fn process_hour(args: &ConvertArgs, dt: DateTime<Utc>, ubx_files: &[PathBuf]) -> Result<()> {
-    let year = dt.format("%Y").to_string();
-    let doy = format!("{:03}", dt.ordinal());
+fn process_hour(
+    args: &ConvertArgs,
+    converter: &dyn Converter,
+    dt: DateTime<Utc>,
+    ubx_files: &[PathBuf],
+) -> Result<()> {
+    let (year, doy) = archive_bucket(&args.archive_timezone, dt)?;
     let hour_label = format!("{} {}", dt.format("%Y-%m-%d"), dt.format("%H:00"));
     let nav_requested = !args.skip_nav;
 
@@ -96,13 +369,17 @@ fn process_hour(args: &ConvertArgs, dt: DateTime<Utc>, ubx_files: &[PathBuf]) ->
     let _workspace_cleanup = WorkspaceCleanup::new(work_dir.clone());
     let data_dir_snapshot_before = snapshot_output_products(&args.data_dir)?;
 
+    // Covers merge, convbin, and product compression in the workspace.
+    let conversion_started = Instant::now();
+    let mut merge_stats = MergeStats::default();
+    let mut obs_epochs = 0_usize;
     let conversion_result: Result<Vec<PathBuf>> = (|| {
         let merged_ubx = work_dir.join(format!("merged_{}.ubx", dt.format("%Y%m%d_%H")));
-        concat_ubx_files(ubx_files, &merged_ubx)?;
+        merge_stats = concat_ubx_files(ubx_files, &merged_ubx)?;
 
-        run_convbin_obs_for_hour(args, dt, &merged_ubx, &work_dir)?;
+        obs_epochs = converter.convert_obs(args, dt, &merged_ubx, &work_dir)?;
         if nav_requested {
-            run_convbin_nav_for_hour(args, dt, &merged_ubx, &work_dir)?;
+            converter.convert_nav(args, dt, &merged_ubx, &work_dir)?;
         }
 
         let mut outputs = collect_output_products_in_dir(&work_dir)?;
@@ -113,1042 +390,5358 @@ fn process_hour(args: &ConvertArgs, dt: DateTime<Utc>, ubx_files: &[PathBuf]) ->
                 &snapshot_output_products(&args.data_dir)?,
             );
             if !outputs.is_empty() {
-                eprintln!(
-                    "Converter emitted products outside workspace for {}; using changed files from {}",
-                    hour_label,
-                    args.data_dir.display()
+                emit_event(
+                    EventKind::Warning,
+                    &format!(
+                        "Converter emitted products outside workspace for {}; using changed files from {}",
+                        hour_label,
+                        args.data_dir.display()
+                    ),
                 );
             }
         }
 
         normalize_long_output_names_for_target_hour(&mut outputs, dt)?;
         validate_hour_outputs(&outputs, args.skip_nav, &hour_label)?;
+        if args.tec_sidecar
+            && let Some(sidecar) = write_tec_sidecar(args, dt, &outputs, &work_dir)?
+        {
+            outputs.push(sidecar);
+        }
+        let qc_summary = qc_summary_path(args, dt, &work_dir)?;
+        if args.qc && qc_summary.is_file() {
+            outputs.push(qc_summary);
+        }
+        if args.archive_ubx {
+            outputs.push(compress_merged_ubx(args, dt, &merged_ubx)?);
+        }
         Ok(outputs)
     })();
 
-    let outputs = match conversion_result {
-        Ok(outputs) => outputs,
-        Err(err) => return Err(err),
-    };
+    let outputs = conversion_result?;
+
+    // A power blip can leave an hour with a handful of epochs; keep it out of the archive.
+    if obs_epochs < args.min_obs_epochs as usize {
+        let parked = reject_sparse_hour(args, &hour_label, &year, &doy, &outputs, obs_epochs)?;
+        // Without --reject-dir the products, including an --archive-ubx copy, are dropped, so
+        // --keep-ubx sources are the only raw data left and must stay.
+        let remove_sources = if parked {
+            removes_source_ubx(args)
+        } else {
+            !args.keep_ubx
+        };
+        if remove_sources {
+            for ubx in ubx_files {
+                remove_file_if_exists(ubx)?;
+            }
+        }
+        return Ok(());
+    }
 
-    // Move final outputs into archive/<year>/<doy>/.
-    let archive_path = args.archive_dir.join(&year).join(&doy);
+    // Move final outputs into archive/[<station>/]<year>/<doy>/.
+    let archive_path = archive_root(args).join(&year).join(&doy);
     fs::create_dir_all(&archive_path)
         .with_context(|| format!("creating archive path failed: {}", archive_path.display()))?;
 
+    let mut archived = Vec::with_capacity(outputs.len());
     for output in &outputs {
-        move_into_dir(output, &archive_path)?;
+        let product_dir = product_archive_dir(args, &archive_path, output)?;
+        if product_dir != archive_path {
+            fs::create_dir_all(&product_dir).with_context(|| {
+                format!("creating archive path failed: {}", product_dir.display())
+            })?;
+        }
+        archived.push(move_into_dir(output, &product_dir)?);
     }
+    let conversion_elapsed = conversion_started.elapsed();
+    let (obs_bytes, nav_bytes) = archived_product_bytes(&archived)?;
+    emit_event(
+        EventKind::ConvertFinish,
+        &format_hour_summary(dt, conversion_elapsed, obs_bytes, nav_bytes, merge_stats),
+    );
 
-    if !args.keep_ubx {
-        for ubx in ubx_files {
-            remove_file_if_exists(ubx)?;
+    if args.write_checksums {
+        let mut sidecars = Vec::with_capacity(archived.len());
+        for product in &archived {
+            sidecars.push(write_sha256_sidecar(product)?);
         }
+        archived.extend(sidecars);
     }
 
-    Ok(())
-}
-
-// Verify required converter binaries exist and can be executed.
-pub(crate) fn ensure_converter_available(args: &ConvertArgs) -> Result<()> {
-    if args.obs_sampling_secs == 0 {
-        bail!("obs_sampling_secs must be greater than zero");
+    if args.day_manifest {
+        record_archived_hour(&archive_path, dt, &archived, args.write_checksums)?;
     }
 
-    let (program, used_path_fallback) = resolve_convbin_program(&args.convbin_path);
-    let mut cmd = Command::new(&program);
-    cmd.arg("-h");
-    run_checked_command(
-        &mut cmd,
-        &if used_path_fallback {
-            format!(
-                "convbin availability check (requested {} not found; used PATH lookup)",
-                args.convbin_path.display()
-            )
-        } else {
-            format!(
-                "convbin availability check ({})",
-                args.convbin_path.display()
-            )
-        },
-    )?;
+    // Completion signal for pollers: only written once every product is in place.
+    write_ready_marker(args, dt, &archive_path, &archived)?;
+    emit_event(
+        EventKind::ConvertFinish,
+        &format!(
+            "Archived {} file(s) for {hour_label} in {}",
+            archived.len(),
+            archive_path.display()
+        ),
+    );
 
-    if matches!(args.obs_output_format, ObsOutputFormat::Hatanaka) {
-        let (program, used_path_fallback) = resolve_rnx2crx_program(&args.rnx2crx_path);
-        let mut cmd = Command::new(&program);
-        cmd.arg("-h");
-        run_checked_command(
-            &mut cmd,
-            &if used_path_fallback {
-                format!(
-                    "rnx2crx availability check (requested {} not found; used PATH lookup)",
-                    args.rnx2crx_path.display()
-                )
-            } else {
-                format!(
-                    "rnx2crx availability check ({})",
-                    args.rnx2crx_path.display()
-                )
-            },
-        )?;
+    if let Some(hook) = args.post_hook.as_deref() {
+        run_post_hook(args, hook, dt, &year, &doy, &archived);
     }
 
-    Ok(())
-}
+    if args.upload_queue
+        && let Err(err) =
+            UploadQueue::new(&args.archive_dir).append_pending(&args.archive_dir, &archived)
+    {
+        emit_event(
+            EventKind::Upload,
+            &format!("Queueing archived products for upload failed for {hour_label}: {err:#}"),
+        );
+    }
 
-// Resolve convbin executable path.
-// If configured absolute path is missing, fall back to PATH lookup.
-fn resolve_convbin_program(configured_path: &Path) -> (OsString, bool) {
-    if configured_path.exists() {
-        return (configured_path.as_os_str().to_owned(), false);
+    if let Some(target) = args.rsync_target.as_deref()
+        && upload_archived_hour(args, target, &year, &doy, &archived)
+    {
+        forget_uploaded_hour(args, dt, &archive_path)?;
     }
-    (OsString::from("convbin"), true)
-}
 
-// Resolve rnx2crx executable path.
-// If configured absolute path is missing, fall back to PATH lookup.
-fn resolve_rnx2crx_program(configured_path: &Path) -> (OsString, bool) {
-    if configured_path.exists() {
-        return (configured_path.as_os_str().to_owned(), false);
+    if removes_source_ubx(args) {
+        for ubx in ubx_files {
+            remove_file_if_exists(ubx)?;
+        }
+        // Drop the day directory once its last hour is converted; fails harmlessly while
+        // other hours (or the logger's current file) are still in it.
+        if args.data_layout == DataLayout::Daily
+            && let Some(day_dir) = ubx_files.first().and_then(|path| path.parent())
+        {
+            let _ = fs::remove_dir(day_dir);
+        }
     }
-    (OsString::from("rnx2crx"), true)
-}
 
-#[derive(Clone, Copy)]
-struct NavSystemSpec {
-    suffix: &'static str,
-    exclude: &'static [char],
+    Ok(())
 }
 
-const NAV_SYSTEM_SPECS: [NavSystemSpec; 5] = [
-    NavSystemSpec {
-        suffix: "GN",
-        exclude: &['R', 'E', 'J', 'S', 'C'],
-    },
-    NavSystemSpec {
-        suffix: "RN",
-        exclude: &['G', 'E', 'J', 'S', 'C'],
-    },
-    NavSystemSpec {
-        suffix: "EN",
-        exclude: &['G', 'R', 'J', 'S', 'C'],
-    },
-    NavSystemSpec {
-        suffix: "CN",
-        exclude: &['G', 'R', 'E', 'J', 'S'],
-    },
-    NavSystemSpec {
-        suffix: "JN",
-        exclude: &['G', 'R', 'E', 'S', 'C'],
-    },
-];
-
-fn run_convbin_obs_for_hour(
+// Run `--post-hook` for one archived hour, with the hour and its products in the environment.
+// Runs before uploads, so the products are still local. A failing hook is only a warning; the
+// hour stays archived.
+fn run_post_hook(
     args: &ConvertArgs,
+    hook: &str,
     dt: DateTime<Utc>,
-    merged_ubx: &Path,
-    output_dir: &Path,
-) -> Result<()> {
-    if args.obs_sampling_secs == 0 {
-        bail!("obs_sampling_secs must be greater than zero");
+    year: &str,
+    doy: &str,
+    archived: &[PathBuf],
+) {
+    let hour_label = dt.format("%Y-%m-%d %H:00");
+    let result = split_command_line(hook)
+        .with_context(|| format!("parsing --post-hook failed: {hook}"))
+        .and_then(|tokens| {
+            let Some((program, hook_args)) = tokens.split_first() else {
+                bail!("--post-hook is empty");
+            };
+            let products = archived
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let mut cmd = Command::new(program);
+            cmd.args(hook_args)
+                .env("GNSS2TEC_YEAR", year)
+                .env("GNSS2TEC_DOY", doy)
+                .env("GNSS2TEC_HOUR", dt.format("%H").to_string())
+                .env("GNSS2TEC_ARCHIVE_DIR", &args.archive_dir)
+                .env("GNSS2TEC_PRODUCTS", products);
+            run_checked_command(&mut cmd, "post-hook")
+        });
+    if let Err(err) = result {
+        emit_event(
+            EventKind::Warning,
+            &format!("Post-hook failed for {hour_label}; products stay archived: {err:#}"),
+        );
     }
+}
 
-    let (program, used_path_fallback) = resolve_convbin_program(&args.convbin_path);
-    let prefix = format!(
-        "{}00{}_R_{}{:03}{}_01H_{}_MO",
-        args.station,
-        args.country,
-        dt.format("%Y"),
-        dt.ordinal(),
-        dt.format("%H"),
-        sampling_token_from_seconds(args.obs_sampling_secs)
-    );
-    let obs_rnx = output_dir.join(format!("{prefix}.rnx"));
-
-    let mut cmd = Command::new(&program);
-    cmd.arg("-r")
-        .arg("ubx")
-        .arg("-v")
-        .arg("3.04")
-        // Explicitly request the richest practical observation export:
-        // -od: Doppler observables, -os: signal strength observables,
-        // -oi/-ot/-ol: include iono/time/leap metadata where applicable.
-        .arg("-od")
-        .arg("-os")
-        .arg("-oi")
-        .arg("-ot")
-        .arg("-ol")
-        .arg("-ti")
-        .arg(args.obs_sampling_secs.to_string())
-        .arg("-hm")
-        .arg(format!("{}00", args.station))
-        .arg("-ho")
-        .arg(format!("{}/{}", args.observer, args.country))
-        .arg("-hr")
-        .arg(format!("NA/{}/NA", args.receiver_type))
-        .arg("-ha")
-        .arg(format!("NA/{}", args.antenna_type))
-        .arg("-o")
-        .arg(&obs_rnx)
-        .arg(merged_ubx);
+// Source UBX files are deleted after conversion unless `--keep-ubx`; with `--archive-ubx` the
+// archived copy replaces them, so they are deleted either way.
+fn removes_source_ubx(args: &ConvertArgs) -> bool {
+    !args.keep_ubx || args.archive_ubx
+}
 
-    let label = if used_path_fallback {
+// Gzip the merged hour as `<prefix>.ubx.gz` for `--archive-ubx`, so raw data can be
+// reprocessed later from the archive instead of the data directory.
+fn compress_merged_ubx(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    merged_ubx: &Path,
+) -> Result<PathBuf> {
+    let named = merged_ubx.with_file_name(format!("{}.ubx", hour_product_prefix(args, dt)?));
+    fs::rename(merged_ubx, &named).with_context(|| {
         format!(
-            "convbin observation conversion (requested {} not found; used PATH lookup)",
-            args.convbin_path.display()
+            "renaming merged UBX failed: {} -> {}",
+            merged_ubx.display(),
+            named.display()
         )
-    } else {
-        "convbin observation conversion".to_string()
-    };
+    })?;
+    compress_file(named, ProductCompression::Gzip)
+}
 
-    run_checked_command(&mut cmd, &label)?;
+// Destination for one product inside archive/<year>/<doy>/: observation and navigation files
+// go into `--obs-archive-subdir` / `--nav-archive-subdir` when set; IONEX and sidecar outputs
+// stay in the day directory next to the `.ready` marker.
+fn product_archive_dir(args: &ConvertArgs, day_path: &Path, product: &Path) -> Result<PathBuf> {
+    let kind = product
+        .file_name()
+        .map(|name| classify_output_name(&name.to_string_lossy()))
+        .unwrap_or(OutputKind::Other);
+    match kind {
+        OutputKind::Observation => archive_subdir(day_path, args.obs_archive_subdir.as_deref()),
+        OutputKind::Navigation => archive_subdir(day_path, args.nav_archive_subdir.as_deref()),
+        OutputKind::Ionex | OutputKind::Other => Ok(day_path.to_path_buf()),
+    }
+}
 
-    if !file_exists_and_nonempty(&obs_rnx) {
+// `day_path/<subdir>`; only plain relative components are accepted so products cannot leave
+// the day directory.
+fn archive_subdir(day_path: &Path, subdir: Option<&Path>) -> Result<PathBuf> {
+    let Some(subdir) = subdir.filter(|subdir| !subdir.as_os_str().is_empty()) else {
+        return Ok(day_path.to_path_buf());
+    };
+    if !subdir
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
         bail!(
-            "convbin finished but expected observation file was not generated: {}",
-            obs_rnx.display()
+            "archive subdirectory must be a relative path without `..`: {}",
+            subdir.display()
         );
     }
+    Ok(day_path.join(subdir))
+}
 
-    // Optional IONEX artifact generation from observation RINEX.
-    if args.output_ionex
-        && let Err(err) = generate_ionex_product(args, dt, &obs_rnx, output_dir)
-    {
-        eprintln!(
-            "IONEX generation skipped for {}: {err:#}",
-            dt.format("%Y-%m-%d %H:00")
+// Drop an hour's products below `--min-obs-epochs`, or park them in `--reject-dir`/<year>/<doy>/.
+// Returns whether the products were parked.
+fn reject_sparse_hour(
+    args: &ConvertArgs,
+    hour_label: &str,
+    year: &str,
+    doy: &str,
+    outputs: &[PathBuf],
+    obs_epochs: usize,
+) -> Result<bool> {
+    let Some(reject_dir) = args.reject_dir.as_ref() else {
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "Not archiving {hour_label}: {obs_epochs} observation epoch(s), below --min-obs-epochs {}",
+                args.min_obs_epochs
+            ),
         );
+        return Ok(false);
+    };
+    let reject_path = reject_dir.join(year).join(doy);
+    fs::create_dir_all(&reject_path)
+        .with_context(|| format!("creating reject path failed: {}", reject_path.display()))?;
+    for output in outputs {
+        move_into_dir(output, &reject_path)?;
     }
+    emit_event(
+        EventKind::Warning,
+        &format!(
+            "Moved {} product(s) for {hour_label} to {}: {obs_epochs} observation epoch(s), below --min-obs-epochs {}",
+            outputs.len(),
+            reject_path.display(),
+            args.min_obs_epochs
+        ),
+    );
+    Ok(true)
+}
 
-    match args.obs_output_format {
-        ObsOutputFormat::Rinex => {
-            let _ = gzip_file(obs_rnx)?;
-        }
-        ObsOutputFormat::Hatanaka => {
-            let obs_crx = run_rnx2crx_for_observation(args, &obs_rnx)?;
-            let _ = gzip_file(obs_crx)?;
-        }
+// Total archived observation and navigation bytes for one hour (IONEX and sidecars excluded).
+fn archived_product_bytes(archived: &[PathBuf]) -> Result<(u64, u64)> {
+    let (mut obs_bytes, mut nav_bytes) = (0_u64, 0_u64);
+    for path in archived {
+        let kind = path
+            .file_name()
+            .map(|name| classify_output_name(&name.to_string_lossy()))
+            .unwrap_or(OutputKind::Other);
+        let total = match kind {
+            OutputKind::Observation => &mut obs_bytes,
+            OutputKind::Navigation => &mut nav_bytes,
+            OutputKind::Ionex | OutputKind::Other => continue,
+        };
+        *total += fs::metadata(path)
+            .with_context(|| format!("reading metadata for {}", path.display()))?
+            .len();
     }
-
-    Ok(())
+    Ok((obs_bytes, nav_bytes))
 }
 
-fn run_rnx2crx_for_observation(args: &ConvertArgs, obs_rnx: &Path) -> Result<PathBuf> {
-    let (program, used_path_fallback) = resolve_rnx2crx_program(&args.rnx2crx_path);
-    let obs_crx = obs_rnx.with_extension("crx");
-
-    let mut cmd = Command::new(&program);
-    cmd.arg(obs_rnx).arg("-f");
-
-    let label = if used_path_fallback {
-        format!(
-            "rnx2crx conversion (requested {} not found; used PATH lookup)",
-            args.rnx2crx_path.display()
-        )
+// One line per converted hour for pipeline tuning:
+// "Converted 2024-001 12:00 in 4.2s (merge 0.1s) -> obs 8.1MB nav 0.3MB".
+fn format_hour_summary(
+    dt: DateTime<Utc>,
+    elapsed: Duration,
+    obs_bytes: u64,
+    nav_bytes: u64,
+    merge: MergeStats,
+) -> String {
+    let megabytes = |bytes: u64| bytes as f64 / 1_000_000.0;
+    let skipped = if merge.skipped_files > 0 {
+        format!(", {} skipped", merge.skipped_files)
     } else {
-        "rnx2crx conversion".to_string()
+        String::new()
     };
-    run_rnx2crx_command(&mut cmd, &label)?;
-
-    if !file_exists_and_nonempty(&obs_crx) {
-        bail!(
-            "rnx2crx finished but expected CRINEX file was not generated: {}",
-            obs_crx.display()
-        );
-    }
-
-    remove_file_if_exists(obs_rnx)?;
-    Ok(obs_crx)
+    format!(
+        "Converted {} in {:.1}s (merge {:.1}s) -> obs {:.1}MB nav {:.1}MB ({} UBX frame(s) from {} file(s), {} byte(s) outside frames{})",
+        dt.format("%Y-%j %H:00"),
+        elapsed.as_secs_f64(),
+        merge.elapsed.as_secs_f64(),
+        megabytes(obs_bytes),
+        megabytes(nav_bytes),
+        merge.frames,
+        merge.merged_files,
+        merge.stray_bytes,
+        skipped
+    )
 }
 
-fn generate_ionex_product(
+// Write `<prefix>.ready` listing the hour's archived files and sizes.
+// Written to a temporary name and renamed, so the marker appears atomically and complete.
+fn write_ready_marker(
     args: &ConvertArgs,
     dt: DateTime<Utc>,
-    obs_rnx: &Path,
-    output_dir: &Path,
+    archive_path: &Path,
+    archived: &[PathBuf],
 ) -> Result<PathBuf> {
-    let rinex = Rinex::from_file(obs_rnx)
-        .with_context(|| format!("parsing observation RINEX failed: {}", obs_rnx.display()))?;
-
-    let fallback_epoch = ionex_epoch_from_utc_hour(dt)?;
-    let first_epoch = rinex.first_epoch().unwrap_or(fallback_epoch);
-    let last_epoch = rinex.last_epoch().unwrap_or(first_epoch);
+    let mut files = Vec::with_capacity(archived.len());
+    for path in archived {
+        let size = fs::metadata(path)
+            .with_context(|| format!("reading metadata for {}", path.display()))?
+            .len();
+        // Relative to the marker, so split obs/nav subdirectories show up as `obs/<file>`.
+        let name = path.strip_prefix(archive_path).unwrap_or(path);
+        files.push(json!({
+            "name": name.to_string_lossy(),
+            "size": size,
+        }));
+    }
+    let marker = json!({
+        "hour": dt.format("%Y-%m-%dT%H:00:00Z").to_string(),
+        "archived_at": Utc::now().to_rfc3339(),
+        "files": files,
+    });
+
+    let prefix = hour_product_prefix(args, dt)?;
+    let path = archive_path.join(format!("{prefix}.ready"));
+    let tmp_path = archive_path.join(format!(".{prefix}.ready.tmp"));
+    let mut contents =
+        serde_json::to_vec_pretty(&marker).context("encoding ready marker failed")?;
+    contents.push(b'\n');
+    fs::write(&tmp_path, contents)
+        .with_context(|| format!("writing ready marker failed: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("publishing ready marker failed: {}", path.display()))?;
+    Ok(path)
+}
 
-    let (latitude_ddeg, longitude_ddeg) = rinex
-        .header
-        .rx_position
-        .and_then(ecef_to_geodetic_ddeg)
-        .map(|(lat, lon, _)| (lat, lon))
-        .unwrap_or((0.0, 0.0));
+// Timezone used only to pick the archive/<year>/<doy>/ bucket; file names and RINEX stay UTC.
+#[derive(Clone, Copy, Debug)]
+enum ArchiveTimezone {
+    Utc,
+    Local,
+    Fixed(FixedOffset),
+}
 
-    let mut record = IonexRecord::default();
-    record.insert(
-        IonexKey::from_decimal_degrees_km(first_epoch, latitude_ddeg, longitude_ddeg, 350.0),
-        IonexTec::from_tecu(0.0),
-    );
+// Accepts `utc`, `local`, or a fixed offset such as `+05:30`, `-0400`, or `+9`.
+fn parse_archive_timezone(raw: &str) -> Result<ArchiveTimezone> {
+    let value = raw.trim();
+    if value.eq_ignore_ascii_case("utc") || value.eq_ignore_ascii_case("z") {
+        return Ok(ArchiveTimezone::Utc);
+    }
+    if value.eq_ignore_ascii_case("local") {
+        return Ok(ArchiveTimezone::Local);
+    }
 
-    let mut header = IonexHeader::default();
-    header.program = Some("gnss2tec-logger".to_string());
-    header.run_by = Some(args.observer.clone());
-    header.date = Some(Utc::now().format("%Y%m%d %H%M%S UTC").to_string());
-    header.description = Some(
-        "Generated from OBS RINEX by gnss2tec-logger with placeholder TEC at receiver position."
-            .to_string(),
-    );
-    header.number_of_maps = 1;
-    header.epoch_of_first_map = first_epoch;
-    header.epoch_of_last_map = if last_epoch >= first_epoch {
-        last_epoch
-    } else {
-        first_epoch
-    };
-    header.sampling_period = IonexDuration::from_hours(1.0);
-    header.grid.latitude = IonexLinspace {
-        start: latitude_ddeg,
-        end: latitude_ddeg,
-        spacing: -1.0,
-    };
-    header.grid.longitude = IonexLinspace {
-        start: longitude_ddeg,
-        end: longitude_ddeg,
-        spacing: 1.0,
+    let invalid = || anyhow!("invalid --archive-timezone (expected utc, local, or +HH:MM): {raw}");
+    let (sign, rest) = match value.as_bytes().first() {
+        Some(b'+') => (1, &value[1..]),
+        Some(b'-') => (-1, &value[1..]),
+        _ => return Err(invalid()),
     };
-    header.grid.altitude = IonexLinspace {
-        start: 350.0,
-        end: 350.0,
-        spacing: 0.0,
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let (hours, minutes) = if digits.len() <= 2 {
+        (digits.parse::<i32>()?, 0)
+    } else {
+        let split = digits.len() - 2;
+        (
+            digits[..split].parse::<i32>()?,
+            digits[split..].parse::<i32>()?,
+        )
     };
-    header
-        .comments
-        .push("IONEX output is optional and intended for compatibility/diagnostics.".to_string());
+    if hours > 23 || minutes > 59 {
+        return Err(invalid());
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+        .map(ArchiveTimezone::Fixed)
+        .ok_or_else(invalid)
+}
 
-    let ionex = IONEX::new(header, record);
-    let file_prefix = format!(
-        "{}00{}_R_{}{:03}{}_01H_IO",
-        args.station,
-        args.country,
-        dt.format("%Y"),
-        dt.ordinal(),
-        dt.format("%H")
+// Calendar date of a UTC instant in `--archive-timezone`, i.e. the day its bucket is named after.
+fn archive_date(timezone: &str, dt: DateTime<Utc>) -> Result<NaiveDate> {
+    Ok(match parse_archive_timezone(timezone)? {
+        ArchiveTimezone::Utc => dt.date_naive(),
+        ArchiveTimezone::Local => dt.with_timezone(&Local).date_naive(),
+        ArchiveTimezone::Fixed(offset) => dt.with_timezone(&offset).date_naive(),
+    })
+}
+
+// Archive (<year>, <doy>) for a UTC hour, interpreted in `--archive-timezone`.
+fn archive_bucket(timezone: &str, dt: DateTime<Utc>) -> Result<(String, String)> {
+    let date = archive_date(timezone, dt)?;
+    Ok((
+        format!("{:04}", date.year()),
+        format!("{:03}", date.ordinal()),
+    ))
+}
+
+// Push one hour's archived products to `--rsync-target`, keeping the <year>/<doy>/ layout.
+// Upload failures are logged but never fail the hour; the files stay in the archive for a retry.
+// Returns true once `--rsync-delete-after-upload` has removed every local copy.
+fn upload_archived_hour(
+    args: &ConvertArgs,
+    target: &str,
+    year: &str,
+    doy: &str,
+    archived: &[PathBuf],
+) -> bool {
+    if archived.is_empty() {
+        return false;
+    }
+
+    let mut cmd = Command::new(&args.rsync_path);
+    cmd.arg("-a").arg("--relative");
+    for path in archived {
+        let Ok(relative) = path.strip_prefix(&args.archive_dir) else {
+            continue;
+        };
+        // The `/./` component tells rsync --relative to recreate only <year>/<doy>/[<subdir>/]<file>
+        // remotely.
+        cmd.arg(args.archive_dir.join(".").join(relative));
+    }
+    cmd.arg(target);
+
+    let label = format!("rsync upload of {} file(s) to {}", archived.len(), target);
+    if let Err(err) = run_checked_command(&mut cmd, &label) {
+        emit_event(
+            EventKind::Upload,
+            &format!("{label} failed; products kept locally: {err:#}"),
+        );
+        return false;
+    }
+    emit_event(
+        EventKind::Upload,
+        &format!(
+            "Uploaded {} archived file(s) for {}/{} to {}",
+            archived.len(),
+            year,
+            doy,
+            target
+        ),
     );
-    let ionex_path = output_dir.join(format!("{file_prefix}.ionex"));
-    ionex.to_file(&ionex_path).with_context(|| {
-        format!(
-            "writing IONEX output failed for observation file {}",
-            obs_rnx.display()
-        )
-    })?;
-    gzip_file(ionex_path)
+
+    if !args.rsync_delete_after_upload {
+        return false;
+    }
+    match remove_uploaded_files(args, archived) {
+        Ok(()) => true,
+        Err(err) => {
+            emit_event(
+                EventKind::Upload,
+                &format!("Removing uploaded local copies failed: {err:#}"),
+            );
+            false
+        }
+    }
 }
 
-fn ionex_epoch_from_utc_hour(dt: DateTime<Utc>) -> Result<IonexEpoch> {
-    let epoch_text = dt.format("%Y-%m-%dT%H:%M:%S UTC").to_string();
-    IonexEpoch::from_str(&epoch_text)
-        .with_context(|| format!("building IONEX epoch from UTC datetime failed: {epoch_text}"))
+// Delete uploaded local copies. Their upload-queue records are marked `done` first (rsync
+// delivered them, and the digest can only be computed while the file exists), so `upload`
+// never retries a file that is gone.
+fn remove_uploaded_files(args: &ConvertArgs, archived: &[PathBuf]) -> Result<()> {
+    if args.upload_queue {
+        let queue = UploadQueue::new(&args.archive_dir);
+        for path in archived {
+            let relative = path.strip_prefix(&args.archive_dir).unwrap_or(path);
+            queue.update(
+                &relative.to_string_lossy(),
+                &sha256_file(path)?,
+                json!({ "status": STATUS_DONE, "uploaded_at": Utc::now().to_rfc3339() }),
+            )?;
+        }
+    }
+    for path in archived {
+        remove_file_if_exists(path)?;
+    }
+    Ok(())
 }
 
-fn ecef_to_geodetic_ddeg(ecef_m: (f64, f64, f64)) -> Option<(f64, f64, f64)> {
-    let (x, y, z) = ecef_m;
-    if !x.is_finite() || !y.is_finite() || !z.is_finite() {
-        return None;
+// Drop the hour's day-manifest entry and `.ready` marker once its products were deleted after
+// upload, so `verify-archive` and marker pollers do not chase missing files.
+fn forget_uploaded_hour(args: &ConvertArgs, dt: DateTime<Utc>, archive_path: &Path) -> Result<()> {
+    if args.day_manifest {
+        remove_archived_hour(archive_path, dt)?;
     }
+    remove_file_if_exists(&archive_path.join(format!("{}.ready", hour_product_prefix(args, dt)?)))
+}
 
-    const A: f64 = 6_378_137.0; // WGS84 semi-major axis [m]
-    const E2: f64 = 6.694_379_990_14e-3; // WGS84 first eccentricity squared
-    const B: f64 = 6_356_752.314_245; // WGS84 semi-minor axis [m]
-    const EP2: f64 = (A * A - B * B) / (B * B);
+// Directory holding the <year>/<doy>/ buckets: the archive itself, or archive/<station>/ with
+// --archive-station-prefix.
+pub(crate) fn station_archive_root(archive_dir: &Path, station: &str, prefix: bool) -> PathBuf {
+    if prefix {
+        archive_dir.join(station)
+    } else {
+        archive_dir.to_path_buf()
+    }
+}
 
-    let p = (x * x + y * y).sqrt();
-    if p == 0.0 {
-        return None;
+fn archive_root(args: &ConvertArgs) -> PathBuf {
+    station_archive_root(
+        &args.archive_dir,
+        &args.station,
+        args.archive_station_prefix,
+    )
+}
+
+// Remove <year>/<doy>/ buckets under `archive_root` (see `station_archive_root`) older than the
+// retention window. Only directories whose path components parse as a valid year and day-of-year are touched,
+// and symlinks are never followed or removed. The window ends at today in `archive_timezone`,
+// the timezone the buckets are named in.
+pub(crate) fn prune_expired_archive_days(
+    archive_root: &Path,
+    archive_timezone: &str,
+    retention_days: u32,
+) -> Result<u32> {
+    if retention_days == 0 {
+        return Ok(0);
     }
 
-    let theta = (z * A).atan2(p * B);
-    let sin_theta = theta.sin();
-    let cos_theta = theta.cos();
-    let lat = (z + EP2 * B * sin_theta.powi(3)).atan2(p - E2 * A * cos_theta.powi(3));
-    let lon = y.atan2(x);
-    let sin_lat = lat.sin();
-    let n = A / (1.0 - E2 * sin_lat * sin_lat).sqrt();
-    let alt_m = p / lat.cos() - n;
+    let cutoff = archive_date(archive_timezone, Utc::now())?
+        - ChronoDuration::days(i64::from(retention_days));
+    let mut removed = 0_u32;
+    for (year, year_path) in list_numeric_subdirs(archive_root, 4)? {
+        for (doy, day_path) in list_numeric_subdirs(&year_path, 3)? {
+            let Some(day) = NaiveDate::from_yo_opt(year as i32, doy) else {
+                continue;
+            };
+            if day >= cutoff {
+                continue;
+            }
 
-    if !lat.is_finite() || !lon.is_finite() || !alt_m.is_finite() {
-        return None;
+            fs::remove_dir_all(&day_path).with_context(|| {
+                format!(
+                    "removing expired archive directory failed: {}",
+                    day_path.display()
+                )
+            })?;
+            emit_event(
+                EventKind::Archive,
+                &format!(
+                    "Removed expired archive directory {} ({})",
+                    day_path.display(),
+                    day.format("%Y-%m-%d")
+                ),
+            );
+            removed += 1;
+        }
+
+        // Drop the year bucket once its last day directory is gone; non-empty dirs are kept.
+        if fs::read_dir(&year_path)
+            .map(|mut entries| entries.next().is_none())
+            .unwrap_or(false)
+        {
+            let _ = fs::remove_dir(&year_path);
+        }
     }
 
-    Some((
-        lat.to_degrees(),
-        normalize_longitude_ddeg(lon.to_degrees()),
-        alt_m / 1_000.0,
-    ))
+    Ok(removed)
 }
 
-fn normalize_longitude_ddeg(mut lon_ddeg: f64) -> f64 {
-    while lon_ddeg > 180.0 {
-        lon_ddeg -= 360.0;
+// List real (non-symlink) subdirectories whose names are exactly `digits` ASCII digits.
+pub(crate) fn list_numeric_subdirs(dir: &Path, digits: usize) -> Result<Vec<(u32, PathBuf)>> {
+    let mut out = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("reading metadata for {}", path.display()))?;
+        if file_type.is_symlink() || !file_type.is_dir() {
+            continue;
+        }
+
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if name.len() != digits || !name.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let Ok(value) = name.parse::<u32>() else {
+            continue;
+        };
+        out.push((value, path));
     }
-    while lon_ddeg <= -180.0 {
-        lon_ddeg += 360.0;
+    out.sort();
+    Ok(out)
+}
+
+// Merge each complete UTC day in the lookback window into one `_01D_` observation product.
+// Days that already have a daily product are skipped, so repeated runs are no-ops; days with
+// missing hours merge whatever hourly observation products exist.
+pub(crate) fn merge_completed_days(args: &ConvertArgs) -> Result<u32> {
+    if !args.daily {
+        return Ok(0);
     }
-    lon_ddeg
+
+    let now = Utc::now();
+    let today = now.date_naive();
+    let mut merged = 0_u32;
+    for offset in 0..=i64::from(args.max_days_back) {
+        let day = today - ChronoDuration::days(offset);
+        if !is_day_complete(day, now, args.shift_hours) {
+            continue;
+        }
+        match merge_daily_observations(args, day) {
+            Ok(true) => merged += 1,
+            Ok(false) => {}
+            Err(err) => emit_event(
+                EventKind::ConvertFailed,
+                &format!("Daily merge failed for {day} (hourly products kept): {err:#}"),
+            ),
+        }
+    }
+    Ok(merged)
 }
 
-// rnx2crx returns exit code 2 when warnings are encountered but output is usable.
-fn run_rnx2crx_command(cmd: &mut Command, label: &str) -> Result<()> {
-    let debug = format!("{cmd:?}");
-    let output = cmd
-        .output()
-        .with_context(|| format!("spawning command failed for {label}: {debug}"))?;
+// A UTC day is complete once `now - shift_hours` has passed the following midnight,
+// i.e. the same point at which its last hour becomes eligible for hourly conversion.
+fn is_day_complete(day: NaiveDate, now: DateTime<Utc>, shift_hours: u32) -> bool {
+    let Some(next_midnight) = day.succ_opt().and_then(|next| next.and_hms_opt(0, 0, 0)) else {
+        return false;
+    };
+    now - ChronoDuration::hours(i64::from(shift_hours)) >= next_midnight.and_utc()
+}
 
-    let code = output.status.code().unwrap_or(-1);
-    if output.status.success() || code == 2 {
-        if code == 2 {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            eprintln!(
-                "{label} completed with warnings.\nstdout:\n{}\nstderr:\n{}",
-                stdout.trim(),
-                stderr.trim()
-            );
+// Merge one day's archived hourly OBS products with gfzrnx and archive the `_01D_` result.
+// Returns false when the day has no hourly products or was already merged.
+fn merge_daily_observations(args: &ConvertArgs, day: NaiveDate) -> Result<bool> {
+    let day_start = day
+        .and_hms_opt(0, 0, 0)
+        .ok_or_else(|| anyhow!("invalid day start for {day}"))?
+        .and_utc();
+    let (year, doy) = archive_bucket(&args.archive_timezone, day_start)?;
+    let daily_dir = archive_subdir(
+        &archive_root(args).join(&year).join(&doy),
+        args.obs_archive_subdir.as_deref(),
+    )?;
+    let daily_prefix = match args.rinex_style {
+        RinexStyle::Long => format!("{}.", daily_obs_product_stem(args, day_start)?),
+        RinexStyle::Short => format!("{}.", short_name_stem(args, day, DAILY_SESSION)?),
+    };
+    if list_dir_names(&daily_dir)?.iter().any(|name| {
+        let name = name.to_string_lossy();
+        name.starts_with(&daily_prefix) && !name.ends_with(".sha256")
+    }) {
+        return Ok(false);
+    }
+
+    // Hourly products can sit in two buckets when --archive-timezone is not UTC.
+    let mut hourly = Vec::new();
+    for hour in 0..24 {
+        let dt = day_start + ChronoDuration::hours(hour);
+        let (hour_year, hour_doy) = archive_bucket(&args.archive_timezone, dt)?;
+        let bucket = archive_root(args).join(hour_year).join(hour_doy);
+        let dir = archive_subdir(&bucket, args.obs_archive_subdir.as_deref())?;
+        let stem = match args.rinex_style {
+            RinexStyle::Long => format!("{}.", obs_product_stem(args, dt)?),
+            RinexStyle::Short => format!(
+                "{}.",
+                short_name_stem(args, dt.date_naive(), session_letter(dt.hour())?)?
+            ),
+        };
+        for os_name in list_dir_names(&dir)? {
+            let name = os_name.to_string_lossy();
+            let base = strip_compression_suffix(&name);
+            let is_obs = match args.rinex_style {
+                RinexStyle::Long => base.ends_with(".rnx") || base.ends_with(".crx"),
+                RinexStyle::Short => {
+                    classify_rinex2_short_kind(base) == Some(OutputKind::Observation)
+                }
+            };
+            if name.starts_with(&stem) && is_obs {
+                hourly.push(HourlyObservation {
+                    hour: dt,
+                    bucket: bucket.clone(),
+                    path: dir.join(&os_name),
+                });
+            }
         }
-        return Ok(());
+    }
+    if hourly.is_empty() {
+        return Ok(false);
+    }
+    if hourly.len() < 24 {
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "Daily merge for {day}: only {} of 24 hourly observation product(s) present; merging those",
+                hourly.len()
+            ),
+        );
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    bail!(
-        "{label} failed with status {}.\nstdout:\n{}\nstderr:\n{}",
-        output.status,
-        stdout.trim(),
-        stderr.trim()
+    let work_dir = create_conversion_workspace(&args.data_dir, day_start)?;
+    let _workspace_cleanup = WorkspaceCleanup::new(work_dir.clone());
+
+    // gfzrnx reads plain RINEX or CRINEX; strip only the gzip/zstd layer.
+    let mut inputs = Vec::with_capacity(hourly.len());
+    for product in &hourly {
+        inputs.push(decompress_into_dir(&product.path, &work_dir)?);
+    }
+
+    let daily_rnx = match args.rinex_style {
+        RinexStyle::Long => work_dir.join(format!("{daily_prefix}rnx")),
+        RinexStyle::Short => work_dir.join(format!("{daily_prefix}{}o", day_start.format("%y"))),
+    };
+    let program = args.gfzrnx_path.as_os_str();
+    let mut cmd = converter_command(args, program, &work_dir)?;
+    cmd.arg("-finp")
+        .args(&inputs)
+        .arg("-fout")
+        .arg(&daily_rnx)
+        .arg("-epo_beg")
+        .arg(day_start.format("%Y%m%d_%H%M%S").to_string())
+        .arg("-d")
+        .arg("86400")
+        .arg("-kv")
+        .arg("-f")
+        .arg("-q");
+    run_checked_command(&mut cmd, "gfzrnx daily merge")?;
+    if !file_exists_and_nonempty(&daily_rnx) {
+        bail!(
+            "gfzrnx finished but expected daily observation file was not generated: {}",
+            daily_rnx.display()
+        );
+    }
+
+    let daily_product = match args.effective_obs_output_format() {
+        ObsOutputFormat::Rinex | ObsOutputFormat::IndividualGz => {
+            compress_file(daily_rnx, args.effective_compression())?
+        }
+        ObsOutputFormat::Hatanaka => compress_file(
+            run_rnx2crx_for_observation(args, &daily_rnx)?,
+            args.effective_compression(),
+        )?,
+    };
+
+    fs::create_dir_all(&daily_dir)
+        .with_context(|| format!("creating archive path failed: {}", daily_dir.display()))?;
+    let mut archived = vec![move_into_dir(&daily_product, &daily_dir)?];
+    if args.write_checksums {
+        let sidecar = write_sha256_sidecar(&archived[0])?;
+        archived.push(sidecar);
+    }
+    if args.upload_queue
+        && let Err(err) =
+            UploadQueue::new(&args.archive_dir).append_pending(&args.archive_dir, &archived)
+    {
+        emit_event(
+            EventKind::Upload,
+            &format!("Queueing daily product for upload failed for {day}: {err:#}"),
+        );
+    }
+    if let Some(target) = args.rsync_target.as_deref() {
+        upload_archived_hour(args, target, &year, &doy, &archived);
+    }
+    emit_event(
+        EventKind::ConvertFinish,
+        &format!(
+            "Merged {} hourly observation product(s) into {}",
+            hourly.len(),
+            archived[0].display()
+        ),
     );
+
+    if !args.keep_hourly {
+        remove_merged_hourly_observations(args, &hourly)?;
+    }
+    Ok(true)
 }
 
-fn run_convbin_nav_for_hour(
+// One archived hourly OBS product picked up by the daily merge.
+struct HourlyObservation {
+    hour: DateTime<Utc>,
+    // The archive/<year>/<doy>/ bucket holding the hour's manifest and `.ready` marker.
+    bucket: PathBuf,
+    path: PathBuf,
+}
+
+// Delete merged hourly OBS products (`--daily` without `--keep-hourly`) together with every
+// record still pointing at them: their day-manifest entries, the hour's `.ready` marker, and
+// their upload-queue entries, so `verify-archive` and pollers do not chase missing files.
+fn remove_merged_hourly_observations(
     args: &ConvertArgs,
-    dt: DateTime<Utc>,
-    merged_ubx: &Path,
-    output_dir: &Path,
+    hourly: &[HourlyObservation],
 ) -> Result<()> {
-    let (program, used_path_fallback) = resolve_convbin_program(&args.convbin_path);
-    let prefix = format!(
-        "{}00{}_R_{}{:03}{}_01H",
-        args.station,
-        args.country,
-        dt.format("%Y"),
-        dt.ordinal(),
-        dt.format("%H")
-    );
-
-    match args.nav_output_format {
-        NavOutputFormat::Mixed => {
-            let nav_rnx = output_dir.join(format!("{prefix}_MN.rnx"));
-            run_convbin_nav_command(
-                args,
-                &program,
-                used_path_fallback,
-                &merged_ubx,
-                &nav_rnx,
-                &[],
-                "mixed",
+    let mut queued = Vec::with_capacity(hourly.len() * 2);
+    for product in hourly {
+        let sidecar = with_suffix(&product.path, ".sha256");
+        remove_file_if_exists(&product.path)?;
+        remove_file_if_exists(&sidecar)?;
+        if args.day_manifest {
+            remove_archived_files(
+                &product.bucket,
+                product.hour,
+                std::slice::from_ref(&product.path),
             )?;
+        }
+        remove_file_if_exists(&product.bucket.join(format!(
+            "{}.ready",
+            hour_product_prefix(args, product.hour)?
+        )))?;
+        queued.push(product.path.clone());
+        queued.push(sidecar);
+    }
 
-            if !file_exists_and_nonempty(&nav_rnx) {
-                bail!(
-                    "convbin finished but expected mixed NAV file was not generated: {}",
-                    nav_rnx.display()
-                );
-            }
-            let _ = gzip_file(nav_rnx)?;
+    if args.upload_queue
+        && let Err(err) = UploadQueue::new(&args.archive_dir).remove(&args.archive_dir, &queued)
+    {
+        emit_event(
+            EventKind::Upload,
+            &format!("Dropping merged hourly products from the upload queue failed: {err:#}"),
+        );
+    }
+    Ok(())
+}
+
+// File names in a directory; a missing directory yields an empty list.
+fn list_dir_names(dir: &Path) -> Result<Vec<OsString>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("reading directory failed: {}", dir.display()));
+        }
+    };
+
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("reading entry in {}", dir.display()))?;
+        names.push(entry.file_name());
+    }
+    names.sort();
+    Ok(names)
+}
+
+// Decompress `<name>.gz` / `<name>.zst` into `dir/<name>` and return the new path.
+// Uncompressed products are copied as-is.
+fn decompress_into_dir(path: &Path, dir: &Path) -> Result<PathBuf> {
+    let name = match path.extension().and_then(OsStr::to_str) {
+        Some("gz" | "zst") => path.file_stem(),
+        _ => path.file_name(),
+    }
+    .ok_or_else(|| anyhow!("invalid product name: {}", path.display()))?;
+    let output = dir.join(name);
+    let mut decoder = open_product_reader(path)?;
+    let mut writer = BufWriter::new(
+        File::create(&output).with_context(|| format!("creating {} failed", output.display()))?,
+    );
+    io::copy(&mut decoder, &mut writer)
+        .with_context(|| format!("decompressing {} failed", path.display()))?;
+    writer
+        .flush()
+        .with_context(|| format!("flushing {} failed", output.display()))?;
+    Ok(output)
+}
+
+// Verify required converter binaries exist and can be executed.
+pub(crate) fn ensure_converter_available(args: &ConvertArgs) -> Result<()> {
+    ensure_converter_available_with(args, converter_for(args), &mut false)
+}
+
+// `ensure_converter_available` probing an explicit backend. `sampling_checked` records whether
+// the caller already ran the sampling-rate check: `run` probes before every hour but only
+// needs the warning once per worker.
+pub(crate) fn ensure_converter_available_with(
+    args: &ConvertArgs,
+    converter: &dyn Converter,
+    sampling_checked: &mut bool,
+) -> Result<()> {
+    if args.obs_sampling_secs == 0 {
+        bail!("obs_sampling_secs must be greater than zero");
+    }
+    parse_constellations(&args.constellations)?;
+    obs_excluded_systems(args)?;
+    rinex_version(args)?;
+    approx_position_xyz(args)?;
+    parse_archive_timezone(&args.archive_timezone)?;
+    convbin_extra_args(args)?;
+    validate_name_template(&args.obs_name_template, "--obs-name-template", false)?;
+    validate_name_template(&args.nav_name_template, "--nav-name-template", true)?;
+    if matches!(
+        args.effective_obs_output_format(),
+        ObsOutputFormat::IndividualGz
+    ) {
+        validate_obs_constellation_template(&args.obs_name_template)?;
+        if args.daily {
+            bail!(
+                "--daily merges one observation product per hour and does not support --obs-output-format individual-gz"
+            );
+        }
+    }
+    if args.rinex_style == RinexStyle::Short {
+        short_product_name(args, Utc::now(), 'o')?;
+        if matches!(
+            args.effective_obs_output_format(),
+            ObsOutputFormat::IndividualGz
+        ) {
+            bail!(
+                "--rinex-style short writes one mixed observation file; use --obs-output-format rinex or hatanaka"
+            );
+        }
+    }
+    if !std::mem::replace(sampling_checked, true)
+        && let Some(warning) = sampling_rate_warning(args)
+    {
+        emit_event(EventKind::Warning, &warning);
+    }
+
+    converter.probe(args)?;
+
+    if matches!(
+        args.effective_obs_output_format(),
+        ObsOutputFormat::Hatanaka
+    ) {
+        let (program, used_path_fallback) = resolve_rnx2crx_program(&args.rnx2crx_path);
+        let mut cmd = converter_command(args, &program, &args.data_dir)?;
+        cmd.arg("-h");
+        run_checked_command(
+            &mut cmd,
+            &if used_path_fallback {
+                format!(
+                    "rnx2crx availability check (requested {} not found; used PATH lookup)",
+                    args.rnx2crx_path.display()
+                )
+            } else {
+                format!(
+                    "rnx2crx availability check ({})",
+                    args.rnx2crx_path.display()
+                )
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+// Warning when `--obs-sampling-secs` does not fit the receiver measurement interval set by the
+// `--config-file` CFG-RATE (or CFG-VALSET CFG-RATE-MEAS) line: a shorter sampling interval
+// yields epochs the receiver never measured, and a non-multiple decimates unevenly. A missing
+// or unparsable config gives no warning here; the logger reports it.
+fn sampling_rate_warning(args: &ConvertArgs) -> Option<String> {
+    let config_file = args.config_file.as_deref()?;
+    let packets = parse_ubx_config(config_file).ok()?;
+    let (measure_rate_ms, nav_rate) = configured_measurement_rate(&packets)?;
+    let sampling_ms = u64::from(args.obs_sampling_secs) * 1000;
+    let measure_rate_ms_u64 = u64::from(measure_rate_ms);
+    if measure_rate_ms == 0 || sampling_ms.is_multiple_of(measure_rate_ms_u64) {
+        return None;
+    }
+    let problem = if sampling_ms < measure_rate_ms_u64 {
+        "is shorter than"
+    } else {
+        "is not a multiple of"
+    };
+    Some(format!(
+        "--obs-sampling-secs {} {problem} the receiver measurement interval of {measure_rate_ms} ms (navRate {nav_rate}) from {}; observation epochs will be missing or unevenly decimated",
+        args.obs_sampling_secs,
+        config_file.display()
+    ))
+}
+
+// Backend that turns one hour's merged UBX file into RINEX products in the conversion
+// workspace. `process_hour` only talks to this trait; `--converter` selects the implementation.
+// Product naming, compression, and archiving stay in the shared pipeline.
+pub(crate) trait Converter: Sync {
+    // Check the backend can run with these options; called once before any hour is converted.
+    fn probe(&self, args: &ConvertArgs) -> Result<()>;
+    // Write the hour's observation product into `output_dir`; returns its epoch count.
+    fn convert_obs(
+        &self,
+        args: &ConvertArgs,
+        dt: DateTime<Utc>,
+        merged_ubx: &Path,
+        output_dir: &Path,
+    ) -> Result<usize>;
+    // Write the hour's navigation product(s) into `output_dir` per `--nav-output-format`.
+    fn convert_nav(
+        &self,
+        args: &ConvertArgs,
+        dt: DateTime<Utc>,
+        merged_ubx: &Path,
+        output_dir: &Path,
+    ) -> Result<()>;
+}
+
+// RTKLIB convbin, the default backend.
+struct ConvbinConverter;
+
+impl Converter for ConvbinConverter {
+    fn probe(&self, args: &ConvertArgs) -> Result<()> {
+        let (program, used_path_fallback) = resolve_convbin_program(&args.convbin_path);
+        let mut cmd = converter_command(args, &program, &args.data_dir)?;
+        cmd.arg("-h");
+        run_checked_command(
+            &mut cmd,
+            &if used_path_fallback {
+                format!(
+                    "convbin availability check (requested {} not found; used PATH lookup)",
+                    args.convbin_path.display()
+                )
+            } else {
+                format!(
+                    "convbin availability check ({})",
+                    args.convbin_path.display()
+                )
+            },
+        )
+    }
+
+    fn convert_obs(
+        &self,
+        args: &ConvertArgs,
+        dt: DateTime<Utc>,
+        merged_ubx: &Path,
+        output_dir: &Path,
+    ) -> Result<usize> {
+        run_convbin_obs_for_hour(args, dt, merged_ubx, output_dir)
+    }
+
+    fn convert_nav(
+        &self,
+        args: &ConvertArgs,
+        dt: DateTime<Utc>,
+        merged_ubx: &Path,
+        output_dir: &Path,
+    ) -> Result<()> {
+        run_convbin_nav_for_hour(args, dt, merged_ubx, output_dir)
+    }
+}
+
+// Backend selected by `--converter`.
+pub(crate) fn converter_for(args: &ConvertArgs) -> &'static dyn Converter {
+    match args.converter {
+        ConverterBackend::Convbin => &ConvbinConverter,
+    }
+}
+
+// Build an external converter invocation, wrapped by `--convert-sandbox` when configured.
+// `{workspace}` and `{archive}` in the prefix expand to the conversion workspace and archive root,
+// so a wrapper such as bwrap/firejail can bind only those paths writable.
+fn converter_command(args: &ConvertArgs, program: &OsStr, workspace: &Path) -> Result<Command> {
+    let Some(prefix) = args.convert_sandbox.as_deref() else {
+        return Ok(Command::new(program));
+    };
+    let tokens = split_command_line(prefix)
+        .with_context(|| format!("parsing --convert-sandbox failed: {prefix}"))?;
+    let Some((wrapper, wrapper_args)) = tokens.split_first() else {
+        return Ok(Command::new(program));
+    };
+
+    // Expanded as OsString so non-UTF-8 workspace or archive paths are bound unchanged.
+    let expand = |token: &str| {
+        let mut out = OsString::new();
+        let mut rest = token;
+        loop {
+            let next = [
+                ("{workspace}", workspace.as_os_str()),
+                ("{archive}", args.archive_dir.as_os_str()),
+            ]
+            .into_iter()
+            .filter_map(|(placeholder, value)| {
+                rest.find(placeholder).map(|at| (at, placeholder, value))
+            })
+            .min_by_key(|&(at, _, _)| at);
+            let Some((at, placeholder, value)) = next else {
+                out.push(rest);
+                return out;
+            };
+            out.push(&rest[..at]);
+            out.push(value);
+            rest = &rest[at + placeholder.len()..];
+        }
+    };
+
+    let mut cmd = Command::new(expand(wrapper));
+    for token in wrapper_args {
+        cmd.arg(expand(token));
+    }
+    cmd.arg(program);
+    Ok(cmd)
+}
+
+// convbin options that name output files or directories; product collection relies on the
+// `-o`/`-n` targets chosen here, so `--convbin-extra-args` may not set them.
+const RESERVED_CONVBIN_OPTIONS: [&str; 10] =
+    ["-o", "-n", "-g", "-h", "-q", "-l", "-b", "-i", "-s", "-d"];
+
+// Tokenize `--convbin-extra-args` with shell-style quoting and reject output-target options.
+fn convbin_extra_args(args: &ConvertArgs) -> Result<Vec<String>> {
+    let Some(raw) = args.convbin_extra_args.as_deref() else {
+        return Ok(Vec::new());
+    };
+    let tokens = split_command_line(raw)
+        .with_context(|| format!("parsing --convbin-extra-args failed: {raw}"))?;
+    if let Some(reserved) = tokens
+        .iter()
+        .find(|token| RESERVED_CONVBIN_OPTIONS.contains(&token.as_str()))
+    {
+        bail!("--convbin-extra-args may not set output option {reserved}: {raw}");
+    }
+    Ok(tokens)
+}
+
+// Resolve convbin executable path.
+// If configured absolute path is missing, fall back to PATH lookup.
+pub(crate) fn resolve_convbin_program(configured_path: &Path) -> (OsString, bool) {
+    if configured_path.exists() {
+        return (configured_path.as_os_str().to_owned(), false);
+    }
+    (OsString::from("convbin"), true)
+}
+
+// Resolve rnx2crx executable path.
+// If configured absolute path is missing, fall back to PATH lookup.
+pub(crate) fn resolve_rnx2crx_program(configured_path: &Path) -> (OsString, bool) {
+    if configured_path.exists() {
+        return (configured_path.as_os_str().to_owned(), false);
+    }
+    (OsString::from("rnx2crx"), true)
+}
+
+#[derive(Clone, Copy)]
+struct NavSystemSpec {
+    system: char,
+    suffix: &'static str,
+    exclude: &'static [char],
+}
+
+const NAV_SYSTEM_SPECS: [NavSystemSpec; 5] = [
+    NavSystemSpec {
+        system: 'G',
+        suffix: "GN",
+        exclude: &['R', 'E', 'J', 'S', 'C'],
+    },
+    NavSystemSpec {
+        system: 'R',
+        suffix: "RN",
+        exclude: &['G', 'E', 'J', 'S', 'C'],
+    },
+    NavSystemSpec {
+        system: 'E',
+        suffix: "EN",
+        exclude: &['G', 'R', 'J', 'S', 'C'],
+    },
+    NavSystemSpec {
+        system: 'C',
+        suffix: "CN",
+        exclude: &['G', 'R', 'E', 'J', 'S'],
+    },
+    NavSystemSpec {
+        system: 'J',
+        suffix: "JN",
+        exclude: &['G', 'R', 'E', 'S', 'C'],
+    },
+];
+
+// WGS84 ellipsoid used to turn `--approx-llh` into ECEF coordinates.
+const WGS84_A: f64 = 6_378_137.0;
+const WGS84_F: f64 = 1.0 / 298.257_223_563;
+
+// Geocentric radius band (meters) for a ground station, from the Dead Sea to high mountains.
+const STATION_RADIUS_RANGE_M: std::ops::RangeInclusive<f64> = 6_330_000.0..=6_400_000.0;
+
+// OBS header APPROX POSITION XYZ for convbin `-hp`, or None to let convbin derive it.
+fn approx_position_xyz(args: &ConvertArgs) -> Result<Option<[f64; 3]>> {
+    let (xyz, option) = if let Some(raw) = &args.approx_xyz {
+        (
+            parse_coordinate_triple(raw, "--approx-xyz")?,
+            "--approx-xyz",
+        )
+    } else if let Some(raw) = &args.approx_llh {
+        let [lat, lon, height] = parse_coordinate_triple(raw, "--approx-llh")?;
+        if !(-90.0..=90.0).contains(&lat) {
+            bail!("--approx-llh latitude {lat} is outside -90..90 degrees");
+        }
+        if !(-180.0..=180.0).contains(&lon) {
+            bail!("--approx-llh longitude {lon} is outside -180..180 degrees");
+        }
+        if !(-1_000.0..=10_000.0).contains(&height) {
+            bail!("--approx-llh height {height} is outside -1000..10000 meters");
+        }
+        (geodetic_to_ecef(lat, lon, height), "--approx-llh")
+    } else {
+        return Ok(None);
+    };
+
+    let radius = xyz.iter().map(|v| v * v).sum::<f64>().sqrt();
+    if !STATION_RADIUS_RANGE_M.contains(&radius) {
+        bail!(
+            "{option} puts the station {:.0} m from the Earth's center (expected {:.0}..{:.0} m)",
+            radius,
+            STATION_RADIUS_RANGE_M.start(),
+            STATION_RADIUS_RANGE_M.end()
+        );
+    }
+    Ok(Some(xyz))
+}
+
+// Parse "a,b,c" into three finite numbers.
+fn parse_coordinate_triple(raw: &str, option: &str) -> Result<[f64; 3]> {
+    let values = raw
+        .split(',')
+        .map(|part| {
+            part.trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|value| value.is_finite())
+                .with_context(|| format!("invalid number {:?} in {option} {raw:?}", part.trim()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let [a, b, c] = values[..] else {
+        bail!("{option} expects three comma-separated values, got {raw:?}");
+    };
+    Ok([a, b, c])
+}
+
+fn geodetic_to_ecef(lat_deg: f64, lon_deg: f64, height: f64) -> [f64; 3] {
+    let (lat, lon) = (lat_deg.to_radians(), lon_deg.to_radians());
+    let e2 = WGS84_F * (2.0 - WGS84_F);
+    let n = WGS84_A / (1.0 - e2 * lat.sin().powi(2)).sqrt();
+    [
+        (n + height) * lat.cos() * lon.cos(),
+        (n + height) * lat.cos() * lon.sin(),
+        (n * (1.0 - e2) + height) * lat.sin(),
+    ]
+}
+
+// RINEX 3 versions convbin can write. All of them use the long product names built here.
+const SUPPORTED_RINEX_VERSIONS: [&str; 4] = ["3.02", "3.03", "3.04", "3.05"];
+// Version written for `--rinex-style short`, whose names only exist in RINEX 2.
+const SHORT_NAME_RINEX_VERSION: &str = "2.11";
+
+// Validated `--rinex-version` for convbin's `-v`.
+fn rinex_version(args: &ConvertArgs) -> Result<&str> {
+    if args.rinex_style == RinexStyle::Short {
+        return Ok(SHORT_NAME_RINEX_VERSION);
+    }
+    let version = args.rinex_version.trim();
+    if !SUPPORTED_RINEX_VERSIONS.contains(&version) {
+        bail!(
+            "unsupported --rinex-version {version} (expected one of {})",
+            SUPPORTED_RINEX_VERSIONS.join(", ")
+        );
+    }
+    Ok(version)
+}
+
+// convbin system letters that `--constellations` may select from.
+const KNOWN_SYSTEMS: [char; 6] = ['G', 'R', 'E', 'C', 'J', 'S'];
+
+// Parse a `--constellations` selection such as "GRE" into validated system letters.
+fn parse_constellations(raw: &str) -> Result<Vec<char>> {
+    let systems = parse_system_letters(raw, "--constellations")?;
+    if systems.is_empty() {
+        bail!("--constellations must list at least one system");
+    }
+    Ok(systems)
+}
+
+// Validate convbin system letters (case-insensitive, duplicates ignored) given to `option`.
+fn parse_system_letters(raw: &str, option: &str) -> Result<Vec<char>> {
+    let mut systems = Vec::new();
+    for c in raw.trim().chars() {
+        let sys = c.to_ascii_uppercase();
+        if !KNOWN_SYSTEMS.contains(&sys) {
+            bail!(
+                "unknown constellation '{c}' in {option} (expected letters from {})",
+                KNOWN_SYSTEMS.iter().collect::<String>()
+            );
+        }
+        if !systems.contains(&sys) {
+            systems.push(sys);
+        }
+    }
+    Ok(systems)
+}
+
+// Systems convbin must exclude (`-y`) so OBS and NAV only carry the selected constellations.
+fn excluded_systems(args: &ConvertArgs) -> Result<Vec<char>> {
+    let selected = parse_constellations(&args.constellations)?;
+    Ok(KNOWN_SYSTEMS
+        .iter()
+        .copied()
+        .filter(|sys| !selected.contains(sys))
+        .collect())
+}
+
+// OBS exclusions: systems outside `--constellations` plus `--exclude-systems`.
+fn obs_excluded_systems(args: &ConvertArgs) -> Result<Vec<char>> {
+    let mut excluded = excluded_systems(args)?;
+    if let Some(raw) = args.exclude_systems.as_deref() {
+        for sys in parse_system_letters(raw, "--exclude-systems")? {
+            if !excluded.contains(&sys) {
+                excluded.push(sys);
+            }
+        }
+    }
+    if excluded.len() == KNOWN_SYSTEMS.len() {
+        bail!("--exclude-systems leaves no constellation in the observation output");
+    }
+    Ok(excluded)
+}
+
+// Systems left in a product after `excluded`, in KNOWN_SYSTEMS order (e.g. "GE").
+fn kept_systems(excluded: &[char]) -> String {
+    KNOWN_SYSTEMS
+        .iter()
+        .filter(|sys| !excluded.contains(sys))
+        .collect()
+}
+
+fn run_convbin_obs_for_hour(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    merged_ubx: &Path,
+    output_dir: &Path,
+) -> Result<usize> {
+    if args.obs_sampling_secs == 0 {
+        bail!("obs_sampling_secs must be greater than zero");
+    }
+
+    let (program, used_path_fallback) = resolve_convbin_program(&args.convbin_path);
+    let options = ObsRunOptions::resolve(args, dt, &program, used_path_fallback)?;
+    let obs_format = args.effective_obs_output_format();
+    if matches!(obs_format, ObsOutputFormat::IndividualGz) {
+        return run_convbin_obs_per_constellation(args, dt, &options, merged_ubx, output_dir);
+    }
+
+    let obs_rnx = match args.rinex_style {
+        RinexStyle::Long => output_dir.join(format!("{}.rnx", obs_product_stem(args, dt)?)),
+        RinexStyle::Short => output_dir.join(short_product_name(args, dt, 'o')?),
+    };
+    run_convbin_obs_command(
+        args,
+        &options,
+        merged_ubx,
+        &obs_rnx,
+        &obs_excluded_systems(args)?,
+        None,
+    )?;
+
+    if !file_exists_and_nonempty(&obs_rnx) {
+        bail!(
+            "convbin finished but expected observation file was not generated: {}",
+            obs_rnx.display()
+        );
+    }
+
+    let epochs = scan_obs_epochs(&obs_rnx)?;
+    if args.annotate_gaps {
+        annotate_obs_gaps(args, dt, &epochs, &obs_rnx)?;
+    }
+    generate_requested_ionex(args, dt, &obs_rnx, output_dir);
+    if args.qc {
+        write_qc_summary(args, dt, &[obs_rnx.as_path()], output_dir)?;
+    }
+
+    if matches!(obs_format, ObsOutputFormat::Hatanaka) {
+        let obs_crx = run_rnx2crx_for_observation(args, &obs_rnx)?;
+        let _ = compress_file(obs_crx, args.effective_compression())?;
+    } else {
+        let _ = compress_file(obs_rnx, args.effective_compression())?;
+    }
+
+    Ok(epochs.len())
+}
+
+// `--obs-output-format individual-gz`: one convbin OBS run per kept constellation, using the
+// same per-system exclusions as the NAV split, each `_<X>O.rnx` compressed on its own.
+// A constellation that fails or has no epochs is skipped with a warning; returns the largest
+// epoch count among the products.
+fn run_convbin_obs_per_constellation(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    options: &ObsRunOptions,
+    merged_ubx: &Path,
+    output_dir: &Path,
+) -> Result<usize> {
+    let excluded = obs_excluded_systems(args)?;
+    let mut produced = Vec::new();
+    let mut epoch_count = 0;
+
+    for spec in NAV_SYSTEM_SPECS {
+        if excluded.contains(&spec.system) {
+            continue;
+        }
+        let product_type = format!("{}O", spec.system);
+        let obs_rnx = output_dir.join(format!(
+            "{}.rnx",
+            obs_constellation_stem(args, dt, spec.system)?
+        ));
+        let label = format!("constellation {product_type}");
+        if let Err(err) = run_convbin_obs_command(
+            args,
+            options,
+            merged_ubx,
+            &obs_rnx,
+            spec.exclude,
+            Some(&label),
+        ) {
+            emit_event(
+                EventKind::Warning,
+                &format!("convbin OBS generation skipped for {product_type}: {err:#}"),
+            );
+            remove_file_if_exists(&obs_rnx)?;
+            continue;
+        }
+
+        // convbin writes a header even when the constellation was never tracked.
+        let epochs = if file_exists_and_nonempty(&obs_rnx) {
+            scan_obs_epochs(&obs_rnx)?
+        } else {
+            Vec::new()
+        };
+        if epochs.is_empty() {
+            remove_file_if_exists(&obs_rnx)?;
+            continue;
+        }
+        if args.annotate_gaps {
+            annotate_obs_gaps(args, dt, &epochs, &obs_rnx)?;
+        }
+        epoch_count = epoch_count.max(epochs.len());
+        produced.push(obs_rnx);
+    }
+
+    let Some(first) = produced.first() else {
+        bail!(
+            "no per-constellation observation files were generated for hour {}",
+            dt.format("%Y-%m-%d %H:00")
+        );
+    };
+    // IONEX is built from the first product, i.e. GPS whenever GPS was kept.
+    generate_requested_ionex(args, dt, first, output_dir);
+    if args.qc {
+        let paths: Vec<&Path> = produced.iter().map(PathBuf::as_path).collect();
+        write_qc_summary(args, dt, &paths, output_dir)?;
+    }
+    for path in produced {
+        compress_file(path, args.effective_compression())?;
+    }
+    Ok(epoch_count)
+}
+
+// What every convbin OBS run of one hour shares, resolved once: the program, the RINEX
+// version, the approximate position for the header, and the `--obs-window` bounds.
+struct ObsRunOptions<'a> {
+    program: &'a OsString,
+    used_path_fallback: bool,
+    version: &'a str,
+    position: Option<[f64; 3]>,
+    window: Option<[String; 6]>,
+}
+
+impl<'a> ObsRunOptions<'a> {
+    fn resolve(
+        args: &'a ConvertArgs,
+        dt: DateTime<Utc>,
+        program: &'a OsString,
+        used_path_fallback: bool,
+    ) -> Result<Self> {
+        Ok(Self {
+            program,
+            used_path_fallback,
+            version: rinex_version(args)?,
+            position: approx_position_xyz(args)?,
+            window: (!args.no_obs_window).then(|| obs_window_args(dt)),
+        })
+    }
+}
+
+fn run_convbin_obs_command(
+    args: &ConvertArgs,
+    options: &ObsRunOptions,
+    merged_ubx: &Path,
+    obs_rnx: &Path,
+    exclude_systems: &[char],
+    mode_label: Option<&str>,
+) -> Result<()> {
+    let workspace = obs_rnx.parent().unwrap_or_else(|| Path::new("."));
+    let mut cmd = converter_command(args, options.program, workspace)?;
+    cmd.arg("-r")
+        .arg("ubx")
+        .arg("-v")
+        .arg(options.version)
+        // Explicitly request the richest practical observation export:
+        // -od: Doppler observables, -os: signal strength observables,
+        // -oi/-ot/-ol: include iono/time/leap metadata where applicable.
+        .arg("-od")
+        .arg("-os")
+        .arg("-oi")
+        .arg("-ot")
+        .arg("-ol")
+        .arg("-ti")
+        .arg(args.obs_sampling_secs.to_string())
+        .arg("-hm")
+        .arg(format!("{}00", args.station))
+        .arg("-ho")
+        .arg(format!("{}/{}", args.observer, args.country))
+        .arg("-hr")
+        .arg(format!("NA/{}/NA", args.receiver_type))
+        .arg("-ha")
+        .arg(format!("NA/{}", args.antenna_type));
+    if let Some([x, y, z]) = options.position {
+        cmd.arg("-hp").arg(format!("{x:.4}/{y:.4}/{z:.4}"));
+    }
+    if let Some(window) = &options.window {
+        cmd.args(window);
+    }
+
+    for sys in exclude_systems {
+        cmd.arg("-y").arg(sys.to_string());
+    }
+
+    // User options go after the built-in flags (convbin keeps the last value given) and before
+    // the output target and input file.
+    cmd.args(convbin_extra_args(args)?);
+    cmd.arg("-o").arg(obs_rnx).arg(merged_ubx);
+
+    let base = match mode_label {
+        Some(mode_label) => format!("convbin observation conversion ({mode_label})"),
+        None => "convbin observation conversion".to_string(),
+    };
+    let label = if options.used_path_fallback {
+        format!(
+            "{base} (requested {} not found; used PATH lookup)",
+            args.convbin_path.display()
+        )
+    } else {
+        base
+    };
+
+    run_checked_command_with_timeout(&mut cmd, &label, converter_timeout(args))
+}
+
+// convbin `-ts`/`-te` bounds for the target hour. Merged inputs can overlap the neighbouring
+// hours (fragments, late flushes), so the window keeps exactly the `[hour, hour+1)` epochs;
+// `-te` is inclusive, hence the end 1 ms before the next hour.
+fn obs_window_args(dt: DateTime<Utc>) -> [String; 6] {
+    let end = dt + ChronoDuration::hours(1) - ChronoDuration::milliseconds(1);
+    [
+        "-ts".to_string(),
+        dt.format("%Y/%m/%d").to_string(),
+        dt.format("%H:%M:%S").to_string(),
+        "-te".to_string(),
+        end.format("%Y/%m/%d").to_string(),
+        end.format("%H:%M:%S%.3f").to_string(),
+    ]
+}
+
+// Optional IONEX artifact generation from observation RINEX; failures only warn.
+fn generate_requested_ionex(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    obs_rnx: &Path,
+    output_dir: &Path,
+) {
+    if args.output_ionex
+        && let Err(err) = generate_ionex_product(args, dt, obs_rnx, output_dir)
+    {
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "IONEX generation skipped for {}: {err:#}",
+                dt.format("%Y-%m-%d %H:00")
+            ),
+        );
+    }
+}
+
+// Document epoch gaps above `--gap-threshold-secs` as COMMENT lines in the OBS header.
+fn annotate_obs_gaps(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    epochs: &[NaiveDateTime],
+    obs_rnx: &Path,
+) -> Result<()> {
+    let window_start = dt.naive_utc();
+    let window_end = window_start + ChronoDuration::hours(1)
+        - ChronoDuration::seconds(i64::from(args.obs_sampling_secs));
+    let threshold = ChronoDuration::seconds(i64::from(args.gap_threshold_secs));
+    let gaps = find_epoch_gaps(epochs, window_start, window_end, threshold);
+    if gaps.is_empty() {
+        return Ok(());
+    }
+
+    let mut comments = vec![format!(
+        "{} DATA GAP(S) > {} S IN THIS HOUR",
+        gaps.len(),
+        args.gap_threshold_secs
+    )];
+    for gap in &gaps {
+        comments.push(format!(
+            "GAP {} - {} {}S",
+            gap.start.format("%Y/%m/%d %H:%M:%S"),
+            gap.end.format("%Y/%m/%d %H:%M:%S"),
+            gap.duration().num_seconds()
+        ));
+    }
+    insert_header_comments(obs_rnx, &comments)?;
+    emit_event(
+        EventKind::Info,
+        &format!(
+            "Annotated {} observation gap(s) in {}",
+            gaps.len(),
+            obs_rnx.display()
+        ),
+    );
+    Ok(())
+}
+
+// Name rnx2crx gives its output: `.rnx` -> `.crx`, and RINEX 2 `.yyo` -> `.yyd`.
+fn crinex_path(obs_rnx: &Path) -> PathBuf {
+    match obs_rnx.extension().and_then(OsStr::to_str) {
+        Some(ext) if ext.len() == 3 && ext.ends_with('o') => {
+            obs_rnx.with_extension(format!("{}d", &ext[..2]))
+        }
+        _ => obs_rnx.with_extension("crx"),
+    }
+}
+
+fn run_rnx2crx_for_observation(args: &ConvertArgs, obs_rnx: &Path) -> Result<PathBuf> {
+    let (program, used_path_fallback) = resolve_rnx2crx_program(&args.rnx2crx_path);
+    let obs_crx = crinex_path(obs_rnx);
+    let workspace = obs_rnx.parent().unwrap_or_else(|| Path::new("."));
+
+    let mut cmd = converter_command(args, &program, workspace)?;
+    cmd.arg(obs_rnx).arg("-f");
+
+    let label = if used_path_fallback {
+        format!(
+            "rnx2crx conversion (requested {} not found; used PATH lookup)",
+            args.rnx2crx_path.display()
+        )
+    } else {
+        "rnx2crx conversion".to_string()
+    };
+    run_rnx2crx_command(&mut cmd, &label, converter_timeout(args))?;
+
+    if !file_exists_and_nonempty(&obs_crx) {
+        bail!(
+            "rnx2crx finished but expected CRINEX file was not generated: {}",
+            obs_crx.display()
+        );
+    }
+
+    remove_file_if_exists(obs_rnx)?;
+    Ok(obs_crx)
+}
+
+fn generate_ionex_product(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    obs_rnx: &Path,
+    output_dir: &Path,
+) -> Result<PathBuf> {
+    let rinex = Rinex::from_file(obs_rnx)
+        .with_context(|| format!("parsing observation RINEX failed: {}", obs_rnx.display()))?;
+
+    let fallback_epoch = ionex_epoch_from_utc_hour(dt)?;
+    let first_epoch = rinex.first_epoch().unwrap_or(fallback_epoch);
+    let last_epoch = rinex.last_epoch().unwrap_or(first_epoch);
+
+    let (latitude_ddeg, longitude_ddeg) = rinex
+        .header
+        .rx_position
+        .and_then(ecef_to_geodetic_ddeg)
+        .map(|(lat, lon, _)| (lat, lon))
+        .unwrap_or((0.0, 0.0));
+
+    let mut record = IonexRecord::default();
+    record.insert(
+        IonexKey::from_decimal_degrees_km(first_epoch, latitude_ddeg, longitude_ddeg, 350.0),
+        IonexTec::from_tecu(0.0),
+    );
+
+    let mut header = IonexHeader::default();
+    header.program = Some("gnss2tec-logger".to_string());
+    header.run_by = Some(args.observer.clone());
+    header.date = Some(Utc::now().format("%Y%m%d %H%M%S UTC").to_string());
+    header.description = Some(
+        "Generated from OBS RINEX by gnss2tec-logger with placeholder TEC at receiver position."
+            .to_string(),
+    );
+    header.number_of_maps = 1;
+    header.epoch_of_first_map = first_epoch;
+    header.epoch_of_last_map = if last_epoch >= first_epoch {
+        last_epoch
+    } else {
+        first_epoch
+    };
+    header.sampling_period = IonexDuration::from_hours(1.0);
+    header.grid.latitude = IonexLinspace {
+        start: latitude_ddeg,
+        end: latitude_ddeg,
+        spacing: -1.0,
+    };
+    header.grid.longitude = IonexLinspace {
+        start: longitude_ddeg,
+        end: longitude_ddeg,
+        spacing: 1.0,
+    };
+    header.grid.altitude = IonexLinspace {
+        start: 350.0,
+        end: 350.0,
+        spacing: 0.0,
+    };
+    header
+        .comments
+        .push("IONEX output is optional and intended for compatibility/diagnostics.".to_string());
+
+    let ionex = IONEX::new(header, record);
+    let ionex_path = ionex_product_path(args, dt, output_dir)?;
+    ionex.to_file(&ionex_path).with_context(|| {
+        format!(
+            "writing IONEX output failed for observation file {}",
+            obs_rnx.display()
+        )
+    })?;
+    compress_file(ionex_path, args.effective_compression())
+}
+
+// `<prefix>_IO.ionex`, so custom name templates and short names apply to IONEX too.
+fn ionex_product_path(args: &ConvertArgs, dt: DateTime<Utc>, dir: &Path) -> Result<PathBuf> {
+    Ok(dir.join(format!("{}_IO.ionex", hour_product_prefix(args, dt)?)))
+}
+
+fn ionex_epoch_from_utc_hour(dt: DateTime<Utc>) -> Result<IonexEpoch> {
+    let epoch_text = dt.format("%Y-%m-%dT%H:%M:%S UTC").to_string();
+    IonexEpoch::from_str(&epoch_text)
+        .with_context(|| format!("building IONEX epoch from UTC datetime failed: {epoch_text}"))
+}
+
+fn ecef_to_geodetic_ddeg(ecef_m: (f64, f64, f64)) -> Option<(f64, f64, f64)> {
+    let (x, y, z) = ecef_m;
+    if !x.is_finite() || !y.is_finite() || !z.is_finite() {
+        return None;
+    }
+
+    const A: f64 = 6_378_137.0; // WGS84 semi-major axis [m]
+    const E2: f64 = 6.694_379_990_14e-3; // WGS84 first eccentricity squared
+    const B: f64 = 6_356_752.314_245; // WGS84 semi-minor axis [m]
+    const EP2: f64 = (A * A - B * B) / (B * B);
+
+    let p = (x * x + y * y).sqrt();
+    if p == 0.0 {
+        return None;
+    }
+
+    let theta = (z * A).atan2(p * B);
+    let sin_theta = theta.sin();
+    let cos_theta = theta.cos();
+    let lat = (z + EP2 * B * sin_theta.powi(3)).atan2(p - E2 * A * cos_theta.powi(3));
+    let lon = y.atan2(x);
+    let sin_lat = lat.sin();
+    let n = A / (1.0 - E2 * sin_lat * sin_lat).sqrt();
+    let alt_m = p / lat.cos() - n;
+
+    if !lat.is_finite() || !lon.is_finite() || !alt_m.is_finite() {
+        return None;
+    }
+
+    Some((
+        lat.to_degrees(),
+        normalize_longitude_ddeg(lon.to_degrees()),
+        alt_m / 1_000.0,
+    ))
+}
+
+fn normalize_longitude_ddeg(mut lon_ddeg: f64) -> f64 {
+    while lon_ddeg > 180.0 {
+        lon_ddeg -= 360.0;
+    }
+    while lon_ddeg <= -180.0 {
+        lon_ddeg += 360.0;
+    }
+    lon_ddeg
+}
+
+// rnx2crx returns exit code 2 when warnings are encountered but output is usable.
+fn run_rnx2crx_command(cmd: &mut Command, label: &str, timeout: Option<Duration>) -> Result<()> {
+    let output = command_output(cmd, label, timeout)?;
+
+    let code = output.status.code().unwrap_or(-1);
+    if output.status.success() || code == 2 {
+        if code == 2 {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "{label} completed with warnings.\nstdout:\n{}\nstderr:\n{}",
+                    stdout.trim(),
+                    stderr.trim()
+                ),
+            );
+        }
+        return Ok(());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    bail!(
+        "{label} failed with status {}.\nstdout:\n{}\nstderr:\n{}",
+        output.status,
+        stdout.trim(),
+        stderr.trim()
+    );
+}
+
+fn run_convbin_nav_for_hour(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    merged_ubx: &Path,
+    output_dir: &Path,
+) -> Result<()> {
+    let (program, used_path_fallback) = resolve_convbin_program(&args.convbin_path);
+    let selected = parse_constellations(&args.constellations)?;
+    if args.rinex_style == RinexStyle::Short {
+        return run_convbin_nav_short_names(
+            args,
+            dt,
+            &program,
+            used_path_fallback,
+            merged_ubx,
+            output_dir,
+            &selected,
+        );
+    }
+
+    match args.nav_output_format {
+        NavOutputFormat::Mixed => {
+            let nav_rnx = output_dir.join(format!("{}.rnx", nav_product_stem(args, dt, "MN")?));
+            let excluded = excluded_systems(args)?;
+            run_convbin_nav_command(
+                args,
+                &program,
+                used_path_fallback,
+                merged_ubx,
+                &[("-n", nav_rnx.as_path())],
+                &excluded,
+                "mixed",
+            )?;
+
+            if !file_exists_and_nonempty(&nav_rnx) {
+                bail!(
+                    "convbin finished but expected mixed NAV file was not generated: {}",
+                    nav_rnx.display()
+                );
+            }
+            let _ = compress_file(nav_rnx, args.effective_compression())?;
         }
         NavOutputFormat::IndividualTarGz => {
-            let mut produced = Vec::new();
-
-            for spec in NAV_SYSTEM_SPECS {
-                let nav_rnx = output_dir.join(format!("{prefix}_{}.rnx", spec.suffix));
-                let label = format!("constellation {}", spec.suffix);
-                if let Err(err) = run_convbin_nav_command(
-                    args,
-                    &program,
-                    used_path_fallback,
-                    &merged_ubx,
-                    &nav_rnx,
-                    spec.exclude,
-                    &label,
-                ) {
-                    eprintln!(
-                        "convbin NAV generation skipped for {}: {err:#}",
-                        spec.suffix
-                    );
-                    remove_file_if_exists(&nav_rnx)?;
-                    continue;
-                }
+            let produced = run_convbin_nav_per_constellation(
+                args,
+                dt,
+                &program,
+                used_path_fallback,
+                merged_ubx,
+                output_dir,
+                &selected,
+            )?;
+            let archive = output_dir.join(format!("{}.tar", nav_product_stem(args, dt, "NAVSET")?));
+            bundle_files_into_tar(&produced, &archive)?;
+            compress_file(archive, args.effective_compression())?;
+            for path in produced {
+                remove_file_if_exists(&path)?;
+            }
+        }
+        NavOutputFormat::IndividualGz => {
+            // Same per-constellation files, archived one by one instead of bundled.
+            let produced = run_convbin_nav_per_constellation(
+                args,
+                dt,
+                &program,
+                used_path_fallback,
+                merged_ubx,
+                output_dir,
+                &selected,
+            )?;
+            for path in produced {
+                compress_file(path, args.effective_compression())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// One convbin NAV run per selected constellation; returns the non-empty `_<X>N.rnx` files.
+// A constellation that fails or produces nothing is skipped with a warning.
+fn run_convbin_nav_per_constellation(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    program: &OsString,
+    used_path_fallback: bool,
+    merged_ubx: &Path,
+    output_dir: &Path,
+    selected: &[char],
+) -> Result<Vec<PathBuf>> {
+    let mut produced = Vec::new();
+
+    for spec in NAV_SYSTEM_SPECS {
+        if !selected.contains(&spec.system) {
+            continue;
+        }
+        let nav_rnx = output_dir.join(format!("{}.rnx", nav_product_stem(args, dt, spec.suffix)?));
+        let label = format!("constellation {}", spec.suffix);
+        if let Err(err) = run_convbin_nav_command(
+            args,
+            program,
+            used_path_fallback,
+            merged_ubx,
+            &[("-n", nav_rnx.as_path())],
+            spec.exclude,
+            &label,
+        ) {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "convbin NAV generation skipped for {}: {err:#}",
+                    spec.suffix
+                ),
+            );
+            remove_file_if_exists(&nav_rnx)?;
+            continue;
+        }
+
+        if file_exists_and_nonempty(&nav_rnx) {
+            produced.push(nav_rnx);
+        } else {
+            remove_file_if_exists(&nav_rnx)?;
+        }
+    }
+
+    if produced.is_empty() {
+        bail!(
+            "no per-constellation NAV files were generated for hour {}",
+            dt.format("%Y-%m-%d %H:00")
+        );
+    }
+    Ok(produced)
+}
+
+// RINEX 2.11 NAV files are per system; convbin option and short-name type letter for each
+// constellation `--rinex-style short` writes. BeiDou and SBAS have no RINEX 2.11 NAV file.
+const SHORT_NAME_NAV_FILES: [(char, &str, char); 4] = [
+    ('G', "-n", 'n'),
+    ('R', "-g", 'g'),
+    ('E', "-l", 'l'),
+    ('J', "-q", 'q'),
+];
+
+// `--rinex-style short`: one convbin run writing a `.yyn`/`.yyg`/`.yyl`/`.yyq` file per
+// selected constellation; empty files are dropped, and at least one must remain.
+fn run_convbin_nav_short_names(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    program: &OsString,
+    used_path_fallback: bool,
+    merged_ubx: &Path,
+    output_dir: &Path,
+    selected: &[char],
+) -> Result<()> {
+    let mut outputs = Vec::new();
+    for (system, option, letter) in SHORT_NAME_NAV_FILES {
+        if selected.contains(&system) {
+            outputs.push((
+                option,
+                output_dir.join(short_product_name(args, dt, letter)?),
+            ));
+        }
+    }
+    if outputs.is_empty() {
+        bail!(
+            "--rinex-style short has no RINEX 2 NAV file for --constellations {}",
+            args.constellations
+        );
+    }
+    let targets: Vec<(&str, &Path)> = outputs
+        .iter()
+        .map(|(option, path)| (*option, path.as_path()))
+        .collect();
+    run_convbin_nav_command(
+        args,
+        program,
+        used_path_fallback,
+        merged_ubx,
+        &targets,
+        &excluded_systems(args)?,
+        "RINEX 2",
+    )?;
+
+    let mut produced = 0;
+    for (_, path) in outputs {
+        if file_exists_and_nonempty(&path) {
+            compress_file(path, args.effective_compression())?;
+            produced += 1;
+        } else {
+            remove_file_if_exists(&path)?;
+        }
+    }
+    if produced == 0 {
+        bail!(
+            "no RINEX 2 NAV files were generated for hour {}",
+            dt.format("%Y-%m-%d %H:00")
+        );
+    }
+    Ok(())
+}
+
+// `outputs` pairs a convbin NAV option (`-n`, `-g`, ...) with its target file.
+fn run_convbin_nav_command(
+    args: &ConvertArgs,
+    program: &OsString,
+    used_path_fallback: bool,
+    merged_ubx: &Path,
+    outputs: &[(&str, &Path)],
+    exclude_systems: &[char],
+    mode_label: &str,
+) -> Result<()> {
+    let workspace = outputs
+        .first()
+        .and_then(|(_, path)| path.parent())
+        .unwrap_or_else(|| Path::new("."));
+    let mut cmd = converter_command(args, program, workspace)?;
+    cmd.arg("-r")
+        .arg("ubx")
+        .arg("-v")
+        .arg(rinex_version(args)?)
+        // Mirror metadata flags for NAV generation too.
+        .arg("-oi")
+        .arg("-ot")
+        .arg("-ol")
+        .arg("-hm")
+        .arg(format!("{}00", args.station))
+        .arg("-ho")
+        .arg(format!("{}/{}", args.observer, args.country))
+        .arg("-hr")
+        .arg(format!("NA/{}/NA", args.receiver_type))
+        .arg("-ha")
+        .arg(format!("NA/{}", args.antenna_type));
+
+    for sys in exclude_systems {
+        cmd.arg("-y").arg(sys.to_string());
+    }
+
+    cmd.args(convbin_extra_args(args)?);
+    for (option, path) in outputs {
+        cmd.arg(option).arg(path);
+    }
+    cmd.arg(merged_ubx);
+
+    let label = if used_path_fallback {
+        format!(
+            "convbin navigation conversion ({mode_label}, requested {} not found; used PATH lookup)",
+            args.convbin_path.display()
+        )
+    } else {
+        format!("convbin navigation conversion ({mode_label})")
+    };
+
+    run_checked_command_with_timeout(&mut cmd, &label, converter_timeout(args))
+}
+
+// Placeholders understood by `--obs-name-template`/`--nav-name-template`.
+const NAME_TEMPLATE_PLACEHOLDERS: [&str; 8] = [
+    "station", "country", "year", "doy", "hour", "sampling", "type", "systems",
+];
+
+// Reject unknown placeholders and templates that could collide across hours or products.
+// NAV templates must end in `_{type}` because product collection classifies NAV files by their
+// `_MN.` / `_<X>N.` / `_NAVSET.tar` suffix.
+fn validate_name_template(template: &str, option: &str, is_nav: bool) -> Result<()> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            bail!("{option} has an unterminated placeholder: {template}");
+        };
+        let name = &rest[start + 1..start + len];
+        if !NAME_TEMPLATE_PLACEHOLDERS.contains(&name) {
+            bail!("{option} uses unknown placeholder {{{name}}}: {template}");
+        }
+        rest = &rest[start + len + 1..];
+    }
+    if template.contains('/') {
+        bail!("{option} must be a file name, not a path: {template}");
+    }
+    for required in ["{year}", "{doy}", "{hour}"] {
+        if !template.contains(required) {
+            bail!("{option} must contain {required} so hourly products do not collide: {template}");
+        }
+    }
+    if is_nav && !template.ends_with("_{type}") {
+        bail!("{option} must end with _{{type}} so NAV products are recognized: {template}");
+    }
+    Ok(())
+}
+
+// Per-constellation OBS products share one template, so it must vary with the constellation;
+// otherwise every run would overwrite the same `.rnx`.
+fn validate_obs_constellation_template(template: &str) -> Result<()> {
+    if !template.contains("{type}") && !template.contains("{systems}") {
+        bail!(
+            "--obs-output-format individual-gz needs {{type}} or {{systems}} in --obs-name-template so constellations do not collide: {template}"
+        );
+    }
+    Ok(())
+}
+
+// Expand a product name template for one UTC hour.
+fn expand_name_template(
+    args: &ConvertArgs,
+    template: &str,
+    dt: DateTime<Utc>,
+    product_type: &str,
+    systems: &str,
+) -> String {
+    template
+        .replace("{station}", &args.station)
+        .replace("{country}", &args.country)
+        .replace("{year}", &dt.format("%Y").to_string())
+        .replace("{doy}", &format!("{:03}", dt.ordinal()))
+        .replace("{hour}", &dt.format("%H").to_string())
+        .replace(
+            "{sampling}",
+            &sampling_token_from_seconds(args.obs_sampling_secs),
+        )
+        .replace("{type}", product_type)
+        .replace("{systems}", systems)
+}
+
+// Observation product name without extension, from `--obs-name-template`.
+fn obs_product_stem(args: &ConvertArgs, dt: DateTime<Utc>) -> Result<String> {
+    validate_name_template(&args.obs_name_template, "--obs-name-template", false)?;
+    Ok(expand_name_template(
+        args,
+        &args.obs_name_template,
+        dt,
+        "MO",
+        &kept_systems(&obs_excluded_systems(args)?),
+    ))
+}
+
+// Daily observation product name without extension for `--daily`: the OBS template with
+// `{hour}` as `0000` and the `_01H` period as `_01D`, so the default stays
+// `<station>_R_<YYYY><DOY>0000_01D_<sampling>_MO`.
+fn daily_obs_product_stem(args: &ConvertArgs, day_start: DateTime<Utc>) -> Result<String> {
+    validate_name_template(&args.obs_name_template, "--obs-name-template", false)?;
+    let template = args
+        .obs_name_template
+        .replace("{hour}", "0000")
+        .replace("_01H", "_01D");
+    Ok(expand_name_template(
+        args,
+        &template,
+        day_start,
+        "MO",
+        &kept_systems(&obs_excluded_systems(args)?),
+    ))
+}
+
+// Per-constellation observation product name (`GO`, `RO`, ...) for
+// `--obs-output-format individual-gz`.
+fn obs_constellation_stem(args: &ConvertArgs, dt: DateTime<Utc>, system: char) -> Result<String> {
+    validate_name_template(&args.obs_name_template, "--obs-name-template", false)?;
+    validate_obs_constellation_template(&args.obs_name_template)?;
+    Ok(expand_name_template(
+        args,
+        &args.obs_name_template,
+        dt,
+        &format!("{system}O"),
+        &system.to_string(),
+    ))
+}
+
+// NAV product name without extension (`MN`, a per-constellation suffix, or `NAVSET`).
+fn nav_product_stem(args: &ConvertArgs, dt: DateTime<Utc>, product_type: &str) -> Result<String> {
+    validate_name_template(&args.nav_name_template, "--nav-name-template", true)?;
+    Ok(expand_name_template(
+        args,
+        &args.nav_name_template,
+        dt,
+        product_type,
+        &kept_systems(&excluded_systems(args)?),
+    ))
+}
+
+// RINEX 2 session field of a whole-day file; hourly files use `a`..`x` (see `session_letter`).
+const DAILY_SESSION: char = '0';
+
+// RINEX 2 session letter of an hourly file: `a` for 00 UTC through `x` for 23 UTC, so the 24
+// hours of a day and its daily `0` file never share a name.
+fn session_letter(hour: u32) -> Result<char> {
+    if hour > 23 {
+        bail!("hour {hour} has no RINEX 2 session letter (expected 0-23)");
+    }
+    Ok(char::from(b'a' + hour as u8))
+}
+
+// RINEX 2 short name without extension, `ssssddds`: 4-character station, day of year, session.
+fn short_name_stem(args: &ConvertArgs, day: NaiveDate, session: char) -> Result<String> {
+    if args.station.len() != 4 || !args.station.chars().all(|c| c.is_ascii_alphanumeric()) {
+        bail!(
+            "--rinex-style short needs a 4-character alphanumeric --station, got {:?}",
+            args.station
+        );
+    }
+    Ok(format!("{}{:03}{session}", args.station, day.ordinal()))
+}
+
+// RINEX 2 short name of an hourly product, `ssssdddh.yyt`, with `t` the file type letter.
+fn short_product_name(args: &ConvertArgs, dt: DateTime<Utc>, type_letter: char) -> Result<String> {
+    Ok(format!(
+        "{}.{}{type_letter}",
+        short_name_stem(args, dt.date_naive(), session_letter(dt.hour())?)?,
+        dt.format("%y")
+    ))
+}
+
+// Prefix shared by all products of one station hour (without product type): the NAV template
+// minus its `_{type}` suffix, or the RINEX 2 `ssssdddh` stem with `--rinex-style short`.
+pub(crate) fn hour_product_prefix(args: &ConvertArgs, dt: DateTime<Utc>) -> Result<String> {
+    match args.rinex_style {
+        RinexStyle::Long => {
+            validate_name_template(&args.nav_name_template, "--nav-name-template", true)?;
+            let template = args
+                .nav_name_template
+                .strip_suffix("_{type}")
+                .unwrap_or(&args.nav_name_template);
+            Ok(expand_name_template(
+                args,
+                template,
+                dt,
+                "",
+                &kept_systems(&excluded_systems(args)?),
+            ))
+        }
+        RinexStyle::Short => short_name_stem(args, dt.date_naive(), session_letter(dt.hour())?),
+    }
+}
+
+// Write a small descriptor pairing the hour's OBS product with its NAV product(s) for TEC tooling.
+// Only written when both kinds were produced; names are relative to the archive day directory.
+fn write_tec_sidecar(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    outputs: &[PathBuf],
+    work_dir: &Path,
+) -> Result<Option<PathBuf>> {
+    let mut obs = None;
+    let mut nav = Vec::new();
+    for path in outputs {
+        let Some(name) = lossy_file_name(path) else {
+            continue;
+        };
+        match classify_output_name(&name) {
+            OutputKind::Observation if obs.is_none() => obs = Some((path, name.to_string())),
+            OutputKind::Navigation => nav.push(name.to_string()),
+            _ => {}
+        }
+    }
+
+    let Some((obs_path, obs_name)) = obs else {
+        return Ok(None);
+    };
+    if nav.is_empty() {
+        return Ok(None);
+    }
+
+    let constellations = read_obs_header_systems(obs_path)?;
+    let sidecar = json!({
+        "hour": dt.format("%Y-%m-%dT%H:00:00Z").to_string(),
+        "station": format!("{}00{}", args.station, args.country),
+        "obs": obs_name,
+        "nav": nav,
+        "sampling_interval_secs": args.obs_sampling_secs,
+        "constellations": constellations,
+        "approx_position_xyz": approx_position_xyz(args)?,
+    });
+
+    let path = work_dir.join(format!("{}.tec.json", hour_product_prefix(args, dt)?));
+    let file = File::create(&path)
+        .with_context(|| format!("creating TEC sidecar failed: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, &sidecar)
+        .with_context(|| format!("writing TEC sidecar failed: {}", path.display()))?;
+    writer
+        .write_all(b"\n")
+        .and_then(|()| writer.flush())
+        .with_context(|| format!("flushing TEC sidecar failed: {}", path.display()))?;
+    Ok(Some(path))
+}
+
+fn qc_summary_path(args: &ConvertArgs, dt: DateTime<Utc>, dir: &Path) -> Result<PathBuf> {
+    Ok(dir.join(format!("{}.qc.json", hour_product_prefix(args, dt)?)))
+}
+
+// `--qc`: summarize the hour's plain OBS file(s) into `<prefix>.qc.json` before compression
+// (Hatanaka output cannot be read back without crx2rnx). Per-constellation products are
+// merged into one summary.
+fn write_qc_summary(
+    args: &ConvertArgs,
+    dt: DateTime<Utc>,
+    obs_files: &[&Path],
+    output_dir: &Path,
+) -> Result<()> {
+    let mut qc = ObsQc::default();
+    for path in obs_files {
+        qc.merge(summarize_obs(path)?);
+    }
+    let expected_epochs = 3600 / u64::from(args.obs_sampling_secs.max(1));
+    let epochs = qc.epochs.len() as u64;
+    let epoch_percent = epochs as f64 * 100.0 / expected_epochs as f64;
+    let observation_percent = qc.observation_completeness_percent();
+    let round = |percent: f64| (percent * 10.0).round() / 10.0;
+    let counts: serde_json::Map<String, serde_json::Value> = qc
+        .satellites
+        .iter()
+        .map(|(system, satellites)| (system.to_string(), json!(satellites.len())))
+        .collect();
+    let ids: serde_json::Map<String, serde_json::Value> = qc
+        .satellites
+        .iter()
+        .map(|(system, satellites)| (system.to_string(), json!(satellites)))
+        .collect();
+    let format_epoch = |epoch: Option<&NaiveDateTime>| {
+        epoch.map(|epoch| epoch.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string())
+    };
+    let summary = json!({
+        "hour": dt.format("%Y-%m-%dT%H:00:00Z").to_string(),
+        "station": format!("{}00{}", args.station, args.country),
+        "sampling_interval_secs": args.obs_sampling_secs,
+        "epochs": epochs,
+        "expected_epochs": expected_epochs,
+        "epoch_completeness_percent": round(epoch_percent),
+        "first_epoch": format_epoch(qc.epochs.first()),
+        "last_epoch": format_epoch(qc.epochs.last()),
+        "observations_expected": qc.observations_expected,
+        "observations_present": qc.observations_present,
+        "observation_completeness_percent": round(observation_percent),
+        "satellites": counts,
+        "satellite_ids": ids,
+    });
+
+    let path = qc_summary_path(args, dt, output_dir)?;
+    let mut contents = serde_json::to_vec_pretty(&summary).context("encoding QC summary failed")?;
+    contents.push(b'\n');
+    fs::write(&path, contents)
+        .with_context(|| format!("writing QC summary failed: {}", path.display()))?;
+
+    let systems = qc
+        .satellites
+        .iter()
+        .map(|(system, satellites)| format!("{system}:{}", satellites.len()))
+        .collect::<Vec<_>>()
+        .join(" ");
+    emit_event(
+        EventKind::Info,
+        &format!(
+            "QC {}: {epochs}/{expected_epochs} epochs ({epoch_percent:.1}%), observations {observation_percent:.1}% complete, satellites {}",
+            dt.format("%Y-%m-%d %H:00"),
+            if systems.is_empty() {
+                "none"
+            } else {
+                systems.as_str()
+            }
+        ),
+    );
+    Ok(())
+}
+
+// Read constellation letters from `SYS / # / OBS TYPES` header records of an OBS product.
+fn read_obs_header_systems(path: &Path) -> Result<Vec<String>> {
+    let reader = open_product_reader(path)?;
+
+    let mut systems = Vec::new();
+    for line in BufReader::new(reader).lines() {
+        let line =
+            line.with_context(|| format!("reading observation header failed: {}", path.display()))?;
+        let label = line.get(60..).unwrap_or("").trim();
+        if label == "END OF HEADER" {
+            break;
+        }
+        if label != "SYS / # / OBS TYPES" {
+            continue;
+        }
+        // Continuation records leave the system column blank.
+        if let Some(sys) = line.chars().next().filter(|c| c.is_ascii_alphabetic()) {
+            let sys = sys.to_string();
+            if !systems.contains(&sys) {
+                systems.push(sys);
+            }
+        }
+    }
+    Ok(systems)
+}
+
+fn file_exists_and_nonempty(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(meta) => meta.is_file() && meta.len() > 0,
+        Err(_) => false,
+    }
+}
+
+// Merge one hour of UBX fragments into a single converter input.
+// Compressed `.ubx.gz` fragments are decoded on the fly; a fragment truncated by a crash keeps
+// everything up to its last sync flush. Each fragment is scanned for checksum-valid UBX frames
+// on the way through; one with none (empty, truncated, noise) is dropped from the merge.
+fn concat_ubx_files(inputs: &[PathBuf], output: &Path) -> Result<MergeStats> {
+    let merge_started = Instant::now();
+    let mut merged = File::create(output).with_context(|| {
+        format!(
+            "creating temporary UBX merge file failed: {}",
+            output.display()
+        )
+    })?;
+
+    let mut stats = MergeStats::default();
+    for input in inputs {
+        let (frames, bytes, framed_bytes) = if is_compressed_input(input) {
+            append_compressed_ubx_input(input, &mut merged, output)?
+        } else {
+            append_raw_ubx_input(input, &mut merged)?
+        };
+
+        if frames == 0 {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "Skipping UBX input with no valid UBX frames ({} bytes): {}",
+                    bytes,
+                    input.display()
+                ),
+            );
+            stats.skipped_files += 1;
+            continue;
+        }
+        emit_event(
+            EventKind::Info,
+            &format!(
+                "UBX input {}: {} valid frame(s), {} byte(s) outside frames",
+                input.display(),
+                frames,
+                bytes - framed_bytes
+            ),
+        );
+        stats.merged_files += 1;
+        stats.frames += frames;
+        stats.stray_bytes += bytes - framed_bytes;
+    }
+    if stats.merged_files == 0 {
+        bail!(
+            "none of the {} UBX input file(s) contain valid UBX frames",
+            inputs.len()
+        );
+    }
+    stats.elapsed = merge_started.elapsed();
+    Ok(stats)
+}
+
+fn is_compressed_input(input: &Path) -> bool {
+    lossy_file_name(input).is_some_and(|name| is_compressed_log_name(&name))
+}
+
+// Scan a raw UBX input for valid frames, then rewind the same handle and append it
+// file-to-file, so each fragment is opened once and the second read comes from the page cache.
+// `io::copy` between two `File`s lets std use `copy_file_range`/`sendfile` on Linux, so
+// same-filesystem merges stay in the kernel; other platforms and cross-device copies fall back
+// to a buffered userspace copy. Noise-only inputs are rejected by the scan and never reach the
+// merge file. Returns (valid frames, input bytes, bytes inside valid frames).
+// Generic over the sink so tests can force the userspace copy: std only picks the kernel path
+// when the sink is a `File`.
+fn append_raw_ubx_input<W: Write>(input: &Path, merged: &mut W) -> Result<(u64, u64, u64)> {
+    let mut file = File::open(input)
+        .with_context(|| format!("opening UBX input failed: {}", input.display()))?;
+    let mut sink = io::sink();
+    let mut counter = FrameCountingWriter::new(&mut sink);
+    io::copy(&mut BufReader::new(&mut file), &mut counter)
+        .with_context(|| format!("scanning UBX input failed: {}", input.display()))?;
+    let counts = (counter.frames, counter.bytes, counter.framed_bytes);
+    if counts.0 == 0 {
+        return Ok(counts);
+    }
+
+    file.seek(SeekFrom::Start(0))
+        .with_context(|| format!("rewinding UBX input failed: {}", input.display()))?;
+    io::copy(&mut file, merged).with_context(|| {
+        format!(
+            "appending UBX input into temporary merge file failed: {}",
+            input.display()
+        )
+    })?;
+    Ok(counts)
+}
+
+// Decompress a gzip UBX input into the merge file while counting frames. A noise-only input
+// has already been written by the time it is recognized, so the merge file is truncated back.
+fn append_compressed_ubx_input(
+    input: &Path,
+    merged: &mut File,
+    output: &Path,
+) -> Result<(u64, u64, u64)> {
+    let start = merged
+        .stream_position()
+        .with_context(|| format!("positioning merge file failed: {}", output.display()))?;
+    let file = File::open(input)
+        .with_context(|| format!("opening UBX input failed: {}", input.display()))?;
+    let mut writer = BufWriter::new(&mut *merged);
+    let mut counter = FrameCountingWriter::new(&mut writer);
+    let mut decoder = MultiGzDecoder::new(BufReader::new(file));
+    match io::copy(&mut decoder, &mut counter) {
+        Ok(_) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "Compressed UBX input is truncated; using readable prefix: {}",
+                    input.display()
+                ),
+            );
+        }
+        Err(err) => {
+            return Err(err).with_context(|| {
+                format!(
+                    "decompressing UBX input into temporary merge file failed: {}",
+                    input.display()
+                )
+            });
+        }
+    }
+    let counts = (counter.frames, counter.bytes, counter.framed_bytes);
+    writer.flush().with_context(|| {
+        format!(
+            "flushing temporary UBX merge file failed: {}",
+            output.display()
+        )
+    })?;
+    drop(writer);
+
+    if counts.0 == 0 {
+        // Truncated or noise-only input: keep it away from the converter.
+        merged.set_len(start).with_context(|| {
+            format!(
+                "truncating temporary UBX merge file failed: {}",
+                output.display()
+            )
+        })?;
+        merged
+            .seek(SeekFrom::Start(start))
+            .with_context(|| format!("positioning merge file failed: {}", output.display()))?;
+    }
+    Ok(counts)
+}
+
+// Per-hour totals from the pre-conversion UBX sanity scan.
+#[derive(Clone, Copy, Debug, Default)]
+struct MergeStats {
+    merged_files: u32,
+    skipped_files: u32,
+    frames: u64,
+    stray_bytes: u64,
+    elapsed: Duration,
+}
+
+// Pass-through writer that counts checksum-valid UBX frames in the merged bytes.
+struct FrameCountingWriter<'a, W: Write> {
+    inner: &'a mut W,
+    scanner: FrameScanner,
+    scanned: Vec<Frame>,
+    frames: u64,
+    bytes: u64,
+    framed_bytes: u64,
+}
+
+impl<'a, W: Write> FrameCountingWriter<'a, W> {
+    fn new(inner: &'a mut W) -> Self {
+        Self {
+            inner,
+            scanner: FrameScanner::new(),
+            scanned: Vec::new(),
+            frames: 0,
+            bytes: 0,
+            framed_bytes: 0,
+        }
+    }
+}
+
+impl<W: Write> Write for FrameCountingWriter<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes += written as u64;
+        self.scanner.push_bytes(&buf[..written], &mut self.scanned);
+        for frame in self.scanned.drain(..) {
+            self.frames += 1;
+            self.framed_bytes += frame.payload.len() as u64 + 8;
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+// Compress a finished product in place and return the archived path.
+// `none` keeps the plain file; gzip and zstd append `.gz` / `.zst`.
+fn compress_file(path: PathBuf, compression: ProductCompression) -> Result<PathBuf> {
+    let compressed_path = match compression {
+        ProductCompression::None => return Ok(path),
+        ProductCompression::Gzip => with_suffix(&path, ".gz"),
+        ProductCompression::Zstd => with_suffix(&path, ".zst"),
+    };
+    let mut input = BufReader::new(
+        File::open(&path)
+            .with_context(|| format!("opening file for compression failed: {}", path.display()))?,
+    );
+    let out_file = File::create(&compressed_path).with_context(|| {
+        format!(
+            "creating compressed output failed: {}",
+            compressed_path.display()
+        )
+    })?;
+    let writer = BufWriter::new(out_file);
+    let mut writer = if compression == ProductCompression::Zstd {
+        let mut encoder = zstd::stream::write::Encoder::new(writer, 0).with_context(|| {
+            format!("starting zstd output failed: {}", compressed_path.display())
+        })?;
+        io::copy(&mut input, &mut encoder)
+            .with_context(|| format!("zstd compression failed: {}", path.display()))?;
+        encoder.finish().with_context(|| {
+            format!(
+                "finalizing zstd output failed: {}",
+                compressed_path.display()
+            )
+        })?
+    } else {
+        let mut encoder = GzEncoder::new(writer, Compression::default());
+        io::copy(&mut input, &mut encoder)
+            .with_context(|| format!("gzip compression failed: {}", path.display()))?;
+        encoder.finish().with_context(|| {
+            format!(
+                "finalizing gzip output failed: {}",
+                compressed_path.display()
+            )
+        })?
+    };
+    writer.flush().with_context(|| {
+        format!(
+            "flushing compressed output failed: {}",
+            compressed_path.display()
+        )
+    })?;
+    remove_file_if_exists(&path)?;
+    Ok(compressed_path)
+}
+
+// Reader over a product's content, decoding `.gz` and `.zst` by extension.
+fn open_product_reader(path: &Path) -> Result<Box<dyn Read>> {
+    let file =
+        File::open(path).with_context(|| format!("opening product failed: {}", path.display()))?;
+    let reader = BufReader::new(file);
+    Ok(match path.extension().and_then(OsStr::to_str) {
+        Some("gz") => Box::new(MultiGzDecoder::new(reader)),
+        Some("zst") => Box::new(
+            zstd::stream::read::Decoder::with_buffer(reader)
+                .with_context(|| format!("opening zstd product failed: {}", path.display()))?,
+        ),
+        _ => Box::new(reader),
+    })
+}
+
+// Product name without a trailing `.gz` / `.zst` compression suffix.
+fn strip_compression_suffix(name: &str) -> &str {
+    name.strip_suffix(".gz")
+        .or_else(|| name.strip_suffix(".zst"))
+        .unwrap_or(name)
+}
+
+fn sampling_token_from_seconds(seconds: u32) -> String {
+    if seconds < 100 {
+        format!("{seconds:02}S")
+    } else {
+        format!("{seconds}S")
+    }
+}
+
+// Bundle NAV files into a plain tar; `compress_file` applies the product compression afterwards.
+fn bundle_files_into_tar(files: &[PathBuf], archive_path: &Path) -> Result<()> {
+    let out = File::create(archive_path).with_context(|| {
+        format!(
+            "creating navigation archive failed: {}",
+            archive_path.display()
+        )
+    })?;
+    let writer = BufWriter::new(out);
+    let mut tar = Builder::new(writer);
+
+    for path in files {
+        let Some(name) = path.file_name() else {
+            bail!("missing file name for NAV file: {}", path.display());
+        };
+        tar.append_path_with_name(path, Path::new(name))
+            .with_context(|| {
+                format!("adding NAV file to tar archive failed: {}", path.display())
+            })?;
+    }
+
+    let mut writer = tar
+        .into_inner()
+        .with_context(|| format!("finalizing tar stream failed: {}", archive_path.display()))?;
+    writer
+        .flush()
+        .with_context(|| format!("flushing archive failed: {}", archive_path.display()))?;
+    Ok(())
+}
+
+// Collect final output product files in one directory.
+fn collect_output_products_in_dir(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut outputs = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+        if !entry
+            .file_type()
+            .with_context(|| format!("reading metadata for {}", entry.path().display()))?
+            .is_file()
+        {
+            continue;
+        }
+
+        let path = entry.path();
+        let Some(name) = lossy_file_name(&path) else {
+            continue;
+        };
+        if is_output_product_name(&name) {
+            outputs.push(path);
+        }
+    }
+    outputs.sort();
+    Ok(outputs)
+}
+
+// Validate required products were created.
+fn validate_hour_outputs(outputs: &[PathBuf], skip_nav: bool, label: &str) -> Result<()> {
+    let mut has_obs = false;
+    let mut has_nav = false;
+    let mut names = Vec::new();
+
+    for path in outputs {
+        let Some(name) = lossy_file_name(path) else {
+            continue;
+        };
+        match classify_output_name(&name) {
+            OutputKind::Observation => has_obs = true,
+            OutputKind::Navigation => has_nav = true,
+            OutputKind::Ionex => {}
+            OutputKind::Other => {}
+        }
+        names.push(name.into_owned());
+    }
+
+    if !has_obs {
+        bail!(
+            "no observation product generated for {label}; collected outputs: {}",
+            names.join(", ")
+        );
+    }
+
+    if !skip_nav && !has_nav {
+        bail!(
+            "no navigation product generated for {label}; collected outputs: {}",
+            names.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+// True if file is one of the final products we archive.
+fn is_output_product_name(name: &str) -> bool {
+    !matches!(classify_output_name(name), OutputKind::Other)
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum OutputKind {
+    Observation,
+    Navigation,
+    Ionex,
+    Other,
+}
+
+// Identify product kind across multiple RINEX naming styles.
+fn classify_output_name(name: &str) -> OutputKind {
+    let lower = name.to_ascii_lowercase();
+    let base = strip_compression_suffix(&lower);
+
+    if base.contains("_navset.") && base.ends_with(".tar") {
+        return OutputKind::Navigation;
+    }
+
+    if base.ends_with(".ionex") {
+        return OutputKind::Ionex;
+    }
+
+    // RINEX v3 long names.
+    if lower.contains("_mn.") {
+        return OutputKind::Navigation;
+    }
+    // Per-constellation NAV files archived individually (`_GN.`, `_RN.`, ...).
+    if NAV_SYSTEM_SPECS
+        .iter()
+        .any(|spec| lower.contains(&format!("_{}.", spec.suffix.to_ascii_lowercase())))
+    {
+        return OutputKind::Navigation;
+    }
+    if lower.contains("_mo.") {
+        return OutputKind::Observation;
+    }
+    // Per-constellation OBS files (`_GO.`, `_RO.`, ...).
+    if NAV_SYSTEM_SPECS
+        .iter()
+        .any(|spec| lower.contains(&format!("_{}o.", spec.system.to_ascii_lowercase())))
+    {
+        return OutputKind::Observation;
+    }
+
+    // Compression driven extension style.
+    if base.ends_with(".crx") {
+        return OutputKind::Observation;
+    }
+    if base.ends_with(".rnx") {
+        // If kind is ambiguous, treat as observation to avoid false-negative failures.
+        return OutputKind::Observation;
+    }
+
+    // RINEX v2 short names (e.g. ".26o", ".26d", ".26n"), optionally gzip/zstd-compressed.
+    classify_rinex2_short_kind(base).unwrap_or(OutputKind::Other)
+}
+
+fn classify_rinex2_short_kind(trimmed: &str) -> Option<OutputKind> {
+    let ext = trimmed.rsplit('.').next()?;
+    // Short-name extensions are two year digits plus a type letter (e.g. "26o").
+    if ext.len() != 3 || !ext[..2].chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let kind = ext.chars().last()?;
+    match kind {
+        'o' | 'd' => Some(OutputKind::Observation),
+        'n' | 'g' | 'l' | 'p' | 'q' => Some(OutputKind::Navigation),
+        _ => None,
+    }
+}
+
+#[derive(Clone)]
+struct ProductSnapshot {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+fn snapshot_output_products(dir: &Path) -> Result<Vec<ProductSnapshot>> {
+    let mut out = Vec::new();
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+        if !entry
+            .file_type()
+            .with_context(|| format!("reading metadata for {}", entry.path().display()))?
+            .is_file()
+        {
+            continue;
+        }
+
+        let path = entry.path();
+        let Some(name) = lossy_file_name(&path) else {
+            continue;
+        };
+        if !is_output_product_name(&name) {
+            continue;
+        }
+
+        let meta = fs::metadata(&path)
+            .with_context(|| format!("reading metadata failed: {}", path.display()))?;
+        out.push(ProductSnapshot {
+            path,
+            modified: meta.modified().ok(),
+            len: meta.len(),
+        });
+    }
+    out.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(out)
+}
+
+fn collect_changed_output_products(
+    before: &[ProductSnapshot],
+    after: &[ProductSnapshot],
+) -> Vec<PathBuf> {
+    let mut before_map = HashMap::new();
+    for snap in before {
+        before_map.insert(snap.path.clone(), (snap.modified, snap.len));
+    }
+
+    let mut changed = Vec::new();
+    for snap in after {
+        match before_map.get(&snap.path) {
+            None => changed.push(snap.path.clone()),
+            Some((prev_modified, prev_len))
+                if prev_modified != &snap.modified || prev_len != &snap.len =>
+            {
+                changed.push(snap.path.clone());
+            }
+            _ => {}
+        }
+    }
+    changed.sort();
+    changed
+}
+
+// Some converter outputs can emit long-name epoch tokens with HHMM fixed to 0000.
+// Normalize those product names to the target conversion hour to avoid archive collisions.
+fn normalize_long_output_names_for_target_hour(
+    outputs: &mut [PathBuf],
+    dt: DateTime<Utc>,
+) -> Result<()> {
+    let target_epoch = format!(
+        "{}{:03}{}00",
+        dt.format("%Y"),
+        dt.ordinal(),
+        dt.format("%H")
+    );
+
+    for path in outputs.iter_mut() {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some(normalized_name) = rewrite_long_name_epoch(file_name, &target_epoch) else {
+            continue;
+        };
+
+        let parent = path
+            .parent()
+            .ok_or_else(|| anyhow!("missing parent directory for {}", path.display()))?;
+        let destination = unique_destination_path(parent, OsStr::new(&normalized_name));
+        let source = path.clone();
+
+        fs::rename(&source, &destination).with_context(|| {
+            format!(
+                "renaming output product failed: {} -> {}",
+                source.display(),
+                destination.display()
+            )
+        })?;
+        *path = destination;
+    }
+
+    outputs.sort();
+    Ok(())
+}
+
+// Rewrite long-name `_R_YYYYDOYHHMM_` epoch segment to a specific hour.
+fn rewrite_long_name_epoch(file_name: &str, target_epoch: &str) -> Option<String> {
+    let marker = "_R_";
+    let start = file_name.find(marker)? + marker.len();
+    let remaining = &file_name[start..];
+    let epoch_end_rel = remaining.find('_')?;
+    let epoch = &remaining[..epoch_end_rel];
+
+    if epoch.len() != 11 || !epoch.chars().all(|c| c.is_ascii_digit()) || epoch == target_epoch {
+        return None;
+    }
+
+    let mut rewritten = String::with_capacity(file_name.len());
+    rewritten.push_str(&file_name[..start]);
+    rewritten.push_str(target_epoch);
+    rewritten.push_str(&remaining[epoch_end_rel..]);
+    Some(rewritten)
+}
+
+fn create_conversion_workspace(data_dir: &Path, dt: DateTime<Utc>) -> Result<PathBuf> {
+    let base = data_dir.join(".convert-work");
+    fs::create_dir_all(&base)
+        .with_context(|| format!("creating conversion workspace failed: {}", base.display()))?;
+    let name = format!(
+        "{}_{}_{}",
+        dt.format("%Y%m%d_%H"),
+        std::process::id(),
+        Utc::now().timestamp_nanos_opt().unwrap_or_default()
+    );
+    let path = base.join(name);
+    fs::create_dir_all(&path)
+        .with_context(|| format!("creating hour workspace failed: {}", path.display()))?;
+    Ok(path)
+}
+
+struct WorkspaceCleanup {
+    path: PathBuf,
+}
+
+impl WorkspaceCleanup {
+    fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+}
+
+impl Drop for WorkspaceCleanup {
+    fn drop(&mut self) {
+        if let Err(err) = fs::remove_dir_all(&self.path)
+            && err.kind() != io::ErrorKind::NotFound
+        {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "cleanup warning: failed to remove conversion workspace {}: {}",
+                    self.path.display(),
+                    err
+                ),
+            );
+        }
+    }
+}
+
+// Run external command and include stdout/stderr when failing.
+pub(crate) fn run_checked_command(cmd: &mut Command, label: &str) -> Result<()> {
+    run_checked_command_with_timeout(cmd, label, None)
+}
+
+// `run_checked_command` for converter runs, killed after `timeout` when one is given.
+fn run_checked_command_with_timeout(
+    cmd: &mut Command,
+    label: &str,
+    timeout: Option<Duration>,
+) -> Result<()> {
+    let output = command_output(cmd, label, timeout)?;
+
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    bail!(
+        "{label} failed with status {}.\nstdout:\n{}\nstderr:\n{}",
+        output.status,
+        stdout.trim(),
+        stderr.trim()
+    );
+}
+
+// A converter run killed by `--convert-timeout-secs`. Kept as a distinct error so the `run`
+// worker can tell a wedged converter from one that exited with an error.
+#[derive(Debug, thiserror::Error)]
+#[error("{label} timed out after {secs}s; converter process killed")]
+pub(crate) struct ConverterTimeout {
+    label: String,
+    secs: u64,
+}
+
+// True when `err` (or anything in its context chain) is a `ConverterTimeout`.
+pub(crate) fn is_converter_timeout(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| cause.is::<ConverterTimeout>())
+}
+
+fn converter_timeout(args: &ConvertArgs) -> Option<Duration> {
+    (args.convert_timeout_secs > 0).then(|| Duration::from_secs(args.convert_timeout_secs))
+}
+
+// Run `cmd` to completion collecting stdout/stderr, like `Command::output`. With a timeout the
+// child is polled instead of waited on, and killed once the deadline passes.
+fn command_output(cmd: &mut Command, label: &str, timeout: Option<Duration>) -> Result<Output> {
+    let debug = format!("{cmd:?}");
+    log_at(Level::Verbose, EventKind::Info, &format!("Running {label}"));
+    log_at(Level::Debug, EventKind::Info, &format!("{label}: {debug}"));
+    let Some(timeout) = timeout else {
+        return cmd
+            .output()
+            .with_context(|| format!("spawning command failed for {label}: {debug}"));
+    };
+
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("spawning command failed for {label}: {debug}"))?;
+    // Drain both pipes on their own threads so a chatty converter cannot block on a full pipe
+    // while we poll for its exit.
+    let stdout = drain_pipe(child.stdout.take());
+    let stderr = drain_pipe(child.stderr.take());
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .with_context(|| format!("waiting for {label} failed: {debug}"))?
+        {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            // kill() fails only if the child already exited; wait() reaps it either way.
+            let _ = child.kill();
+            let _ = child.wait();
+            // The reader threads are left to finish on their own: a grandchild (e.g. under
+            // --convert-sandbox) may still hold the pipes open.
+            return Err(ConverterTimeout {
+                label: label.to_string(),
+                secs: timeout.as_secs(),
+            }
+            .into());
+        }
+        thread::sleep(COMMAND_POLL_INTERVAL);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+fn drain_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut bytes = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut bytes);
+        }
+        bytes
+    })
+}
+
+// List UBX files (`.ubx` or `.ubx.gz`) in data_dir that belong to a UTC hour prefix (YYYYMMDD_HH...).
+// Files modified within `min_file_age` of now are returned separately as still active.
+fn list_hour_ubx_files(
+    data_dir: &Path,
+    prefix: &str,
+    min_file_age: Duration,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut files = Vec::new();
+    let mut active = Vec::new();
+    let now = SystemTime::now();
+    let entries = match fs::read_dir(data_dir) {
+        Ok(entries) => entries,
+        // A daily-layout day directory only exists once something was logged that day.
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((files, active)),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("reading data directory failed: {}", data_dir.display()));
+        }
+    };
+    for entry in entries {
+        let entry = entry.with_context(|| format!("iterating {}", data_dir.display()))?;
+        if !entry
+            .file_type()
+            .with_context(|| format!("reading metadata for {}", entry.path().display()))?
+            .is_file()
+        {
+            continue;
+        }
+
+        let path = entry.path();
+        let Some(file_name) = lossy_file_name(&path) else {
+            continue;
+        };
+        let Some(stem) = log_file_stem(&file_name) else {
+            continue;
+        };
+        if !stem.starts_with(prefix) {
+            continue;
+        }
+
+        let modified = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| format!("reading modification time for {}", path.display()))?;
+        // A future mtime (clock step) counts as fresh too.
+        let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+        if !min_file_age.is_zero() && age < min_file_age {
+            active.push(path);
+        } else {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    active.sort();
+    Ok((files, active))
+}
+
+// Best-effort delete helper used by cleanup paths.
+fn remove_file_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| format!("removing file failed: {}", path.display())),
+    }
+}
+
+// File name for matching against product and log name patterns, which are plain ASCII.
+// Invalid UTF-8 bytes become U+FFFD, so such a file is still matched by its ASCII parts
+// instead of being skipped; the original `Path` is kept for every file operation.
+fn lossy_file_name(path: &Path) -> Option<Cow<'_, str>> {
+    path.file_name().map(OsStr::to_string_lossy)
+}
+
+// `path` with `suffix` appended to its file name, byte for byte (no UTF-8 round trip).
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn dup_file_name(file_name: &OsStr, idx: u32) -> OsString {
+    let mut name = file_name.to_os_string();
+    name.push(format!(".dup{idx}"));
+    name
+}
+
+// Return a non-colliding destination path within one directory.
+fn unique_destination_path(dst_dir: &Path, file_name: &OsStr) -> PathBuf {
+    let first_try = dst_dir.join(file_name);
+    if !first_try.exists() {
+        return first_try;
+    }
+
+    for idx in 1.. {
+        let candidate = dst_dir.join(dup_file_name(file_name, idx));
+        if !candidate.exists() {
+            return candidate;
+        }
+    }
+
+    unreachable!("duplicate suffix search should always find an unused path");
+}
+
+// Existing `file_name` (or `.dupN` of it) in `dst_dir` with the same size and SHA-256 as `src`.
+fn identical_archived_copy(
+    src: &Path,
+    dst_dir: &Path,
+    file_name: &OsStr,
+) -> Result<Option<PathBuf>> {
+    let size = fs::metadata(src)
+        .with_context(|| format!("reading metadata for {}", src.display()))?
+        .len();
+    let mut src_digest = None;
+    for idx in 0.. {
+        let candidate = if idx == 0 {
+            dst_dir.join(file_name)
+        } else {
+            dst_dir.join(dup_file_name(file_name, idx))
+        };
+        let Ok(metadata) = fs::metadata(&candidate) else {
+            return Ok(None);
+        };
+        if !metadata.is_file() || metadata.len() != size {
+            continue;
+        }
+        if src_digest.is_none() {
+            src_digest = Some(sha256_file(src)?);
+        }
+        if src_digest.as_deref() == Some(sha256_file(&candidate)?.as_str()) {
+            return Ok(Some(candidate));
+        }
+    }
+    unreachable!("duplicate suffix search should always reach an unused path");
+}
+
+// Move file into destination directory, with copy+delete fallback for cross-device moves.
+// A byte-identical copy already there (the name itself or one of its `.dupN`s, e.g. from an
+// earlier run over the same hour) is kept instead and the source is dropped, so only
+// genuinely different contents get a new `.dupN`.
+fn move_into_dir(src: &Path, dst_dir: &Path) -> Result<PathBuf> {
+    let file_name = src
+        .file_name()
+        .ok_or_else(|| anyhow!("missing file name for source: {}", src.display()))?;
+    if let Some(existing) = identical_archived_copy(src, dst_dir, file_name)? {
+        fs::remove_file(src)
+            .with_context(|| format!("removing source file failed: {}", src.display()))?;
+        emit_event(
+            EventKind::Archive,
+            &format!(
+                "Already archived with identical contents: {}",
+                existing.display()
+            ),
+        );
+        return Ok(existing);
+    }
+    let dst = unique_destination_path(dst_dir, file_name);
+
+    match fs::rename(src, &dst) {
+        Ok(()) => Ok(dst),
+        Err(_) => {
+            fs::copy(src, &dst).with_context(|| {
+                format!(
+                    "copying file to archive failed: {} -> {}",
+                    src.display(),
+                    dst.display()
+                )
+            })?;
+            fs::remove_file(src)
+                .with_context(|| format!("removing source file failed: {}", src.display()))?;
+            Ok(dst)
+        }
+    }
+}
+
+// An hour is convertible once it has ended and `min_complete_minutes` more have passed.
+// The in-progress UTC hour never qualifies.
+pub(crate) fn is_hour_complete(
+    hour_start: DateTime<Utc>,
+    now: DateTime<Utc>,
+    min_complete_minutes: u32,
+) -> bool {
+    let ready_at = hour_start
+        + ChronoDuration::hours(1)
+        + ChronoDuration::minutes(i64::from(min_complete_minutes));
+    now >= ready_at
+}
+
+// Truncate a DateTime to top-of-hour in UTC for deterministic hourly windowing.
+pub(crate) fn floor_to_hour(dt: DateTime<Utc>) -> DateTime<Utc> {
+    dt.with_minute(0)
+        .and_then(|v| v.with_second(0))
+        .and_then(|v| v.with_nanosecond(0))
+        .expect("UTC floor-to-hour should always be valid")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::args::{AppCommand, Cli};
+    use crate::shared::day_manifest::DAY_MANIFEST_FILE_NAME;
+    use chrono::TimeZone;
+    use clap::Parser;
+
+    // Stand-in backend that writes minimal OBS/NAV products without any external binary.
+    // The first `failures` observation conversions fail, as a wedged or missing converter would.
+    pub(crate) struct MockConverter {
+        failures: AtomicU32,
+        pub(crate) obs_calls: AtomicU32,
+    }
+
+    impl MockConverter {
+        pub(crate) fn failing(failures: u32) -> Self {
+            Self {
+                failures: AtomicU32::new(failures),
+                obs_calls: AtomicU32::new(0),
+            }
+        }
+    }
+
+    impl Converter for MockConverter {
+        fn probe(&self, _args: &ConvertArgs) -> Result<()> {
+            Ok(())
+        }
+
+        fn convert_obs(
+            &self,
+            args: &ConvertArgs,
+            dt: DateTime<Utc>,
+            _merged_ubx: &Path,
+            output_dir: &Path,
+        ) -> Result<usize> {
+            self.obs_calls.fetch_add(1, Ordering::SeqCst);
+            if self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |left| {
+                    left.checked_sub(1)
+                })
+                .is_ok()
+            {
+                bail!("mock converter failure");
+            }
+            let obs = output_dir.join(format!("{}.rnx", obs_product_stem(args, dt)?));
+            fs::write(&obs, "mock observation product\n")?;
+            compress_file(obs, args.effective_compression())?;
+            Ok(3600)
+        }
+
+        fn convert_nav(
+            &self,
+            args: &ConvertArgs,
+            dt: DateTime<Utc>,
+            _merged_ubx: &Path,
+            output_dir: &Path,
+        ) -> Result<()> {
+            let nav = output_dir.join(format!("{}.rnx", nav_product_stem(args, dt, "MN")?));
+            fs::write(&nav, "mock navigation product\n")?;
+            compress_file(nav, args.effective_compression())?;
+            Ok(())
+        }
+    }
+
+    // Scratch data and archive directories plus arguments pointing at them.
+    pub(crate) fn scratch_convert_args(name: &str, extra: &[&str]) -> (PathBuf, ConvertArgs) {
+        let root = scratch_dir(name);
+        let data_dir = root.join("data");
+        let archive_dir = root.join("archive");
+        fs::create_dir_all(&data_dir).unwrap();
+        let lock_file = root.join("convert.lock");
+        let mut argv = vec![
+            "--data-dir",
+            data_dir.to_str().unwrap(),
+            "--archive-dir",
+            archive_dir.to_str().unwrap(),
+            "--lock-file",
+            lock_file.to_str().unwrap(),
+            "--min-file-age-secs",
+            "0",
+        ];
+        argv.extend_from_slice(extra);
+        let args = convert_args(&argv);
+        (root, args)
+    }
+
+    // One closed hour of UBX data in the logger's layout; returns the file.
+    pub(crate) fn write_hour_ubx(args: &ConvertArgs, dt: DateTime<Utc>) -> PathBuf {
+        let dir = log_day_dir(
+            &args.data_dir,
+            args.data_layout,
+            &dt.format("%Y%m%d").to_string(),
+        );
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}0000.ubx", dt.format("%Y%m%d_%H")));
+        fs::write(&path, ubx_frame(0x02, 0x15, 32)).unwrap();
+        path
+    }
+
+    // Convert arguments as the CLI would build them from `convert <extra...>`.
+    fn convert_args(extra: &[&str]) -> ConvertArgs {
+        let argv = ["gnss2tec-logger", "convert"]
+            .into_iter()
+            .chain(extra.iter().copied());
+        match Cli::try_parse_from(argv).unwrap().command {
+            AppCommand::Convert(args) => args,
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    // Fresh scratch directory under the system temp dir, unique per test and process.
+    pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-convert-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    // A checksum-valid UBX frame with `len` payload bytes.
+    fn ubx_frame(class: u8, id: u8, len: usize) -> Vec<u8> {
+        let payload: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        crate::shared::ubx::encode_packet(class, id, &payload)
+    }
+
+    fn write_gz(path: &Path, bytes: &[u8]) {
+        let mut encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap();
+    }
+
+    // Stub convbin under `root` that records its arguments, one per line, instead of
+    // converting; returns the stub and the file it writes.
+    #[cfg(unix)]
+    fn recording_convbin_stub(root: &Path) -> (PathBuf, PathBuf) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let recorded = root.join("argv.txt");
+        let stub = root.join("convbin");
+        fs::write(
+            &stub,
+            format!(
+                "#!/bin/sh\nprintf '%s\\n' \"$@\" > {}\n",
+                recorded.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+        (stub, recorded)
+    }
+
+    #[cfg(unix)]
+    fn recorded_argv(recorded: &Path) -> Vec<String> {
+        fs::read_to_string(recorded)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    // convbin argv of one observation conversion for `convert <extra...>`.
+    #[cfg(unix)]
+    fn recorded_convbin_obs_argv(name: &str, extra: &[&str], exclude: &[char]) -> Vec<String> {
+        let (scratch, args) = scratch_convert_args(&format!("{name}-args"), extra);
+        let argv = recorded_obs_argv_for(name, &args, exclude);
+        fs::remove_dir_all(&scratch).unwrap();
+        argv
+    }
+
+    // convbin argv of one observation conversion of 2026-03-01 12:00 with `args`.
+    #[cfg(unix)]
+    fn recorded_obs_argv_for(name: &str, args: &ConvertArgs, exclude: &[char]) -> Vec<String> {
+        let root = scratch_dir(name);
+        let (stub, recorded) = recording_convbin_stub(&root);
+        let program = stub.into_os_string();
+        let options =
+            ObsRunOptions::resolve(args, utc(2026, 3, 1, 12, 0), &program, false).unwrap();
+        run_convbin_obs_command(
+            args,
+            &options,
+            Path::new("merged.ubx"),
+            Path::new("obs.rnx"),
+            exclude,
+            None,
+        )
+        .unwrap();
+        let argv = recorded_argv(&recorded);
+        fs::remove_dir_all(&root).unwrap();
+        argv
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extra_convbin_args_land_before_the_output_target() {
+        let argv = recorded_convbin_obs_argv(
+            "extra-args",
+            &["--convbin-extra-args", "-ti 1 -hc 'two words'"],
+            &['R'],
+        );
+        let tail = &argv[argv.len() - 7..];
+        assert_eq!(
+            tail,
+            [
+                "-ti",
+                "1",
+                "-hc",
+                "two words",
+                "-o",
+                "obs.rnx",
+                "merged.ubx"
+            ]
+        );
+        // After every built-in flag, so convbin keeps the user's value.
+        let last_builtin = argv.iter().rposition(|arg| arg == "-y").unwrap() + 1;
+        assert_eq!(argv.len() - 7, last_builtin + 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn excluded_systems_become_y_flags() {
+        let (root, args) = scratch_convert_args(
+            "exclude-systems",
+            &["--constellations", "GRE", "--exclude-systems", "rE"],
+        );
+        let excluded = obs_excluded_systems(&args).unwrap();
+        assert_eq!(excluded, ['C', 'J', 'S', 'R', 'E']);
+        assert_eq!(kept_systems(&excluded), "G");
+        fs::remove_dir_all(&root).unwrap();
+
+        let argv = recorded_convbin_obs_argv("exclude-systems-argv", &[], &excluded);
+        let flags: Vec<&str> = argv
+            .windows(2)
+            .filter(|pair| pair[0] == "-y")
+            .map(|pair| pair[1].as_str())
+            .collect();
+        assert_eq!(flags, ["C", "J", "S", "R", "E"]);
+    }
+
+    #[test]
+    fn unknown_excluded_system_is_rejected() {
+        let (root, args) =
+            scratch_convert_args("exclude-systems-bad", &["--exclude-systems", "GX"]);
+        let err = obs_excluded_systems(&args).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("unknown constellation 'X'"),
+            "{err:#}"
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn rinex_version_is_passed_to_obs_and_nav_conversions() {
+        let version_of = |argv: &[String]| {
+            let at = argv.iter().position(|arg| arg == "-v").unwrap();
+            argv[at + 1].clone()
+        };
+        let argv =
+            recorded_convbin_obs_argv("rinex-version-obs", &["--rinex-version", "3.02"], &[]);
+        assert_eq!(version_of(&argv), "3.02");
+
+        let root = scratch_dir("rinex-version-nav");
+        let (stub, recorded) = recording_convbin_stub(&root);
+        let (scratch, args) =
+            scratch_convert_args("rinex-version-nav-args", &["--rinex-version", "3.05"]);
+        run_convbin_nav_command(
+            &args,
+            &stub.into_os_string(),
+            false,
+            Path::new("merged.ubx"),
+            &[("-n", Path::new("nav.rnx"))],
+            &[],
+            "mixed",
+        )
+        .unwrap();
+        assert_eq!(version_of(&recorded_argv(&recorded)), "3.05");
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&scratch).unwrap();
+
+        let (root, args) = scratch_convert_args("rinex-version-bad", &["--rinex-version", "4.00"]);
+        assert!(rinex_version(&args).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn approx_position_becomes_the_hp_header_flag() {
+        let position_of = |argv: &[String]| {
+            argv.iter()
+                .position(|arg| arg == "-hp")
+                .map(|at| argv[at + 1].clone())
+        };
+        let argv = recorded_convbin_obs_argv(
+            "approx-xyz",
+            &["--approx-xyz", "1334000.1,-4654000.2,4138000.3"],
+            &[],
+        );
+        assert_eq!(
+            position_of(&argv).as_deref(),
+            Some("1334000.1000/-4654000.2000/4138000.3000")
+        );
+
+        // On the equator at the prime meridian the ECEF position is the WGS84 semi-major axis.
+        let argv = recorded_convbin_obs_argv("approx-llh", &["--approx-llh", "0,0,0"], &[]);
+        assert_eq!(
+            position_of(&argv).as_deref(),
+            Some("6378137.0000/0.0000/0.0000")
+        );
+
+        let argv = recorded_convbin_obs_argv("approx-none", &[], &[]);
+        assert_eq!(position_of(&argv), None);
+    }
+
+    #[test]
+    fn implausible_approx_position_is_rejected() {
+        for (option, raw) in [
+            ("--approx-xyz", "1,2,3"),
+            ("--approx-xyz", "1,2"),
+            ("--approx-llh", "91,0,0"),
+            ("--approx-llh", "0,0,20000"),
+        ] {
+            let (root, args) = scratch_convert_args("approx-bad", &[option, raw]);
+            assert!(approx_position_xyz(&args).is_err(), "{option} {raw}");
+            fs::remove_dir_all(&root).unwrap();
+        }
+    }
+
+    #[test]
+    fn obs_window_covers_exactly_the_target_hour() {
+        assert_eq!(
+            obs_window_args(utc(2026, 3, 1, 23, 0)),
+            [
+                "-ts",
+                "2026/03/01",
+                "23:00:00",
+                "-te",
+                "2026/03/01",
+                "23:59:59.999"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn obs_window_flags_are_sent_unless_disabled() {
+        let argv = recorded_convbin_obs_argv("obs-window", &[], &[]);
+        let at = argv.iter().position(|arg| arg == "-ts").unwrap();
+        assert_eq!(
+            argv[at..at + 6],
+            [
+                "-ts",
+                "2026/03/01",
+                "12:00:00",
+                "-te",
+                "2026/03/01",
+                "12:59:59.999"
+            ]
+        );
+
+        let argv = recorded_convbin_obs_argv("obs-window-off", &["--no-obs-window"], &[]);
+        assert!(!argv.iter().any(|arg| arg == "-ts" || arg == "-te"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn no_obs_window_env_var_drops_the_window_in_run_mode() {
+        // SAFETY: no other test reads or writes this variable.
+        unsafe { std::env::set_var("GNSS2TEC_NO_OBS_WINDOW", "true") };
+        let cli = Cli::parse_from(["gnss2tec-logger", "run"]);
+        unsafe { std::env::remove_var("GNSS2TEC_NO_OBS_WINDOW") };
+        let AppCommand::Run(args) = cli.command else {
+            panic!("expected run");
+        };
+        let argv = recorded_obs_argv_for("obs-window-env", &args.to_convert_args(), &[]);
+        assert!(!argv.iter().any(|arg| arg == "-ts" || arg == "-te"));
+    }
+
+    #[test]
+    fn extra_convbin_args_may_not_set_output_targets() {
+        for raw in ["-o out.rnx", "-n nav.rnx", "-d /tmp"] {
+            let (root, args) =
+                scratch_convert_args("extra-args-reserved", &["--convbin-extra-args", raw]);
+            assert!(convbin_extra_args(&args).is_err(), "{raw}");
+            fs::remove_dir_all(&root).unwrap();
+        }
+        // Options that only start like a reserved one are convbin flags of their own.
+        let (root, args) =
+            scratch_convert_args("extra-args-allowed", &["--convbin-extra-args", "-od -os"]);
+        assert_eq!(convbin_extra_args(&args).unwrap(), ["-od", "-os"]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn archive_bucket_follows_the_local_date_across_midnight() {
+        let bucket = |tz: &str, dt| archive_bucket(tz, dt).unwrap();
+        let late = utc(2026, 3, 1, 21, 0);
+        assert_eq!(bucket("utc", late), ("2026".into(), "060".into()));
+        assert_eq!(bucket("+05:00", late), ("2026".into(), "061".into()));
+        let early = utc(2026, 3, 2, 2, 0);
+        assert_eq!(bucket("-0500", early), ("2026".into(), "060".into()));
+        // The local date can also be in another year.
+        assert_eq!(
+            bucket("+3", utc(2025, 12, 31, 22, 0)),
+            ("2026".into(), "001".into())
+        );
+        assert!(archive_bucket("+25:00", late).is_err());
+        assert!(archive_bucket("mars", late).is_err());
+    }
+
+    #[test]
+    fn shifted_bucket_keeps_utc_product_names() {
+        let (root, args) =
+            scratch_convert_args("archive-timezone", &["--archive-timezone", "+05:00"]);
+        let dt = utc(2026, 3, 1, 21, 0);
+        write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let obs = format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap());
+        assert!(obs.contains("2026060"), "{obs}");
+        assert!(
+            args.archive_dir
+                .join("2026")
+                .join("061")
+                .join(&obs)
+                .is_file()
+        );
+        assert!(!args.archive_dir.join("2026").join("060").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn in_progress_hour_is_never_complete() {
+        let hour = utc(2026, 3, 1, 12, 0);
+        assert!(!is_hour_complete(hour, utc(2026, 3, 1, 12, 0), 0));
+        assert!(!is_hour_complete(hour, utc(2026, 3, 1, 12, 59), 0));
+        assert!(is_hour_complete(hour, utc(2026, 3, 1, 13, 0), 0));
+    }
+
+    #[test]
+    fn previous_hour_waits_for_min_complete_minutes() {
+        let hour = utc(2026, 3, 1, 23, 0);
+        assert!(!is_hour_complete(hour, utc(2026, 3, 2, 0, 4), 5));
+        assert!(is_hour_complete(hour, utc(2026, 3, 2, 0, 5), 5));
+        assert!(is_hour_complete(
+            utc(2026, 3, 1, 22, 0),
+            utc(2026, 3, 2, 0, 4),
+            5
+        ));
+    }
 
-                if file_exists_and_nonempty(&nav_rnx) {
-                    produced.push(nav_rnx);
-                } else {
-                    remove_file_if_exists(&nav_rnx)?;
-                }
-            }
+    #[test]
+    fn floor_to_hour_truncates_minutes_and_seconds() {
+        let dt = Utc.with_ymd_and_hms(2026, 3, 1, 12, 34, 56).unwrap();
+        assert_eq!(floor_to_hour(dt), utc(2026, 3, 1, 12, 0));
+    }
 
-            if produced.is_empty() {
-                bail!(
-                    "no per-constellation NAV files were generated for hour {}",
-                    dt.format("%Y-%m-%d %H:00")
-                );
-            }
+    #[test]
+    fn station_prefix_nests_archive_under_station() {
+        let archive = Path::new("/srv/archive");
+        assert_eq!(
+            station_archive_root(archive, "NJIT", true),
+            Path::new("/srv/archive/NJIT")
+        );
+        assert_eq!(station_archive_root(archive, "NJIT", false), archive);
+    }
 
-            let archive = output_dir.join(format!("{prefix}_NAVSET.tar.gz"));
-            bundle_files_into_tar_gz(&produced, &archive)?;
-            for path in produced {
-                remove_file_if_exists(&path)?;
-            }
+    #[test]
+    fn retention_prunes_only_the_prefixed_station_tree() {
+        let archive = scratch_dir("retention");
+        let station = station_archive_root(&archive, "NJIT", true);
+        let today = Utc::now().date_naive();
+        let old = station.join("2000").join("001");
+        let fresh = station
+            .join(format!("{:04}", today.year()))
+            .join(format!("{:03}", today.ordinal()));
+        // An unprefixed day next to the station directory belongs to another layout.
+        let unprefixed = archive.join("2000").join("001");
+        for dir in [&old, &fresh, &unprefixed] {
+            fs::create_dir_all(dir).unwrap();
         }
+
+        assert_eq!(prune_expired_archive_days(&station, "utc", 30).unwrap(), 1);
+        assert!(!old.exists());
+        assert!(!station.join("2000").exists());
+        assert!(fresh.is_dir());
+        assert!(unprefixed.is_dir());
+        fs::remove_dir_all(&archive).unwrap();
     }
 
-    Ok(())
-}
+    #[test]
+    fn per_constellation_obs_products_get_distinct_names() {
+        let args = convert_args(&["--station", "NJIT", "--country", "USA"]);
+        let dt = utc(2026, 3, 1, 12, 0);
+        let names: Vec<String> = ['G', 'R', 'E']
+            .into_iter()
+            .map(|system| obs_constellation_stem(&args, dt, system).unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "NJIT00USA_R_202606012_01H_01S_GO",
+                "NJIT00USA_R_202606012_01H_01S_RO",
+                "NJIT00USA_R_202606012_01H_01S_EO",
+            ]
+        );
+    }
 
-fn run_convbin_nav_command(
-    args: &ConvertArgs,
-    program: &OsString,
-    used_path_fallback: bool,
-    merged_ubx: &Path,
-    output_nav: &Path,
-    exclude_systems: &[char],
-    mode_label: &str,
-) -> Result<()> {
-    let mut cmd = Command::new(program);
-    cmd.arg("-r")
-        .arg("ubx")
-        .arg("-v")
-        .arg("3.04")
-        // Mirror metadata flags for NAV generation too.
-        .arg("-oi")
-        .arg("-ot")
-        .arg("-ol")
-        .arg("-hm")
-        .arg(format!("{}00", args.station))
-        .arg("-ho")
-        .arg(format!("{}/{}", args.observer, args.country))
-        .arg("-hr")
-        .arg(format!("NA/{}/NA", args.receiver_type))
-        .arg("-ha")
-        .arg(format!("NA/{}", args.antenna_type));
+    #[test]
+    fn ionex_name_follows_the_product_prefix() {
+        let dt = utc(2026, 3, 1, 12, 0);
+        let dir = Path::new("out");
+        let args = convert_args(&["--station", "NJIT", "--country", "USA"]);
+        assert_eq!(
+            ionex_product_path(&args, dt, dir).unwrap(),
+            dir.join("NJIT00USA_R_202606012_01H_IO.ionex")
+        );
+        let args = convert_args(&["--nav-name-template", "{station}-{year}{doy}-{hour}_{type}"]);
+        assert_eq!(
+            ionex_product_path(&args, dt, dir).unwrap(),
+            dir.join(format!("{}-2026060-12_IO.ionex", args.station))
+        );
+        let args = convert_args(&["--station", "NJIT", "--rinex-style", "short"]);
+        assert_eq!(
+            ionex_product_path(&args, dt, dir).unwrap(),
+            dir.join("NJIT060m_IO.ionex")
+        );
+    }
 
-    for sys in exclude_systems {
-        cmd.arg("-y").arg(sys.to_string());
+    #[test]
+    fn per_constellation_obs_requires_type_or_systems() {
+        let args = convert_args(&["--obs-name-template", "{station}_{year}{doy}{hour}"]);
+        assert!(obs_constellation_stem(&args, utc(2026, 3, 1, 12, 0), 'G').is_err());
+        assert!(
+            validate_obs_constellation_template("{station}_{year}{doy}{hour}_{systems}").is_ok()
+        );
     }
 
-    cmd.arg("-n").arg(output_nav).arg(merged_ubx);
+    #[test]
+    fn raw_fast_path_and_decoded_path_merge_identical_bytes() {
+        let dir = scratch_dir("merge-paths");
+        let one = [ubx_frame(0x02, 0x15, 32), ubx_frame(0x02, 0x13, 16)].concat();
+        let two = [b"noise".to_vec(), ubx_frame(0x01, 0x07, 92)].concat();
+
+        let raw_inputs = [dir.join("a.ubx"), dir.join("b.ubx")];
+        fs::write(&raw_inputs[0], &one).unwrap();
+        fs::write(&raw_inputs[1], &two).unwrap();
+        let gz_inputs = [dir.join("a.ubx.gz"), dir.join("b.ubx.gz")];
+        write_gz(&gz_inputs[0], &one);
+        write_gz(&gz_inputs[1], &two);
+
+        let raw_merged = dir.join("raw.merged");
+        let gz_merged = dir.join("gz.merged");
+        let raw_stats = concat_ubx_files(&raw_inputs, &raw_merged).unwrap();
+        let gz_stats = concat_ubx_files(&gz_inputs, &gz_merged).unwrap();
+
+        let expected = [one, two].concat();
+        assert_eq!(fs::read(&raw_merged).unwrap(), expected);
+        assert_eq!(fs::read(&gz_merged).unwrap(), expected);
+        assert_eq!(raw_stats.frames, 3);
+        assert_eq!(gz_stats.frames, 3);
+        assert_eq!(raw_stats.stray_bytes, 5);
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-    let label = if used_path_fallback {
-        format!(
-            "convbin navigation conversion ({mode_label}, requested {} not found; used PATH lookup)",
-            args.convbin_path.display()
-        )
-    } else {
-        format!("convbin navigation conversion ({mode_label})")
-    };
+    #[test]
+    fn kernel_copy_and_buffered_copy_merge_identical_bytes() {
+        // Forwards to a `File` without being one, so `io::copy` cannot take the kernel path.
+        struct BufferedSink<'a>(&'a mut File);
+        impl Write for BufferedSink<'_> {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                self.0.flush()
+            }
+        }
 
-    run_checked_command(&mut cmd, &label)
-}
+        let dir = scratch_dir("merge-copy-paths");
+        let small = [ubx_frame(0x02, 0x15, 32), ubx_frame(0x02, 0x13, 16)].concat();
+        let noisy = [b"noise".to_vec(), ubx_frame(0x01, 0x07, 92)].concat();
+        // Large enough to take several rounds of the userspace copy buffer.
+        let large: Vec<u8> = (0..2000).flat_map(|_| ubx_frame(0x02, 0x15, 512)).collect();
+        let inputs = [dir.join("a.ubx"), dir.join("b.ubx"), dir.join("c.ubx")];
+        fs::write(&inputs[0], &small).unwrap();
+        fs::write(&inputs[1], &noisy).unwrap();
+        fs::write(&inputs[2], &large).unwrap();
+
+        let kernel_merged = dir.join("kernel.merged");
+        let buffered_merged = dir.join("buffered.merged");
+        let mut kernel = File::create(&kernel_merged).unwrap();
+        let mut buffered = File::create(&buffered_merged).unwrap();
+        for input in &inputs {
+            let kernel_counts = append_raw_ubx_input(input, &mut kernel).unwrap();
+            let buffered_counts =
+                append_raw_ubx_input(input, &mut BufferedSink(&mut buffered)).unwrap();
+            assert_eq!(kernel_counts, buffered_counts);
+        }
+        drop(kernel);
+        drop(buffered);
 
-fn file_exists_and_nonempty(path: &Path) -> bool {
-    match fs::metadata(path) {
-        Ok(meta) => meta.is_file() && meta.len() > 0,
-        Err(_) => false,
+        let expected = [small, noisy, large].concat();
+        assert_eq!(fs::read(&kernel_merged).unwrap(), expected);
+        assert_eq!(fs::read(&buffered_merged).unwrap(), expected);
+        fs::remove_dir_all(&dir).unwrap();
     }
-}
 
-fn concat_ubx_files(inputs: &[PathBuf], output: &Path) -> Result<()> {
-    let mut writer = BufWriter::new(File::create(output).with_context(|| {
-        format!(
-            "creating temporary UBX merge file failed: {}",
-            output.display()
+    #[test]
+    fn empty_and_noise_only_inputs_are_skipped() {
+        let dir = scratch_dir("skip-bad-inputs");
+        let healthy = [ubx_frame(0x02, 0x15, 32), ubx_frame(0x02, 0x13, 16)].concat();
+        let inputs = [
+            dir.join("20260301_1200.ubx.gz"),
+            dir.join("20260301_1215.ubx"),
+            dir.join("20260301_1230.ubx"),
+            dir.join("20260301_1245.ubx"),
+        ];
+        // A noise-only gzip input ahead of the healthy one must be truncated out of the merge.
+        write_gz(&inputs[0], b"$GNGGA,no fix*00\r\n");
+        fs::write(&inputs[1], &healthy).unwrap();
+        fs::write(&inputs[2], b"").unwrap();
+        fs::write(&inputs[3], vec![0x55; 4096]).unwrap();
+
+        let merged = dir.join("hour.merged");
+        let stats = concat_ubx_files(&inputs, &merged).unwrap();
+        assert_eq!(stats.merged_files, 1);
+        assert_eq!(stats.skipped_files, 3);
+        assert_eq!(stats.frames, 2);
+        assert_eq!(fs::read(&merged).unwrap(), healthy);
+
+        let err = concat_ubx_files(
+            &[inputs[0].clone(), inputs[2].clone(), inputs[3].clone()],
+            &merged,
         )
-    })?);
-
-    for input in inputs {
-        let mut reader = BufReader::new(
-            File::open(input)
-                .with_context(|| format!("opening UBX input failed: {}", input.display()))?,
+        .unwrap_err();
+        assert!(
+            format!("{err:#}").contains("none of the 3 UBX input file(s)"),
+            "{err:#}"
         );
-        io::copy(&mut reader, &mut writer).with_context(|| {
-            format!(
-                "appending UBX input into temporary merge file failed: {}",
-                input.display()
-            )
-        })?;
+        fs::remove_dir_all(&dir).unwrap();
     }
-    writer.flush().with_context(|| {
-        format!(
-            "flushing temporary UBX merge file failed: {}",
-            output.display()
-        )
-    })?;
-    Ok(())
-}
 
-fn gzip_file(path: PathBuf) -> Result<PathBuf> {
-    let gz_path = PathBuf::from(format!("{}.gz", path.display()));
-    let mut input = BufReader::new(
-        File::open(&path)
-            .with_context(|| format!("opening file for gzip failed: {}", path.display()))?,
-    );
-    let out_file = File::create(&gz_path)
-        .with_context(|| format!("creating gzip output failed: {}", gz_path.display()))?;
-    let writer = BufWriter::new(out_file);
-    let mut encoder = GzEncoder::new(writer, Compression::default());
-    io::copy(&mut input, &mut encoder)
-        .with_context(|| format!("gzip compression failed: {}", path.display()))?;
-    let mut writer = encoder
-        .finish()
-        .with_context(|| format!("finalizing gzip output failed: {}", gz_path.display()))?;
-    writer
-        .flush()
-        .with_context(|| format!("flushing gzip output failed: {}", gz_path.display()))?;
-    remove_file_if_exists(&path)?;
-    Ok(gz_path)
-}
+    #[test]
+    fn mock_converter_drives_process_hour_end_to_end() {
+        let (root, args) = scratch_convert_args("process-hour", &[]);
+        let dt = utc(2026, 3, 1, 12, 0);
+        let ubx = write_hour_ubx(&args, dt);
+        let converter = MockConverter::failing(0);
+
+        assert!(convert_hour_with(&args, &converter, dt).unwrap());
+
+        let day = args.archive_dir.join("2026").join("060");
+        let obs = day.join(format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap()));
+        let nav = day.join(format!(
+            "{}.rnx.gz",
+            nav_product_stem(&args, dt, "MN").unwrap()
+        ));
+        assert!(obs.is_file(), "missing {}", obs.display());
+        assert!(nav.is_file(), "missing {}", nav.display());
+        assert!(
+            day.join(format!("{}.ready", hour_product_prefix(&args, dt).unwrap()))
+                .is_file()
+        );
+        assert!(!ubx.exists());
+        assert_eq!(converter.obs_calls.load(Ordering::SeqCst), 1);
+        // Nothing left to convert: a second pass is a no-op.
+        assert!(!convert_hour_with(&args, &converter, dt).unwrap());
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-fn sampling_token_from_seconds(seconds: u32) -> String {
-    if seconds < 100 {
-        format!("{seconds:02}S")
-    } else {
-        format!("{seconds}S")
+    #[test]
+    fn sampling_shorter_than_the_measurement_interval_warns() {
+        // `--obs-sampling-secs` is whole seconds, so the 1 Hz-receiver-vs-0.5 s-sampling case
+        // is checked at the next scale: 1 s sampling from a 0.5 Hz (2000 ms) CFG-RATE.
+        let (root, _) = scratch_convert_args("sampling-rate", &[]);
+        let slow = root.join("slow.dat");
+        fs::write(&slow, "!UBX CFG-RATE 2000 1 1\n").unwrap();
+        let one_hz = root.join("one-hz.dat");
+        fs::write(&one_hz, "!UBX CFG-RATE 1000 1 1\n").unwrap();
+
+        let (scratch, args) = scratch_convert_args(
+            "sampling-rate-slow",
+            &[
+                "--config-file",
+                slow.to_str().unwrap(),
+                "--obs-sampling-secs",
+                "1",
+            ],
+        );
+        let warning = sampling_rate_warning(&args).unwrap();
+        assert!(
+            warning.contains("is shorter than the receiver measurement interval of 2000 ms"),
+            "{warning}"
+        );
+        fs::remove_dir_all(&scratch).unwrap();
+
+        let (scratch, args) = scratch_convert_args(
+            "sampling-rate-1hz",
+            &[
+                "--config-file",
+                one_hz.to_str().unwrap(),
+                "--obs-sampling-secs",
+                "30",
+            ],
+        );
+        assert_eq!(sampling_rate_warning(&args), None);
+        fs::remove_dir_all(&scratch).unwrap();
+        fs::remove_dir_all(&root).unwrap();
     }
-}
 
-fn bundle_files_into_tar_gz(files: &[PathBuf], archive_path: &Path) -> Result<()> {
-    let out = File::create(archive_path).with_context(|| {
-        format!(
-            "creating navigation archive failed: {}",
-            archive_path.display()
-        )
-    })?;
-    let writer = BufWriter::new(out);
-    let encoder = GzEncoder::new(writer, Compression::default());
-    let mut tar = Builder::new(encoder);
+    #[test]
+    fn held_convert_lock_is_a_lock_error() {
+        let (root, args) = scratch_convert_args("lock-error", &["--lock-wait-secs", "0"]);
+        let _held = LockGuard::acquire(&args.lock_file).unwrap();
 
-    for path in files {
-        let Some(name) = path.file_name() else {
-            bail!("missing file name for NAV file: {}", path.display());
-        };
-        tar.append_path_with_name(path, Path::new(name))
-            .with_context(|| {
-                format!("adding NAV file to tar archive failed: {}", path.display())
-            })?;
+        let err = run_convert(args).unwrap_err();
+        assert!(matches!(err, PipelineError::Lock(_)), "{err:?}");
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    let encoder = tar
-        .into_inner()
-        .with_context(|| format!("finalizing tar stream failed: {}", archive_path.display()))?;
-    let mut writer = encoder
-        .finish()
-        .with_context(|| format!("finalizing gzip stream failed: {}", archive_path.display()))?;
-    writer
-        .flush()
-        .with_context(|| format!("flushing archive failed: {}", archive_path.display()))?;
-    Ok(())
-}
+    #[test]
+    fn archived_ubx_lands_in_the_day_directory_and_originals_go() {
+        let (root, args) = scratch_convert_args("archive-ubx", &["--archive-ubx", "--keep-ubx"]);
+        let dt = utc(2026, 3, 1, 12, 0);
+        let ubx = write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let archived = args.archive_dir.join("2026").join("060").join(format!(
+            "{}.ubx.gz",
+            hour_product_prefix(&args, dt).unwrap()
+        ));
+        let mut raw = Vec::new();
+        MultiGzDecoder::new(File::open(&archived).unwrap())
+            .read_to_end(&mut raw)
+            .unwrap();
+        assert_eq!(raw, ubx_frame(0x02, 0x15, 32));
+        // The archive holds the raw data now, so --keep-ubx no longer keeps the original.
+        assert!(!ubx.exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-// Collect final output product files in one directory.
-fn collect_output_products_in_dir(dir: &Path) -> Result<Vec<PathBuf>> {
-    let mut outputs = Vec::new();
-    for entry in
-        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
-    {
-        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
-        if !entry
-            .file_type()
-            .with_context(|| format!("reading metadata for {}", entry.path().display()))?
-            .is_file()
-        {
-            continue;
-        }
+    #[test]
+    fn failed_conversion_keeps_the_ubx_input() {
+        let (root, args) = scratch_convert_args("process-hour-fail", &[]);
+        let dt = utc(2026, 3, 1, 13, 0);
+        let ubx = write_hour_ubx(&args, dt);
 
-        let path = entry.path();
-        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
-            continue;
-        };
-        if is_output_product_name(name) {
-            outputs.push(path);
-        }
+        assert!(convert_hour_with(&args, &MockConverter::failing(1), dt).is_err());
+        assert!(ubx.is_file());
+        fs::remove_dir_all(&root).unwrap();
     }
-    outputs.sort();
-    Ok(outputs)
-}
-
-// Validate required products were created.
-fn validate_hour_outputs(outputs: &[PathBuf], skip_nav: bool, label: &str) -> Result<()> {
-    let mut has_obs = false;
-    let mut has_nav = false;
-    let mut names = Vec::new();
 
-    for path in outputs {
-        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
-            continue;
-        };
-        names.push(name.to_string());
-        match classify_output_name(name) {
-            OutputKind::Observation => has_obs = true,
-            OutputKind::Navigation => has_nav = true,
-            OutputKind::Ionex => {}
-            OutputKind::Other => {}
-        }
+    #[test]
+    fn failed_conversion_writes_no_ready_marker() {
+        let (root, args) = scratch_convert_args("process-hour-no-marker", &[]);
+        let dt = utc(2026, 3, 1, 13, 0);
+        write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(1), dt).is_err());
+        let marker = args
+            .archive_dir
+            .join("2026")
+            .join("060")
+            .join(format!("{}.ready", hour_product_prefix(&args, dt).unwrap()));
+        assert!(!marker.exists());
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    if !has_obs {
-        bail!(
-            "no observation product generated for {label}; collected outputs: {}",
-            names.join(", ")
+    #[test]
+    fn hour_prefix_follows_the_product_names() {
+        let dt = utc(2026, 3, 1, 12, 0);
+        let (root, args) = scratch_convert_args("prefix-default", &[]);
+        assert_eq!(
+            hour_product_prefix(&args, dt).unwrap(),
+            format!("{}00{}_R_202606012_01H", args.station, args.country)
         );
-    }
+        fs::remove_dir_all(&root).unwrap();
 
-    if !skip_nav {
-        if !has_nav {
-            bail!(
-                "no navigation product generated for {label}; collected outputs: {}",
-                names.join(", ")
-            );
-        }
+        let (root, args) = scratch_convert_args(
+            "prefix-short",
+            &["--rinex-style", "short", "--station", "NJIT"],
+        );
+        assert_eq!(hour_product_prefix(&args, dt).unwrap(), "NJIT060m");
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    Ok(())
-}
+    #[test]
+    fn custom_templates_name_the_ready_marker() {
+        let (root, args) = scratch_convert_args(
+            "prefix-custom",
+            &[
+                "--obs-name-template",
+                "{station}-{year}{doy}-{hour}-{type}",
+                "--nav-name-template",
+                "{station}-{year}{doy}-{hour}_{type}",
+            ],
+        );
+        let dt = utc(2026, 3, 1, 12, 0);
+        write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let day = args.archive_dir.join("2026").join("060");
+        assert!(
+            day.join(format!("{}-2026060-12.ready", args.station))
+                .is_file()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-// True if file is one of the final products we archive.
-fn is_output_product_name(name: &str) -> bool {
-    !matches!(classify_output_name(name), OutputKind::Other)
-}
+    #[test]
+    fn delete_after_upload_clears_marker_manifest_and_queue() {
+        let (root, args) = scratch_convert_args(
+            "delete-after-upload",
+            &[
+                "--rsync-target",
+                "backup:/archive",
+                "--rsync-path",
+                "true",
+                "--rsync-delete-after-upload",
+                "--day-manifest",
+                "--upload-queue",
+                "--write-checksums",
+            ],
+        );
+        let dt = utc(2026, 3, 1, 12, 0);
+        write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let day = args.archive_dir.join("2026").join("060");
+        let mut left: Vec<String> = list_dir_names(&day)
+            .unwrap()
+            .iter()
+            .map(|name| name.to_string_lossy().into_owned())
+            .collect();
+        left.sort();
+        assert_eq!(left, [DAY_MANIFEST_FILE_NAME]);
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(day.join(DAY_MANIFEST_FILE_NAME)).unwrap())
+                .unwrap();
+        assert_eq!(manifest["hour_count"], 0);
+
+        let entries = UploadQueue::new(&args.archive_dir).load().unwrap();
+        assert!(!entries.is_empty());
+        assert!(entries.iter().all(|entry| entry["status"] == STATUS_DONE));
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
-enum OutputKind {
-    Observation,
-    Navigation,
-    Ionex,
-    Other,
-}
+    #[test]
+    fn sparse_hour_keeps_kept_ubx_without_a_reject_dir() {
+        let (root, args) = scratch_convert_args(
+            "sparse-keep",
+            &["--min-obs-epochs", "5000", "--keep-ubx", "--archive-ubx"],
+        );
+        let dt = utc(2026, 3, 1, 12, 0);
+        let ubx = write_hour_ubx(&args, dt);
 
-// Identify product kind across multiple RINEX naming styles.
-fn classify_output_name(name: &str) -> OutputKind {
-    let lower = name.to_ascii_lowercase();
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        assert!(ubx.is_file(), "raw UBX of a dropped hour was deleted");
+        assert!(!args.archive_dir.join("2026").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-    if lower.contains("_navset.tar.gz") {
-        return OutputKind::Navigation;
+    #[test]
+    fn sparse_hour_is_parked_in_the_reject_dir() {
+        let root = scratch_dir("sparse-reject");
+        let reject_dir = root.join("rejected");
+        let (args_root, args) = scratch_convert_args(
+            "sparse-reject-args",
+            &[
+                "--min-obs-epochs",
+                "5000",
+                "--keep-ubx",
+                "--archive-ubx",
+                "--reject-dir",
+                reject_dir.to_str().unwrap(),
+            ],
+        );
+        let dt = utc(2026, 3, 1, 12, 0);
+        let ubx = write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let parked = reject_dir.join("2026").join("060");
+        let prefix = hour_product_prefix(&args, dt).unwrap();
+        assert!(parked.join(format!("{prefix}.ubx.gz")).is_file());
+        assert!(
+            parked
+                .join(format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap()))
+                .is_file()
+        );
+        // The parked `.ubx.gz` holds the raw data, so the source goes as with --archive-ubx.
+        assert!(!ubx.exists());
+        assert!(!args.archive_dir.join("2026").exists());
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&args_root).unwrap();
     }
 
-    if lower.ends_with(".ionex") || lower.ends_with(".ionex.gz") {
-        return OutputKind::Ionex;
+    #[test]
+    fn rearchiving_identical_contents_keeps_the_existing_copy() {
+        let root = scratch_dir("dup-identical");
+        let archive = root.join("archive");
+        fs::create_dir_all(&archive).unwrap();
+        let name = "NJIT00USA_R_202606012_01H_01S_MO.rnx.gz";
+        fs::write(archive.join(name), b"same bytes").unwrap();
+        let src = root.join(name);
+        fs::write(&src, b"same bytes").unwrap();
+
+        assert_eq!(move_into_dir(&src, &archive).unwrap(), archive.join(name));
+        assert!(!src.exists());
+        assert!(!archive.join(format!("{name}.dup1")).exists());
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    // RINEX v3 long names.
-    if lower.contains("_mn.") {
-        return OutputKind::Navigation;
+    #[test]
+    fn rearchiving_different_contents_creates_a_dup() {
+        let root = scratch_dir("dup-different");
+        let archive = root.join("archive");
+        fs::create_dir_all(&archive).unwrap();
+        let name = "NJIT00USA_R_202606012_01H_01S_MO.rnx.gz";
+        fs::write(archive.join(name), b"first run").unwrap();
+        let src = root.join(name);
+        fs::write(&src, b"second run").unwrap();
+
+        let dup = archive.join(format!("{name}.dup1"));
+        assert_eq!(move_into_dir(&src, &archive).unwrap(), dup);
+        assert!(!src.exists());
+        assert_eq!(fs::read(archive.join(name)).unwrap(), b"first run");
+        assert_eq!(fs::read(&dup).unwrap(), b"second run");
+
+        // A third run matching the `.dup1` copy is recognized as archived too.
+        fs::write(&src, b"second run").unwrap();
+        assert_eq!(
+            identical_archived_copy(&src, &archive, OsStr::new(name)).unwrap(),
+            Some(dup.clone())
+        );
+        assert_eq!(move_into_dir(&src, &archive).unwrap(), dup);
+        assert!(!archive.join(format!("{name}.dup2")).exists());
+        fs::remove_dir_all(&root).unwrap();
     }
-    if lower.contains("_mo.") {
-        return OutputKind::Observation;
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_file_names_are_listed_and_kept_byte_for_byte() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let root = scratch_dir("non-utf8");
+        let raw_name = OsStr::from_bytes(b"20260301_12\xff.ubx");
+        let ubx = root.join(raw_name);
+        fs::write(&ubx, ubx_frame(0x02, 0x15, 16)).unwrap();
+
+        let (files, active) = list_hour_ubx_files(&root, "20260301_12", Duration::ZERO).unwrap();
+        assert_eq!(files, vec![ubx.clone()]);
+        assert!(active.is_empty());
+        assert_eq!(
+            lossy_file_name(&ubx).as_deref(),
+            Some("20260301_12\u{FFFD}.ubx")
+        );
+
+        let tmp = with_suffix(&ubx, ".tmp");
+        assert_eq!(
+            tmp.file_name().unwrap().as_bytes(),
+            b"20260301_12\xff.ubx.tmp"
+        );
+        assert_eq!(
+            dup_file_name(raw_name, 2).as_bytes(),
+            b"20260301_12\xff.ubx.dup2"
+        );
+
+        let archive = root.join("archive");
+        fs::create_dir_all(&archive).unwrap();
+        assert_eq!(
+            move_into_dir(&ubx, &archive).unwrap(),
+            archive.join(raw_name)
+        );
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    // Compression driven extension style.
-    if lower.ends_with(".crx") || lower.ends_with(".crx.gz") {
-        return OutputKind::Observation;
+    #[cfg(unix)]
+    #[test]
+    fn day_is_complete_once_the_shifted_midnight_passes() {
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        assert!(!is_day_complete(day, utc(2026, 3, 1, 23, 59), 0));
+        assert!(is_day_complete(day, utc(2026, 3, 2, 0, 0), 0));
+        assert!(!is_day_complete(day, utc(2026, 3, 2, 1, 59), 2));
+        assert!(is_day_complete(day, utc(2026, 3, 2, 2, 0), 2));
+        assert!(!is_day_complete(day, utc(2026, 2, 28, 12, 0), 0));
     }
-    if lower.ends_with(".rnx") || lower.ends_with(".rnx.gz") {
-        // If kind is ambiguous, treat as observation to avoid false-negative failures.
-        return OutputKind::Observation;
+
+    #[test]
+    fn daily_name_follows_the_obs_template() {
+        let day_start = utc(2026, 3, 1, 0, 0);
+        let (root, args) = scratch_convert_args("daily-name-default", &[]);
+        let expected = format!(
+            "{}00{}_R_20260600000_01D_{}_MO",
+            args.station,
+            args.country,
+            sampling_token_from_seconds(args.obs_sampling_secs)
+        );
+        assert_eq!(daily_obs_product_stem(&args, day_start).unwrap(), expected);
+        fs::remove_dir_all(&root).unwrap();
+
+        let (root, args) = scratch_convert_args(
+            "daily-name-custom",
+            &["--obs-name-template", "{station}-{year}{doy}-{hour}-{type}"],
+        );
+        assert_eq!(
+            daily_obs_product_stem(&args, day_start).unwrap(),
+            format!("{}-2026060-0000-MO", args.station)
+        );
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    // RINEX v2 short names (e.g. ".26o", ".26d", ".26n"), optionally gzip-compressed.
-    classify_rinex2_short_kind(&lower).unwrap_or(OutputKind::Other)
-}
+    #[test]
+    fn merged_day_with_a_custom_template_is_not_merged_again() {
+        let (root, args) = scratch_convert_args(
+            "daily-idempotent",
+            &[
+                "--daily",
+                "--obs-name-template",
+                "{station}-{year}{doy}-{hour}-{type}",
+                "--gfzrnx-path",
+                "/nonexistent/gfzrnx",
+            ],
+        );
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let day_start = utc(2026, 3, 1, 0, 0);
+        let dir = archive_subdir(
+            &archive_root(&args).join("2026").join("060"),
+            args.obs_archive_subdir.as_deref(),
+        )
+        .unwrap();
+        fs::create_dir_all(&dir).unwrap();
+        let hourly = format!("{}.rnx.gz", obs_product_stem(&args, day_start).unwrap());
+        write_gz(&dir.join(hourly), b"hourly observation product\n");
 
-fn classify_rinex2_short_kind(lower_name: &str) -> Option<OutputKind> {
-    let trimmed = lower_name.strip_suffix(".gz").unwrap_or(lower_name);
-    let ext = trimmed.rsplit('.').next()?;
-    let kind = ext.chars().last()?;
-    match kind {
-        'o' | 'd' => Some(OutputKind::Observation),
-        'n' | 'g' | 'l' | 'p' | 'q' => Some(OutputKind::Navigation),
-        _ => None,
+        // Without a daily product the merge runs (and fails on the missing gfzrnx).
+        assert!(merge_daily_observations(&args, day).is_err());
+
+        let daily = format!(
+            "{}.rnx.gz",
+            daily_obs_product_stem(&args, day_start).unwrap()
+        );
+        write_gz(&dir.join(daily), b"daily observation product\n");
+        assert!(!merge_daily_observations(&args, day).unwrap());
+        fs::remove_dir_all(&root).unwrap();
     }
-}
 
-#[derive(Clone)]
-struct ProductSnapshot {
-    path: PathBuf,
-    modified: Option<SystemTime>,
-    len: u64,
-}
+    #[cfg(unix)]
+    #[test]
+    fn hung_converter_is_killed_at_the_timeout() {
+        let root = scratch_dir("timeout");
+        let pid_file = root.join("stub.pid");
+        // The sandbox wrapper stands in for a convbin that never finishes; `exec` keeps the
+        // recorded pid on the sleeping process itself.
+        let stub = format!("sh -c 'echo $$ > {}; exec sleep 30'", pid_file.display());
+        let (_, args) = scratch_convert_args(
+            "timeout-args",
+            &[
+                "--convert-sandbox",
+                &stub,
+                "--convert-timeout-secs",
+                "1",
+                "--obs-output-format",
+                "rinex",
+            ],
+        );
+        let merged = root.join("merged.ubx");
+        fs::write(&merged, ubx_frame(0x02, 0x15, 16)).unwrap();
+
+        let started = Instant::now();
+        let err = ConvbinConverter
+            .convert_obs(&args, utc(2026, 3, 1, 12, 0), &merged, &root)
+            .unwrap_err();
+        assert!(is_converter_timeout(&err), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let alive = Command::new("kill")
+            .args(["-0", pid.trim()])
+            .stderr(Stdio::null())
+            .status()
+            .unwrap();
+        assert!(
+            !alive.success(),
+            "stub converter {} still running",
+            pid.trim()
+        );
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(args.data_dir.parent().unwrap()).unwrap();
+    }
 
-fn snapshot_output_products(dir: &Path) -> Result<Vec<ProductSnapshot>> {
-    let mut out = Vec::new();
-    for entry in
-        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
-    {
-        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
-        if !entry
-            .file_type()
-            .with_context(|| format!("reading metadata for {}", entry.path().display()))?
-            .is_file()
-        {
-            continue;
+    #[test]
+    fn daily_layout_hours_are_found_in_their_day_directory() {
+        let (root, args) = scratch_convert_args("daily-layout", &["--data-layout", "daily"]);
+        let dt = utc(2026, 3, 1, 23, 0);
+        let ubx = write_hour_ubx(&args, dt);
+        assert_eq!(ubx.parent().unwrap(), args.data_dir.join("20260301"));
+        // A same-named file left in the flat data directory is not part of this layout.
+        let flat = args.data_dir.join(ubx.file_name().unwrap());
+        fs::write(&flat, ubx_frame(0x02, 0x15, 32)).unwrap();
+
+        let converter = MockConverter::failing(0);
+        assert!(hour_has_ubx_files(&args.data_dir, args.data_layout, dt).unwrap());
+        assert!(
+            !hour_has_ubx_files(&args.data_dir, args.data_layout, utc(2026, 3, 2, 0, 0)).unwrap()
+        );
+        assert!(convert_hour_with(&args, &converter, dt).unwrap());
+        assert_eq!(converter.obs_calls.load(Ordering::SeqCst), 1);
+        assert!(!ubx.exists());
+        assert!(flat.is_file());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn products_compress_with_each_algorithm_and_decompress() {
+        let root = scratch_dir("compression");
+        let contents =
+            b"     3.04           OBSERVATION DATA    M                   RINEX VERSION / TYPE\n";
+        for (compression, suffix) in [
+            (ProductCompression::Gzip, ".gz"),
+            (ProductCompression::Zstd, ".zst"),
+            (ProductCompression::None, ""),
+        ] {
+            let plain = root.join("NJIT00USA_R_20260601200_01H_01S_MO.rnx");
+            fs::write(&plain, contents).unwrap();
+            let archived = compress_file(plain.clone(), compression).unwrap();
+            assert_eq!(archived, with_suffix(&plain, suffix));
+            assert_eq!(plain.exists(), compression == ProductCompression::None);
+
+            let mut decoded = Vec::new();
+            open_product_reader(&archived)
+                .unwrap()
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, contents);
+            let name = archived.file_name().unwrap().to_str().unwrap();
+            assert_eq!(classify_output_name(name), OutputKind::Observation);
+            fs::remove_file(&archived).unwrap();
+        }
+        assert_eq!(
+            classify_output_name("NJIT00USA_R_20260601200_01H_MN.rnx.zst"),
+            OutputKind::Navigation
+        );
+        assert_eq!(
+            classify_output_name("njit0600.26o.zst"),
+            OutputKind::Observation
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn zstd_compression_archives_zst_products() {
+        let (root, args) = scratch_convert_args("compression-zstd", &["--compression", "zstd"]);
+        let dt = utc(2026, 3, 1, 12, 0);
+        write_hour_ubx(&args, dt);
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+
+        let day = args.archive_dir.join("2026").join("060");
+        let obs = day.join(format!("{}.rnx.zst", obs_product_stem(&args, dt).unwrap()));
+        let mut decoded = String::new();
+        open_product_reader(&obs)
+            .unwrap()
+            .read_to_string(&mut decoded)
+            .unwrap();
+        assert_eq!(decoded, "mock observation product\n");
+        let nav = day.join(format!(
+            "{}.rnx.zst",
+            nav_product_stem(&args, dt, "MN").unwrap()
+        ));
+        assert!(nav.is_file(), "missing {}", nav.display());
+        assert!(
+            !day.join(format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap()))
+                .exists()
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn short_style_builds_rinex2_names_and_classifies_them() {
+        let args = convert_args(&["--station", "NJIT", "--rinex-style", "short"]);
+        let dt = utc(2026, 1, 1, 0, 0);
+        assert_eq!(short_product_name(&args, dt, 'o').unwrap(), "NJIT001a.26o");
+        assert_eq!(rinex_version(&args).unwrap(), "2.11");
+
+        for (letter, kind) in [
+            ('o', OutputKind::Observation),
+            ('n', OutputKind::Navigation),
+            ('g', OutputKind::Navigation),
+            ('l', OutputKind::Navigation),
+            ('q', OutputKind::Navigation),
+        ] {
+            let name = short_product_name(&args, utc(2026, 12, 31, 23, 0), letter).unwrap();
+            assert_eq!(name, format!("NJIT365x.26{letter}"));
+            assert_eq!(classify_output_name(&format!("{name}.gz")), kind, "{name}");
         }
+        // Hatanaka-compressed short names become `.yyd`, long names `.crx`.
+        let crinex = crinex_path(Path::new("out/NJIT001a.26o"));
+        assert_eq!(crinex, Path::new("out/NJIT001a.26d"));
+        assert_eq!(
+            classify_output_name("NJIT001a.26d.zst"),
+            OutputKind::Observation
+        );
+        assert_eq!(
+            crinex_path(Path::new("out/NJIT00USA_R_20260010000_01H_01S_MO.rnx")),
+            Path::new("out/NJIT00USA_R_20260010000_01H_01S_MO.crx")
+        );
+    }
 
-        let path = entry.path();
-        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
-            continue;
-        };
-        if !is_output_product_name(name) {
-            continue;
+    #[test]
+    fn short_style_needs_a_four_character_station() {
+        let dt = utc(2026, 1, 1, 0, 0);
+        for station in ["NJIT00", "NJ", "NJ-T"] {
+            let args = convert_args(&["--station", station, "--rinex-style", "short"]);
+            assert!(short_product_name(&args, dt, 'o').is_err(), "{station}");
         }
+        // The long style keeps the configured RINEX 3 version.
+        let args = convert_args(&["--rinex-version", "3.02"]);
+        assert_eq!(rinex_version(&args).unwrap(), "3.02");
+    }
 
-        let meta = fs::metadata(&path)
-            .with_context(|| format!("reading metadata failed: {}", path.display()))?;
-        out.push(ProductSnapshot {
-            path,
-            modified: meta.modified().ok(),
-            len: meta.len(),
-        });
+    #[test]
+    fn session_letters_cover_the_day_without_collisions() {
+        let letters: String = (0..24).map(|hour| session_letter(hour).unwrap()).collect();
+        assert_eq!(letters, "abcdefghijklmnopqrstuvwx");
+        assert!(session_letter(24).is_err());
+
+        let args = convert_args(&["--station", "NJIT", "--rinex-style", "short"]);
+        let names: std::collections::BTreeSet<String> = (0..24)
+            .map(|hour| short_product_name(&args, utc(2026, 3, 1, hour, 0), 'o').unwrap())
+            .collect();
+        assert_eq!(names.len(), 24);
+        assert_eq!(names.first().unwrap(), "NJIT060a.26o");
+        assert_eq!(names.last().unwrap(), "NJIT060x.26o");
+
+        // The daily product uses session 0, which no hourly file can take.
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        let daily = short_name_stem(&args, day, DAILY_SESSION).unwrap();
+        assert_eq!(daily, "NJIT0600");
+        assert!(!names.iter().any(|name| name.starts_with(&daily)));
     }
-    out.sort_by(|a, b| a.path.cmp(&b.path));
-    Ok(out)
-}
 
-fn collect_changed_output_products(
-    before: &[ProductSnapshot],
-    after: &[ProductSnapshot],
-) -> Vec<PathBuf> {
-    let mut before_map = HashMap::new();
-    for snap in before {
-        before_map.insert(snap.path.clone(), (snap.modified, snap.len));
+    // Stub convbin that writes a one-epoch product to its `-n`/`-o` target, or an empty file
+    // when the run keeps `quiet_system` (i.e. `-y` never excludes it).
+    #[cfg(unix)]
+    fn product_writing_convbin_stub(root: &Path, quiet_system: char) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let stub = root.join("convbin");
+        fs::write(
+            &stub,
+            format!(
+                "#!/bin/sh\nout=; prev=; quiet=yes\nfor arg in \"$@\"; do\n  case \"$prev\" in\n    -n|-o) out=$arg ;;\n    -y) [ \"$arg\" = {quiet_system} ] && quiet=no ;;\n  esac\n  prev=$arg\ndone\nif [ $quiet = yes ]; then : > \"$out\"; else printf '%60s%s\\n> 2026 03 01 12 00  0.0000000  0  1\\n' '' 'END OF HEADER' > \"$out\"; fi\n"
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&stub, fs::Permissions::from_mode(0o755)).unwrap();
+        stub
     }
 
-    let mut changed = Vec::new();
-    for snap in after {
-        match before_map.get(&snap.path) {
-            None => changed.push(snap.path.clone()),
-            Some((prev_modified, prev_len))
-                if prev_modified != &snap.modified || prev_len != &snap.len =>
-            {
-                changed.push(snap.path.clone());
-            }
-            _ => {}
-        }
+    #[cfg(unix)]
+    fn archived_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".gz"))
+            .collect();
+        names.sort();
+        names
     }
-    changed.sort();
-    changed
-}
 
-// Some converter outputs can emit long-name epoch tokens with HHMM fixed to 0000.
-// Normalize those product names to the target conversion hour to avoid archive collisions.
-fn normalize_long_output_names_for_target_hour(
-    outputs: &mut Vec<PathBuf>,
-    dt: DateTime<Utc>,
-) -> Result<()> {
-    let target_epoch = format!(
-        "{}{:03}{}00",
-        dt.format("%Y"),
-        dt.ordinal(),
-        dt.format("%H")
-    );
+    #[cfg(unix)]
+    #[test]
+    fn individual_gz_archives_each_constellation_nav_file_on_its_own() {
+        let root = scratch_dir("nav-individual-gz");
+        let stub = product_writing_convbin_stub(&root, 'E');
+        let (scratch, args) = scratch_convert_args(
+            "nav-individual-gz-args",
+            &[
+                "--convbin-path",
+                stub.to_str().unwrap(),
+                "--station",
+                "NJIT",
+                "--country",
+                "USA",
+                "--constellations",
+                "GRE",
+                "--nav-output-format",
+                "individual-gz",
+            ],
+        );
+        let output_dir = root.join("out");
+        fs::create_dir_all(&output_dir).unwrap();
+
+        run_convbin_nav_for_hour(
+            &args,
+            utc(2026, 3, 1, 12, 0),
+            Path::new("merged.ubx"),
+            &output_dir,
+        )
+        .unwrap();
+        // Galileo produced nothing and is skipped; no NAVSET bundle is written.
+        assert_eq!(
+            archived_names(&output_dir),
+            [
+                "NJIT00USA_R_202606012_01H_GN.rnx.gz",
+                "NJIT00USA_R_202606012_01H_RN.rnx.gz",
+            ]
+        );
+        assert_eq!(fs::read_dir(&output_dir).unwrap().count(), 2);
 
-    for path in outputs.iter_mut() {
-        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
-            continue;
-        };
-        let Some(normalized_name) = rewrite_long_name_epoch(file_name, &target_epoch) else {
-            continue;
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&scratch).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn individual_gz_observation_products_are_split_by_constellation() {
+        let root = scratch_dir("obs-individual-gz");
+        let stub = product_writing_convbin_stub(&root, 'E');
+        let dt = utc(2026, 3, 1, 12, 0);
+        let output_dir = root.join("out");
+        fs::create_dir_all(&output_dir).unwrap();
+        let split = |constellations: &str| {
+            let (scratch, args) = scratch_convert_args(
+                "obs-individual-gz-args",
+                &[
+                    "--convbin-path",
+                    stub.to_str().unwrap(),
+                    "--station",
+                    "NJIT",
+                    "--country",
+                    "USA",
+                    "--constellations",
+                    constellations,
+                    "--obs-output-format",
+                    "individual-gz",
+                ],
+            );
+            let program = stub.clone().into_os_string();
+            let options = ObsRunOptions::resolve(&args, dt, &program, false).unwrap();
+            let result = run_convbin_obs_per_constellation(
+                &args,
+                dt,
+                &options,
+                Path::new("merged.ubx"),
+                &output_dir,
+            );
+            fs::remove_dir_all(&scratch).unwrap();
+            result
         };
 
-        let parent = path
-            .parent()
-            .ok_or_else(|| anyhow!("missing parent directory for {}", path.display()))?;
-        let destination = unique_destination_path(parent, OsStr::new(&normalized_name));
-        let source = path.clone();
+        // Galileo has no epochs and is dropped; the others are archived one by one.
+        assert_eq!(split("GRE").unwrap(), 1);
+        assert_eq!(
+            archived_names(&output_dir),
+            [
+                "NJIT00USA_R_202606012_01H_01S_GO.rnx.gz",
+                "NJIT00USA_R_202606012_01H_01S_RO.rnx.gz",
+            ]
+        );
+        assert_eq!(fs::read_dir(&output_dir).unwrap().count(), 2);
 
-        fs::rename(&source, &destination).with_context(|| {
-            format!(
-                "renaming output product failed: {} -> {}",
-                source.display(),
-                destination.display()
-            )
-        })?;
-        *path = destination;
+        let err = split("E").unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("no per-constellation observation files were generated"),
+            "{err:#}"
+        );
+
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    outputs.sort();
-    Ok(())
-}
+    #[cfg(unix)]
+    #[test]
+    fn hatanaka_archives_the_crinex_observation() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = scratch_dir("hatanaka-crx");
+        // Mixed output excludes Galileo, so this stub writes a one-epoch observation file.
+        let convbin = product_writing_convbin_stub(&root, 'E');
+        let rnx2crx = root.join("rnx2crx");
+        fs::write(&rnx2crx, "#!/bin/sh\ncp \"$1\" \"${1%.rnx}.crx\"\n").unwrap();
+        fs::set_permissions(&rnx2crx, fs::Permissions::from_mode(0o755)).unwrap();
+        let (scratch, args) = scratch_convert_args(
+            "hatanaka-crx-args",
+            &[
+                "--convbin-path",
+                convbin.to_str().unwrap(),
+                "--rnx2crx-path",
+                rnx2crx.to_str().unwrap(),
+                "--station",
+                "NJIT",
+                "--country",
+                "USA",
+                "--constellations",
+                "G",
+                "--hatanaka",
+            ],
+        );
+        let output_dir = root.join("out");
+        fs::create_dir_all(&output_dir).unwrap();
+
+        let epochs = run_convbin_obs_for_hour(
+            &args,
+            utc(2026, 3, 1, 12, 0),
+            Path::new("merged.ubx"),
+            &output_dir,
+        )
+        .unwrap();
+        assert_eq!(epochs, 1);
+        // The plain `.rnx` is replaced by the gzipped CRINEX file.
+        let names = archived_names(&output_dir);
+        assert_eq!(names, ["NJIT00USA_R_202606012_01H_01S_MO.crx.gz"]);
+        assert_eq!(fs::read_dir(&output_dir).unwrap().count(), 1);
+        assert_eq!(classify_output_name(&names[0]), OutputKind::Observation);
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&scratch).unwrap();
+    }
 
-// Rewrite long-name `_R_YYYYDOYHHMM_` epoch segment to a specific hour.
-fn rewrite_long_name_epoch(file_name: &str, target_epoch: &str) -> Option<String> {
-    let marker = "_R_";
-    let start = file_name.find(marker)? + marker.len();
-    let remaining = &file_name[start..];
-    let epoch_end_rel = remaining.find('_')?;
-    let epoch = &remaining[..epoch_end_rel];
+    #[cfg(unix)]
+    #[test]
+    fn no_compress_products_archives_plain_rinex_and_tar() {
+        let root = scratch_dir("no-compress-products");
+        let stub = product_writing_convbin_stub(&root, 'E');
+        let (scratch, args) = scratch_convert_args(
+            "no-compress-products-args",
+            &[
+                "--convbin-path",
+                stub.to_str().unwrap(),
+                "--station",
+                "NJIT",
+                "--country",
+                "USA",
+                "--constellations",
+                "GR",
+                "--nav-output-format",
+                "individual-tar-gz",
+                "--no-compress-products",
+            ],
+        );
+        let dt = utc(2026, 3, 1, 12, 0);
+        let output_dir = root.join("out");
+        fs::create_dir_all(&output_dir).unwrap();
+
+        run_convbin_obs_for_hour(&args, dt, Path::new("merged.ubx"), &output_dir).unwrap();
+        run_convbin_nav_for_hour(&args, dt, Path::new("merged.ubx"), &output_dir).unwrap();
+        let mut names: Vec<String> = fs::read_dir(&output_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "NJIT00USA_R_202606012_01H_01S_MO.rnx",
+                "NJIT00USA_R_202606012_01H_NAVSET.tar",
+            ]
+        );
+        assert_eq!(classify_output_name(&names[0]), OutputKind::Observation);
+        assert_eq!(classify_output_name(&names[1]), OutputKind::Navigation);
 
-    if epoch.len() != 11 || !epoch.chars().all(|c| c.is_ascii_digit()) || epoch == target_epoch {
-        return None;
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&scratch).unwrap();
     }
 
-    let mut rewritten = String::with_capacity(file_name.len());
-    rewritten.push_str(&file_name[..start]);
-    rewritten.push_str(target_epoch);
-    rewritten.push_str(&remaining[epoch_end_rel..]);
-    Some(rewritten)
-}
+    #[test]
+    fn explicit_range_covers_every_hour_inclusive() {
+        let hours = explicit_hour_range("20260301_00", "20260302_23", false).unwrap();
+        assert_eq!(hours.len(), 48);
+        assert_eq!(hours[0], utc(2026, 3, 1, 0, 0));
+        assert_eq!(hours[47], utc(2026, 3, 2, 23, 0));
+        assert!(
+            hours
+                .windows(2)
+                .all(|pair| pair[1] - pair[0] == ChronoDuration::hours(1))
+        );
+        assert_eq!(
+            explicit_hour_range("20260301_12", "20260301_12", false).unwrap(),
+            [utc(2026, 3, 1, 12, 0)]
+        );
+    }
 
-fn create_conversion_workspace(data_dir: &Path, dt: DateTime<Utc>) -> Result<PathBuf> {
-    let base = data_dir.join(".convert-work");
-    fs::create_dir_all(&base)
-        .with_context(|| format!("creating conversion workspace failed: {}", base.display()))?;
-    let name = format!(
-        "{}_{}_{}",
-        dt.format("%Y%m%d_%H"),
-        std::process::id(),
-        Utc::now().timestamp_nanos_opt().unwrap_or_default()
-    );
-    let path = base.join(name);
-    fs::create_dir_all(&path)
-        .with_context(|| format!("creating hour workspace failed: {}", path.display()))?;
-    Ok(path)
-}
+    #[test]
+    fn explicit_range_rejects_reversed_malformed_and_oversized_ranges() {
+        assert!(explicit_hour_range("20260302_00", "20260301_23", false).is_err());
+        assert!(explicit_hour_range("2026-03-01 00", "20260301_23", false).is_err());
+        assert!(explicit_hour_range("20260301_24", "20260302_00", false).is_err());
+        // 32 days needs --allow-large-range.
+        assert!(explicit_hour_range("20260101_00", "20260201_23", false).is_err());
+        assert_eq!(
+            explicit_hour_range("20260101_00", "20260201_23", true)
+                .unwrap()
+                .len(),
+            32 * 24
+        );
+    }
 
-struct WorkspaceCleanup {
-    path: PathBuf,
-}
+    #[test]
+    fn hour_summary_reports_time_and_archived_sizes() {
+        assert_eq!(
+            format_hour_summary(
+                utc(2024, 1, 1, 12, 0),
+                Duration::from_millis(4_200),
+                8_100_000,
+                300_000,
+                MergeStats {
+                    merged_files: 2,
+                    skipped_files: 0,
+                    frames: 7_200,
+                    stray_bytes: 0,
+                    elapsed: Duration::from_millis(100),
+                }
+            ),
+            "Converted 2024-001 12:00 in 4.2s (merge 0.1s) -> obs 8.1MB nav 0.3MB \
+             (7200 UBX frame(s) from 2 file(s), 0 byte(s) outside frames)"
+        );
 
-impl WorkspaceCleanup {
-    fn new(path: PathBuf) -> Self {
-        Self { path }
+        let dir = scratch_dir("hour-summary-sizes");
+        let archived: Vec<PathBuf> = [
+            ("NJIT00USA_R_20240011200_01H_01S_MO.rnx.gz", 1_500_000),
+            ("NJIT00USA_R_20240011200_01H_NAVSET.tar.gz", 200_000),
+            ("NJIT00USA_R_20240011200_01H_GN.rnx.gz", 100_000),
+            ("NJIT00USA_R_20240011200_01H_IO.ionex.gz", 50_000),
+        ]
+        .into_iter()
+        .map(|(name, len)| {
+            let path = dir.join(name);
+            fs::write(&path, vec![0_u8; len]).unwrap();
+            path
+        })
+        .collect();
+        let (obs_bytes, nav_bytes) = archived_product_bytes(&archived).unwrap();
+        assert_eq!((obs_bytes, nav_bytes), (1_500_000, 300_000));
+        assert_eq!(
+            format_hour_summary(
+                utc(2024, 1, 1, 12, 0),
+                Duration::from_secs(3),
+                obs_bytes,
+                nav_bytes,
+                MergeStats {
+                    skipped_files: 1,
+                    ..MergeStats::default()
+                }
+            ),
+            "Converted 2024-001 12:00 in 3.0s (merge 0.0s) -> obs 1.5MB nav 0.3MB \
+             (0 UBX frame(s) from 0 file(s), 0 byte(s) outside frames, 1 skipped)"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
     }
-}
 
-impl Drop for WorkspaceCleanup {
-    fn drop(&mut self) {
-        if let Err(err) = fs::remove_dir_all(&self.path)
-            && err.kind() != io::ErrorKind::NotFound
-        {
-            eprintln!(
-                "cleanup warning: failed to remove conversion workspace {}: {}",
-                self.path.display(),
-                err
-            );
+    #[test]
+    fn split_archive_subdirs_separate_observation_and_navigation() {
+        let (root, args) = scratch_convert_args(
+            "archive-subdirs",
+            &[
+                "--obs-archive-subdir",
+                "obs",
+                "--nav-archive-subdir",
+                "nav/rinex",
+            ],
+        );
+        let dt = utc(2026, 3, 1, 12, 0);
+        write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let day = args.archive_dir.join("2026").join("060");
+        let obs = format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap());
+        let nav = format!("{}.rnx.gz", nav_product_stem(&args, dt, "MN").unwrap());
+        assert!(day.join("obs").join(&obs).is_file());
+        assert!(day.join("nav").join("rinex").join(&nav).is_file());
+        assert!(!day.join(&obs).exists() && !day.join(&nav).exists());
+
+        // The marker stays in the day directory and names products relative to it.
+        let marker = day.join(format!("{}.ready", hour_product_prefix(&args, dt).unwrap()));
+        let marker: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(marker).unwrap()).unwrap();
+        let mut names: Vec<&str> = marker["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|file| file["name"].as_str().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, [format!("nav/rinex/{nav}"), format!("obs/{obs}")]);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn archive_subdirs_must_stay_inside_the_day_directory() {
+        let day = Path::new("archive/2026/060");
+        assert_eq!(archive_subdir(day, None).unwrap(), day);
+        assert_eq!(archive_subdir(day, Some(Path::new(""))).unwrap(), day);
+        assert_eq!(
+            archive_subdir(day, Some(Path::new("obs"))).unwrap(),
+            day.join("obs")
+        );
+        for bad in ["../obs", "/srv/obs", "obs/../../x", "./obs"] {
+            assert!(archive_subdir(day, Some(Path::new(bad))).is_err(), "{bad}");
         }
     }
-}
 
-// Run external command and include stdout/stderr when failing.
-fn run_checked_command(cmd: &mut Command, label: &str) -> Result<()> {
-    let debug = format!("{cmd:?}");
-    let output = cmd
-        .output()
-        .with_context(|| format!("spawning command failed for {label}: {debug}"))?;
+    #[cfg(unix)]
+    #[test]
+    fn post_hook_sees_the_hour_and_its_products() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = scratch_dir("post-hook");
+        let hook = root.join("hook.sh");
+        let recorded = root.join("env.txt");
+        fs::write(
+            &hook,
+            "#!/bin/sh\n{ echo \"$GNSS2TEC_YEAR\"; echo \"$GNSS2TEC_DOY\"; echo \"$GNSS2TEC_HOUR\"; \
+             echo \"$GNSS2TEC_ARCHIVE_DIR\"; echo \"$GNSS2TEC_PRODUCTS\"; } > \"$1\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        let hook_command = format!("{} {}", hook.display(), recorded.display());
+        let (scratch, args) =
+            scratch_convert_args("post-hook-args", &["--post-hook", &hook_command]);
+        let dt = utc(2026, 3, 1, 12, 0);
+        write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let day = args.archive_dir.join("2026").join("060");
+        let recorded = fs::read_to_string(&recorded).unwrap();
+        let lines: Vec<&str> = recorded.lines().collect();
+        assert_eq!(
+            lines[..4],
+            ["2026", "060", "12", args.archive_dir.to_str().unwrap()]
+        );
+        let mut products = lines[4..].to_vec();
+        products.sort();
+        let expected = [
+            day.join(format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap())),
+            day.join(format!(
+                "{}.rnx.gz",
+                nav_product_stem(&args, dt, "MN").unwrap()
+            )),
+        ];
+        let mut expected: Vec<String> = expected
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect();
+        expected.sort();
+        assert_eq!(products, expected);
+
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&scratch).unwrap();
+    }
 
-    if output.status.success() {
-        return Ok(());
+    #[cfg(unix)]
+    #[test]
+    fn failing_post_hook_leaves_the_hour_archived() {
+        let (root, args) = scratch_convert_args("post-hook-fails", &["--post-hook", "false"]);
+        let dt = utc(2026, 3, 1, 12, 0);
+        write_hour_ubx(&args, dt);
+
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        let obs = format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap());
+        assert!(
+            args.archive_dir
+                .join("2026")
+                .join("060")
+                .join(obs)
+                .is_file()
+        );
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    bail!(
-        "{label} failed with status {}.\nstdout:\n{}\nstderr:\n{}",
-        output.status,
-        stdout.trim(),
-        stderr.trim()
-    );
-}
+    #[test]
+    fn qc_summary_reports_a_known_fixture() {
+        let dir = scratch_dir("qc-summary");
+        let header = |text: &str, label: &str| format!("{text:<60}{label}");
+        let observations = |satellite: &str, values: &[Option<f64>]| {
+            let fields: String = values
+                .iter()
+                .map(|value| match value {
+                    Some(value) => format!("{value:14.3}  "),
+                    None => " ".repeat(16),
+                })
+                .collect();
+            format!("{satellite}{}", fields.trim_end())
+        };
+        // GPS has two observation types and GLONASS three; G05 and the second R07 miss one.
+        let lines = [
+            header(
+                "     3.04           OBSERVATION DATA    M",
+                "RINEX VERSION / TYPE",
+            ),
+            header("G    2 C1C L1C", "SYS / # / OBS TYPES"),
+            header("R    3 C1C L1C D1C", "SYS / # / OBS TYPES"),
+            header("", "END OF HEADER"),
+            "> 2026 03 01 12 00  0.0000000  0  3".to_string(),
+            observations("G01", &[Some(20_000_000.0), Some(105_000_000.0)]),
+            observations("G05", &[Some(21_000_000.0), None]),
+            observations(
+                "R07",
+                &[Some(19_000_000.0), Some(101_000_000.0), Some(-120.5)],
+            ),
+            "> 2026 03 01 12 00 30.0000000  0  2".to_string(),
+            observations("G01", &[Some(20_000_001.0), Some(105_000_005.0)]),
+            observations("R07", &[Some(19_000_001.0), None, Some(-120.4)]),
+        ];
+        let obs = dir.join("fixture.rnx");
+        fs::write(&obs, lines.join("\n") + "\n").unwrap();
+        let args = convert_args(&[
+            "--station",
+            "NJIT",
+            "--country",
+            "USA",
+            "--obs-sampling-secs",
+            "30",
+        ]);
+        let dt = utc(2026, 3, 1, 12, 0);
+
+        write_qc_summary(&args, dt, &[obs.as_path()], &dir).unwrap();
+        let summary: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(qc_summary_path(&args, dt, &dir).unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(summary["epochs"], 2);
+        assert_eq!(summary["expected_epochs"], 120);
+        assert_eq!(summary["epoch_completeness_percent"], 1.7);
+        assert_eq!(summary["first_epoch"], "2026-03-01T12:00:00.000Z");
+        assert_eq!(summary["last_epoch"], "2026-03-01T12:00:30.000Z");
+        assert_eq!(summary["observations_expected"], 12);
+        assert_eq!(summary["observations_present"], 10);
+        assert_eq!(summary["observation_completeness_percent"], 83.3);
+        assert_eq!(summary["satellites"], json!({"G": 2, "R": 1}));
+        assert_eq!(
+            summary["satellite_ids"],
+            json!({"G": ["G01", "G05"], "R": ["R07"]})
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
 
-// List UBX files in data_dir that belong to a UTC hour prefix (YYYYMMDD_HH...).
-fn list_hour_ubx_files(data_dir: &Path, prefix: &str) -> Result<Vec<PathBuf>> {
-    let mut files = Vec::new();
-    for entry in fs::read_dir(data_dir)
-        .with_context(|| format!("reading data directory failed: {}", data_dir.display()))?
-    {
-        let entry = entry.with_context(|| format!("iterating {}", data_dir.display()))?;
-        if !entry
-            .file_type()
-            .with_context(|| format!("reading metadata for {}", entry.path().display()))?
-            .is_file()
-        {
-            continue;
+    #[cfg(unix)]
+    #[test]
+    fn merge_without_keep_hourly_forgets_the_hourly_observations() {
+        use crate::commands::verify_archive::run_verify_archive;
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = scratch_dir("daily-forget");
+        let gfzrnx = root.join("gfzrnx");
+        fs::write(
+            &gfzrnx,
+            "#!/bin/sh\nwhile [ $# -gt 0 ]; do\n  if [ \"$1\" = -fout ]; then printf 'daily\\n' > \"$2\"; fi\n  shift\ndone\n",
+        )
+        .unwrap();
+        fs::set_permissions(&gfzrnx, fs::Permissions::from_mode(0o755)).unwrap();
+        let (scratch, args) = scratch_convert_args(
+            "daily-forget-args",
+            &[
+                "--daily",
+                "--day-manifest",
+                "--write-checksums",
+                "--upload-queue",
+                "--obs-archive-subdir",
+                "obs",
+                "--gfzrnx-path",
+                gfzrnx.to_str().unwrap(),
+            ],
+        );
+        let hours = [utc(2026, 3, 1, 0, 0), utc(2026, 3, 1, 1, 0)];
+        for dt in hours {
+            write_hour_ubx(&args, dt);
+            assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
         }
 
-        let path = entry.path();
-        if path.extension() != Some(OsStr::new("ubx")) {
-            continue;
+        let day = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap();
+        assert!(merge_daily_observations(&args, day).unwrap());
+
+        let day_dir = args.archive_dir.join("2026").join("060");
+        let manifest: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(day_dir.join(DAY_MANIFEST_FILE_NAME)).unwrap(),
+        )
+        .unwrap();
+        for dt in hours {
+            let key = dt.format("%Y-%m-%dT%H:00:00Z").to_string();
+            let files = manifest["hours"][&key]["files"].as_array().unwrap();
+            let nav = format!("{}.rnx.gz", nav_product_stem(&args, dt, "MN").unwrap());
+            assert_eq!(files.len(), 1, "{key}");
+            assert_eq!(files[0]["name"], nav.as_str());
+            let prefix = hour_product_prefix(&args, dt).unwrap();
+            assert!(!day_dir.join(format!("{prefix}.ready")).exists());
+        }
+        let queued: Vec<String> = UploadQueue::new(&args.archive_dir)
+            .load()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["path"].as_str().unwrap().to_string())
+            .collect();
+        let daily = daily_obs_product_stem(&args, hours[0]).unwrap();
+        assert!(
+            queued.iter().any(|path| path.contains(&daily)),
+            "{queued:?}"
+        );
+        for dt in hours {
+            let obs = obs_product_stem(&args, dt).unwrap();
+            assert!(!queued.iter().any(|path| path.contains(&obs)), "{queued:?}");
         }
 
-        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
-            continue;
+        let cli = Cli::parse_from([
+            "gnss2tec-logger",
+            "verify-archive",
+            "--archive-dir",
+            args.archive_dir.to_str().unwrap(),
+        ]);
+        let AppCommand::VerifyArchive(verify_args) = cli.command else {
+            panic!("expected verify-archive");
         };
-        if file_name.starts_with(prefix) {
-            files.push(path);
-        }
+        run_verify_archive(verify_args).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        fs::remove_dir_all(&scratch).unwrap();
     }
 
-    files.sort();
-    Ok(files)
-}
-
-// Best-effort delete helper used by cleanup paths.
-fn remove_file_if_exists(path: &Path) -> Result<()> {
-    match fs::remove_file(path) {
-        Ok(()) => Ok(()),
-        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
-        Err(err) => Err(err).with_context(|| format!("removing file failed: {}", path.display())),
+    // A gzipped OBS header whose GPS record continues on a second `SYS / # / OBS TYPES` line.
+    fn write_obs_header_gz(path: &Path) {
+        let record = |content: &str, label: &str| format!("{content:<60}{label}\n");
+        let header = [
+            record(
+                "     3.04           OBSERVATION DATA    M",
+                "RINEX VERSION / TYPE",
+            ),
+            record(
+                "G   14 C1C L1C D1C S1C C2L L2L D2L S2L C5Q L5Q D5Q S5Q C1L",
+                "SYS / # / OBS TYPES",
+            ),
+            record("       L1L", "SYS / # / OBS TYPES"),
+            record("E    4 C1C L1C D1C S1C", "SYS / # / OBS TYPES"),
+            record("R    4 C1C L1C D1C S1C", "SYS / # / OBS TYPES"),
+            record("", "END OF HEADER"),
+            record("C    4 C2I L2I D2I S2I", "SYS / # / OBS TYPES"),
+        ]
+        .concat();
+        write_gz(path, header.as_bytes());
     }
-}
 
-// Return a non-colliding destination path within one directory.
-fn unique_destination_path(dst_dir: &Path, file_name: &OsStr) -> PathBuf {
-    let first_try = dst_dir.join(file_name);
-    if !first_try.exists() {
-        return first_try;
+    #[test]
+    fn obs_header_systems_skip_continuation_lines() {
+        let root = scratch_dir("tec-header");
+        let obs = root.join("obs.rnx.gz");
+        write_obs_header_gz(&obs);
+        assert_eq!(read_obs_header_systems(&obs).unwrap(), ["G", "E", "R"]);
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    let base = file_name.to_string_lossy();
-    for idx in 1.. {
-        let candidate = dst_dir.join(format!("{base}.dup{idx}"));
-        if !candidate.exists() {
-            return candidate;
+    #[test]
+    fn tec_sidecar_pairs_the_obs_product_with_its_nav_products() {
+        let (root, args) = scratch_convert_args(
+            "tec-sidecar",
+            &[
+                "--tec-sidecar",
+                "--approx-xyz",
+                "1334000.1,-4654000.2,4138000.3",
+            ],
+        );
+        let dt = utc(2026, 3, 1, 12, 0);
+        let work_dir = root.join("work");
+        fs::create_dir_all(&work_dir).unwrap();
+        let obs_name = format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap());
+        let obs = work_dir.join(&obs_name);
+        write_obs_header_gz(&obs);
+        let nav_names: Vec<String> = ["GN", "EN"]
+            .iter()
+            .map(|kind| format!("{}.rnx.gz", nav_product_stem(&args, dt, kind).unwrap()))
+            .collect();
+        let mut outputs = vec![obs];
+        for name in &nav_names {
+            let nav = work_dir.join(name);
+            write_gz(&nav, b"navigation product\n");
+            outputs.push(nav);
         }
+
+        let path = write_tec_sidecar(&args, dt, &outputs, &work_dir)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            path.file_name().unwrap().to_string_lossy(),
+            format!("{}.tec.json", hour_product_prefix(&args, dt).unwrap())
+        );
+        let sidecar: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(sidecar["hour"], "2026-03-01T12:00:00Z");
+        assert_eq!(sidecar["obs"], obs_name.as_str());
+        assert_eq!(sidecar["nav"], json!(nav_names));
+        assert_eq!(sidecar["sampling_interval_secs"], args.obs_sampling_secs);
+        assert_eq!(sidecar["constellations"], json!(["G", "E", "R"]));
+        assert_eq!(
+            sidecar["approx_position_xyz"],
+            json!([1334000.1, -4654000.2, 4138000.3])
+        );
+        fs::remove_dir_all(&root).unwrap();
     }
 
-    unreachable!("duplicate suffix search should always find an unused path");
-}
+    #[test]
+    fn tec_sidecar_needs_a_nav_product() {
+        let (root, args) = scratch_convert_args("tec-sidecar-no-nav", &["--tec-sidecar"]);
+        let dt = utc(2026, 3, 1, 12, 0);
+        let work_dir = root.join("work");
+        fs::create_dir_all(&work_dir).unwrap();
+        let obs = work_dir.join(format!("{}.rnx.gz", obs_product_stem(&args, dt).unwrap()));
+        write_obs_header_gz(&obs);
+
+        assert!(
+            write_tec_sidecar(&args, dt, &[obs], &work_dir)
+                .unwrap()
+                .is_none()
+        );
+        assert!(fs::read_dir(&work_dir).unwrap().all(|entry| {
+            !entry
+                .unwrap()
+                .file_name()
+                .to_string_lossy()
+                .ends_with(".tec.json")
+        }));
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-// Move file into destination directory, with copy+delete fallback for cross-device moves.
-fn move_into_dir(src: &Path, dst_dir: &Path) -> Result<PathBuf> {
-    let file_name = src
-        .file_name()
-        .ok_or_else(|| anyhow!("missing file name for source: {}", src.display()))?;
-    let dst = unique_destination_path(dst_dir, file_name);
+    #[test]
+    fn only_files_older_than_min_file_age_are_selected() {
+        let root = scratch_dir("min-file-age");
+        let old = root.join("20260301_120000.ubx");
+        let fresh = root.join("20260301_123000.ubx");
+        for path in [&old, &fresh] {
+            fs::write(path, ubx_frame(0x02, 0x15, 16)).unwrap();
+        }
+        File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(600))
+            .unwrap();
+
+        let (files, active) =
+            list_hour_ubx_files(&root, "20260301_12", Duration::from_secs(60)).unwrap();
+        assert_eq!(files, vec![old]);
+        assert_eq!(active, vec![fresh]);
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-    match fs::rename(src, &dst) {
-        Ok(()) => Ok(dst),
-        Err(_) => {
-            fs::copy(src, &dst).with_context(|| {
-                format!(
-                    "copying file to archive failed: {} -> {}",
-                    src.display(),
-                    dst.display()
-                )
-            })?;
-            fs::remove_file(src)
-                .with_context(|| format!("removing source file failed: {}", src.display()))?;
-            Ok(dst)
+    #[cfg(unix)]
+    #[test]
+    fn retention_prunes_only_valid_expired_days() {
+        let archive = scratch_dir("retention-plain");
+        let today = archive_date("utc", Utc::now()).unwrap();
+        let day_dir = |date: NaiveDate| {
+            archive
+                .join(format!("{:04}", date.year()))
+                .join(format!("{:03}", date.ordinal()))
+        };
+        let expired = day_dir(today - ChronoDuration::days(40));
+        let kept = day_dir(today - ChronoDuration::days(5));
+        let not_a_day = archive.join("2025").join("notes");
+        let invalid_doy = archive.join("2025").join("366");
+        let not_a_year = archive.join("misc").join("001");
+        for dir in [&expired, &kept, &not_a_day, &invalid_doy, &not_a_year] {
+            fs::create_dir_all(dir).unwrap();
         }
+        // An expired-looking day that is only a symlink into another tree.
+        let elsewhere = archive.join("elsewhere");
+        fs::create_dir_all(&elsewhere).unwrap();
+        fs::write(elsewhere.join("keep.rnx.gz"), b"keep").unwrap();
+        let linked = day_dir(today - ChronoDuration::days(50));
+        fs::create_dir_all(linked.parent().unwrap()).unwrap();
+        std::os::unix::fs::symlink(&elsewhere, &linked).unwrap();
+
+        assert_eq!(prune_expired_archive_days(&archive, "utc", 30).unwrap(), 1);
+        assert!(!expired.exists());
+        for dir in [&kept, &not_a_day, &invalid_doy, &not_a_year] {
+            assert!(dir.is_dir(), "{}", dir.display());
+        }
+        assert!(fs::symlink_metadata(&linked).unwrap().is_symlink());
+        assert!(elsewhere.join("keep.rnx.gz").is_file());
+        fs::remove_dir_all(&archive).unwrap();
     }
-}
 
-// Truncate a DateTime to top-of-hour in UTC for deterministic hourly windowing.
-fn floor_to_hour(dt: DateTime<Utc>) -> DateTime<Utc> {
-    dt.with_minute(0)
-        .and_then(|v| v.with_second(0))
-        .and_then(|v| v.with_nanosecond(0))
-        .expect("UTC floor-to-hour should always be valid")
+    #[test]
+    fn retention_cutoff_follows_the_archive_timezone() {
+        let archive = scratch_dir("retention-timezone");
+        // East of the date line it is always at least one day later than west of it, so the
+        // last day kept at -12:00 has already expired at +14:00.
+        let west_cutoff = archive_date("-12:00", Utc::now()).unwrap() - ChronoDuration::days(30);
+        let day = archive
+            .join(format!("{:04}", west_cutoff.year()))
+            .join(format!("{:03}", west_cutoff.ordinal()));
+        fs::create_dir_all(&day).unwrap();
+
+        assert_eq!(
+            prune_expired_archive_days(&archive, "-12:00", 30).unwrap(),
+            0
+        );
+        assert!(day.is_dir());
+        assert_eq!(
+            prune_expired_archive_days(&archive, "+14:00", 30).unwrap(),
+            1
+        );
+        assert!(!day.exists());
+        fs::remove_dir_all(&archive).unwrap();
+    }
 }
@@ -0,0 +1,165 @@
+use crate::args::{DoctorArgs, ObsOutputFormat};
+use crate::commands::convert::{
+    resolve_convbin_program, resolve_rnx2crx_program, run_checked_command,
+};
+use crate::commands::log::parse_ubx_config;
+use crate::shared::lock::LockGuard;
+use crate::shared::serial::{
+    AUTO_SERIAL_PORT, SerialSettings, UsbPortFilter, acquire_device_lock, open_serial_port,
+    resolve_serial_port,
+};
+use anyhow::{Context, Result, bail};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::path::Path;
+use std::process::Command;
+
+// Public doctor command entrypoint.
+// Runs every environment probe, prints one PASS/WARN/FAIL line each,
+// and fails if any hard check failed.
+pub fn run_doctor(args: DoctorArgs) -> Result<()> {
+    let mut report = DoctorReport::default();
+
+    report.check("config file parses", true, || {
+        let packets = parse_ubx_config(&args.config_file)?;
+        if packets.is_empty() {
+            bail!("no UBX commands found in {}", args.config_file.display());
+        }
+        Ok(format!(
+            "{} UBX command(s) in {}",
+            packets.len(),
+            args.config_file.display()
+        ))
+    });
+
+    report.check("convbin runs", true, || {
+        check_program_help("convbin", &args.convbin_path, resolve_convbin_program)
+    });
+    let hatanaka = args.hatanaka || matches!(args.obs_output_format, ObsOutputFormat::Hatanaka);
+    report.check("rnx2crx runs", hatanaka, || {
+        check_program_help("rnx2crx", &args.rnx2crx_path, resolve_rnx2crx_program)
+    });
+
+    report.check("data directory writable", true, || {
+        check_dir_writable(&args.data_dir)
+    });
+    report.check("archive directory writable", true, || {
+        check_dir_writable(&args.archive_dir)
+    });
+
+    report.check("logger lock acquirable", true, || {
+        check_lock(&args.log_lock_file)
+    });
+    report.check("convert lock acquirable", true, || {
+        check_lock(&args.convert_lock_file)
+    });
+    // With `--serial-port auto` the lock and open checks need the detected device; they are
+    // skipped when detection already failed.
+    let mut serial_port = (args.serial_port != AUTO_SERIAL_PORT).then(|| args.serial_port.clone());
+    if serial_port.is_none() {
+        report.check("serial port detected", true, || {
+            let port = resolve_serial_port(
+                &args.serial_port,
+                UsbPortFilter {
+                    vid: args.usb_vid,
+                    pid: args.usb_pid,
+                },
+            )?;
+            serial_port = Some(port.clone());
+            Ok(port)
+        });
+    }
+
+    if let Some(serial_port) = &serial_port {
+        if !args.no_device_lock {
+            report.check("device lock acquirable", true, || {
+                drop(acquire_device_lock(serial_port, &args.device_lock_dir)?);
+                Ok(format!("{serial_port} is not claimed by another instance"))
+            });
+        }
+
+        report.check("serial port opens", true, || {
+            let settings = SerialSettings::new(
+                args.baud_rate,
+                args.data_bits,
+                args.parity,
+                args.stop_bits,
+                args.flow_control,
+            )?;
+            drop(open_serial_port(
+                serial_port,
+                &settings,
+                args.read_timeout_ms,
+            )?);
+            Ok(format!("{serial_port} @ {}", settings.describe()))
+        });
+    }
+
+    if report.failures > 0 {
+        bail!("{} doctor check(s) failed", report.failures);
+    }
+    println!("All doctor checks passed");
+    Ok(())
+}
+
+// Tracks hard failures while printing one line per check.
+#[derive(Default)]
+struct DoctorReport {
+    failures: usize,
+}
+
+impl DoctorReport {
+    // Soft checks (`hard == false`) print WARN on failure and do not affect the exit status.
+    fn check(&mut self, label: &str, hard: bool, probe: impl FnOnce() -> Result<String>) {
+        match probe() {
+            Ok(detail) => println!("PASS  {label}: {detail}"),
+            Err(err) if hard => {
+                self.failures += 1;
+                println!("FAIL  {label}: {err:#}");
+            }
+            Err(err) => println!("WARN  {label}: {err:#}"),
+        }
+    }
+}
+
+// Run `<tool> -h` through the same resolution and error surfacing as conversion.
+fn check_program_help(
+    name: &str,
+    configured_path: &Path,
+    resolve: fn(&Path) -> (OsString, bool),
+) -> Result<String> {
+    let (program, used_path_fallback) = resolve(configured_path);
+    let mut cmd = Command::new(&program);
+    cmd.arg("-h");
+    run_checked_command(&mut cmd, &format!("{name} availability check"))?;
+    if used_path_fallback {
+        Ok(format!(
+            "{} not found; using {} from PATH",
+            configured_path.display(),
+            name
+        ))
+    } else {
+        Ok(configured_path.display().to_string())
+    }
+}
+
+// Create the directory if needed and prove a file can be written there.
+fn check_dir_writable(dir: &Path) -> Result<String> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("creating directory failed: {}", dir.display()))?;
+    let probe = dir.join(".gnss2tec-doctor-probe");
+    OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&probe)
+        .with_context(|| format!("writing probe file failed: {}", probe.display()))?;
+    fs::remove_file(&probe)
+        .with_context(|| format!("removing probe file failed: {}", probe.display()))?;
+    Ok(dir.display().to_string())
+}
+
+fn check_lock(path: &Path) -> Result<String> {
+    drop(LockGuard::acquire(path)?);
+    Ok(path.display().to_string())
+}
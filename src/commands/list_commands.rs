@@ -0,0 +1,34 @@
+use crate::commands::log::UBX_CONFIG_COMMANDS;
+use anyhow::Result;
+
+// Public list-commands entrypoint.
+// Prints the `!UBX` commands the config parser understands, straight from its dispatch table.
+pub fn run_list_commands() -> Result<()> {
+    let name_width = UBX_CONFIG_COMMANDS
+        .iter()
+        .map(|entry| entry.name.len())
+        .max()
+        .unwrap_or(0);
+    let args_width = UBX_CONFIG_COMMANDS
+        .iter()
+        .map(|entry| entry.arguments.len())
+        .max()
+        .unwrap_or(0)
+        .max("ARGS".len());
+
+    println!(
+        "{:name_width$}  {:args_width$}  DESCRIPTION",
+        "COMMAND", "ARGS"
+    );
+    for entry in UBX_CONFIG_COMMANDS {
+        println!(
+            "{:name_width$}  {:args_width$}  {}",
+            entry.name, entry.arguments, entry.summary
+        );
+    }
+    println!();
+    println!(
+        "Config lines look like `!UBX <COMMAND> <args...>`; numbers may be decimal or 0x hex."
+    );
+    Ok(())
+}
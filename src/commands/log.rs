@@ -1,71 +1,301 @@
-use crate::args::LogArgs;
+use crate::args::{Durability, LogArgs};
+use crate::commands::convert::{floor_to_hour, run_checked_command};
+use crate::commands::probe::probe_receiver;
+use crate::shared::chunk_queue::{ChunkQueue, DropCounts};
+use crate::shared::clock_skew::ClockSkewGuard;
+use crate::shared::constellation_stats::ConstellationStats;
+use crate::shared::diskspace::{DiskSpaceGuard, SpaceEvent, is_out_of_space};
+use crate::shared::epoch_rotation::EpochRotation;
+use crate::shared::error::{PipelineError, PipelineResultExt};
+use crate::shared::event::{EventKind, Level, emit_event, log_at};
+use crate::shared::forward::Forwarder;
+use crate::shared::health_socket::{self, HealthSocket};
+use crate::shared::link_check::LinkCheck;
 use crate::shared::lock::LockGuard;
+use crate::shared::logfile::{
+    HourBoundary, LogWriter, SyncData, acquire_hour_file_lock, log_file_dir, log_file_name,
+    log_file_token,
+};
 use crate::shared::nmea::NmeaMonitor;
-use crate::shared::signal::install_ctrlc_handler;
+use crate::shared::read_cadence::ReadCadence;
+use crate::shared::sd_notify::Watchdog;
+use crate::shared::serial::{SerialSettings, UsbPortFilter, acquire_device_lock};
+use crate::shared::shell::split_command_line;
+use crate::shared::signal::{install_ctrlc_handler, install_reload_handler};
+use crate::shared::source::{
+    ByteSource, ConnectKind, InputSpec, OnConnect, ReconnectBackoff, ReconnectingSource,
+};
+use crate::shared::stale::check_stale_ubx_files;
+use crate::shared::stall::StallDetector;
+use crate::shared::state_file::{LoggerState, load_logger_state, save_logger_state};
+use crate::shared::stats_csv::{StatsCsv, StatsRow};
+use crate::shared::status_file::{LoggerStatus, write_logger_status};
+use crate::shared::time_drift::TimeDriftMonitor;
+use crate::shared::ubx::{
+    self, CLASS_CFG, ID_CFG_NAV5, ID_CFG_RATE, ID_CFG_RST, ID_CFG_TMODE3, ID_CFG_VALSET,
+    await_ubx_frame,
+};
+use crate::shared::ubx_filter::LogClassFilter;
 use anyhow::{Context, Result, anyhow, bail};
-use chrono::Utc;
-use serialport::SerialPort;
-use std::fs::{self, File, OpenOptions};
+use chrono::{DateTime, Utc};
+use std::fs;
 use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::process::Command;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::thread;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 use ublox::cfg_gnss::{CfgGnssBuilder, GnssConfigBlock, GnssId};
 use ublox::cfg_msg::CfgMsgAllPortsBuilder;
 use ublox::cfg_rate::{AlignmentToReferenceTime, CfgRateBuilder};
 
 // Public log command entrypoint. This mode configures the receiver and then streams UBX bytes to disk.
-pub fn run_log(args: LogArgs) -> Result<()> {
-    let running = install_ctrlc_handler()?;
-    run_log_with_signal(args, running)
+pub fn run_log(args: LogArgs) -> Result<(), PipelineError> {
+    if args.probe_receiver {
+        return probe_receiver(&args).or_pipeline(PipelineError::Input);
+    }
+    let running = install_ctrlc_handler().or_pipeline(PipelineError::Signal)?;
+    let reload_requested = install_reload_handler().or_pipeline(PipelineError::Signal)?;
+    run_log_with_signal(args, running, reload_requested)
 }
 
 // Shared logger implementation used by both `log` and `run` commands.
 // A shared run flag allows run-mode to coordinate shutdown between logger and converter thread.
-pub(crate) fn run_log_with_signal(args: LogArgs, running: Arc<AtomicBool>) -> Result<()> {
+pub(crate) fn run_log_with_signal(
+    args: LogArgs,
+    running: Arc<AtomicBool>,
+    reload_requested: Arc<AtomicBool>,
+) -> Result<(), PipelineError> {
     // Prepare runtime output folder and enforce single-instance execution.
-    fs::create_dir_all(&args.data_dir).with_context(|| {
-        format!(
-            "creating data directory failed: {}",
-            args.data_dir.display()
-        )
-    })?;
-    let _lock = LockGuard::acquire(&args.lock_file)?;
+    fs::create_dir_all(&args.data_dir)
+        .with_context(|| {
+            format!(
+                "creating data directory failed: {}",
+                args.data_dir.display()
+            )
+        })
+        .or_pipeline(PipelineError::Storage)?;
+    let _lock = LockGuard::acquire(&args.lock_file).or_pipeline(PipelineError::Lock)?;
+    let _hour_file_lock = if args.single_file_per_hour {
+        Some(acquire_hour_file_lock(&args.data_dir).or_pipeline(PipelineError::Lock)?)
+    } else {
+        None
+    };
+    check_stale_ubx_files(
+        &args.data_dir,
+        args.stale_max_age_hours,
+        args.quarantine_stale,
+    )
+    .or_pipeline(PipelineError::Storage)?;
+
+    let log_filter = LogClassFilter::from_spec(args.log_classes.as_deref())
+        .or_pipeline(PipelineError::InvalidConfig)?;
 
     // Parse config file and push UBX commands to the receiver before logging starts.
-    let packets = parse_ubx_config(&args.config_file)?;
+    let packets = parse_ubx_config(&args.config_file).or_pipeline(PipelineError::ConfigParse)?;
     if packets.is_empty() {
-        bail!(
-            "no UBX commands found in configuration file: {}",
-            args.config_file.display()
+        return Err(PipelineError::ConfigParse(
+            format!(
+                "no UBX commands found in configuration file: {}",
+                args.config_file.display()
+            )
+            .into(),
+        ));
+    }
+
+    let serial_settings = SerialSettings::new(
+        args.baud_rate,
+        args.data_bits,
+        args.parity,
+        args.stop_bits,
+        args.flow_control,
+    )
+    .or_pipeline(PipelineError::InvalidConfig)?;
+    // Declared before the input so the post-close command runs after the port is released.
+    let _port_commands = PortCommands::start(
+        args.pre_open_command.as_deref(),
+        args.post_close_command.as_deref(),
+    )
+    .or_pipeline(PipelineError::SerialOpen)?;
+    let input = match args.input_file.as_ref() {
+        Some(path) => InputSpec::File {
+            path: path.clone(),
+            realtime: args.replay_realtime,
+        },
+        None => InputSpec::from_args(
+            args.input.as_deref(),
+            &args.serial_port,
+            serial_settings,
+            UsbPortFilter {
+                vid: args.usb_vid,
+                pid: args.usb_pid,
+            },
+        )
+        .or_pipeline(PipelineError::InvalidConfig)?,
+    };
+    let _device_lock = match input.serial_path() {
+        Some(path) if !args.no_device_lock => Some(
+            acquire_device_lock(path, &args.device_lock_dir).or_pipeline(PipelineError::Lock)?,
+        ),
+        _ => None,
+    };
+
+    // Receiver config is pushed on the initial connect and again after every reconnect,
+    // since a receiver that dropped off the bus may have power-cycled.
+    let command_gap = Duration::from_millis(args.command_gap_ms);
+    let reset_settle = Duration::from_millis(args.reset_settle_ms);
+    let config_label = args.config_file.display().to_string();
+    // A replayed file has no receiver to answer, and the check would eat the start of the replay.
+    let verify_window = (args.verify_ubx_ms > 0 && !input.is_replay())
+        .then(|| Duration::from_millis(args.verify_ubx_ms));
+    let packets = Arc::new(Mutex::new(packets));
+    let packets_for_connect = Arc::clone(&packets);
+    // CFG-RST goes out on the first connect only; see `send_ubx_packets`.
+    let mut first_connect = true;
+    let on_connect: OnConnect = Box::new(move |source: &mut dyn ByteSource, kind| {
+        let packets = packets_for_connect
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let send_reset = std::mem::take(&mut first_connect);
+        let sent = send_ubx_packets(source, &packets, command_gap, reset_settle, send_reset)?;
+        emit_event(
+            EventKind::Config,
+            &format!(
+                "Sent {} UBX configuration commands from {} to {}",
+                sent,
+                config_label,
+                source.label()
+            ),
         );
+        // The check swallows what it reads, so a resend on the live connection skips it.
+        if let Some(window) = verify_window
+            && kind == ConnectKind::Opened
+        {
+            await_ubx_frame(source, window)
+                .with_context(|| format!("receiver on {} failed the UBX check", source.label()))?;
+        }
+        Ok(())
+    });
+    let port = ReconnectingSource::connect(
+        input,
+        Duration::from_millis(args.read_timeout_ms),
+        ReconnectBackoff::new(
+            Duration::from_millis(args.reconnect_delay_ms),
+            Duration::from_secs(args.reconnect_max_backoff_secs),
+        ),
+        on_connect,
+    )
+    .or_pipeline(PipelineError::SerialOpen)?;
+    let input_label = port.label().to_string();
+    let mut watchdog = Watchdog::from_env();
+    watchdog.ready();
+
+    // Receiver reads run on their own thread and feed the writer through a bounded buffer.
+    let queue = Arc::new(ChunkQueue::new(args.ring_buffer_bytes, args.overflow));
+    let _health_socket = match args.health_socket.as_deref() {
+        Some(path) => Some(
+            HealthSocket::bind(path, Arc::clone(&queue))
+                .or_pipeline(PipelineError::InvalidConfig)?,
+        ),
+        None => None,
+    };
+    let reader = InputReader {
+        port,
+        queue: Arc::clone(&queue),
+        read_buffer_bytes: args.read_buffer_bytes,
+        running,
+        reload_requested,
+        config_file: args.config_file.clone(),
+        packets,
+        stall_detector: StallDetector::new(args.stall_timeout_secs),
+        stall_resend_config: args.stall_resend_config,
+        link_check: LinkCheck::new(args.link_check_secs),
+        watchdog,
+        read_cadence: ReadCadence::new(args.read_gap_warn_ms),
     }
+    .spawn()
+    .or_pipeline(PipelineError::Input)?;
 
-    let mut port = serialport::new(&args.serial_port, args.baud_rate)
-        .timeout(Duration::from_millis(args.read_timeout_ms))
-        .open()
-        .with_context(|| {
-            format!(
-                "opening serial port failed: {} @ {}",
-                args.serial_port, args.baud_rate
-            )
-        })?;
-
-    send_ubx_packets(
-        &mut *port,
-        &packets,
-        Duration::from_millis(args.command_gap_ms),
-    )?;
-    eprintln!(
-        "Sent {} UBX configuration commands from {}",
-        packets.len(),
-        args.config_file.display()
+    let options = StreamOptions {
+        write_buffer_bytes: 0,
+        rotation_durability: args.durability,
+    };
+    let written = write_log_stream(
+        &args,
+        &options,
+        &queue,
+        &input_label,
+        log_filter,
+        &mut |_event| {},
+    );
+    queue.close();
+    // A read error closes the queue early and is the root cause of any write-side fallout.
+    join_input_reader(reader).or_pipeline(PipelineError::Input)?;
+    let (total_bytes, dropped) = written.or_pipeline(PipelineError::LogWrite)?;
+    emit_event(
+        EventKind::Shutdown,
+        &format!(
+            "Logger stopped, wrote {} bytes{}",
+            total_bytes,
+            describe_dropped(dropped)
+        ),
     );
+    Ok(())
+}
+
+// What the writer loop reports to its caller while it runs; `run` feeds the conversion
+// worker from these, `log` ignores them.
+pub(crate) enum StreamEvent {
+    // Writing paused for lack of disk space.
+    DiskPaused,
+    // The UTC hour starting here is closed for good: its file was rotated away, or the hour
+    // ended while writing was paused.
+    HourClosed(DateTime<Utc>),
+}
+
+// Writer settings that differ between `log` and `run`.
+pub(crate) struct StreamOptions {
+    // Userspace buffer in front of each hourly file (`run --write-buffer-bytes`).
+    pub write_buffer_bytes: usize,
+    // Durability of a file closed at rotation. `run` converts it right away, so it always
+    // syncs; a crash right after rotation must not leave the hour half-persisted.
+    pub rotation_durability: Durability,
+}
 
-    // Main logging loop: read serial bytes, rotate files hourly, and flush periodically.
-    let mut buffer = vec![0_u8; args.read_buffer_bytes.max(1_024)];
+// Writer side of the logger: drain `queue` into hourly files until the reader closes it.
+// Returns the bytes written and the chunks the ring buffer dropped.
+pub(crate) fn write_log_stream(
+    args: &LogArgs,
+    options: &StreamOptions,
+    queue: &ChunkQueue,
+    input_label: &str,
+    log_filter: LogClassFilter,
+    on_event: &mut dyn FnMut(StreamEvent),
+) -> Result<(u64, DropCounts)> {
+    write_log_stream_at(
+        args,
+        options,
+        queue,
+        input_label,
+        log_filter,
+        on_event,
+        &Utc::now,
+    )
+}
+
+// `write_log_stream` with the wall clock injected, so tests can cross an hour boundary.
+pub(crate) fn write_log_stream_at(
+    args: &LogArgs,
+    options: &StreamOptions,
+    queue: &ChunkQueue,
+    input_label: &str,
+    mut log_filter: LogClassFilter,
+    on_event: &mut dyn FnMut(StreamEvent),
+    wall_now: &dyn Fn() -> DateTime<Utc>,
+) -> Result<(u64, DropCounts)> {
+    // Main logging loop: drain received bytes, rotate files hourly, and flush periodically.
+    let poll_interval = Duration::from_millis(args.read_timeout_ms.max(1));
     let flush_interval = Duration::from_secs(args.flush_interval_secs.max(1));
     let stats_interval = if args.stats_interval_secs == 0 {
         None
@@ -75,42 +305,162 @@ pub(crate) fn run_log_with_signal(args: LogArgs, running: Arc<AtomicBool>) -> Re
     let mut last_flush = Instant::now();
     let mut last_stats = Instant::now();
     let mut stats_window_bytes: u64 = 0;
-    let mut total_bytes: u64 = 0;
-    let mut nmea_monitor = NmeaMonitor::new(args.nmea_log_interval_secs, args.nmea_log_format);
-
-    let (mut active_hour_key, mut writer, current_path) = open_new_log_file(&args.data_dir)?;
-    eprintln!("Logging UBX data to {}", current_path.display());
-
-    while running.load(Ordering::SeqCst) {
-        match port.read(&mut buffer) {
-            Ok(0) => {}
-            Ok(size) => {
-                writer
-                    .write_all(&buffer[..size])
-                    .context("writing UBX bytes to file failed")?;
-                total_bytes += size as u64;
-                stats_window_bytes += size as u64;
-                nmea_monitor.ingest(&buffer[..size]);
+    // Cumulative across restarts via the state file; session bytes are reported at shutdown.
+    let restored = load_logger_state(&args.data_dir);
+    let resumed_bytes = restored.total_bytes;
+    let mut total_bytes = resumed_bytes;
+    if resumed_bytes > 0 {
+        emit_event(
+            EventKind::Startup,
+            &format!(
+                "Resuming byte counter at {} bytes (last hour {})",
+                resumed_bytes,
+                restored.last_hour_key.as_deref().unwrap_or("unknown")
+            ),
+        );
+    }
+    let mut nmea_monitor = NmeaMonitor::new(args.nmea_log_interval_secs, args.nmea_log_format)
+        .with_position_alert(
+            args.position_alert_meters,
+            Duration::from_secs(args.position_alert_reset_secs),
+        )
+        .with_per_talker(args.nmea_per_talker);
+    let mut constellation_stats = ConstellationStats::new(stats_interval.is_some());
+    let mut time_drift = TimeDriftMonitor::new(args.log_time_drift_secs);
+    let mut clock_skew = ClockSkewGuard::new(args.max_clock_skew_secs, args.allow_clock_skew);
+    let forwarder = args
+        .forward
+        .as_deref()
+        .map(|target| Forwarder::start(target, args.forward_buffer_bytes))
+        .transpose()?;
+    let mut epoch_rotation = EpochRotation::new(
+        args.rotate_on_epoch
+            .then(|| Duration::from_secs(args.rotate_on_epoch_grace_secs)),
+    );
+    let mut stats_csv = match args.stats_file.as_deref() {
+        Some(path) if stats_interval.is_some() => Some(StatsCsv::open(path)?),
+        _ => None,
+    };
+    let mut dropped_total = DropCounts::default();
+
+    let opened_at = wall_now();
+    let (mut active_hour_key, mut active_hour_start, mut writer, mut current_path) =
+        open_new_log_file(args, options, opened_at)?;
+    let mut hour_boundary = HourBoundary::new(opened_at, Instant::now());
+    emit_event(
+        EventKind::Startup,
+        &format!("Logging UBX data to {}", current_path.display()),
+    );
+    health_socket::record_current_file(&current_path);
+    let mut last_rotation = wall_now();
+    let mut disk_guard = DiskSpaceGuard::new(&args.data_dir, args.min_free_bytes);
+
+    // The reader closes the queue on shutdown (or a read error); drain it, then stop.
+    while !queue.is_drained() {
+        if let Some(chunk) = queue.pop_timeout(poll_interval) {
+            let logged = log_filter.apply(&chunk);
+            if let Some(forwarder) = &forwarder {
+                forwarder.send(logged);
             }
-            Err(err) if err.kind() == io::ErrorKind::TimedOut => {}
-            Err(err) => {
-                return Err(err).context("reading GNSS stream from serial port failed");
+            disk_guard
+                .write_chunk(&mut writer, epoch_rotation.pass(logged))
+                .context("writing UBX bytes to file failed")?;
+            // Counters report what reached disk, so --log-classes drops are not counted.
+            total_bytes += logged.len() as u64;
+            health_socket::record_total_bytes(total_bytes);
+            stats_window_bytes += logged.len() as u64;
+            nmea_monitor.ingest(&chunk);
+            constellation_stats.ingest(&chunk);
+            time_drift.ingest(&chunk);
+            clock_skew.ingest(&chunk);
+        }
+
+        match disk_guard.check() {
+            Some(SpaceEvent::Paused) => on_event(StreamEvent::DiskPaused),
+            Some(SpaceEvent::Resumed) => {
+                // The previous file may end mid-frame (or mid-gzip-member); start a clean one.
+                let opened_at = wall_now();
+                let (new_hour_key, new_hour_start, new_writer, path) =
+                    open_new_log_file(args, options, opened_at)?;
+                hour_boundary = HourBoundary::new(opened_at, Instant::now());
+                let old_writer = std::mem::replace(&mut writer, new_writer);
+                if let Err(err) =
+                    finish_log_writer(old_writer, &disk_guard, options.rotation_durability)
+                {
+                    emit_event(
+                        EventKind::DiskSpace,
+                        &format!("Finishing log file interrupted by full disk failed: {err}"),
+                    );
+                }
+                // A rotation waiting for an epoch is moot now; its bytes start the new file.
+                disk_guard
+                    .write_chunk(&mut writer, &epoch_rotation.release())
+                    .context("writing UBX bytes to file failed")?;
+                // The hour may have rolled over while paused.
+                if new_hour_key != active_hour_key {
+                    on_event(StreamEvent::HourClosed(active_hour_start));
+                }
+                active_hour_key = new_hour_key;
+                active_hour_start = new_hour_start;
+                emit_event(
+                    EventKind::Rotation,
+                    &format!("Resumed UBX output in {}", path.display()),
+                );
+                health_socket::record_current_file(&path);
+                current_path = path;
+                last_rotation = opened_at;
             }
+            None => {}
         }
 
-        let now = Utc::now();
-        let hour_key = now.format("%Y%m%d_%H").to_string();
-        if hour_key != active_hour_key {
-            writer.flush().context("flushing log file failed")?;
-            let (new_hour_key, new_writer, path) = open_new_log_file(&args.data_dir)?;
+        if let Some(now) = hour_boundary.crossed(Instant::now(), wall_now)
+            && !clock_skew.holds_rotation()
+            && epoch_rotation.rotation_due()
+        {
+            let (closing, opening) = epoch_rotation.take_split();
+            disk_guard
+                .write_chunk(&mut writer, &closing)
+                .context("writing UBX bytes to file failed")?;
+            // Flush and rotate quickly first to avoid any logging gaps.
+            disk_guard
+                .flush(&mut writer)
+                .context("flushing log file failed")?;
+            let closed_hour = active_hour_start;
+            let (new_hour_key, new_hour_start, new_writer, path) =
+                open_new_log_file(args, options, now)?;
+            hour_boundary = HourBoundary::new(now, Instant::now());
+            let old_writer = std::mem::replace(&mut writer, new_writer);
+            finish_log_writer(old_writer, &disk_guard, options.rotation_durability)
+                .context("finishing log file failed")?;
+            disk_guard
+                .write_chunk(&mut writer, &opening)
+                .context("writing UBX bytes to file failed")?;
             active_hour_key = new_hour_key;
-            writer = new_writer;
-            eprintln!("Rotated UBX output to {}", path.display());
+            active_hour_start = new_hour_start;
+            log_at(
+                Level::Debug,
+                EventKind::Rotation,
+                &format!(
+                    "Closed {} at hour boundary {}",
+                    current_path.display(),
+                    now.format("%Y-%m-%d %H:%M:%S%.3f UTC")
+                ),
+            );
+            emit_event(
+                EventKind::Rotation,
+                &format!("Rotated UBX output to {}", path.display()),
+            );
+            health_socket::record_current_file(&path);
+            current_path = path;
+            last_rotation = now;
+            on_event(StreamEvent::HourClosed(closed_hour));
         }
 
         if last_flush.elapsed() >= flush_interval {
-            writer.flush().context("periodic flush failed")?;
+            periodic_flush(&mut disk_guard, &mut writer, args.durability)
+                .context("periodic flush failed")?;
             last_flush = Instant::now();
+            save_state(&args.data_dir, total_bytes, &active_hour_key);
         }
 
         if let Some(interval) = stats_interval
@@ -118,60 +468,434 @@ pub(crate) fn run_log_with_signal(args: LogArgs, running: Arc<AtomicBool>) -> Re
         {
             let elapsed = last_stats.elapsed().as_secs_f64().max(0.001);
             let bps = ((stats_window_bytes as f64 * 8.0) / elapsed).round() as u64;
-            eprintln!(
-                "[STAT] {:>10} B {:>7} bps {}",
-                total_bytes, bps, args.serial_port
+            health_socket::record_bits_per_sec(bps);
+            let dropped = queue.take_dropped();
+            dropped_total.add(dropped);
+            emit_event(
+                EventKind::Stats,
+                &format!(
+                    "[STAT] {:>10} B {:>7} bps {}{}",
+                    total_bytes,
+                    bps,
+                    input_label,
+                    describe_dropped(dropped)
+                ),
             );
+            let (frames, bad_frames) = constellation_stats.take_frame_counts();
+            if let Some(csv) = stats_csv.as_mut() {
+                let row = StatsRow {
+                    timestamp: wall_now(),
+                    total_bytes,
+                    bits_per_sec: bps,
+                    frames,
+                    bad_frames,
+                    port: input_label,
+                };
+                if let Err(err) = csv.append(&row) {
+                    emit_event(EventKind::Warning, &format!("{err:#}"));
+                }
+            }
+            if let Some(summary) = constellation_stats.take_summary() {
+                emit_event(EventKind::Stats, &format!("[STAT] {summary}"));
+            }
+            let status = LoggerStatus {
+                input: input_label,
+                current_file: &current_path,
+                last_rotation,
+                total_bytes,
+                bits_per_sec: bps,
+            };
+            if let Err(err) = write_logger_status(&args.data_dir, &status) {
+                emit_event(EventKind::Warning, &format!("{err:#}"));
+            }
             stats_window_bytes = 0;
             last_stats = Instant::now();
         }
 
         nmea_monitor.maybe_emit_logs();
+        time_drift.maybe_emit_logs();
     }
 
-    writer.flush().context("final flush failed")?;
-    eprintln!("Logger stopped, wrote {} bytes", total_bytes);
-    Ok(())
+    disk_guard
+        .write_chunk(&mut writer, &epoch_rotation.release())
+        .context("writing UBX bytes to file failed")?;
+    finish_log_writer(writer, &disk_guard, args.durability).context("final flush failed")?;
+    save_state(&args.data_dir, total_bytes, &active_hour_key);
+    dropped_total.add(queue.take_dropped());
+    Ok((total_bytes - resumed_bytes, dropped_total))
+}
+
+// `--pre-open-command` / `--post-close-command` for receivers that need a `usbreset` or a
+// power toggle around their use. The pre-open command runs before the input is resolved and
+// opened (so also before `--serial-port auto` detection) and a failure aborts startup. The
+// post-close command runs when the guard drops, after the port and its locks are released,
+// on every exit path; its failure is only a warning.
+pub(crate) struct PortCommands {
+    post_close: Option<String>,
+}
+
+impl PortCommands {
+    pub(crate) fn start(pre_open: Option<&str>, post_close: Option<&str>) -> Result<Self> {
+        if let Some(command) = pre_open {
+            run_port_command("--pre-open-command", command)?;
+            emit_event(
+                EventKind::Startup,
+                &format!("Ran --pre-open-command: {command}"),
+            );
+        }
+        Ok(Self {
+            post_close: post_close.map(str::to_string),
+        })
+    }
+}
+
+impl Drop for PortCommands {
+    fn drop(&mut self) {
+        let Some(command) = self.post_close.as_deref() else {
+            return;
+        };
+        match run_port_command("--post-close-command", command) {
+            Ok(()) => emit_event(
+                EventKind::Shutdown,
+                &format!("Ran --post-close-command: {command}"),
+            ),
+            Err(err) => emit_event(
+                EventKind::Warning,
+                &format!("--post-close-command failed: {err:#}"),
+            ),
+        }
+    }
+}
+
+// Commands are split like `--post-hook` (quotes, no shell); use `sh -c '...'` for pipelines.
+fn run_port_command(flag: &str, command: &str) -> Result<()> {
+    let tokens =
+        split_command_line(command).with_context(|| format!("parsing {flag} failed: {command}"))?;
+    let Some((program, command_args)) = tokens.split_first() else {
+        bail!("{flag} is empty");
+    };
+    run_checked_command(Command::new(program).args(command_args), flag)
+}
+
+// Persist the cumulative byte counter; a failed save only costs continuity after a restart.
+pub(crate) fn save_state(data_dir: &Path, total_bytes: u64, hour_key: &str) {
+    let state = LoggerState {
+        total_bytes,
+        last_hour_key: Some(hour_key.to_string()),
+    };
+    if let Err(err) = save_logger_state(data_dir, &state) {
+        emit_event(EventKind::Warning, &format!("{err:#}"));
+    }
+}
+
+// Input side of the logging loop. It runs on its own thread so a slow disk never stalls
+// receiver reads, and it owns everything that writes back to the receiver (SIGHUP reloads
+// and stall config resends). The queue is closed when it stops, for any reason.
+pub(crate) struct InputReader {
+    pub port: ReconnectingSource,
+    pub queue: Arc<ChunkQueue>,
+    pub read_buffer_bytes: usize,
+    pub running: Arc<AtomicBool>,
+    pub reload_requested: Arc<AtomicBool>,
+    pub config_file: PathBuf,
+    pub packets: Arc<Mutex<Vec<Vec<u8>>>>,
+    pub stall_detector: StallDetector,
+    pub stall_resend_config: bool,
+    pub link_check: LinkCheck,
+    pub watchdog: Watchdog,
+    pub read_cadence: ReadCadence,
+}
+
+impl InputReader {
+    pub(crate) fn spawn(self) -> Result<JoinHandle<Result<()>>> {
+        thread::Builder::new()
+            .name("gnss-input".to_string())
+            .spawn(move || {
+                let queue = Arc::clone(&self.queue);
+                let _close = queue.close_on_drop();
+                self.run()
+            })
+            .context("starting input reader thread failed")
+    }
+
+    fn run(mut self) -> Result<()> {
+        let mut buffer = vec![0_u8; self.read_buffer_bytes.max(1_024)];
+        while self.running.load(Ordering::SeqCst) && !self.queue.is_closed() {
+            self.read_cadence
+                .before_read(Instant::now(), &mut buffer, self.port.label());
+            let result = self.port.read(&mut buffer);
+            self.read_cadence.after_read(Instant::now());
+            match result {
+                Ok(0) => {}
+                Ok(size) => {
+                    self.stall_detector.record_data(self.port.label());
+                    health_socket::record_read();
+                    self.watchdog.ping();
+                    self.link_check.ingest(&buffer[..size]);
+                    if !self.queue.push(buffer[..size].to_vec()) {
+                        // The writer has stopped; its error is reported from the writer side.
+                        return Ok(());
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::TimedOut => {}
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && self.port.is_replay() => {
+                    // Closing the queue lets the writer drain, flush, and stop.
+                    emit_event(
+                        EventKind::Shutdown,
+                        &format!("Replay of {} finished", self.port.label()),
+                    );
+                    return Ok(());
+                }
+                Err(err) => {
+                    return Err(err).context("reading GNSS input stream failed");
+                }
+            }
+
+            self.link_check.check(self.port.label());
+
+            if self.reload_requested.swap(false, Ordering::SeqCst)
+                && reload_ubx_config(&self.config_file, &self.packets)
+                && let Err(err) = self.port.rerun_on_connect()
+            {
+                emit_event(
+                    EventKind::Config,
+                    &format!("Sending reloaded UBX configuration failed: {err:#}"),
+                );
+            }
+
+            if self.stall_detector.check(self.port.label())
+                && self.stall_resend_config
+                && let Err(err) = self.port.rerun_on_connect()
+            {
+                emit_event(
+                    EventKind::Stall,
+                    &format!("[STALL] resending UBX configuration failed: {err:#}"),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+// Wait for the input reader after the writer has finished; its read error, if any, wins.
+pub(crate) fn join_input_reader(reader: JoinHandle<Result<()>>) -> Result<()> {
+    reader
+        .join()
+        .unwrap_or_else(|_| Err(anyhow!("input reader thread panicked")))
+}
+
+// Stats suffix for chunks the ring buffer dropped during the window (empty when none were).
+pub(crate) fn describe_dropped(dropped: DropCounts) -> String {
+    if dropped.chunks == 0 {
+        return String::new();
+    }
+    format!(
+        " (buffer overflow dropped {} B in {} chunk(s))",
+        dropped.bytes, dropped.chunks
+    )
+}
+
+// Open a fresh UTC-timestamped output file (or reopen this hour's file for appending with
+// `--single-file-per-hour`) and return its hour key and start for rotation bookkeeping.
+fn open_new_log_file(
+    args: &LogArgs,
+    options: &StreamOptions,
+    now: DateTime<Utc>,
+) -> Result<(String, DateTime<Utc>, LogWriter, PathBuf)> {
+    let hour_start = floor_to_hour(now);
+    let hour_key = hour_start.format("%Y%m%d_%H").to_string();
+    let file_name = log_file_name(
+        &log_file_token(now, args.single_file_per_hour),
+        args.compress_logs,
+    );
+    let path = log_file_dir(&args.data_dir, args.data_layout, now)?.join(file_name);
+    let writer = LogWriter::create(&path, args.compress_logs, options.write_buffer_bytes)?;
+    Ok((hour_key, hour_start, writer, path))
 }
 
-// Open a fresh UTC-timestamped output file and return the hour key for rotation comparisons.
-fn open_new_log_file(data_dir: &Path) -> Result<(String, File, PathBuf)> {
-    let now = Utc::now();
-    let hour_key = now.format("%Y%m%d_%H").to_string();
-    let file_name = format!("{}.ubx", now.format("%Y%m%d_%H%M%S"));
-    let path = data_dir.join(file_name);
-    let file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&path)
-        .with_context(|| format!("opening log output failed: {}", path.display()))?;
-    Ok((hour_key, file, path))
+// Periodic flush according to `--durability`: `none` leaves bytes to the OS (and the gzip
+// encoder), `flush` hands them to the kernel, `fsync` also waits until they reach the disk.
+pub(crate) fn periodic_flush<W: SyncData + ?Sized>(
+    disk_guard: &mut DiskSpaceGuard,
+    writer: &mut W,
+    durability: Durability,
+) -> io::Result<()> {
+    match durability {
+        Durability::None => Ok(()),
+        Durability::Flush => disk_guard.flush(writer),
+        Durability::Fsync => disk_guard.sync_data(writer),
+    }
+}
+
+// Finish a log writer; out-of-space errors are reported but not fatal while the disk is full.
+// With `--durability fsync` the closed file (including any gzip trailer) is synced as well.
+pub(crate) fn finish_log_writer(
+    writer: LogWriter,
+    disk_guard: &DiskSpaceGuard,
+    durability: Durability,
+) -> io::Result<()> {
+    let finished = writer
+        .finish()
+        .and_then(|mut file| sync_closed_file(&mut file, durability));
+    match finished {
+        Ok(()) => Ok(()),
+        Err(err) if is_out_of_space(&err) || disk_guard.is_paused() => {
+            emit_event(
+                EventKind::DiskSpace,
+                &format!("Finishing log file on a full disk lost buffered bytes: {err}"),
+            );
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// What `durability` asks of a file once it is closed: only `fsync` waits for the disk.
+pub(crate) fn sync_closed_file<F: SyncData + ?Sized>(
+    file: &mut F,
+    durability: Durability,
+) -> io::Result<()> {
+    match durability {
+        Durability::Fsync => file.sync_data(),
+        Durability::None | Durability::Flush => Ok(()),
+    }
+}
+
+// Re-parse `ubx.dat` after SIGHUP and swap in the new packets.
+// A broken or empty file is reported and the previous packets stay active.
+pub(crate) fn reload_ubx_config(config_file: &Path, packets: &Mutex<Vec<Vec<u8>>>) -> bool {
+    match parse_ubx_config(config_file) {
+        Ok(reloaded) if !reloaded.is_empty() => {
+            emit_event(
+                EventKind::Config,
+                &format!(
+                    "SIGHUP: reloaded {} UBX configuration commands from {}",
+                    reloaded.len(),
+                    config_file.display()
+                ),
+            );
+            *packets
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()) = reloaded;
+            true
+        }
+        Ok(_) => {
+            emit_event(
+                EventKind::Config,
+                &format!(
+                    "SIGHUP: no UBX commands found in {}; keeping previous configuration",
+                    config_file.display()
+                ),
+            );
+            false
+        }
+        Err(err) => {
+            emit_event(
+                EventKind::Config,
+                &format!(
+                    "SIGHUP: reloading UBX configuration failed; keeping previous configuration: {err:#}"
+                ),
+            );
+            false
+        }
+    }
 }
 
 // Write each UBX config packet with a short delay so the receiver can process command bursts.
-pub(crate) fn send_ubx_packets(
-    port: &mut dyn SerialPort,
+// CFG-RST is not acknowledged and restarts the receiver, so it gets a longer settle pause instead.
+// It is only sent when `send_reset` is set (the first connect): a reset drops USB, and resending
+// it on the reconnect, stall resend, or reload that follows would reset the receiver forever.
+// Returns the number of packets written.
+pub(crate) fn send_ubx_packets<W: Write + ?Sized>(
+    port: &mut W,
     packets: &[Vec<u8>],
     pause_between_commands: Duration,
-) -> Result<()> {
+    reset_settle: Duration,
+    send_reset: bool,
+) -> Result<usize> {
+    let mut sent = 0;
     for packet in packets {
+        if is_cfg_rst_packet(packet) {
+            if !send_reset {
+                continue;
+            }
+            port.write_all(packet)
+                .context("writing UBX CFG-RST command failed")?;
+            // The receiver may drop the link while resetting; a failed flush is not fatal here.
+            let _ = port.flush();
+            emit_event(
+                EventKind::Config,
+                &format!(
+                    "Sent UBX CFG-RST; waiting {} ms for receiver to settle",
+                    reset_settle.as_millis()
+                ),
+            );
+            thread::sleep(reset_settle);
+            sent += 1;
+            continue;
+        }
+
         port.write_all(packet)
             .context("writing UBX config command failed")?;
         port.flush().context("flushing UBX config command failed")?;
         thread::sleep(pause_between_commands);
+        sent += 1;
     }
-    Ok(())
+    Ok(sent)
+}
+
+fn is_cfg_rst_packet(packet: &[u8]) -> bool {
+    ubx::packet_class_id(packet) == Some((CLASS_CFG, ID_CFG_RST))
 }
 
 // Parse `ubx.dat`-style lines into full UBX packets.
 // Packet encoding is delegated to the `ublox` crate builders where available.
 pub(crate) fn parse_ubx_config(config_file: &Path) -> Result<Vec<Vec<u8>>> {
+    parse_ubx_config_lines(config_file)?
+        .into_iter()
+        .map(|entry| {
+            entry.packet.with_context(|| {
+                format!(
+                    "invalid UBX command at {}:{}",
+                    config_file.display(),
+                    entry.line
+                )
+            })
+        })
+        .collect()
+}
+
+// One `!UBX` line of a config file: its 1-based line number, the command name, and either the
+// encoded packet or the reason it was rejected.
+pub(crate) struct ConfigLine {
+    pub line: usize,
+    pub command: String,
+    pub packet: Result<Vec<u8>>,
+}
+
+// Parse every `!UBX` line without stopping at the first error, so `check-config` can report
+// each one. Only an unreadable file fails as a whole.
+pub(crate) fn parse_ubx_config_lines(config_file: &Path) -> Result<Vec<ConfigLine>> {
     let contents = fs::read_to_string(config_file)
         .with_context(|| format!("reading UBX config failed: {}", config_file.display()))?;
-    let mut packets = Vec::new();
+    let mut entries = Vec::new();
+    // A trailing `\` joins the next physical line; comments are stripped per physical line first.
+    let mut continued: Option<(usize, String)> = None;
 
-    for (line_idx, raw) in contents.lines().enumerate() {
-        let line = raw.split('#').next().unwrap_or("").trim();
+    for (physical_idx, raw) in contents.lines().enumerate() {
+        let stripped = raw.split('#').next().unwrap_or("").trim();
+        let (line_idx, mut joined) = continued
+            .take()
+            .unwrap_or_else(|| (physical_idx, String::new()));
+        if let Some(head) = stripped.strip_suffix('\\') {
+            joined.push_str(head);
+            joined.push(' ');
+            continued = Some((line_idx, joined));
+            continue;
+        }
+        joined.push_str(stripped);
+
+        let line = joined.trim();
         if line.is_empty() {
             continue;
         }
@@ -180,38 +904,160 @@ pub(crate) fn parse_ubx_config(config_file: &Path) -> Result<Vec<Vec<u8>>> {
         }
 
         let tokens: Vec<&str> = line.split_whitespace().collect();
-        if tokens.len() < 3 {
-            bail!(
-                "invalid UBX config line {} in {}",
-                line_idx + 1,
-                config_file.display()
-            );
-        }
+        let command = tokens.get(1).copied().unwrap_or_default().to_string();
+        let packet = if tokens.len() < 3 {
+            Err(anyhow!(
+                "expected `!UBX <COMMAND> <args...>`, got {} token(s)",
+                tokens.len()
+            ))
+        } else {
+            build_ubx_packet_from_config(&command, &tokens[2..])
+        };
+        entries.push(ConfigLine {
+            line: line_idx + 1,
+            command,
+            packet,
+        });
+    }
 
-        let command = tokens[1];
-        let args = &tokens[2..];
-        let packet = build_ubx_packet_from_config(command, args).with_context(|| {
-            format!(
-                "invalid UBX command at {}:{}",
-                config_file.display(),
-                line_idx + 1
-            )
-        })?;
+    if let Some((line_idx, joined)) = continued {
+        let tokens: Vec<&str> = joined.split_whitespace().collect();
+        entries.push(ConfigLine {
+            line: line_idx + 1,
+            command: tokens.get(1).copied().unwrap_or_default().to_string(),
+            packet: Err(anyhow!(
+                "line ends with a `\\` continuation but no line follows"
+            )),
+        });
+    }
+    Ok(entries)
+}
+
+// CFG-RATE-MEAS (ms) and CFG-RATE-NAV keys for CFG-VALSET on generation 9+ receivers.
+const KEY_CFG_RATE_MEAS: u32 = 0x3021_0001;
+const KEY_CFG_RATE_NAV: u32 = 0x3021_0002;
 
-        packets.push(packet);
+// Measurement interval (ms) and navigation rate that the config packets leave the receiver
+// at, from CFG-RATE or CFG-VALSET CFG-RATE-* keys; the last setting in file order wins.
+// None when the config never sets the measurement rate.
+pub(crate) fn configured_measurement_rate(packets: &[Vec<u8>]) -> Option<(u16, u16)> {
+    let mut measure_rate_ms = None;
+    let mut nav_rate = 1_u16;
+    for packet in packets {
+        let Some(payload) = packet.get(6..packet.len().saturating_sub(2)) else {
+            continue;
+        };
+        match ubx::packet_class_id(packet) {
+            Some((CLASS_CFG, ID_CFG_RATE)) if payload.len() >= 4 => {
+                measure_rate_ms = Some(u16::from_le_bytes([payload[0], payload[1]]));
+                nav_rate = u16::from_le_bytes([payload[2], payload[3]]);
+            }
+            Some((CLASS_CFG, ID_CFG_VALSET)) => {
+                // version, layers, 2 reserved bytes, then keyId/value items.
+                let mut items = payload.get(4..).unwrap_or_default();
+                while items.len() >= 4 {
+                    let key = u32::from_le_bytes([items[0], items[1], items[2], items[3]]);
+                    let size = match (key >> 28) & 0x07 {
+                        1 | 2 => 1,
+                        3 => 2,
+                        4 => 4,
+                        5 => 8,
+                        _ => break,
+                    };
+                    let Some(value) = items.get(4..4 + size) else {
+                        break;
+                    };
+                    if size == 2 {
+                        let value = u16::from_le_bytes([value[0], value[1]]);
+                        match key {
+                            KEY_CFG_RATE_MEAS => measure_rate_ms = Some(value),
+                            KEY_CFG_RATE_NAV => nav_rate = value,
+                            _ => {}
+                        }
+                    }
+                    items = &items[4 + size..];
+                }
+            }
+            _ => {}
+        }
     }
+    measure_rate_ms.map(|measure_rate_ms| (measure_rate_ms, nav_rate))
+}
 
-    Ok(packets)
+// Text fields per CFG-GNSS block on a `ubx.dat` line.
+const CFG_GNSS_BLOCK_FIELDS: usize = 5;
+
+// One `!UBX <name> ...` line form understood by the config parser.
+// `list-commands` prints this table, so help text and parsing share one source.
+pub struct UbxConfigCommand {
+    pub name: &'static str,
+    pub arguments: &'static str,
+    pub summary: &'static str,
+    build: fn(&[&str]) -> Result<Vec<u8>>,
 }
 
+pub const UBX_CONFIG_COMMANDS: &[UbxConfigCommand] = &[
+    UbxConfigCommand {
+        name: "CFG-MSG",
+        arguments: "8",
+        summary: "Set one message's output rate on every port (class id ddc uart1 uart2 usb spi reserved)",
+        build: build_cfg_msg_packet,
+    },
+    UbxConfigCommand {
+        name: "CFG-GNSS",
+        arguments: "4 + 5 per block",
+        summary: "Enable or disable constellations (msgVer numTrkChHw numTrkChUse numConfigBlocks, then gnssId resTrkCh maxTrkCh reserved1 flags)",
+        build: build_cfg_gnss_packet,
+    },
+    UbxConfigCommand {
+        name: "CFG-NAV5",
+        arguments: "17",
+        summary: "Navigation engine settings (dynamic model, fix mode, elevation mask, DOP and accuracy limits) in raw protocol units",
+        build: build_cfg_nav5_packet,
+    },
+    UbxConfigCommand {
+        name: "CFG-RATE",
+        arguments: "3",
+        summary: "Measurement rate (measureRateMs navRate timeRef; timeRef 0=UTC 1=GPS 2=GLO 3=BDS 4=GAL)",
+        build: build_cfg_rate_packet,
+    },
+    UbxConfigCommand {
+        name: "CFG-RST",
+        arguments: "3",
+        summary: "Reset the receiver (navBbrMask resetMode reserved1)",
+        build: build_cfg_rst_packet,
+    },
+    UbxConfigCommand {
+        name: "CFG-TMODE3",
+        arguments: "11",
+        summary: "Base-station time mode (mode ECEF|LLH x/lat y/lon z/alt xHP yHP zHP fixedPosAcc svinMinDur svinAccLimit; mode 0=disabled 1=survey-in 2=fixed)",
+        build: build_cfg_tmode3_packet,
+    },
+    UbxConfigCommand {
+        name: "CFG-VALSET",
+        arguments: "1 + 2 per key (up to 64 keys)",
+        summary: "Set configuration keys (layers, then keyId value pairs; layers combine RAM 1, BBR 2, Flash 4)",
+        build: build_cfg_valset_packet,
+    },
+    UbxConfigCommand {
+        name: "RAW",
+        arguments: "1 or more",
+        summary: "Send one complete UBX frame verbatim (bytes or 0x hex blobs, checksum verified)",
+        build: build_raw_packet,
+    },
+];
+
 // Convert each supported textual command to one encoded UBX packet.
 fn build_ubx_packet_from_config(command: &str, args: &[&str]) -> Result<Vec<u8>> {
-    match command {
-        "CFG-MSG" => build_cfg_msg_packet(args),
-        "CFG-GNSS" => build_cfg_gnss_packet(args),
-        "CFG-RATE" => build_cfg_rate_packet(args),
-        _ => bail!("unsupported UBX command in config: {command}"),
-    }
+    let Some(entry) = UBX_CONFIG_COMMANDS
+        .iter()
+        .find(|entry| entry.name == command)
+    else {
+        bail!(
+            "unsupported UBX command in config: {command} (run `gnss2tec-logger list-commands` for the supported set)"
+        );
+    };
+    (entry.build)(args)
 }
 
 // Encode UBX-CFG-MSG (class, id, rates for all ports).
@@ -241,36 +1087,50 @@ fn build_cfg_msg_packet(args: &[&str]) -> Result<Vec<u8>> {
     Ok(packet.to_vec())
 }
 
-// Encode UBX-CFG-GNSS (single block form used by the current ubx.dat format).
+// Encode UBX-CFG-GNSS with one or more config blocks.
+// Line form: msgVer numTrkChHw numTrkChUse numConfigBlocks, then per block
+// gnssId resTrkCh maxTrkCh reserved1 flags (8 encoded bytes, 5 text fields).
 fn build_cfg_gnss_packet(args: &[&str]) -> Result<Vec<u8>> {
-    if args.len() != 9 {
-        bail!("CFG-GNSS expects 9 arguments, got {}", args.len());
+    if args.len() < 4 {
+        bail!(
+            "CFG-GNSS expects at least 4 header arguments, got {}",
+            args.len()
+        );
     }
 
     let msg_version = parse_u8_token(args[0])?;
     let num_trk_ch_hw = parse_u8_token(args[1])?;
     let num_trk_ch_use = parse_u8_token(args[2])?;
     let num_config_blocks = parse_u8_token(args[3])?;
-    if num_config_blocks != 1 {
+    if num_config_blocks == 0 {
+        bail!("CFG-GNSS needs at least one config block");
+    }
+
+    let block_args = &args[4..];
+    let expected = usize::from(num_config_blocks) * CFG_GNSS_BLOCK_FIELDS;
+    if block_args.len() != expected {
         bail!(
-            "CFG-GNSS currently supports one config block per line; got {}",
-            num_config_blocks
+            "CFG-GNSS declares {} block(s), expecting {} block arguments, got {}",
+            num_config_blocks,
+            expected,
+            block_args.len()
         );
     }
 
-    let gnss_id_raw = parse_u8_token(args[4])?;
-    let gnss_id = GnssId::try_from(gnss_id_raw)
-        .map_err(|err| anyhow!("unsupported GNSS id {gnss_id_raw}: {err}"))?;
-
-    let block = GnssConfigBlock {
-        gnss_id,
-        res_trk_ch: parse_u8_token(args[5])?,
-        max_trk_ch: parse_u8_token(args[6])?,
-        reserved1: parse_u8_token(args[7])?,
-        flags: parse_u32_token(args[8])?,
-    };
+    let mut blocks = Vec::with_capacity(usize::from(num_config_blocks));
+    for fields in block_args.chunks_exact(CFG_GNSS_BLOCK_FIELDS) {
+        let gnss_id_raw = parse_u8_token(fields[0])?;
+        let gnss_id = GnssId::try_from(gnss_id_raw)
+            .map_err(|err| anyhow!("unsupported GNSS id {gnss_id_raw}: {err}"))?;
+        blocks.push(GnssConfigBlock {
+            gnss_id,
+            res_trk_ch: parse_u8_token(fields[1])?,
+            max_trk_ch: parse_u8_token(fields[2])?,
+            reserved1: parse_u8_token(fields[3])?,
+            flags: parse_u32_token(fields[4])?,
+        });
+    }
 
-    let blocks = [block];
     let builder = CfgGnssBuilder {
         msg_version,
         num_trk_ch_hw,
@@ -279,11 +1139,180 @@ fn build_cfg_gnss_packet(args: &[&str]) -> Result<Vec<u8>> {
     }
     .with_blocks(&blocks);
 
-    let mut packet = Vec::with_capacity(32);
+    let mut packet = Vec::with_capacity(12 + 8 * blocks.len());
     builder.extend_to(&mut packet);
     Ok(packet)
 }
 
+// Encode UBX-CFG-NAV5 (36-byte payload, reserved bytes zero).
+// Line form: mask dynModel fixMode fixedAlt fixedAltVar minElev drLimit pDop tDop pAcc tAcc
+// staticHoldThresh dgnssTimeout cnoThreshNumSVs cnoThresh staticHoldMaxDist utcStandard,
+// in raw protocol units (fixedAlt 0.01 m, fixedAltVar 0.0001 m^2, pDop/tDop 0.1).
+fn build_cfg_nav5_packet(args: &[&str]) -> Result<Vec<u8>> {
+    if args.len() != 17 {
+        bail!("CFG-NAV5 expects 17 arguments, got {}", args.len());
+    }
+
+    let mask = parse_u16_token(args[0])?;
+    let dyn_model = parse_u8_token(args[1])?;
+    // 0 portable, 2 stationary, 3 pedestrian, 4 automotive, 5 sea, 6-8 airborne <1g/<2g/<4g,
+    // 9 wrist, 10 bike; 1 is reserved.
+    if !matches!(dyn_model, 0 | 2..=10) {
+        bail!(
+            "unsupported CFG-NAV5 dynModel value: {} (expected 0 or 2-10)",
+            dyn_model
+        );
+    }
+    let fix_mode = parse_u8_token(args[2])?;
+    if !matches!(fix_mode, 1..=3) {
+        bail!(
+            "unsupported CFG-NAV5 fixMode value: {} (expected 1=2D, 2=3D, 3=auto)",
+            fix_mode
+        );
+    }
+    let fixed_alt = parse_i32_token(args[3])?;
+    let fixed_alt_var = parse_u32_token(args[4])?;
+    let min_elev = parse_i8_token(args[5])?;
+    if !(-90..=90).contains(&min_elev) {
+        bail!("CFG-NAV5 minElev must be within -90..90 degrees, got {min_elev}");
+    }
+    let dr_limit = parse_u8_token(args[6])?;
+    let p_dop = parse_u16_token(args[7])?;
+    let t_dop = parse_u16_token(args[8])?;
+    let p_acc = parse_u16_token(args[9])?;
+    let t_acc = parse_u16_token(args[10])?;
+    let static_hold_thresh = parse_u8_token(args[11])?;
+    let dgnss_timeout = parse_u8_token(args[12])?;
+    let cno_thresh_num_svs = parse_u8_token(args[13])?;
+    let cno_thresh = parse_u8_token(args[14])?;
+    let static_hold_max_dist = parse_u16_token(args[15])?;
+    let utc_standard = parse_u8_token(args[16])?;
+    if !matches!(utc_standard, 0 | 3 | 5 | 6 | 7) {
+        bail!("unsupported CFG-NAV5 utcStandard value: {}", utc_standard);
+    }
+
+    let mut payload = Vec::with_capacity(36);
+    payload.extend_from_slice(&mask.to_le_bytes());
+    payload.push(dyn_model);
+    payload.push(fix_mode);
+    payload.extend_from_slice(&fixed_alt.to_le_bytes());
+    payload.extend_from_slice(&fixed_alt_var.to_le_bytes());
+    payload.extend_from_slice(&min_elev.to_le_bytes());
+    payload.push(dr_limit);
+    payload.extend_from_slice(&p_dop.to_le_bytes());
+    payload.extend_from_slice(&t_dop.to_le_bytes());
+    payload.extend_from_slice(&p_acc.to_le_bytes());
+    payload.extend_from_slice(&t_acc.to_le_bytes());
+    payload.push(static_hold_thresh);
+    payload.push(dgnss_timeout);
+    payload.push(cno_thresh_num_svs);
+    payload.push(cno_thresh);
+    payload.extend_from_slice(&[0, 0]);
+    payload.extend_from_slice(&static_hold_max_dist.to_le_bytes());
+    payload.push(utc_standard);
+    payload.extend_from_slice(&[0; 5]);
+    Ok(ubx::encode_packet(CLASS_CFG, ID_CFG_NAV5, &payload))
+}
+
+// Encode UBX-CFG-TMODE3 (40-byte payload, version 0, reserved bytes zero).
+// Line form: mode frame x y z xHP yHP zHP fixedPosAcc svinMinDur svinAccLimit, where mode is
+// 0=disabled 1=survey-in 2=fixed and frame is ECEF (x/y/z in cm, HP in 0.1 mm) or LLH
+// (lat/lon in 1e-7 deg with HP in 1e-9 deg, height in cm with HP in 0.1 mm).
+// fixedPosAcc and svinAccLimit are in 0.1 mm, svinMinDur in seconds.
+fn build_cfg_tmode3_packet(args: &[&str]) -> Result<Vec<u8>> {
+    if args.len() != 11 {
+        bail!("CFG-TMODE3 expects 11 arguments, got {}", args.len());
+    }
+
+    let mode = parse_u8_token(args[0])?;
+    if mode > 2 {
+        bail!(
+            "unsupported CFG-TMODE3 mode value: {} (expected 0=disabled, 1=survey-in, 2=fixed)",
+            mode
+        );
+    }
+    let lla = match args[1].to_ascii_uppercase().as_str() {
+        "ECEF" => false,
+        "LLH" => true,
+        other => bail!("unsupported CFG-TMODE3 position frame: {other} (expected ECEF or LLH)"),
+    };
+    let position = [
+        parse_i32_token(args[2])?,
+        parse_i32_token(args[3])?,
+        parse_i32_token(args[4])?,
+    ];
+    let position_hp = [
+        parse_i8_token(args[5])?,
+        parse_i8_token(args[6])?,
+        parse_i8_token(args[7])?,
+    ];
+    if let Some(hp) = position_hp.iter().find(|hp| !(-99..=99).contains(*hp)) {
+        bail!("CFG-TMODE3 high-precision components must be within -99..99, got {hp}");
+    }
+    let fixed_pos_acc = parse_u32_token(args[8])?;
+    let svin_min_dur = parse_u32_token(args[9])?;
+    let svin_acc_limit = parse_u32_token(args[10])?;
+
+    validate_tmode3_position(mode, lla, position)?;
+    if mode == 1 && (svin_min_dur == 0 || svin_acc_limit == 0) {
+        bail!("CFG-TMODE3 survey-in needs a non-zero svinMinDur and svinAccLimit");
+    }
+
+    let flags = u16::from(mode) | if lla { 1 << 8 } else { 0 };
+    let mut payload = Vec::with_capacity(40);
+    payload.push(0); // version
+    payload.push(0);
+    payload.extend_from_slice(&flags.to_le_bytes());
+    for value in position {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    for value in position_hp {
+        payload.extend_from_slice(&value.to_le_bytes());
+    }
+    payload.push(0);
+    payload.extend_from_slice(&fixed_pos_acc.to_le_bytes());
+    payload.extend_from_slice(&svin_min_dur.to_le_bytes());
+    payload.extend_from_slice(&svin_acc_limit.to_le_bytes());
+    payload.extend_from_slice(&[0; 8]);
+    Ok(ubx::encode_packet(CLASS_CFG, ID_CFG_TMODE3, &payload))
+}
+
+// Check the coordinate fields against the ECEF/LLH frame so swapped or mis-scaled values are
+// caught at parse time. Fixed mode needs a real position; survey-in and disabled modes ignore
+// it, so it must be left at zero there.
+fn validate_tmode3_position(mode: u8, lla: bool, position: [i32; 3]) -> Result<()> {
+    if mode != 2 {
+        if position != [0; 3] {
+            bail!("CFG-TMODE3 position fields must be 0 unless mode is 2 (fixed)");
+        }
+        return Ok(());
+    }
+    if lla {
+        let [lat, lon, _] = position;
+        if !(-900_000_000..=900_000_000).contains(&lat) {
+            bail!("CFG-TMODE3 LLH latitude must be within +/-90 deg (1e-7 deg units), got {lat}");
+        }
+        if !(-1_800_000_000..=1_800_000_000).contains(&lon) {
+            bail!("CFG-TMODE3 LLH longitude must be within +/-180 deg (1e-7 deg units), got {lon}");
+        }
+        return Ok(());
+    }
+    // A ground station sits 6350-6390 km from the Earth's centre; anything else is usually
+    // LLH values given with the ECEF frame or metres instead of centimetres.
+    let radius_cm = position
+        .iter()
+        .map(|&axis| f64::from(axis).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    if !(635_000_000.0..=639_000_000.0).contains(&radius_cm) {
+        bail!(
+            "CFG-TMODE3 ECEF position is {:.0} km from the Earth's centre; expected x/y/z in cm (or use LLH)",
+            radius_cm / 100_000.0
+        );
+    }
+    Ok(())
+}
+
 // Encode UBX-CFG-RATE.
 fn build_cfg_rate_packet(args: &[&str]) -> Result<Vec<u8>> {
     if args.len() != 3 {
@@ -311,6 +1340,144 @@ fn build_cfg_rate_packet(args: &[&str]) -> Result<Vec<u8>> {
     Ok(packet.to_vec())
 }
 
+// Encode UBX-CFG-RST (navBbrMask, resetMode, reserved).
+fn build_cfg_rst_packet(args: &[&str]) -> Result<Vec<u8>> {
+    if args.len() != 3 {
+        bail!("CFG-RST expects 3 arguments, got {}", args.len());
+    }
+
+    let nav_bbr_mask = parse_u16_token(args[0])?;
+    let reset_mode = parse_u8_token(args[1])?;
+    if !matches!(reset_mode, 0x00 | 0x01 | 0x02 | 0x04 | 0x08 | 0x09) {
+        bail!("unsupported CFG-RST reset_mode value: {}", reset_mode);
+    }
+    let reserved1 = parse_u8_token(args[2])?;
+
+    let mut payload = Vec::with_capacity(4);
+    payload.extend_from_slice(&nav_bbr_mask.to_le_bytes());
+    payload.push(reset_mode);
+    payload.push(reserved1);
+    Ok(ubx::encode_packet(CLASS_CFG, ID_CFG_RST, &payload))
+}
+
+// Maximum key/value pairs in one UBX-CFG-VALSET message.
+const CFG_VALSET_MAX_ITEMS: usize = 64;
+
+// Encode UBX-CFG-VALSET: `<layers> <keyId> <value> [<keyId> <value> ...]`.
+// Each value is written with the storage size encoded in bits 28-30 of its key ID.
+fn build_cfg_valset_packet(args: &[&str]) -> Result<Vec<u8>> {
+    if args.len() < 3 || args.len().is_multiple_of(2) {
+        bail!(
+            "CFG-VALSET expects layers followed by keyId/value pairs, got {} argument(s)",
+            args.len()
+        );
+    }
+    let layers = parse_u8_token(args[0])?;
+    if layers == 0 || layers & !0x07 != 0 {
+        bail!("CFG-VALSET layers must combine RAM (1), BBR (2), and Flash (4): {layers}");
+    }
+    let pairs = &args[1..];
+    if pairs.len() / 2 > CFG_VALSET_MAX_ITEMS {
+        bail!(
+            "CFG-VALSET carries at most {CFG_VALSET_MAX_ITEMS} keys per line, got {}",
+            pairs.len() / 2
+        );
+    }
+
+    let mut payload = vec![0x00, layers, 0x00, 0x00];
+    for pair in pairs.chunks(2) {
+        let key = parse_u32_token(pair[0])?;
+        let size = match (key >> 28) & 0x07 {
+            1 | 2 => 1,
+            3 => 2,
+            4 => 4,
+            5 => 8,
+            other => bail!("CFG-VALSET key {} has unknown size code {other}", pair[0]),
+        };
+        let value = parse_valset_value(pair[1], size)?;
+        if (key >> 28) & 0x07 == 1 && value > 1 {
+            bail!("CFG-VALSET key {} is boolean; expected 0 or 1", pair[0]);
+        }
+        payload.extend_from_slice(&key.to_le_bytes());
+        payload.extend_from_slice(&value.to_le_bytes()[..size]);
+    }
+    Ok(ubx::encode_packet(CLASS_CFG, ID_CFG_VALSET, &payload))
+}
+
+// Value of `size` bytes; hex or decimal, negative decimals stored as two's complement.
+fn parse_valset_value(raw: &str, size: usize) -> Result<u64> {
+    let bits = size as u32 * 8;
+    if raw.starts_with('-') {
+        let value = raw
+            .parse::<i64>()
+            .with_context(|| format!("invalid integer value: {raw}"))?;
+        if bits < 64 && value < -(1_i64 << (bits - 1)) {
+            bail!("value out of range for {size}-byte key: {raw}");
+        }
+        let mask = if bits == 64 {
+            u64::MAX
+        } else {
+            (1_u64 << bits) - 1
+        };
+        return Ok(value as u64 & mask);
+    }
+    let value = match raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")) {
+        Some(hex) => {
+            u64::from_str_radix(hex, 16).with_context(|| format!("invalid hex value: {raw}"))?
+        }
+        None => raw
+            .parse::<u64>()
+            .with_context(|| format!("invalid integer value: {raw}"))?,
+    };
+    if bits < 64 && value >> bits != 0 {
+        bail!("value out of range for {size}-byte key: {raw}");
+    }
+    Ok(value)
+}
+
+// Send bytes verbatim; they must form exactly one complete UBX frame with a valid checksum.
+// Tokens are single bytes (`0xB5`, `181`) or `0x` blobs such as `0xB5620600`.
+fn build_raw_packet(args: &[&str]) -> Result<Vec<u8>> {
+    let mut packet = Vec::new();
+    for token in args {
+        packet.extend(parse_byte_blob(token)?);
+    }
+    if packet.len() < 8 || packet[0] != ubx::SYNC_CHAR_1 || packet[1] != ubx::SYNC_CHAR_2 {
+        bail!("RAW expects a complete UBX frame starting with B5 62");
+    }
+    let payload_len = u16::from_le_bytes([packet[4], packet[5]]) as usize;
+    if packet.len() != payload_len + 8 {
+        bail!(
+            "RAW frame length field says {payload_len} payload byte(s) but {} were given",
+            packet.len().saturating_sub(8)
+        );
+    }
+    let (ck_a, ck_b) = ubx::checksum(&packet[2..packet.len() - 2]);
+    if packet[packet.len() - 2..] != [ck_a, ck_b] {
+        bail!("RAW frame checksum mismatch (expected {ck_a:02X} {ck_b:02X})");
+    }
+    Ok(packet)
+}
+
+// One byte token, or a `0x` blob whose hex digit pairs expand to consecutive bytes.
+fn parse_byte_blob(raw: &str) -> Result<Vec<u8>> {
+    match raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")) {
+        Some(hex) if hex.len() > 2 => {
+            if hex.len() % 2 != 0 {
+                bail!("hex blob needs an even number of digits: {raw}");
+            }
+            (0..hex.len())
+                .step_by(2)
+                .map(|at| {
+                    u8::from_str_radix(&hex[at..at + 2], 16)
+                        .with_context(|| format!("invalid hex blob: {raw}"))
+                })
+                .collect()
+        }
+        _ => Ok(vec![parse_u8_token(raw)?]),
+    }
+}
+
 // Numeric parsing helpers for config arguments.
 fn parse_u8_token(raw: &str) -> Result<u8> {
     let value = parse_u32_token(raw)?;
@@ -322,6 +1489,17 @@ fn parse_u16_token(raw: &str) -> Result<u16> {
     u16::try_from(value).map_err(|_| anyhow!("value out of range for u16: {raw}"))
 }
 
+fn parse_i8_token(raw: &str) -> Result<i8> {
+    let value = parse_i32_token(raw)?;
+    i8::try_from(value).map_err(|_| anyhow!("value out of range for i8: {raw}"))
+}
+
+// Signed values are decimal only; a leading `-` is allowed.
+fn parse_i32_token(raw: &str) -> Result<i32> {
+    raw.parse::<i32>()
+        .with_context(|| format!("invalid integer value: {raw}"))
+}
+
 fn parse_u32_token(raw: &str) -> Result<u32> {
     if let Some(hex) = raw.strip_prefix("0x").or_else(|| raw.strip_prefix("0X")) {
         return u32::from_str_radix(hex, 16).with_context(|| format!("invalid hex value: {raw}"));
@@ -329,3 +1507,843 @@ fn parse_u32_token(raw: &str) -> Result<u32> {
     raw.parse::<u32>()
         .with_context(|| format!("invalid integer value: {raw}"))
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::args::{AppCommand, Cli, OverflowPolicy};
+    use crate::commands::convert::hour_has_ubx_files;
+    use chrono::TimeZone;
+    use clap::Parser;
+
+    #[test]
+    fn cfg_rst_encodes_controlled_software_reset() {
+        // Hot start (navBbrMask 0x0000), controlled software reset (resetMode 0x01).
+        let packet = build_cfg_rst_packet(&["0x0000", "0x01", "0x00"]).unwrap();
+        assert_eq!(
+            packet,
+            [
+                0xB5, 0x62, 0x06, 0x04, 0x04, 0x00, 0x00, 0x00, 0x01, 0x00, 0x0F, 0x66
+            ]
+        );
+        assert!(is_cfg_rst_packet(&packet));
+    }
+
+    #[test]
+    fn cfg_rst_rejects_unknown_reset_mode() {
+        assert!(build_cfg_rst_packet(&["0x0000", "0x03", "0x00"]).is_err());
+        assert!(build_cfg_rst_packet(&["0x0000", "0x01"]).is_err());
+    }
+
+    #[test]
+    fn cfg_rst_is_only_sent_on_first_connect() {
+        let rate = ubx::encode_packet(
+            CLASS_CFG,
+            ID_CFG_RATE,
+            &[0xE8, 0x03, 0x01, 0x00, 0x01, 0x00],
+        );
+        let reset = build_cfg_rst_packet(&["0x0000", "0x01", "0x00"]).unwrap();
+        let packets = vec![rate.clone(), reset.clone()];
+
+        let mut first = Vec::new();
+        let sent =
+            send_ubx_packets(&mut first, &packets, Duration::ZERO, Duration::ZERO, true).unwrap();
+        assert_eq!(sent, 2);
+        assert_eq!(first, [rate.clone(), reset].concat());
+
+        let mut reconnect = Vec::new();
+        let sent = send_ubx_packets(
+            &mut reconnect,
+            &packets,
+            Duration::ZERO,
+            Duration::ZERO,
+            false,
+        )
+        .unwrap();
+        assert_eq!(sent, 1);
+        assert_eq!(reconnect, rate);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn port_commands_run_around_input_open() {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-port-commands-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let marker = dir.join("marker");
+        let _ = fs::remove_file(&marker);
+        let pre = format!("sh -c 'echo pre-open >> {}'", marker.display());
+        let post = format!("sh -c 'echo post-close >> {}'", marker.display());
+
+        let guard = PortCommands::start(Some(&pre), Some(&post)).unwrap();
+        // The replayed input only exists once the pre-open command has run.
+        let input = InputSpec::File {
+            path: marker.clone(),
+            realtime: false,
+        }
+        .open(Duration::from_millis(100))
+        .unwrap();
+        assert_eq!(fs::read_to_string(&marker).unwrap(), "pre-open\n");
+        drop(input);
+        drop(guard);
+        assert_eq!(
+            fs::read_to_string(&marker).unwrap(),
+            "pre-open\npost-close\n"
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_pre_open_command_aborts() {
+        assert!(PortCommands::start(Some("false"), None).is_err());
+    }
+
+    #[test]
+    fn broken_or_empty_ubx_config_is_a_config_parse_error() {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-config-parse-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, config) in [("broken", "!UBX CFG-MSG 240\n"), ("empty", "# nothing\n")] {
+            let config_file = dir.join(format!("{name}.dat"));
+            fs::write(&config_file, config).unwrap();
+            let data_dir = dir.join("data");
+            let lock_file = dir.join("ubx_log.lock");
+            let argv = [
+                "gnss2tec-logger",
+                "log",
+                "--config-file",
+                config_file.to_str().unwrap(),
+                "--data-dir",
+                data_dir.to_str().unwrap(),
+                "--lock-file",
+                lock_file.to_str().unwrap(),
+            ];
+            let AppCommand::Log(args) = Cli::try_parse_from(argv).unwrap().command else {
+                panic!("expected the log command");
+            };
+
+            let err = run_log_with_signal(
+                args,
+                Arc::new(AtomicBool::new(true)),
+                Arc::new(AtomicBool::new(false)),
+            )
+            .unwrap_err();
+            assert!(
+                matches!(err, PipelineError::ConfigParse(_)),
+                "{name}: {err:?}"
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Run the input reader over a short replay with the reload flag already raised, the way
+    // SIGHUP leaves it. The connect hook stands in for the receiver and records every send.
+    fn reload_with_config(name: &str, config: &str) -> (Vec<Vec<u8>>, Vec<Vec<Vec<u8>>>) {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-reload-{name}-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let replay = dir.join("replay.ubx");
+        fs::write(&replay, [0xB5, 0x62, 0x01, 0x07]).unwrap();
+        let config_file = dir.join("ubx.dat");
+        fs::write(&config_file, config).unwrap();
+
+        let rate = ubx::encode_packet(
+            CLASS_CFG,
+            ID_CFG_RATE,
+            &[0xE8, 0x03, 0x01, 0x00, 0x01, 0x00],
+        );
+        let packets = Arc::new(Mutex::new(vec![rate]));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let hook_packets = Arc::clone(&packets);
+        let hook_sent = Arc::clone(&sent);
+        let on_connect: OnConnect = Box::new(move |_source: &mut dyn ByteSource, _kind| {
+            let packets = hook_packets.lock().unwrap().clone();
+            let mut port = Vec::new();
+            send_ubx_packets(&mut port, &packets, Duration::ZERO, Duration::ZERO, false)?;
+            hook_sent.lock().unwrap().push(packets);
+            Ok(())
+        });
+        let port = ReconnectingSource::connect(
+            InputSpec::File {
+                path: replay,
+                realtime: false,
+            },
+            Duration::from_millis(100),
+            ReconnectBackoff::new(Duration::from_millis(10), Duration::from_millis(10)),
+            on_connect,
+        )
+        .unwrap();
+
+        let reload_requested = Arc::new(AtomicBool::new(true));
+        InputReader {
+            port,
+            queue: Arc::new(ChunkQueue::new(1_024, OverflowPolicy::Block)),
+            read_buffer_bytes: 1_024,
+            running: Arc::new(AtomicBool::new(true)),
+            reload_requested: Arc::clone(&reload_requested),
+            config_file,
+            packets: Arc::clone(&packets),
+            stall_detector: StallDetector::new(0),
+            stall_resend_config: false,
+            link_check: LinkCheck::new(0),
+            watchdog: Watchdog::disabled(),
+            read_cadence: ReadCadence::new(0),
+        }
+        .run()
+        .unwrap();
+        assert!(!reload_requested.load(Ordering::SeqCst));
+        fs::remove_dir_all(&dir).unwrap();
+
+        let active = packets.lock().unwrap().clone();
+        let sent = sent.lock().unwrap().clone();
+        (active, sent)
+    }
+
+    #[test]
+    fn reload_replaces_packets_and_resends_them() {
+        let (active, sent) = reload_with_config("good", "!UBX CFG-MSG 240 0 0 0 0 0 0 0\n");
+        let reloaded = parse_ubx_config_text("!UBX CFG-MSG 240 0 0 0 0 0 0 0\n");
+        assert_eq!(active, reloaded);
+        // The initial connect sent the old packets; the reload sent the new ones.
+        assert_eq!(sent.len(), 2);
+        assert_ne!(sent[0], reloaded);
+        assert_eq!(sent[1], reloaded);
+    }
+
+    #[test]
+    fn broken_reload_keeps_previous_packets() {
+        for (name, config) in [("broken", "!UBX CFG-MSG 240\n"), ("empty", "# nothing\n")] {
+            let (active, sent) = reload_with_config(name, config);
+            // Nothing was resent, and the packets from startup are still active.
+            assert_eq!(sent.len(), 1);
+            assert_eq!(active, sent[0]);
+        }
+    }
+
+    fn parse_ubx_config_text(config: &str) -> Vec<Vec<u8>> {
+        let path = std::env::temp_dir().join(format!(
+            "gnss2tec-reload-expected-{}.dat",
+            std::process::id()
+        ));
+        fs::write(&path, config).unwrap();
+        let packets = parse_ubx_config(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        packets
+    }
+
+    // Payload of an encoded CFG packet, after checking its class and id.
+    fn cfg_payload(packet: &[u8], id: u8) -> &[u8] {
+        assert_eq!(ubx::packet_class_id(packet), Some((CLASS_CFG, id)));
+        &packet[6..packet.len() - 2]
+    }
+
+    #[test]
+    fn cfg_tmode3_encodes_survey_in_and_fixed_llh() {
+        // The packaged example: survey-in for at least one day, down to 2 m.
+        let packet = build_cfg_tmode3_packet(&[
+            "1", "ECEF", "0", "0", "0", "0", "0", "0", "0", "86400", "20000",
+        ])
+        .unwrap();
+        let payload = cfg_payload(&packet, ID_CFG_TMODE3);
+        assert_eq!(payload.len(), 40);
+        assert_eq!(payload[..4], [0, 0, 0x01, 0x00]);
+        assert!(payload[4..24].iter().all(|&byte| byte == 0));
+        assert_eq!(payload[24..28], 86_400_u32.to_le_bytes());
+        assert_eq!(payload[28..32], 20_000_u32.to_le_bytes());
+
+        let packet = build_cfg_tmode3_packet(&[
+            "2",
+            "llh",
+            "407423000",
+            "-741788000",
+            "1000",
+            "5",
+            "-3",
+            "0",
+            "100",
+            "0",
+            "0",
+        ])
+        .unwrap();
+        let payload = cfg_payload(&packet, ID_CFG_TMODE3);
+        assert_eq!(payload[2..4], [0x02, 0x01]);
+        assert_eq!(payload[4..8], 407_423_000_i32.to_le_bytes());
+        assert_eq!(payload[8..12], (-741_788_000_i32).to_le_bytes());
+        assert_eq!(payload[12..16], 1_000_i32.to_le_bytes());
+        assert_eq!(payload[16..19], [5, 0xFD, 0]);
+        assert_eq!(payload[20..24], 100_u32.to_le_bytes());
+    }
+
+    #[test]
+    fn cfg_tmode3_rejects_inconsistent_positions() {
+        for args in [
+            // Unknown mode and frame.
+            [
+                "3", "ECEF", "0", "0", "0", "0", "0", "0", "0", "86400", "20000",
+            ],
+            [
+                "1", "ENU", "0", "0", "0", "0", "0", "0", "0", "86400", "20000",
+            ],
+            // Survey-in with a position, or without duration and accuracy limits.
+            [
+                "1",
+                "ECEF",
+                "133400000",
+                "0",
+                "0",
+                "0",
+                "0",
+                "0",
+                "0",
+                "86400",
+                "20000",
+            ],
+            ["1", "ECEF", "0", "0", "0", "0", "0", "0", "0", "0", "20000"],
+            // ECEF in metres instead of centimetres, and LLH latitude past the pole.
+            [
+                "2", "ECEF", "1334000", "-4654000", "4138000", "0", "0", "0", "100", "0", "0",
+            ],
+            [
+                "2",
+                "LLH",
+                "900000001",
+                "0",
+                "0",
+                "0",
+                "0",
+                "0",
+                "100",
+                "0",
+                "0",
+            ],
+            // High-precision component out of range.
+            [
+                "2",
+                "LLH",
+                "407423000",
+                "-741788000",
+                "1000",
+                "100",
+                "0",
+                "0",
+                "100",
+                "0",
+                "0",
+            ],
+        ] {
+            assert!(build_cfg_tmode3_packet(&args).is_err(), "{args:?}");
+        }
+        assert!(build_cfg_tmode3_packet(&["1", "ECEF", "0"]).is_err());
+
+        // The same station in ECEF centimetres is accepted.
+        assert!(
+            build_cfg_tmode3_packet(&[
+                "2",
+                "ECEF",
+                "133400000",
+                "-465400000",
+                "413800000",
+                "0",
+                "0",
+                "0",
+                "100",
+                "0",
+                "0",
+            ])
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn cfg_nav5_encodes_stationary_model_with_elevation_mask() {
+        // Stationary model, auto 2D/3D, 10 deg mask, 25.0 PDOP/TDOP, 100 m / 300 m accuracy.
+        let line = "0x0003 2 3 0 10000 10 0 250 250 100 300 0 60 0 0 0 0";
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let packet = build_cfg_nav5_packet(&args).unwrap();
+        assert_eq!(
+            packet,
+            [
+                0xB5, 0x62, 0x06, 0x24, 0x24, 0x00, 0x03, 0x00, 0x02, 0x03, 0x00, 0x00, 0x00, 0x00,
+                0x10, 0x27, 0x00, 0x00, 0x0A, 0x00, 0xFA, 0x00, 0xFA, 0x00, 0x64, 0x00, 0x2C, 0x01,
+                0x00, 0x3C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x58, 0x8B
+            ]
+        );
+    }
+
+    #[test]
+    fn cfg_nav5_rejects_out_of_range_fields() {
+        for (from, to) in [
+            // dynModel 1 is reserved, fixMode 0 and minElev 91 are out of range.
+            ("0x0003 2 3", "0x0003 1 3"),
+            ("0x0003 2 3", "0x0003 2 0"),
+            (" 10000 10 ", " 10000 91 "),
+            // utcStandard 1 is not a defined standard.
+            (" 0 0 0 0", " 0 0 0 1"),
+        ] {
+            let line = "0x0003 2 3 0 10000 10 0 250 250 100 300 0 60 0 0 0 0".replacen(from, to, 1);
+            let args: Vec<&str> = line.split_whitespace().collect();
+            assert!(build_cfg_nav5_packet(&args).is_err(), "{line}");
+        }
+        assert!(build_cfg_nav5_packet(&["0x0003", "2", "3"]).is_err());
+    }
+
+    #[test]
+    fn cfg_gnss_encodes_several_blocks_in_one_message() {
+        let gps_line = ["0", "60", "60", "1", "0", "30", "30", "0", "65537"];
+        let gal_line = ["0", "60", "60", "1", "2", "30", "30", "0", "65537"];
+        let both = build_cfg_gnss_packet(&[
+            "0", "60", "60", "2", "0", "30", "30", "0", "65537", "2", "30", "30", "0", "65537",
+        ])
+        .unwrap();
+        assert_eq!(both[2..6], [0x06, 0x3E, 20, 0]);
+        assert_eq!(both[6..10], [0, 60, 60, 2]);
+        assert_eq!(both[10..18], [0, 30, 30, 0, 0x01, 0x00, 0x01, 0x00]);
+        assert_eq!(both[18..26], [2, 30, 30, 0, 0x01, 0x00, 0x01, 0x00]);
+        let (ck_a, ck_b) = ubx::checksum(&both[2..26]);
+        assert_eq!(both[26..], [ck_a, ck_b]);
+
+        // Each block matches what the one-block-per-line form encodes.
+        let gps = build_cfg_gnss_packet(&gps_line).unwrap();
+        let gal = build_cfg_gnss_packet(&gal_line).unwrap();
+        assert_eq!(both[10..18], gps[10..18]);
+        assert_eq!(both[18..26], gal[10..18]);
+    }
+
+    #[test]
+    fn cfg_gnss_block_count_must_match_the_arguments() {
+        // Two blocks declared, one given; one declared, two given; none declared.
+        assert!(
+            build_cfg_gnss_packet(&["0", "60", "60", "2", "0", "30", "30", "0", "65537"]).is_err()
+        );
+        assert!(
+            build_cfg_gnss_packet(&[
+                "0", "60", "60", "1", "0", "30", "30", "0", "65537", "2", "30", "30", "0", "65537",
+            ])
+            .is_err()
+        );
+        assert!(build_cfg_gnss_packet(&["0", "60", "60", "0"]).is_err());
+    }
+
+    #[test]
+    fn continued_valset_and_raw_blob_lines_parse() {
+        let path = std::env::temp_dir().join(format!(
+            "gnss2tec-config-continuation-{}.dat",
+            std::process::id()
+        ));
+        fs::write(
+            &path,
+            "# 1 Hz on RAM only\n\
+             !UBX CFG-VALSET 1 \\   # layers\n\
+             \x20   0x30210001 1000 \\ # CFG-RATE-MEAS\n\
+             \x20   0x30210002 1\n\
+             !UBX RAW 0xB562 0x06 0x08 0x0600 0xE8030100 0x0100 0x01 57\n",
+        )
+        .unwrap();
+        let entries = parse_ubx_config_lines(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            (entries[0].line, entries[0].command.as_str()),
+            (2, "CFG-VALSET")
+        );
+        let valset = entries[0].packet.as_ref().unwrap();
+        assert_eq!(
+            cfg_payload(valset, ID_CFG_VALSET),
+            [
+                0x00, 0x01, 0x00, 0x00, 0x01, 0x00, 0x21, 0x30, 0xE8, 0x03, 0x02, 0x00, 0x21, 0x30,
+                0x01, 0x00
+            ]
+        );
+
+        assert_eq!((entries[1].line, entries[1].command.as_str()), (5, "RAW"));
+        let rate = ubx::encode_packet(
+            CLASS_CFG,
+            ID_CFG_RATE,
+            &[0xE8, 0x03, 0x01, 0x00, 0x01, 0x00],
+        );
+        assert_eq!(entries[1].packet.as_ref().unwrap(), &rate);
+
+        let packets = [valset.clone(), rate];
+        assert_eq!(configured_measurement_rate(&packets), Some((1000, 1)));
+    }
+
+    #[test]
+    fn bad_continuations_and_raw_frames_are_rejected() {
+        let path = std::env::temp_dir().join(format!(
+            "gnss2tec-config-dangling-{}.dat",
+            std::process::id()
+        ));
+        fs::write(&path, "!UBX CFG-VALSET 1 \\\n").unwrap();
+        let entries = parse_ubx_config_lines(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, "CFG-VALSET");
+        assert!(entries[0].packet.is_err());
+
+        // Odd hex digits, a wrong checksum, and a length field that disagrees with the bytes.
+        assert!(build_raw_packet(&["0xB56", "0x06"]).is_err());
+        assert!(build_raw_packet(&["0xB56206080600E80301000100", "0x013A"]).is_err());
+        assert!(build_raw_packet(&["0xB56206080700E80301000100", "0x0139"]).is_err());
+        assert!(build_raw_packet(&["0xB56206080600E80301000100", "0x0139"]).is_ok());
+
+        // Booleans take 0 or 1, and keys come in pairs.
+        assert!(build_cfg_valset_packet(&["1", "0x10740001", "2"]).is_err());
+        assert!(build_cfg_valset_packet(&["1", "0x30210001"]).is_err());
+    }
+
+    // Log arguments writing into `<dir>/data`, as the CLI would build them.
+    fn scratch_log_args(dir: &Path, extra: &[&str]) -> LogArgs {
+        let data_dir = dir.join("data");
+        let lock_file = dir.join("ubx_log.lock");
+        let mut argv = vec![
+            "gnss2tec-logger",
+            "log",
+            "--data-dir",
+            data_dir.to_str().unwrap(),
+            "--lock-file",
+            lock_file.to_str().unwrap(),
+        ];
+        argv.extend_from_slice(extra);
+        let AppCommand::Log(args) = Cli::try_parse_from(argv).unwrap().command else {
+            panic!("expected the log command");
+        };
+        args
+    }
+
+    // Open the output for `now`, write `bytes`, and close it again; returns the file.
+    fn log_session(args: &LogArgs, now: DateTime<Utc>, bytes: &[u8]) -> PathBuf {
+        let options = StreamOptions {
+            write_buffer_bytes: 0,
+            rotation_durability: Durability::None,
+        };
+        let (_, _, mut writer, path) = open_new_log_file(args, &options, now).unwrap();
+        writer.write_all(bytes).unwrap();
+        writer.finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn single_file_per_hour_restart_appends_to_the_hour_file() {
+        let dir = std::env::temp_dir().join(format!("gnss2tec-hour-file-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let args = scratch_log_args(&dir, &["--single-file-per-hour"]);
+        let hour = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+
+        let first = log_session(&args, hour + chrono::Duration::seconds(310), b"first ");
+        // A restart later in the same hour resumes the same file.
+        let second = log_session(&args, hour + chrono::Duration::minutes(40), b"second");
+        assert_eq!(first, args.data_dir.join("20260301_12.ubx"));
+        assert_eq!(second, first);
+        assert_eq!(fs::read(&first).unwrap(), b"first second");
+        assert!(hour_has_ubx_files(&args.data_dir, args.data_layout, hour).unwrap());
+
+        let next = log_session(&args, hour + chrono::Duration::minutes(61), b"next");
+        assert_eq!(next, args.data_dir.join("20260301_13.ubx"));
+
+        // Without the option every start gets its own file.
+        let args = scratch_log_args(&dir, &[]);
+        let restarted = log_session(&args, hour + chrono::Duration::seconds(310), b"x");
+        assert_eq!(restarted, args.data_dir.join("20260301_120510.ubx"));
+
+        // Only one logger may append to the shared hour file at a time.
+        let held = acquire_hour_file_lock(&args.data_dir).unwrap();
+        assert!(acquire_hour_file_lock(&args.data_dir).is_err());
+        drop(held);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn daily_layout_places_files_in_day_directories() {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-daily-layout-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let args = scratch_log_args(&dir, &["--data-layout", "daily"]);
+        let before_midnight = Utc.with_ymd_and_hms(2026, 3, 1, 23, 59, 58).unwrap();
+
+        let path = log_session(&args, before_midnight, b"late");
+        assert_eq!(
+            path,
+            args.data_dir.join("20260301").join("20260301_235958.ubx")
+        );
+        let path = log_session(
+            &args,
+            before_midnight + chrono::Duration::seconds(3),
+            b"early",
+        );
+        assert_eq!(
+            path,
+            args.data_dir.join("20260302").join("20260302_000001.ubx")
+        );
+
+        // The default flat layout keeps writing straight into the data directory.
+        let args = scratch_log_args(&dir, &[]);
+        let path = log_session(&args, before_midnight, b"flat");
+        assert_eq!(path, args.data_dir.join("20260301_235958.ubx"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Writer that records which durability calls reach it.
+    #[derive(Default)]
+    pub(crate) struct RecordingWriter {
+        pub(crate) bytes: Vec<u8>,
+        pub(crate) flushes: u32,
+        pub(crate) syncs: u32,
+    }
+
+    impl Write for RecordingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.bytes.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+
+    impl SyncData for RecordingWriter {
+        fn sync_data(&mut self) -> io::Result<()> {
+            self.syncs += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn periodic_flush_follows_durability() {
+        let mut disk_guard = DiskSpaceGuard::new(&std::env::temp_dir(), 0);
+        for (durability, flushes, syncs) in [
+            (Durability::None, 0, 0),
+            (Durability::Flush, 1, 0),
+            (Durability::Fsync, 0, 1),
+        ] {
+            let mut writer = RecordingWriter::default();
+            periodic_flush(&mut disk_guard, &mut writer, durability).unwrap();
+            periodic_flush(&mut disk_guard, &mut writer, durability).unwrap();
+            assert_eq!(
+                (writer.flushes, writer.syncs),
+                (flushes * 2, syncs * 2),
+                "{durability:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn fsync_durability_reaches_the_file_through_the_buffer() {
+        let dir = std::env::temp_dir().join(format!("gnss2tec-durability-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut disk_guard = DiskSpaceGuard::new(&dir, 0);
+        for compress in [false, true] {
+            let path = dir.join(log_file_name("20260301_120000", compress));
+            let mut writer = LogWriter::create(&path, compress, 4_096).unwrap();
+            writer.write_all(&[0xB5; 100]).unwrap();
+            assert_eq!(fs::metadata(&path).unwrap().len(), 0);
+            // `sync_data` pushes the userspace (and gzip) buffers out before syncing the file.
+            periodic_flush(&mut disk_guard, &mut writer, Durability::Fsync).unwrap();
+            assert!(fs::metadata(&path).unwrap().len() > 0);
+            finish_log_writer(writer, &disk_guard, Durability::Fsync).unwrap();
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn buffered_writes_survive_rotation_intact() {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-write-buffer-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let options = StreamOptions {
+            write_buffer_bytes: 4_096,
+            rotation_durability: Durability::Fsync,
+        };
+        let stream: Vec<u8> = (0..20_000_u32).map(|i| (i % 251) as u8).collect();
+        let (closing, opening) = stream.split_at(13_337);
+        let hour = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        for compress in [false, true] {
+            let extra: &[&str] = if compress { &["--compress-logs"] } else { &[] };
+            let args = scratch_log_args(&dir, extra);
+            let disk_guard = DiskSpaceGuard::new(&dir, 0);
+            let before = hour + chrono::Duration::seconds(3_599);
+            let (_, _, mut writer, old_path) = open_new_log_file(&args, &options, before).unwrap();
+            for chunk in closing.chunks(700) {
+                writer.write_all(chunk).unwrap();
+            }
+            // Rotate the way the writer loop does: open the next hour, then finish the old one.
+            let after = hour + chrono::Duration::hours(1);
+            let (_, _, new_writer, new_path) = open_new_log_file(&args, &options, after).unwrap();
+            let old_writer = std::mem::replace(&mut writer, new_writer);
+            finish_log_writer(old_writer, &disk_guard, options.rotation_durability).unwrap();
+            for chunk in opening.chunks(700) {
+                writer.write_all(chunk).unwrap();
+            }
+            finish_log_writer(writer, &disk_guard, Durability::Flush).unwrap();
+
+            let read_back = |path: &Path| {
+                let raw = fs::read(path).unwrap();
+                if !compress {
+                    return raw;
+                }
+                let mut decoded = Vec::new();
+                flate2::read::MultiGzDecoder::new(raw.as_slice())
+                    .read_to_end(&mut decoded)
+                    .unwrap();
+                decoded
+            };
+            assert_ne!(old_path, new_path);
+            assert_eq!(read_back(&old_path), closing, "compress={compress}");
+            assert_eq!(read_back(&new_path), opening, "compress={compress}");
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn every_registered_command_is_handled_by_the_parser() {
+        // A valid line per registry entry and the CFG message id it must encode to.
+        let samples = [
+            ("CFG-MSG", "240 0 0 1 0 1 0 0", 0x01),
+            ("CFG-GNSS", "0 60 60 1 0 30 30 0 65537", 0x3E),
+            (
+                "CFG-NAV5",
+                "0x0003 2 3 0 10000 10 0 250 250 100 300 0 60 0 0 0 0",
+                ID_CFG_NAV5,
+            ),
+            ("CFG-RATE", "1000 1 1", ID_CFG_RATE),
+            ("CFG-RST", "0x0000 0x01 0x00", ID_CFG_RST),
+            (
+                "CFG-TMODE3",
+                "1 ECEF 0 0 0 0 0 0 0 86400 20000",
+                ID_CFG_TMODE3,
+            ),
+            ("CFG-VALSET", "1 0x30210001 1000", ID_CFG_VALSET),
+            ("RAW", "0xB56206080600E80301000100 0x0139", ID_CFG_RATE),
+        ];
+        let names: Vec<&str> = UBX_CONFIG_COMMANDS.iter().map(|entry| entry.name).collect();
+        let sampled: Vec<&str> = samples.iter().map(|(name, _, _)| *name).collect();
+        assert_eq!(names, sampled);
+
+        for (name, args, id) in samples {
+            let packets = parse_ubx_config_text(&format!("!UBX {name} {args}\n"));
+            assert_eq!(packets.len(), 1, "{name}");
+            assert_eq!(
+                ubx::packet_class_id(&packets[0]),
+                Some((CLASS_CFG, id)),
+                "{name}"
+            );
+        }
+
+        let err = build_ubx_packet_from_config("CFG-PRT", &[]).unwrap_err();
+        assert!(err.to_string().contains("list-commands"), "{err:#}");
+    }
+
+    #[test]
+    fn replayed_capture_rotates_at_the_hour_and_keeps_nmea() {
+        let dir = std::env::temp_dir().join(format!("gnss2tec-replay-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let args = scratch_log_args(&dir, &["--read-timeout-ms", "20"]);
+        fs::create_dir_all(&args.data_dir).unwrap();
+
+        // Two bursts of NMEA and UBX, captured one second apart.
+        let first = [
+            b"$GNGGA,125959.80,,,,,0,00,99.99,,,,,,*7A\r\n".as_slice(),
+            &ubx::encode_packet(0x01, 0x07, &[1; 4]),
+        ]
+        .concat();
+        let second = [
+            b"$GNRMC,130000.80,V,,,,,,,010326,,,N*6B\r\n".as_slice(),
+            &ubx::encode_packet(0x01, 0x07, &[2; 4]),
+        ]
+        .concat();
+        let replay = dir.join("capture.ubx");
+        fs::write(&replay, [first.as_slice(), &second].concat()).unwrap();
+        fs::write(
+            dir.join("capture.ubx.times"),
+            format!("0 {}\n1.0 {}\n", first.len(), first.len() + second.len()),
+        )
+        .unwrap();
+
+        let on_connect: OnConnect = Box::new(|_source: &mut dyn ByteSource, _kind| Ok(()));
+        let port = ReconnectingSource::connect(
+            InputSpec::File {
+                path: replay,
+                realtime: true,
+            },
+            Duration::from_millis(20),
+            ReconnectBackoff::new(Duration::from_millis(10), Duration::from_millis(10)),
+            on_connect,
+        )
+        .unwrap();
+        // The clock starts 0.2 s before 13:00, so the hour ends between the two bursts.
+        let started = Instant::now();
+        let base = Utc.with_ymd_and_hms(2026, 3, 1, 12, 59, 59).unwrap()
+            + chrono::Duration::milliseconds(800);
+        let wall_now = move || base + chrono::Duration::from_std(started.elapsed()).unwrap();
+
+        let queue = Arc::new(ChunkQueue::new(1_024, OverflowPolicy::Block));
+        let reader = InputReader {
+            port,
+            queue: Arc::clone(&queue),
+            read_buffer_bytes: 1_024,
+            running: Arc::new(AtomicBool::new(true)),
+            reload_requested: Arc::new(AtomicBool::new(false)),
+            config_file: dir.join("ubx.dat"),
+            packets: Arc::new(Mutex::new(Vec::new())),
+            stall_detector: StallDetector::new(0),
+            stall_resend_config: false,
+            link_check: LinkCheck::new(0),
+            watchdog: Watchdog::disabled(),
+            read_cadence: ReadCadence::new(0),
+        }
+        .spawn()
+        .unwrap();
+        let options = StreamOptions {
+            write_buffer_bytes: 0,
+            rotation_durability: Durability::None,
+        };
+        let mut closed = Vec::new();
+        let (written, _) = write_log_stream_at(
+            &args,
+            &options,
+            &queue,
+            "replay",
+            LogClassFilter::from_spec(None).unwrap(),
+            &mut |event| {
+                if let StreamEvent::HourClosed(hour) = event {
+                    closed.push(hour);
+                }
+            },
+            &wall_now,
+        )
+        .unwrap();
+        queue.close();
+        join_input_reader(reader).unwrap();
+
+        assert_eq!(written, (first.len() + second.len()) as u64);
+        assert_eq!(
+            closed,
+            [Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap()]
+        );
+        let mut files: Vec<PathBuf> = fs::read_dir(&args.data_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "ubx"))
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 2, "{files:?}");
+        assert_eq!(files[0], args.data_dir.join("20260301_125959.ubx"));
+        assert!(
+            files[1]
+                .file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("20260301_13"),
+            "{files:?}"
+        );
+        // Each file holds its burst whole, NMEA sentences included.
+        assert_eq!(fs::read(&files[0]).unwrap(), first);
+        assert_eq!(fs::read(&files[1]).unwrap(), second);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,8 +1,23 @@
 // Command implementations split by subcommand for clarity.
+pub mod check_config;
 pub mod convert;
+pub mod doctor;
+pub mod list_commands;
 pub mod log;
+pub mod probe;
+pub mod reprocess;
 pub mod run;
+pub mod status;
+pub mod upload;
+pub mod verify_archive;
 
+pub use check_config::run_check_config;
 pub use convert::run_convert;
+pub use doctor::run_doctor;
+pub use list_commands::run_list_commands;
 pub use log::run_log;
+pub use reprocess::run_reprocess;
 pub use run::run_mode;
+pub use status::run_status;
+pub use upload::run_upload;
+pub use verify_archive::run_verify_archive;
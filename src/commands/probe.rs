@@ -0,0 +1,155 @@
+use crate::args::LogArgs;
+use crate::shared::serial::{SerialSettings, UsbPortFilter, acquire_device_lock};
+use crate::shared::source::InputSpec;
+use crate::shared::ubx::{CLASS_MON, ID_MON_VER, encode_packet, read_response};
+use anyhow::{Context, Result, bail};
+use std::io::Write;
+use std::time::Duration;
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+const MON_VER_SW_LEN: usize = 30;
+const MON_VER_HW_LEN: usize = 10;
+const MON_VER_EXT_LEN: usize = 30;
+
+// `log --probe-receiver`: poll UBX-MON-VER, print the receiver's versions, and exit.
+// Confirms the serial/TCP path and the expected hardware before logging; writes no files.
+pub fn probe_receiver(args: &LogArgs) -> Result<()> {
+    let serial_settings = SerialSettings::new(
+        args.baud_rate,
+        args.data_bits,
+        args.parity,
+        args.stop_bits,
+        args.flow_control,
+    )?;
+    let input = InputSpec::from_args(
+        args.input.as_deref(),
+        &args.serial_port,
+        serial_settings,
+        UsbPortFilter {
+            vid: args.usb_vid,
+            pid: args.usb_pid,
+        },
+    )?;
+    let _device_lock = match input.serial_path() {
+        Some(path) if !args.no_device_lock => {
+            Some(acquire_device_lock(path, &args.device_lock_dir)?)
+        }
+        _ => None,
+    };
+    let mut source = input.open(Duration::from_millis(args.read_timeout_ms))?;
+
+    source
+        .write_all(&encode_packet(CLASS_MON, ID_MON_VER, &[]))
+        .and_then(|()| source.flush())
+        .with_context(|| format!("sending MON-VER poll failed: {}", source.label()))?;
+    let frame = read_response(source.as_mut(), CLASS_MON, ID_MON_VER, PROBE_TIMEOUT)
+        .with_context(|| {
+            format!(
+                "receiver on {} did not answer the MON-VER poll (check --baud-rate and that UBX output is enabled)",
+                source.label()
+            )
+        })?;
+
+    let version = parse_mon_ver(&frame.payload)?;
+    println!("receiver: {}", source.label());
+    println!("software version: {}", version.software);
+    println!("hardware version: {}", version.hardware);
+    for extension in &version.extensions {
+        println!("extension: {extension}");
+    }
+    Ok(())
+}
+
+// Decoded UBX-MON-VER payload.
+struct MonVer {
+    software: String,
+    hardware: String,
+    extensions: Vec<String>,
+}
+
+// MON-VER payload: 30-byte swVersion, 10-byte hwVersion, then any number of 30-byte
+// extension strings, all NUL-padded ASCII.
+fn parse_mon_ver(payload: &[u8]) -> Result<MonVer> {
+    let fixed = MON_VER_SW_LEN + MON_VER_HW_LEN;
+    if payload.len() < fixed || !(payload.len() - fixed).is_multiple_of(MON_VER_EXT_LEN) {
+        bail!("unexpected MON-VER payload length {}", payload.len());
+    }
+    Ok(MonVer {
+        software: nul_padded(&payload[..MON_VER_SW_LEN]),
+        hardware: nul_padded(&payload[MON_VER_SW_LEN..fixed]),
+        extensions: payload[fixed..]
+            .chunks(MON_VER_EXT_LEN)
+            .map(nul_padded)
+            .filter(|extension| !extension.is_empty())
+            .collect(),
+    })
+}
+
+fn nul_padded(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn padded(text: &str, len: usize) -> Vec<u8> {
+        let mut field = text.as_bytes().to_vec();
+        field.resize(len, 0);
+        field
+    }
+
+    #[test]
+    fn canned_mon_ver_response_parses_into_version_fields() {
+        let mut payload = padded("EXT CORE 1.00 (3fda8e)", MON_VER_SW_LEN);
+        payload.extend(padded("00190000", MON_VER_HW_LEN));
+        for extension in ["ROM BASE 0x118B2060", "FWVER=HPG 1.32", "PROTVER=27.31", ""] {
+            payload.extend(padded(extension, MON_VER_EXT_LEN));
+        }
+        // The answer arrives after unrelated NMEA output, as on a live port.
+        let mut buffer = b"$GNGGA,,,,,,0,00,99.99,,,,,,*56\r\n".to_vec();
+        buffer.extend(encode_packet(CLASS_MON, ID_MON_VER, &payload));
+
+        let frame = read_response(
+            &mut Cursor::new(buffer),
+            CLASS_MON,
+            ID_MON_VER,
+            PROBE_TIMEOUT,
+        )
+        .unwrap();
+        let version = parse_mon_ver(&frame.payload).unwrap();
+        assert_eq!(version.software, "EXT CORE 1.00 (3fda8e)");
+        assert_eq!(version.hardware, "00190000");
+        // The all-NUL extension slot is dropped.
+        assert_eq!(
+            version.extensions,
+            ["ROM BASE 0x118B2060", "FWVER=HPG 1.32", "PROTVER=27.31"]
+        );
+    }
+
+    #[test]
+    fn mon_ver_payload_length_must_fit_the_layout() {
+        assert!(parse_mon_ver(&[0; 39]).is_err());
+        assert!(parse_mon_ver(&[0; 41]).is_err());
+        let version = parse_mon_ver(&[0; 40]).unwrap();
+        assert!(version.software.is_empty() && version.extensions.is_empty());
+    }
+
+    #[test]
+    fn silent_receiver_times_out_with_a_clear_error() {
+        let err = read_response(
+            &mut Cursor::new(Vec::new()),
+            CLASS_MON,
+            ID_MON_VER,
+            Duration::from_millis(50),
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("no UBX response to class 0x0A id 0x04"),
+            "{err:#}"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use crate::args::ConvertArgs;
+use crate::args::ReprocessArgs;
+use crate::commands::convert::{
+    Converter, converter_for, ensure_converter_available_with, explicit_hour_range,
+    hour_product_prefix, list_numeric_subdirs, reprocess_hour, station_archive_root,
+};
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::lock::LockGuard;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+const ARCHIVED_UBX_SUFFIX: &str = ".ubx.gz";
+// Scratch archive tree for `--replace`, promoted over the originals once an hour succeeds.
+const REPLACE_STAGING_DIR: &str = ".reprocess";
+
+// Public reprocess command entrypoint.
+// Finds the `<prefix>.ubx.gz` hours that `--archive-ubx` left in the archive, and runs each one
+// through the normal conversion with the options given here (RINEX version, station metadata,
+// compression, ...). Products go to `--output-dir`, or replace the originals with `--replace`.
+// Reprocessing never uploads, runs `--post-hook`, merges days, or prunes the archive.
+pub fn run_reprocess(args: ReprocessArgs) -> Result<()> {
+    let converter = converter_for(&args.convert);
+    run_reprocess_with(args, converter)
+}
+
+// `run_reprocess` with an explicit conversion backend.
+fn run_reprocess_with(args: ReprocessArgs, converter: &dyn Converter) -> Result<()> {
+    let (Some(from), Some(to)) = (args.convert.from.as_deref(), args.convert.to.as_deref()) else {
+        bail!("reprocess needs --from and --to (UTC hours as YYYYMMDD_HH)");
+    };
+    let hours = explicit_hour_range(from, to, args.convert.allow_large_range)?;
+    let (first, last) = (hours[0], hours[hours.len() - 1]);
+    if args.output_dir.is_none() && !args.replace {
+        bail!(
+            "reprocess writes into --archive-dir only with --replace; pass --output-dir <dir> to keep the originals"
+        );
+    }
+
+    let source_root = station_archive_root(
+        &args.convert.archive_dir,
+        &args.convert.station,
+        args.convert.archive_station_prefix,
+    );
+    let archived = find_archived_hours(&source_root, &args.convert, &hours)?;
+    if archived.is_empty() {
+        emit_event(
+            EventKind::Info,
+            &format!(
+                "No archived UBX hours between {} and {} under {}",
+                first.format("%Y-%m-%d %H:00"),
+                last.format("%Y-%m-%d %H:00"),
+                source_root.display()
+            ),
+        );
+        return Ok(());
+    }
+
+    let mut convert = args.convert.clone();
+    // The archived UBX is the input here: never delete it or archive a second copy.
+    convert.keep_ubx = true;
+    convert.archive_ubx = false;
+    convert.post_hook = None;
+    convert.rsync_target = None;
+    convert.upload_queue = false;
+    let staging = match &args.output_dir {
+        Some(output_dir) => {
+            convert.archive_dir = output_dir.clone();
+            None
+        }
+        None => {
+            let staging = args.convert.archive_dir.join(REPLACE_STAGING_DIR);
+            convert.archive_dir = staging.clone();
+            // A staged manifest would only list this hour and clobber the real one.
+            convert.day_manifest = false;
+            Some(staging)
+        }
+    };
+
+    fs::create_dir_all(&convert.archive_dir).with_context(|| {
+        format!(
+            "creating archive directory failed: {}",
+            convert.archive_dir.display()
+        )
+    })?;
+    let _lock = LockGuard::acquire_with_timeout(
+        &convert.lock_file,
+        Duration::from_secs(convert.lock_wait_secs),
+        &AtomicBool::new(true),
+    )?;
+    ensure_converter_available_with(&convert, converter, &mut false)?;
+
+    for (dt, ubx) in &archived {
+        emit_event(
+            EventKind::ConvertStart,
+            &format!(
+                "Reprocessing UTC hour {} from {}",
+                dt.format("%Y-%m-%d %H:00"),
+                ubx.display()
+            ),
+        );
+        if let Some(staging) = &staging {
+            // Leftovers from an interrupted run must not be promoted with this hour.
+            remove_staging(staging)?;
+        }
+        reprocess_hour(&convert, converter, *dt, std::slice::from_ref(ubx))
+            .with_context(|| format!("reprocessing {} failed", ubx.display()))?;
+        if let Some(staging) = &staging {
+            let replaced = promote_staged(staging, staging, &args.convert.archive_dir)?;
+            remove_staging(staging)?;
+            emit_event(
+                EventKind::Archive,
+                &format!(
+                    "Replaced {replaced} archived file(s) for {}",
+                    dt.format("%Y-%m-%d %H:00")
+                ),
+            );
+        }
+    }
+
+    emit_event(
+        EventKind::ConvertFinish,
+        &format!("Reprocess complete; regenerated {} hour(s)", archived.len()),
+    );
+    Ok(())
+}
+
+// Archived `<prefix>.ubx.gz` files of the given hours, oldest first. Names are built the way
+// the conversion archived them, so custom name templates and short names are found too.
+fn find_archived_hours(
+    root: &Path,
+    args: &ConvertArgs,
+    hours: &[DateTime<Utc>],
+) -> Result<Vec<(DateTime<Utc>, PathBuf)>> {
+    let mut wanted = HashMap::with_capacity(hours.len());
+    for &dt in hours {
+        wanted.insert(
+            format!("{}{ARCHIVED_UBX_SUFFIX}", hour_product_prefix(args, dt)?),
+            dt,
+        );
+    }
+    let mut found = Vec::new();
+    if !root.is_dir() {
+        return Ok(found);
+    }
+    for (_, year_path) in list_numeric_subdirs(root, 4)? {
+        for (_, day_path) in list_numeric_subdirs(&year_path, 3)? {
+            for entry in fs::read_dir(&day_path)
+                .with_context(|| format!("reading directory failed: {}", day_path.display()))?
+            {
+                let entry = entry.with_context(|| format!("iterating {}", day_path.display()))?;
+                if let Some(&dt) = entry.file_name().to_str().and_then(|name| wanted.get(name)) {
+                    found.push((dt, entry.path()));
+                }
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+// Move every staged file to the same relative path under `archive_dir`, replacing what is
+// there; each rename is atomic, so a reader sees either the old or the new product.
+fn promote_staged(dir: &Path, staging: &Path, archive_dir: &Path) -> Result<u32> {
+    let mut moved = 0_u32;
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("reading metadata for {}", path.display()))?;
+        if file_type.is_dir() {
+            moved += promote_staged(&path, staging, archive_dir)?;
+            continue;
+        }
+        let relative = path.strip_prefix(staging).unwrap_or(&path);
+        let target = archive_dir.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating archive path failed: {}", parent.display()))?;
+        }
+        fs::rename(&path, &target).with_context(|| {
+            format!(
+                "replacing archived product failed: {} -> {}",
+                path.display(),
+                target.display()
+            )
+        })?;
+        moved += 1;
+    }
+    Ok(moved)
+}
+
+fn remove_staging(staging: &Path) -> Result<()> {
+    match fs::remove_dir_all(staging) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err).with_context(|| {
+            format!(
+                "removing reprocess staging directory failed: {}",
+                staging.display()
+            )
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::convert::convert_hour_with;
+    use crate::commands::convert::tests::{MockConverter, scratch_convert_args, write_hour_ubx};
+    use chrono::TimeZone;
+
+    fn utc_hour(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, hour, 0, 0).unwrap()
+    }
+
+    // Archive one hour through the normal conversion with `--archive-ubx`.
+    fn archive_hour(name: &str) -> (PathBuf, ConvertArgs, DateTime<Utc>) {
+        let (root, args) = scratch_convert_args(name, &["--archive-ubx"]);
+        let dt = utc_hour(1, 12);
+        write_hour_ubx(&args, dt);
+        assert!(convert_hour_with(&args, &MockConverter::failing(0), dt).unwrap());
+        (root, args, dt)
+    }
+
+    // Every archived file except the UBX input, with its contents.
+    fn archived_products(day: &Path) -> Vec<(String, Vec<u8>)> {
+        let mut products: Vec<(String, Vec<u8>)> = fs::read_dir(day)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| !path.to_string_lossy().ends_with(ARCHIVED_UBX_SUFFIX))
+            .map(|path| {
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, fs::read(&path).unwrap())
+            })
+            .collect();
+        products.sort();
+        products
+    }
+
+    fn replace_args(args: &ConvertArgs, dt: DateTime<Utc>) -> ReprocessArgs {
+        let hour = dt.format("%Y%m%d_%H").to_string();
+        let mut convert = args.clone();
+        convert.from = Some(hour.clone());
+        convert.to = Some(hour);
+        ReprocessArgs {
+            convert,
+            output_dir: None,
+            replace: true,
+        }
+    }
+
+    #[test]
+    fn archived_hours_are_found_by_their_templated_names() {
+        let (root, args) = scratch_convert_args(
+            "reprocess-find",
+            &["--nav-name-template", "{station}-{year}{doy}-{hour}_{type}"],
+        );
+        let hours = [utc_hour(1, 12), utc_hour(1, 13), utc_hour(2, 0)];
+        for &dt in &hours[1..] {
+            let day = args
+                .archive_dir
+                .join(dt.format("%Y").to_string())
+                .join(dt.format("%j").to_string());
+            fs::create_dir_all(&day).unwrap();
+            let prefix = hour_product_prefix(&args, dt).unwrap();
+            fs::write(day.join(format!("{prefix}{ARCHIVED_UBX_SUFFIX}")), b"ubx").unwrap();
+            // Neither the default long name nor another product of the hour matches.
+            fs::write(day.join(format!("{}.ubx.gz", dt.format("%Y%m%d_%H"))), b"").unwrap();
+            fs::write(day.join(format!("{prefix}.rnx.gz")), b"").unwrap();
+        }
+        assert!(
+            hour_product_prefix(&args, hours[1])
+                .unwrap()
+                .contains("-2026060-13")
+        );
+
+        let found = find_archived_hours(&args.archive_dir, &args, &hours).unwrap();
+        let found_hours: Vec<DateTime<Utc>> = found.iter().map(|(dt, _)| *dt).collect();
+        assert_eq!(found_hours, hours[1..]);
+        assert!(found.iter().all(|(_, path)| path.is_file()));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn replace_promotes_staged_products_and_removes_staging() {
+        let (root, args, dt) = archive_hour("reprocess-replace");
+        let day = args.archive_dir.join("2026").join("060");
+        let fresh = archived_products(&day);
+        assert!(!fresh.is_empty());
+        for (name, _) in &fresh {
+            fs::write(day.join(name), b"stale").unwrap();
+        }
+
+        run_reprocess_with(replace_args(&args, dt), &MockConverter::failing(0)).unwrap();
+        // Same files as the first conversion, every one of them rewritten.
+        let replaced = archived_products(&day);
+        let names = |products: &[(String, Vec<u8>)]| -> Vec<String> {
+            products.iter().map(|(name, _)| name.clone()).collect()
+        };
+        assert_eq!(names(&replaced), names(&fresh));
+        assert!(replaced.iter().all(|(_, bytes)| bytes != b"stale"));
+        assert!(!args.archive_dir.join(REPLACE_STAGING_DIR).exists());
+        let ubx = format!(
+            "{}{ARCHIVED_UBX_SUFFIX}",
+            hour_product_prefix(&args, dt).unwrap()
+        );
+        assert!(day.join(ubx).is_file());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn failed_replace_leaves_the_originals() {
+        let (root, args, dt) = archive_hour("reprocess-replace-fails");
+        let day = args.archive_dir.join("2026").join("060");
+        let before = archived_products(&day);
+
+        assert!(run_reprocess_with(replace_args(&args, dt), &MockConverter::failing(1)).is_err());
+        assert_eq!(archived_products(&day), before);
+        // The next run clears the half-written staging tree before its first hour.
+        run_reprocess_with(replace_args(&args, dt), &MockConverter::failing(0)).unwrap();
+        assert!(!args.archive_dir.join(REPLACE_STAGING_DIR).exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
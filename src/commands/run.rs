@@ -1,17 +1,37 @@
-use crate::args::{ConvertArgs, RunArgs};
-use crate::commands::convert::{convert_hour_utc, ensure_converter_available};
-use crate::commands::log::{parse_ubx_config, send_ubx_packets};
+use crate::args::{ConvertArgs, Durability, RunArgs};
+use crate::commands::convert::{
+    Converter, convert_hour_with, converter_for, ensure_converter_available_with, floor_to_hour,
+    hour_has_ubx_files, is_converter_timeout, is_hour_complete, merge_completed_days,
+    prune_expired_archive_days, station_archive_root,
+};
+use crate::commands::log::{
+    InputReader, PortCommands, StreamEvent, StreamOptions, describe_dropped, join_input_reader,
+    parse_ubx_config, send_ubx_packets, write_log_stream,
+};
+use crate::shared::chunk_queue::ChunkQueue;
+use crate::shared::convert_spool::ConvertSpool;
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::health_socket::{self, HealthSocket};
+use crate::shared::link_check::LinkCheck;
 use crate::shared::lock::LockGuard;
-use crate::shared::nmea::NmeaMonitor;
-use crate::shared::signal::install_ctrlc_handler;
-use anyhow::{Context, Result, bail};
-use chrono::{DateTime, Duration as ChronoDuration, Timelike, Utc};
-use std::fs::{self, File, OpenOptions};
-use std::io::{self, Read, Write};
-use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use crate::shared::logfile::acquire_hour_file_lock;
+use crate::shared::read_cadence::ReadCadence;
+use crate::shared::sd_notify::Watchdog;
+use crate::shared::serial::{SerialSettings, UsbPortFilter, acquire_device_lock};
+use crate::shared::signal::{install_ctrlc_handler, install_reload_handler};
+use crate::shared::source::{
+    ByteSource, ConnectKind, InputSpec, OnConnect, ReconnectBackoff, ReconnectingSource,
+};
+use crate::shared::stale::check_stale_ubx_files;
+use crate::shared::stall::StallDetector;
+use crate::shared::ubx::await_ubx_frame;
+use crate::shared::ubx_filter::LogClassFilter;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use std::fs;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
@@ -19,6 +39,7 @@ use std::time::{Duration, Instant};
 // This is the simplified primary mode: one process, one logging loop, background conversion worker.
 pub fn run_mode(args: RunArgs) -> Result<()> {
     let running = install_ctrlc_handler()?;
+    let reload_requested = install_reload_handler()?;
 
     // Prepare directories once at startup.
     fs::create_dir_all(&args.data_dir).with_context(|| {
@@ -33,6 +54,17 @@ pub fn run_mode(args: RunArgs) -> Result<()> {
             args.archive_dir.display()
         )
     })?;
+    check_stale_ubx_files(
+        &args.data_dir,
+        args.stale_max_age_hours,
+        args.quarantine_stale,
+    )?;
+
+    if args.catchup_only {
+        return run_catchup_only(&args, running);
+    }
+
+    let log_filter = LogClassFilter::from_spec(args.log_classes.as_deref())?;
 
     // Configure receiver before entering logging loop.
     let packets = parse_ubx_config(&args.config_file)?;
@@ -43,229 +75,974 @@ pub fn run_mode(args: RunArgs) -> Result<()> {
         );
     }
 
-    let mut port = serialport::new(&args.serial_port, args.baud_rate)
-        .timeout(Duration::from_millis(args.read_timeout_ms))
-        .open()
-        .with_context(|| {
-            format!(
-                "opening serial port failed: {} @ {}",
-                args.serial_port, args.baud_rate
-            )
-        })?;
-
-    send_ubx_packets(
-        &mut *port,
-        &packets,
-        Duration::from_millis(args.command_gap_ms),
+    let serial_settings = SerialSettings::new(
+        args.baud_rate,
+        args.data_bits,
+        args.parity,
+        args.stop_bits,
+        args.flow_control,
     )?;
-    eprintln!(
-        "Sent {} UBX configuration commands from {}",
-        packets.len(),
-        args.config_file.display()
-    );
+    // Declared before the input so the post-close command runs after the port is released.
+    let _port_commands = PortCommands::start(
+        args.pre_open_command.as_deref(),
+        args.post_close_command.as_deref(),
+    )?;
+    let input = match args.input_file.as_ref() {
+        Some(path) => InputSpec::File {
+            path: path.clone(),
+            realtime: args.replay_realtime,
+        },
+        None => InputSpec::from_args(
+            args.input.as_deref(),
+            &args.serial_port,
+            serial_settings,
+            UsbPortFilter {
+                vid: args.usb_vid,
+                pid: args.usb_pid,
+            },
+        )?,
+    };
+    let _device_lock = match input.serial_path() {
+        Some(path) if !args.no_device_lock => {
+            Some(acquire_device_lock(path, &args.device_lock_dir)?)
+        }
+        _ => None,
+    };
+    let _hour_file_lock = if args.single_file_per_hour {
+        Some(acquire_hour_file_lock(&args.data_dir)?)
+    } else {
+        None
+    };
+
+    // Receiver config is pushed on the initial connect and again after every reconnect,
+    // since a receiver that dropped off the bus may have power-cycled.
+    let command_gap = Duration::from_millis(args.command_gap_ms);
+    let reset_settle = Duration::from_millis(args.reset_settle_ms);
+    let config_label = args.config_file.display().to_string();
+    // A replayed file has no receiver to answer, and the check would eat the start of the replay.
+    let verify_window = (args.verify_ubx_ms > 0 && !input.is_replay())
+        .then(|| Duration::from_millis(args.verify_ubx_ms));
+    let packets = Arc::new(Mutex::new(packets));
+    let packets_for_connect = Arc::clone(&packets);
+    // CFG-RST goes out on the first connect only; see `send_ubx_packets`.
+    let mut first_connect = true;
+    let on_connect: OnConnect = Box::new(move |source: &mut dyn ByteSource, kind| {
+        let packets = packets_for_connect
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let send_reset = std::mem::take(&mut first_connect);
+        let sent = send_ubx_packets(source, &packets, command_gap, reset_settle, send_reset)?;
+        emit_event(
+            EventKind::Config,
+            &format!(
+                "Sent {} UBX configuration commands from {} to {}",
+                sent,
+                config_label,
+                source.label()
+            ),
+        );
+        // The check swallows what it reads, so a resend on the live connection skips it.
+        if let Some(window) = verify_window
+            && kind == ConnectKind::Opened
+        {
+            await_ubx_frame(source, window)
+                .with_context(|| format!("receiver on {} failed the UBX check", source.label()))?;
+        }
+        Ok(())
+    });
+    let port = ReconnectingSource::connect(
+        input,
+        Duration::from_millis(args.read_timeout_ms),
+        ReconnectBackoff::new(
+            Duration::from_millis(args.reconnect_delay_ms),
+            Duration::from_secs(args.reconnect_max_backoff_secs),
+        ),
+        on_connect,
+    )?;
+    let input_label = port.label().to_string();
+    let mut watchdog = Watchdog::from_env();
+    watchdog.ready();
 
     // Start conversion worker so logging never blocks on conversion execution.
     let convert_args = args.to_convert_args();
-    let (convert_tx, convert_worker) = spawn_conversion_worker(convert_args, Arc::clone(&running));
+    let retry = RetryPolicy::from_args(&args);
+    let (convert_queue, convert_worker) =
+        spawn_conversion_worker(convert_args, retry, Arc::clone(&running));
+
+    let requeued = convert_queue.requeue_spooled();
+    if requeued > 0 {
+        emit_event(
+            EventKind::Startup,
+            &format!(
+                "Re-enqueued {} spooled hour(s) from a previous run",
+                requeued
+            ),
+        );
+    }
 
     // Optional startup catch-up: enqueue recent past hours for background conversion.
     if args.convert_on_start {
-        let enqueued = enqueue_startup_catchup_hours(&args, &convert_tx);
+        let enqueued = enqueue_startup_catchup_hours(&args, &convert_queue);
         if enqueued > 0 {
-            eprintln!("Startup catch-up enqueued {} hour(s)", enqueued);
+            emit_event(
+                EventKind::Startup,
+                &format!("Startup catch-up enqueued {} hour(s)", enqueued),
+            );
         }
     }
 
-    // Main single-thread logging loop.
-    let mut buffer = vec![0_u8; args.read_buffer_bytes.max(1_024)];
-    let flush_interval = Duration::from_secs(args.flush_interval_secs.max(1));
-    let stats_interval = if args.stats_interval_secs == 0 {
-        None
-    } else {
-        Some(Duration::from_secs(args.stats_interval_secs.max(1)))
+    // Receiver reads run on their own thread and feed the writer through a bounded buffer.
+    let queue = Arc::new(ChunkQueue::new(args.ring_buffer_bytes, args.overflow));
+    let _health_socket = match args.health_socket.as_deref() {
+        Some(path) => Some(HealthSocket::bind(path, Arc::clone(&queue))?),
+        None => None,
     };
-    let mut last_flush = Instant::now();
-    let mut last_stats = Instant::now();
-    let mut stats_window_bytes: u64 = 0;
-    let mut total_bytes: u64 = 0;
-    let mut nmea_monitor = NmeaMonitor::new(args.nmea_log_interval_secs, args.nmea_log_format);
-
-    let (mut active_hour_key, mut active_hour_start, mut writer, current_path) =
-        open_new_log_file_for_time(&args.data_dir, Utc::now())?;
-    eprintln!("Logging UBX data to {}", current_path.display());
-
-    while running.load(Ordering::SeqCst) {
-        match port.read(&mut buffer) {
-            Ok(0) => {}
-            Ok(size) => {
-                writer
-                    .write_all(&buffer[..size])
-                    .context("writing UBX bytes to file failed")?;
-                total_bytes += size as u64;
-                stats_window_bytes += size as u64;
-                nmea_monitor.ingest(&buffer[..size]);
-            }
-            Err(err) if err.kind() == io::ErrorKind::TimedOut => {}
-            Err(err) => {
-                return Err(err).context("reading GNSS stream from serial port failed");
+    let reader = InputReader {
+        port,
+        queue: Arc::clone(&queue),
+        read_buffer_bytes: args.read_buffer_bytes,
+        running: Arc::clone(&running),
+        reload_requested,
+        config_file: args.config_file.clone(),
+        packets,
+        stall_detector: StallDetector::new(args.stall_timeout_secs),
+        stall_resend_config: args.stall_resend_config,
+        link_check: LinkCheck::new(args.link_check_secs),
+        watchdog,
+        read_cadence: ReadCadence::new(args.read_gap_warn_ms),
+    }
+    .spawn()?;
+
+    // Main writer loop: drain received bytes, rotate hourly, and hand closed hours to the worker.
+    let written = write_log_stream(
+        &args.to_log_args(),
+        &stream_options(&args),
+        &queue,
+        &input_label,
+        log_filter,
+        &mut |event| handle_stream_event(&args, &convert_queue, event),
+    );
+    queue.close();
+    // A read error closes the queue early and is the root cause of any write-side fallout.
+    let read = join_input_reader(reader);
+    if read.is_err() || written.is_err() {
+        // Stop serving retries on a failure exit; hours still pending stay in the spool.
+        running.store(false, Ordering::SeqCst);
+    }
+    drop(convert_queue);
+    if convert_worker.join().is_err() {
+        emit_event(EventKind::Shutdown, "Conversion worker panicked");
+    }
+    read?;
+    let (session_bytes, dropped) = written?;
+    emit_event(
+        EventKind::Shutdown,
+        &format!(
+            "Run mode stopped, wrote {} bytes{}",
+            session_bytes,
+            describe_dropped(dropped)
+        ),
+    );
+    Ok(())
+}
+
+// Writer settings for `run`: a file closed at rotation goes straight to conversion, so it is
+// always synced, whatever `--durability` says about periodic flushes.
+fn stream_options(args: &RunArgs) -> StreamOptions {
+    StreamOptions {
+        write_buffer_bytes: args.write_buffer_bytes,
+        rotation_durability: Durability::Fsync,
+    }
+}
+
+// Run-mode reaction to the writer loop: closed hours go to the conversion worker, and a full
+// disk applies archive retention right away to make room.
+fn handle_stream_event(args: &RunArgs, convert_queue: &ConversionQueue, event: StreamEvent) {
+    match event {
+        StreamEvent::DiskPaused => {
+            let archive_root = station_archive_root(
+                &args.archive_dir,
+                &args.station,
+                args.archive_station_prefix,
+            );
+            if let Err(err) = prune_expired_archive_days(
+                &archive_root,
+                &args.archive_timezone,
+                args.archive_retention_days,
+            ) {
+                emit_event(
+                    EventKind::Archive,
+                    &format!("Archive retention cleanup failed: {err:#}"),
+                );
             }
         }
-
-        let now = Utc::now();
-        let hour_key = now.format("%Y%m%d_%H").to_string();
-        if hour_key != active_hour_key {
-            // Flush and rotate quickly first to avoid any logging gaps.
-            writer.flush().context("flushing log file failed")?;
-            let closed_hour = active_hour_start;
-
-            let (new_hour_key, new_hour_start, new_writer, path) =
-                open_new_log_file_for_time(&args.data_dir, now)?;
-            let old_writer = std::mem::replace(&mut writer, new_writer);
-            drop(old_writer);
-            active_hour_key = new_hour_key;
-            active_hour_start = new_hour_start;
-            eprintln!("Rotated UBX output to {}", path.display());
-
-            if let Err(err) = convert_tx.send(closed_hour) {
-                eprintln!(
-                    "Conversion worker channel closed; skipped conversion for {}: {}",
-                    closed_hour.format("%Y-%m-%d %H:00"),
-                    err
+        StreamEvent::HourClosed(hour) => {
+            if let Err(err) = convert_queue.enqueue(hour) {
+                emit_event(
+                    EventKind::ConvertFailed,
+                    &format!(
+                        "Conversion worker channel closed; skipped conversion for {}: {}",
+                        hour.format("%Y-%m-%d %H:00"),
+                        err
+                    ),
                 );
             }
         }
+    }
+}
 
-        if last_flush.elapsed() >= flush_interval {
-            writer.flush().context("periodic flush failed")?;
-            last_flush = Instant::now();
-        }
+// `--catchup-only`: queue the startup catch-up window, let the worker drain it (including
+// retries), and exit without touching the receiver.
+fn run_catchup_only(args: &RunArgs, running: Arc<AtomicBool>) -> Result<()> {
+    let convert_args = args.to_convert_args();
+    let converter = converter_for(&convert_args);
+    run_catchup_only_with(args, convert_args, converter, running)
+}
 
-        if let Some(interval) = stats_interval
-            && last_stats.elapsed() >= interval
-        {
-            let elapsed = last_stats.elapsed().as_secs_f64().max(0.001);
-            let bps = ((stats_window_bytes as f64 * 8.0) / elapsed).round() as u64;
-            eprintln!(
-                "[STAT] {:>10} B {:>7} bps {}",
-                total_bytes, bps, args.serial_port
-            );
-            stats_window_bytes = 0;
-            last_stats = Instant::now();
-        }
+fn run_catchup_only_with(
+    args: &RunArgs,
+    convert_args: ConvertArgs,
+    converter: &'static dyn Converter,
+    running: Arc<AtomicBool>,
+) -> Result<()> {
+    let retry = RetryPolicy::from_args(args);
+    let (convert_queue, convert_worker) =
+        spawn_conversion_worker_with(convert_args, converter, retry, running);
+    let requeued = convert_queue.requeue_spooled();
+    let enqueued = enqueue_startup_catchup_hours(args, &convert_queue);
+    emit_event(
+        EventKind::Startup,
+        &format!(
+            "Catch-up only: enqueued {enqueued} hour(s) plus {requeued} spooled hour(s) for conversion"
+        ),
+    );
+    drop(convert_queue);
+    if convert_worker.join().is_err() {
+        bail!("conversion worker panicked");
+    }
+    emit_event(EventKind::Shutdown, "Catch-up only run finished");
+    Ok(())
+}
+
+// Retry settings for hours whose conversion failed in the background worker.
+#[derive(Clone, Copy)]
+struct RetryPolicy {
+    max_retries: u32,
+    base_delay: Duration,
+}
 
-        nmea_monitor.maybe_emit_logs();
+impl RetryPolicy {
+    fn from_args(args: &RunArgs) -> Self {
+        Self {
+            max_retries: args.convert_max_retries,
+            base_delay: Duration::from_secs(args.convert_retry_delay_secs.max(1)),
+        }
     }
 
-    writer.flush().context("final flush failed")?;
-    drop(convert_tx);
-    if convert_worker.join().is_err() {
-        eprintln!("Conversion worker panicked");
+    // Exponential backoff: base, 2x base, 4x base, ...
+    fn delay_for(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(1_u32 << attempt.saturating_sub(1).min(16))
     }
-    eprintln!("Run mode stopped, wrote {} bytes", total_bytes);
-    Ok(())
 }
 
-// Open a fresh timestamped UBX file and return the corresponding UTC hour bucket key.
-fn open_new_log_file_for_time(
-    data_dir: &Path,
-    now: DateTime<Utc>,
-) -> Result<(String, DateTime<Utc>, File, PathBuf)> {
-    let hour_start = floor_to_hour(now);
-    let hour_key = hour_start.format("%Y%m%d_%H").to_string();
-    let file_name = format!("{}.ubx", now.format("%Y%m%d_%H%M%S"));
-    let path = data_dir.join(file_name);
-    let file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(&path)
-        .with_context(|| format!("opening log output failed: {}", path.display()))?;
-    Ok((hour_key, hour_start, file, path))
+// One failed hour waiting for its next attempt.
+struct PendingRetry {
+    hour: DateTime<Utc>,
+    attempt: u32,
+    due: Instant,
+}
+
+enum HourOutcome {
+    // Converted, or nothing to convert (no UBX files): never retried.
+    Finished,
+    // Failed in a way that may succeed later (lock busy, converter missing, conversion error).
+    Transient,
 }
 
-// Truncate a DateTime to top-of-hour in UTC for deterministic hour bucket handling.
-fn floor_to_hour(dt: DateTime<Utc>) -> DateTime<Utc> {
-    dt.with_minute(0)
-        .and_then(|v| v.with_second(0))
-        .and_then(|v| v.with_nanosecond(0))
-        .expect("UTC floor-to-hour should always be valid")
+// Sender side of the conversion worker. Hours are spooled to disk before they are sent,
+// so a crash between rotation and conversion does not lose them.
+struct ConversionQueue {
+    tx: Sender<DateTime<Utc>>,
+    spool: ConvertSpool,
+}
+
+impl ConversionQueue {
+    fn enqueue(&self, hour: DateTime<Utc>) -> Result<()> {
+        if let Err(err) = self.spool.push(hour) {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "Spooling conversion for {} failed (still queued in memory): {err:#}",
+                    hour.format("%Y-%m-%d %H:00")
+                ),
+            );
+        }
+        self.tx
+            .send(hour)
+            .map_err(|_| anyhow!("conversion worker channel closed"))
+    }
+
+    // Send hours left in the spool by a previous process; they are already on disk.
+    fn requeue_spooled(&self) -> usize {
+        let hours = match self.spool.load() {
+            Ok(hours) => hours,
+            Err(err) => {
+                emit_event(
+                    EventKind::Warning,
+                    &format!("Reading conversion spool failed: {err:#}"),
+                );
+                return 0;
+            }
+        };
+        let mut requeued = 0_usize;
+        for hour in hours {
+            if self.tx.send(hour).is_err() {
+                break;
+            }
+            requeued += 1;
+        }
+        requeued
+    }
 }
 
 fn spawn_conversion_worker(
     convert_args: ConvertArgs,
+    retry: RetryPolicy,
     running: Arc<AtomicBool>,
-) -> (Sender<DateTime<Utc>>, JoinHandle<()>) {
+) -> (ConversionQueue, JoinHandle<()>) {
+    let converter = converter_for(&convert_args);
+    spawn_conversion_worker_with(convert_args, converter, retry, running)
+}
+
+fn spawn_conversion_worker_with(
+    convert_args: ConvertArgs,
+    converter: &'static dyn Converter,
+    retry: RetryPolicy,
+    running: Arc<AtomicBool>,
+) -> (ConversionQueue, JoinHandle<()>) {
     let (tx, rx) = mpsc::channel::<DateTime<Utc>>();
-    let handle = thread::spawn(move || conversion_worker_loop(convert_args, running, rx));
-    (tx, handle)
+    let spool = ConvertSpool::new(&convert_args.data_dir);
+    let worker_spool = spool.clone();
+    let handle = thread::spawn(move || {
+        conversion_worker_loop(convert_args, converter, retry, worker_spool, running, rx)
+    });
+    (ConversionQueue { tx, spool }, handle)
 }
 
 fn conversion_worker_loop(
     convert_args: ConvertArgs,
+    converter: &dyn Converter,
+    retry: RetryPolicy,
+    spool: ConvertSpool,
     running: Arc<AtomicBool>,
     rx: Receiver<DateTime<Utc>>,
 ) {
-    eprintln!("Conversion worker started");
+    emit_event(EventKind::Startup, "Conversion worker started");
+    let mut retries: Vec<PendingRetry> = Vec::new();
+    let mut sampling_checked = false;
     loop {
-        match rx.recv_timeout(Duration::from_secs(1)) {
-            Ok(hour) => convert_one_hour(&convert_args, hour),
+        // Wake up for the earliest due retry without delaying newly closed hours.
+        let wait = retries
+            .iter()
+            .map(|pending| pending.due.saturating_duration_since(Instant::now()))
+            .min()
+            .unwrap_or(Duration::from_secs(1))
+            .min(Duration::from_secs(1));
+        match rx.recv_timeout(wait) {
+            Ok(hour) => {
+                let outcome = convert_one_hour(
+                    &convert_args,
+                    converter,
+                    hour,
+                    &running,
+                    &mut sampling_checked,
+                );
+                if schedule_retry(&mut retries, &retry, hour, 0, outcome) {
+                    remove_from_spool(&spool, hour);
+                }
+            }
             Err(RecvTimeoutError::Timeout) => {
                 if !running.load(Ordering::SeqCst) {
                     break;
                 }
             }
+            // `--catchup-only` drops the sender once everything is queued; keep serving retries.
+            Err(RecvTimeoutError::Disconnected)
+                if !retries.is_empty() && running.load(Ordering::SeqCst) =>
+            {
+                thread::sleep(wait);
+            }
             Err(RecvTimeoutError::Disconnected) => break,
         }
+
+        let now = Instant::now();
+        let (due, waiting): (Vec<_>, Vec<_>) =
+            retries.drain(..).partition(|pending| pending.due <= now);
+        retries = waiting;
+        for pending in due {
+            emit_event(
+                EventKind::ConvertStart,
+                &format!(
+                    "Retrying conversion for {} (attempt {} of {})",
+                    pending.hour.format("%Y-%m-%d %H:00"),
+                    pending.attempt + 1,
+                    retry.max_retries + 1
+                ),
+            );
+            let outcome = convert_one_hour(
+                &convert_args,
+                converter,
+                pending.hour,
+                &running,
+                &mut sampling_checked,
+            );
+            if schedule_retry(&mut retries, &retry, pending.hour, pending.attempt, outcome) {
+                remove_from_spool(&spool, pending.hour);
+            }
+        }
     }
 
-    // Drain any enqueued jobs before exiting.
+    // Drain any enqueued jobs before exiting; failures stay spooled for the next start.
     while let Ok(hour) = rx.try_recv() {
-        convert_one_hour(&convert_args, hour);
+        if matches!(
+            convert_one_hour(
+                &convert_args,
+                converter,
+                hour,
+                &running,
+                &mut sampling_checked
+            ),
+            HourOutcome::Finished
+        ) {
+            remove_from_spool(&spool, hour);
+        }
+    }
+    if !retries.is_empty() {
+        emit_event(
+            EventKind::Shutdown,
+            &format!(
+                "Leaving {} pending conversion retr(ies) in the spool for the next start",
+                retries.len()
+            ),
+        );
+    }
+    emit_event(EventKind::Shutdown, "Conversion worker stopped");
+}
+
+fn remove_from_spool(spool: &ConvertSpool, hour: DateTime<Utc>) {
+    if let Err(err) = spool.remove(hour) {
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "Removing {} from conversion spool failed: {err:#}",
+                hour.format("%Y-%m-%d %H:00")
+            ),
+        );
+    }
+}
+
+// Queue another attempt after a transient failure, until `--convert-max-retries` is used up.
+// Returns true once the hour is settled (finished or given up) and can leave the spool.
+fn schedule_retry(
+    retries: &mut Vec<PendingRetry>,
+    retry: &RetryPolicy,
+    hour: DateTime<Utc>,
+    attempts_so_far: u32,
+    outcome: HourOutcome,
+) -> bool {
+    if matches!(outcome, HourOutcome::Finished) {
+        return true;
+    }
+    let attempt = attempts_so_far + 1;
+    if attempt > retry.max_retries {
+        if retry.max_retries > 0 {
+            emit_event(
+                EventKind::ConvertFailed,
+                &format!(
+                    "Giving up on conversion for {} after {} retr(ies)",
+                    hour.format("%Y-%m-%d %H:00"),
+                    retry.max_retries
+                ),
+            );
+        }
+        return true;
     }
-    eprintln!("Conversion worker stopped");
+
+    let delay = retry.delay_for(attempt);
+    emit_event(
+        EventKind::ConvertFailed,
+        &format!(
+            "Will retry conversion for {} in {}s",
+            hour.format("%Y-%m-%d %H:00"),
+            delay.as_secs()
+        ),
+    );
+    retries.push(PendingRetry {
+        hour,
+        attempt,
+        due: Instant::now() + delay,
+    });
+    false
 }
 
-fn convert_one_hour(convert_args: &ConvertArgs, hour: DateTime<Utc>) {
-    let _lock = match LockGuard::acquire(&convert_args.lock_file) {
+fn convert_one_hour(
+    convert_args: &ConvertArgs,
+    converter: &dyn Converter,
+    hour: DateTime<Utc>,
+    running: &AtomicBool,
+    sampling_checked: &mut bool,
+) -> HourOutcome {
+    let _lock = match LockGuard::acquire_with_timeout(
+        &convert_args.lock_file,
+        Duration::from_secs(convert_args.lock_wait_secs),
+        running,
+    ) {
         Ok(lock) => lock,
         Err(err) => {
-            eprintln!(
-                "Conversion lock unavailable; skipped conversion for {}: {err:#}",
-                hour.format("%Y-%m-%d %H:00")
+            emit_event(
+                EventKind::ConvertFailed,
+                &format!(
+                    "Conversion lock unavailable; skipped conversion for {}: {err:#}",
+                    hour.format("%Y-%m-%d %H:00")
+                ),
             );
-            return;
+            return HourOutcome::Transient;
         }
     };
 
-    if let Err(err) = ensure_converter_available(convert_args) {
-        eprintln!(
-            "Converter unavailable; skipped conversion for {}: {err:#}",
-            hour.format("%Y-%m-%d %H:00")
+    let available = ensure_converter_available_with(convert_args, converter, sampling_checked);
+    health_socket::record_converter_available(available.is_ok());
+    if let Err(err) = available {
+        emit_event(
+            EventKind::ConvertFailed,
+            &format!(
+                "Converter unavailable; skipped conversion for {}: {err:#}",
+                hour.format("%Y-%m-%d %H:00")
+            ),
         );
-        return;
+        return HourOutcome::Transient;
     }
 
-    if let Err(err) = convert_hour_utc(convert_args, hour) {
-        eprintln!(
-            "Hour conversion failed for {} (logger continues): {err:#}",
-            hour.format("%Y-%m-%d %H:00")
-        );
+    match convert_hour_with(convert_args, converter, hour) {
+        Ok(true) => {
+            let archive_root = station_archive_root(
+                &convert_args.archive_dir,
+                &convert_args.station,
+                convert_args.archive_station_prefix,
+            );
+            if let Err(err) = prune_expired_archive_days(
+                &archive_root,
+                &convert_args.archive_timezone,
+                convert_args.archive_retention_days,
+            ) {
+                emit_event(
+                    EventKind::Archive,
+                    &format!("Archive retention cleanup failed (logger continues): {err:#}"),
+                );
+            }
+            if let Err(err) = merge_completed_days(convert_args) {
+                emit_event(
+                    EventKind::ConvertFailed,
+                    &format!("Daily merge failed (logger continues): {err:#}"),
+                );
+            }
+            HourOutcome::Finished
+        }
+        Ok(false) => HourOutcome::Finished,
+        Err(err) if is_converter_timeout(&err) => {
+            // The killed converter may only have been starved (e.g. by a slow SD card), so the
+            // hour is retried like any other failure, but reported as a hang.
+            emit_event(
+                EventKind::ConvertFailed,
+                &format!(
+                    "Hour conversion timed out for {} (logger continues): {err:#}",
+                    hour.format("%Y-%m-%d %H:00")
+                ),
+            );
+            HourOutcome::Transient
+        }
+        Err(err) => {
+            emit_event(
+                EventKind::ConvertFailed,
+                &format!(
+                    "Hour conversion failed for {} (logger continues): {err:#}",
+                    hour.format("%Y-%m-%d %H:00")
+                ),
+            );
+            HourOutcome::Transient
+        }
     }
 }
 
-fn enqueue_startup_catchup_hours(args: &RunArgs, tx: &Sender<DateTime<Utc>>) -> usize {
+// Contiguous empty hours after which the startup catch-up scan stops looking further back.
+const CATCHUP_EMPTY_RUN_LIMIT: u32 = 72;
+
+// Queue the recent past hours that actually have UBX files, newest first. Empty hours are
+// skipped here instead of in the worker, so a station restarted after a long outage does not
+// bury live conversions under hundreds of no-op hours; the scan stops after
+// CATCHUP_EMPTY_RUN_LIMIT empty hours in a row.
+fn enqueue_startup_catchup_hours(args: &RunArgs, queue: &ConversionQueue) -> usize {
     let total_hours = i64::from(args.max_days_back) * 24;
     if total_hours <= 0 {
         return 0;
     }
 
-    let anchor = floor_to_hour(Utc::now() - ChronoDuration::hours(i64::from(args.shift_hours)));
+    let now = Utc::now();
+    let anchor = floor_to_hour(now - ChronoDuration::hours(i64::from(args.shift_hours)));
     let mut enqueued = 0_usize;
+    let mut empty_run = 0_u32;
     for offset in 0..total_hours {
         let hour = anchor - ChronoDuration::hours(offset);
-        if tx.send(hour).is_err() {
+        // With --shift-hours 0 the anchor is the hour this process is about to log into.
+        if !is_hour_complete(hour, now, 0) {
+            continue;
+        }
+        // A scan error queues the hour anyway; the worker reports the real failure.
+        let populated = hour_has_ubx_files(&args.data_dir, args.data_layout, hour).unwrap_or(true);
+        if !populated {
+            empty_run += 1;
+            if empty_run > CATCHUP_EMPTY_RUN_LIMIT {
+                emit_event(
+                    EventKind::Info,
+                    &format!(
+                        "Startup catch-up stopped at {}: no UBX files in the {} hour(s) before it",
+                        hour.format("%Y-%m-%d %H:00"),
+                        CATCHUP_EMPTY_RUN_LIMIT
+                    ),
+                );
+                break;
+            }
+            continue;
+        }
+        empty_run = 0;
+        if queue.enqueue(hour).is_err() {
             break;
         }
         enqueued += 1;
     }
     enqueued
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{AppCommand, Cli};
+    use crate::commands::convert::tests::{MockConverter, scratch_convert_args, write_hour_ubx};
+    use crate::commands::log::sync_closed_file;
+    use crate::commands::log::tests::RecordingWriter;
+    use chrono::TimeZone;
+    use clap::Parser;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    // Run arguments over scratch data and archive directories, as the CLI would build them.
+    fn scratch_run_args(name: &str, extra: &[&str]) -> (PathBuf, RunArgs) {
+        let (root, convert) = scratch_convert_args(name, &[]);
+        let mut argv = vec![
+            "gnss2tec-logger".to_string(),
+            "run".to_string(),
+            "--data-dir".to_string(),
+            convert.data_dir.display().to_string(),
+            "--archive-dir".to_string(),
+            convert.archive_dir.display().to_string(),
+        ];
+        argv.extend(extra.iter().map(|arg| arg.to_string()));
+        match Cli::try_parse_from(argv).unwrap().command {
+            AppCommand::Run(args) => (root, args),
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn transient_failures_are_retried_until_the_hour_converts() {
+        let (root, args) = scratch_convert_args("retry", &[]);
+        let hour = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let ubx = write_hour_ubx(&args, hour);
+        let spool = ConvertSpool::new(&args.data_dir);
+        spool.push(hour).unwrap();
+
+        // Fails twice, then succeeds; the leaked converter outlives the worker like the
+        // `'static` convbin backend does.
+        let converter: &'static MockConverter = Box::leak(Box::new(MockConverter::failing(2)));
+        let retry = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(10),
+        };
+        let (tx, rx) = mpsc::channel();
+        tx.send(hour).unwrap();
+        drop(tx);
+        conversion_worker_loop(
+            args.clone(),
+            converter,
+            retry,
+            spool.clone(),
+            Arc::new(AtomicBool::new(true)),
+            rx,
+        );
+
+        assert_eq!(converter.obs_calls.load(Ordering::SeqCst), 3);
+        assert!(!ubx.exists());
+        assert!(spool.load().unwrap().is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn retries_stop_after_convert_max_retries() {
+        let (root, args) = scratch_convert_args("retry-give-up", &[]);
+        let hour = Utc.with_ymd_and_hms(2026, 3, 1, 13, 0, 0).unwrap();
+        let ubx = write_hour_ubx(&args, hour);
+
+        let converter: &'static MockConverter = Box::leak(Box::new(MockConverter::failing(5)));
+        let retry = RetryPolicy {
+            max_retries: 1,
+            base_delay: Duration::from_millis(10),
+        };
+        let (tx, rx) = mpsc::channel();
+        tx.send(hour).unwrap();
+        drop(tx);
+        conversion_worker_loop(
+            args.clone(),
+            converter,
+            retry,
+            ConvertSpool::new(&args.data_dir),
+            Arc::new(AtomicBool::new(true)),
+            rx,
+        );
+
+        assert_eq!(converter.obs_calls.load(Ordering::SeqCst), 2);
+        assert!(ubx.is_file());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn catchup_only_converts_pending_hours_without_the_receiver() {
+        // Neither the serial port nor the UBX config exists, so touching the receiver would fail.
+        let (root, args) = scratch_run_args(
+            "catchup-only",
+            &[
+                "--catchup-only",
+                "--serial-port",
+                "/nonexistent/ttyACM-catchup",
+                "--config-file",
+                "/nonexistent/ubx.dat",
+                "--shift-hours",
+                "0",
+                "--max-days-back",
+                "1",
+            ],
+        );
+        let mut convert_args = args.to_convert_args();
+        convert_args.lock_file = root.join("convert.lock");
+        let anchor = floor_to_hour(Utc::now());
+        let ubx: Vec<_> = [2, 3]
+            .into_iter()
+            .map(|back| write_hour_ubx(&convert_args, anchor - ChronoDuration::hours(back)))
+            .collect();
+
+        let converter: &'static MockConverter = Box::leak(Box::new(MockConverter::failing(0)));
+        run_catchup_only_with(
+            &args,
+            convert_args,
+            converter,
+            Arc::new(AtomicBool::new(true)),
+        )
+        .unwrap();
+
+        assert_eq!(converter.obs_calls.load(Ordering::SeqCst), 2);
+        assert!(ubx.iter().all(|path| !path.exists()));
+        assert!(ConvertSpool::new(&args.data_dir).load().unwrap().is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    // Hours the startup catch-up scan queues, newest first, for a data directory seeded with
+    // UBX files the given number of hours back.
+    fn catchup_hours(name: &str, max_days_back: &str, seeded: &[i64]) -> Vec<i64> {
+        let (root, args) = scratch_run_args(
+            name,
+            &["--shift-hours", "0", "--max-days-back", max_days_back],
+        );
+        let anchor = floor_to_hour(Utc::now());
+        for back in seeded {
+            write_hour_ubx(
+                &args.to_convert_args(),
+                anchor - ChronoDuration::hours(*back),
+            );
+        }
+        let (tx, rx) = mpsc::channel();
+        let queue = ConversionQueue {
+            tx,
+            spool: ConvertSpool::new(&args.data_dir),
+        };
+
+        let enqueued = enqueue_startup_catchup_hours(&args, &queue);
+        drop(queue);
+        let queued: Vec<i64> = rx.iter().map(|hour| (anchor - hour).num_hours()).collect();
+        assert_eq!(enqueued, queued.len());
+        fs::remove_dir_all(&root).unwrap();
+        queued
+    }
+
+    #[test]
+    fn startup_catchup_queues_only_populated_hours() {
+        assert_eq!(
+            catchup_hours("catchup-sparse", "2", &[30, 1, 5]),
+            [1, 5, 30]
+        );
+        // Beyond --max-days-back nothing is queued even when files exist.
+        assert_eq!(catchup_hours("catchup-window", "1", &[2, 30]), [2]);
+    }
+
+    #[test]
+    fn startup_catchup_stops_after_a_long_empty_run() {
+        // 78 empty hours separate the two populated ones, more than CATCHUP_EMPTY_RUN_LIMIT.
+        assert_eq!(catchup_hours("catchup-gap", "7", &[1, 80]), [1]);
+        assert_eq!(catchup_hours("catchup-short-gap", "7", &[1, 60]), [1, 60]);
+    }
+
+    #[test]
+    fn rotation_syncs_the_closed_hour_even_without_periodic_durability() {
+        let (root, args) = scratch_run_args("rotation-fsync", &["--durability", "none"]);
+        let options = stream_options(&args);
+        assert_eq!(options.rotation_durability, Durability::Fsync);
+        assert_eq!(args.to_log_args().durability, Durability::None);
+
+        // The rotation closes the hour's file with the run options, and it reaches the disk.
+        let mut closed = RecordingWriter::default();
+        closed.write_all(b"closed hour").unwrap();
+        sync_closed_file(&mut closed, options.rotation_durability).unwrap();
+        assert_eq!((closed.flushes, closed.syncs), (0, 1));
+        // `log` with the same --durability would leave it to the OS.
+        let mut closed = RecordingWriter::default();
+        sync_closed_file(&mut closed, args.durability).unwrap();
+        assert_eq!(closed.syncs, 0);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn spooled_hours_from_a_previous_run_are_converted_once() {
+        let (root, args) = scratch_run_args(
+            "catchup-spool",
+            &[
+                "--catchup-only",
+                "--serial-port",
+                "/nonexistent/ttyACM-spool",
+                "--config-file",
+                "/nonexistent/ubx.dat",
+                "--shift-hours",
+                "0",
+                "--max-days-back",
+                "1",
+            ],
+        );
+        let mut convert_args = args.to_convert_args();
+        convert_args.lock_file = root.join("convert.lock");
+        // Hours left in the spool by a crash, far outside the catch-up scan window; one was
+        // queued twice.
+        let hours = [
+            Utc.with_ymd_and_hms(2026, 3, 1, 10, 0, 0).unwrap(),
+            Utc.with_ymd_and_hms(2026, 3, 1, 11, 0, 0).unwrap(),
+        ];
+        let spool = ConvertSpool::new(&args.data_dir);
+        let ubx: Vec<PathBuf> = hours
+            .iter()
+            .map(|hour| write_hour_ubx(&convert_args, *hour))
+            .collect();
+        for hour in [hours[0], hours[1], hours[0]] {
+            spool.push(hour).unwrap();
+        }
+
+        let converter: &'static MockConverter = Box::leak(Box::new(MockConverter::failing(0)));
+        run_catchup_only_with(
+            &args,
+            convert_args,
+            converter,
+            Arc::new(AtomicBool::new(true)),
+        )
+        .unwrap();
+
+        assert_eq!(converter.obs_calls.load(Ordering::SeqCst), 2);
+        assert!(ubx.iter().all(|path| !path.exists()));
+        assert!(spool.load().unwrap().is_empty());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn retry_delay_doubles_per_attempt_and_saturates() {
+        let retry = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_secs(30),
+        };
+        let delays: Vec<u64> = (1..=4)
+            .map(|attempt| retry.delay_for(attempt).as_secs())
+            .collect();
+        assert_eq!(delays, [30, 60, 120, 240]);
+        // The doubling is capped, so a huge attempt count cannot overflow.
+        assert_eq!(retry.delay_for(u32::MAX), retry.delay_for(17));
+    }
+
+    #[test]
+    fn schedule_retry_queues_until_max_retries_is_used_up() {
+        let retry = RetryPolicy {
+            max_retries: 2,
+            base_delay: Duration::from_secs(60),
+        };
+        let hour = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let mut retries = Vec::new();
+
+        let before = Instant::now();
+        assert!(!schedule_retry(
+            &mut retries,
+            &retry,
+            hour,
+            0,
+            HourOutcome::Transient
+        ));
+        assert!(!schedule_retry(
+            &mut retries,
+            &retry,
+            hour,
+            1,
+            HourOutcome::Transient
+        ));
+        let queued: Vec<(u32, Duration)> = retries
+            .iter()
+            .map(|pending| (pending.attempt, pending.due.duration_since(before)))
+            .collect();
+        assert_eq!(queued.len(), 2);
+        assert_eq!(queued[0].0, 1);
+        assert!(queued[0].1 >= Duration::from_secs(60) && queued[0].1 < Duration::from_secs(61));
+        assert_eq!(queued[1].0, 2);
+        assert!(queued[1].1 >= Duration::from_secs(120) && queued[1].1 < Duration::from_secs(121));
+
+        // The third failure exhausts the retries; a finished hour is never queued.
+        assert!(schedule_retry(
+            &mut retries,
+            &retry,
+            hour,
+            2,
+            HourOutcome::Transient
+        ));
+        assert!(schedule_retry(
+            &mut retries,
+            &retry,
+            hour,
+            0,
+            HourOutcome::Finished
+        ));
+        assert_eq!(retries.len(), 2);
+
+        let no_retries = RetryPolicy {
+            max_retries: 0,
+            ..retry
+        };
+        assert!(schedule_retry(
+            &mut retries,
+            &no_retries,
+            hour,
+            0,
+            HourOutcome::Transient
+        ));
+        assert_eq!(retries.len(), 2);
+    }
+}
@@ -0,0 +1,415 @@
+use crate::args::StatusArgs;
+use crate::commands::convert::station_archive_root;
+use crate::shared::logfile::{log_file_dirs, log_file_stem};
+use crate::shared::stale::parse_log_file_time;
+use crate::shared::status_file::read_logger_status;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// Public status command entrypoint.
+// Prints a one-screen station snapshot and fails when logging looks dead, so it doubles as a
+// health check (`gnss2tec-logger status --stale-secs 120 || restart-logger`).
+pub fn run_status(args: StatusArgs) -> Result<()> {
+    run_status_to(&args, &mut io::stdout().lock())
+}
+
+// `run_status` writing the report to `out`.
+fn run_status_to(args: &StatusArgs, out: &mut dyn Write) -> Result<()> {
+    let ubx_files = list_ubx_files(&args.data_dir)?;
+    let newest = ubx_files.iter().max_by_key(|file| file.modified);
+
+    match newest {
+        Some(file) => writeln!(
+            out,
+            "newest UBX file: {} ({} bytes, modified {} ago)",
+            file.path.display(),
+            file.size,
+            format_age(file.age())
+        )?,
+        None => writeln!(out, "newest UBX file: none in {}", args.data_dir.display())?,
+    }
+
+    // The current UTC hour is still being written; everything older is waiting for conversion.
+    let current_hour = Utc::now().format("%Y%m%d_%H").to_string();
+    let pending_hours: BTreeSet<&str> = ubx_files
+        .iter()
+        .map(|file| file.hour_key.as_str())
+        .filter(|hour| *hour != current_hour)
+        .collect();
+    if pending_hours.is_empty() {
+        writeln!(out, "unconverted hours: 0")?;
+    } else {
+        writeln!(
+            out,
+            "unconverted hours: {} ({})",
+            pending_hours.len(),
+            pending_hours.iter().copied().collect::<Vec<_>>().join(", ")
+        )?;
+    }
+
+    let archive_root = station_archive_root(
+        &args.archive_dir,
+        &args.station,
+        args.archive_station_prefix,
+    );
+    match newest_archived_product(&archive_root)? {
+        Some((path, modified)) => writeln!(
+            out,
+            "newest archived product: {} (modified {} ago)",
+            path.display(),
+            format_age(age_of(modified))
+        )?,
+        None => writeln!(
+            out,
+            "newest archived product: none in {}",
+            args.archive_dir.display()
+        )?,
+    }
+
+    writeln!(
+        out,
+        "free space (data): {}",
+        describe_free_space(&args.data_dir)
+    )?;
+    writeln!(
+        out,
+        "free space (archive): {}",
+        describe_free_space(&args.archive_dir)
+    )?;
+
+    match read_logger_status(&args.data_dir) {
+        Ok(Some(status)) => {
+            let field = |key: &str| {
+                status
+                    .get(key)
+                    .map(|value| match value.as_str() {
+                        Some(text) => text.to_string(),
+                        None => value.to_string(),
+                    })
+                    .unwrap_or_else(|| "unknown".to_string())
+            };
+            writeln!(
+                out,
+                "logger: {} bps from {}, last rotation {}, snapshot updated {}",
+                field("bits_per_sec"),
+                field("input"),
+                field("last_rotation"),
+                field("updated")
+            )?;
+        }
+        Ok(None) => writeln!(
+            out,
+            "logger: no status snapshot (stats disabled or logger not running)"
+        )?,
+        Err(err) => writeln!(out, "logger: {err:#}")?,
+    }
+
+    if args.stale_secs == 0 {
+        return Ok(());
+    }
+    let threshold = Duration::from_secs(args.stale_secs);
+    match newest {
+        None => bail!("no UBX data files in {}", args.data_dir.display()),
+        Some(file) if file.age() > threshold => bail!(
+            "newest UBX file {} is older than --stale-secs {}",
+            file.path.display(),
+            args.stale_secs
+        ),
+        Some(_) => Ok(()),
+    }
+}
+
+struct UbxFile {
+    path: PathBuf,
+    hour_key: String,
+    size: u64,
+    modified: SystemTime,
+}
+
+impl UbxFile {
+    fn age(&self) -> Duration {
+        age_of(self.modified)
+    }
+}
+
+// Logger-named UBX files in data_dir or its daily-layout day directories (anything else is
+// ignored).
+fn list_ubx_files(data_dir: &Path) -> Result<Vec<UbxFile>> {
+    let mut files = Vec::new();
+    for dir in log_file_dirs(data_dir)? {
+        list_ubx_files_in(&dir, &mut files)?;
+    }
+    Ok(files)
+}
+
+fn list_ubx_files_in(dir: &Path, files: &mut Vec<UbxFile>) -> Result<()> {
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+        let path = entry.path();
+        let Some(stem) = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(log_file_stem)
+        else {
+            continue;
+        };
+        let Some(started) = parse_log_file_time(stem) else {
+            continue;
+        };
+        let metadata = entry
+            .metadata()
+            .with_context(|| format!("reading metadata for {}", path.display()))?;
+        if !metadata.is_file() {
+            continue;
+        }
+        files.push(UbxFile {
+            hour_key: started.format("%Y%m%d_%H").to_string(),
+            size: metadata.len(),
+            modified: metadata
+                .modified()
+                .with_context(|| format!("reading modification time for {}", path.display()))?,
+            path,
+        });
+    }
+    Ok(())
+}
+
+// Newest file in the latest non-empty <year>/<doy>/ directory under the archive root.
+fn newest_archived_product(archive_dir: &Path) -> Result<Option<(PathBuf, SystemTime)>> {
+    for year_dir in sorted_subdirs_desc(archive_dir)? {
+        for day_dir in sorted_subdirs_desc(&year_dir)? {
+            let mut newest: Option<(PathBuf, SystemTime)> = None;
+            for entry in fs::read_dir(&day_dir)
+                .with_context(|| format!("reading directory failed: {}", day_dir.display()))?
+            {
+                let entry = entry.with_context(|| format!("iterating {}", day_dir.display()))?;
+                let metadata = entry
+                    .metadata()
+                    .with_context(|| format!("reading metadata for {}", entry.path().display()))?;
+                if !metadata.is_file() {
+                    continue;
+                }
+                let modified = metadata.modified().with_context(|| {
+                    format!("reading modification time for {}", entry.path().display())
+                })?;
+                if newest.as_ref().is_none_or(|(_, best)| modified > *best) {
+                    newest = Some((entry.path(), modified));
+                }
+            }
+            if newest.is_some() {
+                return Ok(newest);
+            }
+        }
+    }
+    Ok(None)
+}
+
+// Numeric subdirectories (years, days of year), newest first; a missing directory is empty.
+fn sorted_subdirs_desc(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("reading directory failed: {}", dir.display()));
+        }
+    };
+
+    let mut dirs = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+        let Some(number) = entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if entry.path().is_dir() {
+            dirs.push((number, entry.path()));
+        }
+    }
+    dirs.sort();
+    Ok(dirs.into_iter().rev().map(|(_, path)| path).collect())
+}
+
+fn describe_free_space(dir: &Path) -> String {
+    match (fs2::available_space(dir), fs2::total_space(dir)) {
+        (Ok(available), Ok(total)) => format!(
+            "{} of {} available on {}",
+            format_bytes(available),
+            format_bytes(total),
+            dir.display()
+        ),
+        (Err(err), _) | (_, Err(err)) => format!("unknown for {} ({err})", dir.display()),
+    }
+}
+
+fn age_of(modified: SystemTime) -> Duration {
+    SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or(Duration::ZERO)
+}
+
+fn format_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3_600 => format!("{}m{:02}s", secs / 60, secs % 60),
+        3_600..86_400 => format!("{}h{:02}m", secs / 3_600, (secs % 3_600) / 60),
+        _ => {
+            let since = DateTime::<Utc>::from(SystemTime::now() - age);
+            format!(
+                "{}d (since {})",
+                secs / 86_400,
+                since.format("%Y-%m-%d %H:%M UTC")
+            )
+        }
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+    format!("{:.1} GiB", bytes as f64 / GIB)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::status_file::{LoggerStatus, write_logger_status};
+    use chrono::TimeZone;
+    use std::fs::File;
+
+    fn scratch_args(name: &str) -> (PathBuf, StatusArgs) {
+        let root =
+            std::env::temp_dir().join(format!("gnss2tec-status-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let args = StatusArgs {
+            config: None,
+            data_dir: root.join("data"),
+            archive_dir: root.join("archive"),
+            archive_station_prefix: false,
+            station: "NJIT".to_string(),
+            stale_secs: 300,
+        };
+        fs::create_dir_all(&args.data_dir).unwrap();
+        fs::create_dir_all(&args.archive_dir).unwrap();
+        (root, args)
+    }
+
+    fn set_age(path: &Path, age: Duration) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::now() - age)
+            .unwrap();
+    }
+
+    fn status_report(args: &StatusArgs) -> (Result<()>, String) {
+        let mut out = Vec::new();
+        let result = run_status_to(args, &mut out);
+        (result, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn reports_data_archive_and_logger_snapshot() {
+        let (root, args) = scratch_args("snapshot");
+        let older = args.data_dir.join("20260301_110000.ubx");
+        fs::write(&older, [0xB5; 10]).unwrap();
+        set_age(&older, Duration::from_secs(3_600));
+        let newest = args.data_dir.join("20260301_120000.ubx");
+        fs::write(&newest, [0xB5; 64]).unwrap();
+        // Not a logger file name: ignored.
+        fs::write(args.data_dir.join("notes.txt"), "x").unwrap();
+        let day = args.archive_dir.join("2026").join("060");
+        fs::create_dir_all(&day).unwrap();
+        let product = day.join("NJIT0600.26o.gz");
+        fs::write(&product, b"obs").unwrap();
+        write_logger_status(
+            &args.data_dir,
+            &LoggerStatus {
+                input: "/dev/ttyACM0",
+                current_file: &newest,
+                last_rotation: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap(),
+                total_bytes: 74,
+                bits_per_sec: 9_600,
+            },
+        )
+        .unwrap();
+
+        let (result, report) = status_report(&args);
+        result.unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 6, "{report}");
+        assert!(lines[0].starts_with(&format!(
+            "newest UBX file: {} (64 bytes, modified ",
+            newest.display()
+        )));
+        assert_eq!(lines[1], "unconverted hours: 2 (20260301_11, 20260301_12)");
+        assert!(lines[2].starts_with(&format!(
+            "newest archived product: {} (modified ",
+            product.display()
+        )));
+        assert!(lines[3].starts_with("free space (data): "));
+        assert!(lines[4].starts_with("free space (archive): "));
+        assert!(
+            lines[5].starts_with(
+                "logger: 9600 bps from /dev/ttyACM0, last rotation 2026-03-01T12:00:00+00:00, snapshot updated "
+            ),
+            "{}",
+            lines[5]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn empty_station_reports_nothing_and_fails_the_stale_check() {
+        let (root, args) = scratch_args("empty");
+        let (result, report) = status_report(&args);
+        assert!(result.is_err());
+        assert!(report.contains(&format!(
+            "newest UBX file: none in {}",
+            args.data_dir.display()
+        )));
+        assert!(report.contains("unconverted hours: 0"));
+        assert!(report.contains("newest archived product: none in"));
+        assert!(report.contains("logger: no status snapshot"));
+
+        let args = StatusArgs {
+            stale_secs: 0,
+            ..args
+        };
+        assert!(status_report(&args).0.is_ok());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn old_newest_file_fails_the_stale_check() {
+        let (root, args) = scratch_args("stale");
+        let file = args.data_dir.join("20260301_120000.ubx");
+        fs::write(&file, [0xB5; 8]).unwrap();
+        set_age(&file, Duration::from_secs(600));
+
+        let (result, report) = status_report(&args);
+        let err = result.unwrap_err();
+        assert!(format!("{err}").contains("older than --stale-secs 300"));
+        assert!(report.contains("modified 10m00s ago"), "{report}");
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ages_are_formatted_by_magnitude() {
+        assert_eq!(format_age(Duration::from_secs(59)), "59s");
+        assert_eq!(format_age(Duration::from_secs(61)), "1m01s");
+        assert_eq!(format_age(Duration::from_secs(3_660)), "1h01m");
+        assert!(format_age(Duration::from_secs(2 * 86_400)).starts_with("2d (since "));
+    }
+}
@@ -0,0 +1,179 @@
+use crate::args::{UploadArgs, UploadMethod};
+use crate::shared::checksum::{sha256_file, sha256_reader};
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::upload_queue::{STATUS_CORRUPT, STATUS_DONE, STATUS_PENDING, UploadQueue};
+use anyhow::{Context, Result, bail};
+use chrono::Utc;
+use serde_json::json;
+use std::path::Path;
+use std::process::{Command, Output};
+
+// Public upload command entrypoint.
+// Walks `upload-queue.jsonl`, uploads each pending product, verifies its checksum remotely,
+// and marks it `done` one entry at a time so an interrupted run resumes where it stopped.
+pub fn run_upload(args: UploadArgs) -> Result<()> {
+    let queue = UploadQueue::new(&args.archive_dir);
+    let entries = queue.load()?;
+    let pending: Vec<(String, String)> = entries
+        .iter()
+        .filter(|entry| entry["status"] == STATUS_PENDING)
+        .filter_map(|entry| {
+            Some((
+                entry["path"].as_str()?.to_string(),
+                entry["sha256"].as_str()?.to_string(),
+            ))
+        })
+        .collect();
+
+    if pending.is_empty() {
+        emit_event(
+            EventKind::Upload,
+            &format!("No pending uploads in {}", queue.path().display()),
+        );
+        return Ok(());
+    }
+    emit_event(
+        EventKind::Upload,
+        &format!(
+            "Uploading {} pending product(s) from {}",
+            pending.len(),
+            queue.path().display()
+        ),
+    );
+
+    let mut failures = 0_usize;
+    for (relative, sha256) in &pending {
+        match upload_one(&args, relative, sha256) {
+            Ok(()) => {
+                queue.update(
+                    relative,
+                    sha256,
+                    json!({ "status": STATUS_DONE, "uploaded_at": Utc::now().to_rfc3339() }),
+                )?;
+                emit_event(EventKind::Upload, &format!("Uploaded {relative}"));
+            }
+            Err(err) => {
+                failures += 1;
+                let status = if err.is::<LocalChecksumMismatch>() {
+                    STATUS_CORRUPT
+                } else {
+                    STATUS_PENDING
+                };
+                queue.update(
+                    relative,
+                    sha256,
+                    json!({ "status": status, "last_error": format!("{err:#}") }),
+                )?;
+                emit_event(
+                    EventKind::Upload,
+                    &format!("Upload failed for {relative}: {err:#}"),
+                );
+            }
+        }
+    }
+
+    if failures > 0 {
+        bail!(
+            "{} of {} upload(s) failed; pending entries will be retried on the next run",
+            failures,
+            pending.len()
+        );
+    }
+    emit_event(
+        EventKind::Upload,
+        &format!("Upload complete; {} product(s) transferred", pending.len()),
+    );
+    Ok(())
+}
+
+// Local file no longer matches the checksum recorded at archive time; retrying cannot help.
+#[derive(Debug)]
+struct LocalChecksumMismatch;
+
+impl std::fmt::Display for LocalChecksumMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("local file checksum does not match upload queue")
+    }
+}
+
+impl std::error::Error for LocalChecksumMismatch {}
+
+fn upload_one(args: &UploadArgs, relative: &str, sha256: &str) -> Result<()> {
+    let local = args.archive_dir.join(relative);
+    if sha256_file(&local)? != sha256 {
+        return Err(LocalChecksumMismatch.into());
+    }
+
+    match args.upload_method {
+        UploadMethod::Rsync => upload_rsync(args, relative),
+        UploadMethod::Http => upload_http(args, &local, relative, sha256),
+    }
+}
+
+// rsync upload, then a `--checksum --dry-run` pass that must report no differences.
+fn upload_rsync(args: &UploadArgs, relative: &str) -> Result<()> {
+    // The `/./` component tells rsync --relative to recreate only <year>/<doy>/<file> remotely.
+    let source = args.archive_dir.join(".").join(relative);
+
+    let mut upload = Command::new(&args.rsync_path);
+    upload
+        .arg("-a")
+        .arg("--relative")
+        .arg(&source)
+        .arg(&args.upload_target);
+    run_capture(&mut upload, "rsync upload")?;
+
+    let mut verify = Command::new(&args.rsync_path);
+    verify
+        .arg("-a")
+        .arg("--relative")
+        .arg("--checksum")
+        .arg("--dry-run")
+        .arg("--itemize-changes")
+        .arg(&source)
+        .arg(&args.upload_target);
+    let output = run_capture(&mut verify, "rsync checksum verification")?;
+    let changes = String::from_utf8_lossy(&output.stdout);
+    if !changes.trim().is_empty() {
+        bail!("remote copy differs after upload: {}", changes.trim());
+    }
+    Ok(())
+}
+
+// HTTP PUT via curl, then download the object back and compare its sha256.
+fn upload_http(args: &UploadArgs, local: &Path, relative: &str, sha256: &str) -> Result<()> {
+    let url = format!("{}/{}", args.upload_target.trim_end_matches('/'), relative);
+
+    let mut upload = Command::new(&args.curl_path);
+    upload.arg("-fsS").arg("-T").arg(local).arg(&url);
+    run_capture(&mut upload, "HTTP upload")?;
+
+    let mut verify = Command::new(&args.curl_path);
+    verify.arg("-fsS").arg(&url);
+    let output = run_capture(&mut verify, "HTTP checksum verification")?;
+    let remote_sha256 =
+        sha256_reader(output.stdout.as_slice()).context("hashing downloaded copy failed")?;
+    if remote_sha256 != sha256 {
+        bail!("remote checksum mismatch for {url}: expected {sha256}, got {remote_sha256}");
+    }
+    Ok(())
+}
+
+fn run_capture(cmd: &mut Command, label: &str) -> Result<Output> {
+    let debug = format!("{cmd:?}");
+    let output = cmd
+        .output()
+        .with_context(|| format!("spawning command failed for {label}: {debug}"))?;
+    if output.status.success() {
+        return Ok(output);
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    bail!(
+        "{label} failed with status {}.\nstdout:\n{}\nstderr:\n{}",
+        output.status,
+        stdout.trim(),
+        stderr.trim()
+    );
+}
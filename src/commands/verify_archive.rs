@@ -0,0 +1,402 @@
+use crate::args::VerifyArchiveArgs;
+use crate::commands::convert::{list_numeric_subdirs, station_archive_root};
+use crate::shared::checksum::sha256_file;
+use crate::shared::day_manifest::DAY_MANIFEST_FILE_NAME;
+use anyhow::{Context, Result, bail};
+use flate2::read::MultiGzDecoder;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+const SIDECAR_SUFFIX: &str = ".sha256";
+
+// One size/digest claim about an archived file, from a day manifest or a `.sha256` sidecar.
+struct Expectation {
+    path: PathBuf,
+    size: Option<u64>,
+    sha256: Option<String>,
+    source: PathBuf,
+}
+
+// Running totals across the whole tree; every problem is printed as it is found.
+#[derive(Default)]
+struct Report {
+    days: u32,
+    files: u32,
+    problems: Vec<(&'static str, PathBuf)>,
+}
+
+impl Report {
+    fn problem(&mut self, kind: &'static str, path: &Path, detail: &str) {
+        self.problems.push((kind, path.to_path_buf()));
+        println!("{kind}: {}: {detail}", path.display());
+    }
+}
+
+// Public verify-archive command entrypoint.
+// Walks `archive/<year>/<doy>/`, checks every file a `manifest.json` or `.sha256` sidecar
+// describes (present, expected size, matching SHA-256), and stream-decompresses every `.gz`
+// and `.zst` product so truncated files are caught. Exits nonzero on any problem.
+pub fn run_verify_archive(args: VerifyArchiveArgs) -> Result<()> {
+    let root = station_archive_root(
+        &args.archive_dir,
+        &args.station,
+        args.archive_station_prefix,
+    );
+    if !root.is_dir() {
+        bail!("archive directory does not exist: {}", root.display());
+    }
+
+    let mut report = Report::default();
+    for (_, year_path) in list_numeric_subdirs(&root, 4)? {
+        for (_, day_path) in list_numeric_subdirs(&year_path, 3)? {
+            verify_day(&day_path, &mut report)?;
+        }
+    }
+
+    println!(
+        "verified {} day(s), {} file(s) under {}: {} problem(s)",
+        report.days,
+        report.files,
+        root.display(),
+        report.problems.len()
+    );
+    if !report.problems.is_empty() {
+        bail!(
+            "archive verification found {} problem(s) under {}",
+            report.problems.len(),
+            root.display()
+        );
+    }
+    Ok(())
+}
+
+fn verify_day(day_dir: &Path, report: &mut Report) -> Result<()> {
+    let mut files = Vec::new();
+    list_files(day_dir, &mut files)?;
+
+    let mut expectations = Vec::new();
+    let manifest = day_dir.join(DAY_MANIFEST_FILE_NAME);
+    if manifest.is_file() {
+        match manifest_expectations(day_dir, &manifest) {
+            Some(found) => expectations.extend(found),
+            None => report.problem("CORRUPT", &manifest, "unreadable day manifest"),
+        }
+    }
+    for sidecar in files
+        .iter()
+        .filter(|path| path.to_string_lossy().ends_with(SIDECAR_SUFFIX))
+    {
+        match sidecar_expectation(sidecar) {
+            Some(expectation) => expectations.push(expectation),
+            None => report.problem("CORRUPT", sidecar, "unreadable checksum sidecar"),
+        }
+    }
+    let compressed: Vec<&PathBuf> = files
+        .iter()
+        .filter(|path| compressed_kind(path).is_some())
+        .collect();
+    if expectations.is_empty() && compressed.is_empty() {
+        return Ok(());
+    }
+    report.days += 1;
+
+    // A product listed by both the manifest and its sidecar is hashed once.
+    let mut digests: BTreeMap<PathBuf, String> = BTreeMap::new();
+    for expectation in &expectations {
+        report.files += 1;
+        let metadata = match fs::metadata(&expectation.path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                report.problem(
+                    "MISSING",
+                    &expectation.path,
+                    &format!("listed in {}", expectation.source.display()),
+                );
+                continue;
+            }
+            Err(err) => {
+                report.problem("UNREADABLE", &expectation.path, &err.to_string());
+                continue;
+            }
+        };
+        if let Some(size) = expectation.size
+            && metadata.len() != size
+        {
+            report.problem(
+                "SIZE",
+                &expectation.path,
+                &format!(
+                    "{} bytes, {} expects {size}",
+                    metadata.len(),
+                    expectation.source.display()
+                ),
+            );
+        }
+        let Some(expected) = &expectation.sha256 else {
+            continue;
+        };
+        let actual = match digests.get(&expectation.path) {
+            Some(digest) => digest.clone(),
+            None => match sha256_file(&expectation.path) {
+                Ok(digest) => {
+                    digests.insert(expectation.path.clone(), digest.clone());
+                    digest
+                }
+                Err(err) => {
+                    report.problem("UNREADABLE", &expectation.path, &format!("{err:#}"));
+                    continue;
+                }
+            },
+        };
+        if !actual.eq_ignore_ascii_case(expected) {
+            report.problem(
+                "CHECKSUM",
+                &expectation.path,
+                &format!(
+                    "sha256 {actual}, {} expects {expected}",
+                    expectation.source.display()
+                ),
+            );
+        }
+    }
+
+    for path in compressed {
+        if let Err(err) = check_decompresses(path) {
+            report.problem("CORRUPT", path, &format!("decompression failed: {err}"));
+        }
+    }
+    Ok(())
+}
+
+// Every file below `dir` (hidden temp files such as `.manifest.json.tmp` excluded).
+fn list_files(dir: &Path, out: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in
+        fs::read_dir(dir).with_context(|| format!("reading directory failed: {}", dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let file_type = entry
+            .file_type()
+            .with_context(|| format!("reading metadata for {}", path.display()))?;
+        if file_type.is_dir() {
+            list_files(&path, out)?;
+        } else if file_type.is_file() {
+            out.push(path);
+        }
+    }
+    out.sort();
+    Ok(())
+}
+
+// Files of every hour in a `--day-manifest` file; None when it is not the expected JSON.
+fn manifest_expectations(day_dir: &Path, manifest: &Path) -> Option<Vec<Expectation>> {
+    let text = fs::read_to_string(manifest).ok()?;
+    let value: Value = serde_json::from_str(&text).ok()?;
+    let mut out = Vec::new();
+    for hour in value.get("hours")?.as_object()?.values() {
+        for file in hour.get("files")?.as_array()? {
+            out.push(Expectation {
+                path: day_dir.join(file.get("name")?.as_str()?),
+                size: file.get("size").and_then(Value::as_u64),
+                sha256: file
+                    .get("sha256")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+                source: manifest.to_path_buf(),
+            });
+        }
+    }
+    Some(out)
+}
+
+// `<hex>  <name>` from a `--write-checksums` sidecar, resolved next to the sidecar.
+fn sidecar_expectation(sidecar: &Path) -> Option<Expectation> {
+    let text = fs::read_to_string(sidecar).ok()?;
+    let (digest, name) = text.lines().next()?.split_once(char::is_whitespace)?;
+    let name = name.trim_start().trim_start_matches('*');
+    if digest.len() != 64 || name.is_empty() {
+        return None;
+    }
+    Some(Expectation {
+        path: sidecar.with_file_name(name),
+        size: None,
+        sha256: Some(digest.to_string()),
+        source: sidecar.to_path_buf(),
+    })
+}
+
+#[derive(Clone, Copy)]
+enum Compressed {
+    Gzip,
+    Zstd,
+}
+
+fn compressed_kind(path: &Path) -> Option<Compressed> {
+    match path.extension()?.to_str()? {
+        "gz" => Some(Compressed::Gzip),
+        "zst" => Some(Compressed::Zstd),
+        _ => None,
+    }
+}
+
+// Stream-decompress the whole file into a sink. Decoding runs from the first block to the
+// trailer, so a damaged header fails at once and a truncated file fails at its last block
+// (gzip also checks the CRC32 and length in the trailer).
+fn check_decompresses(path: &Path) -> io::Result<()> {
+    let file = BufReader::new(File::open(path)?);
+    let mut decoder: Box<dyn Read> = match compressed_kind(path) {
+        Some(Compressed::Zstd) => Box::new(zstd::stream::read::Decoder::with_buffer(file)?),
+        _ => Box::new(MultiGzDecoder::new(file)),
+    };
+    io::copy(&mut decoder, &mut io::sink())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::checksum::write_sha256_sidecar;
+    use crate::shared::day_manifest::record_archived_hour;
+    use chrono::{TimeZone, Utc};
+    use flate2::Compression;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    const OBS: &[u8] =
+        b"     3.04           OBSERVATION DATA    M                   RINEX VERSION / TYPE\n";
+
+    fn scratch_day(name: &str) -> (PathBuf, PathBuf) {
+        let root = std::env::temp_dir().join(format!(
+            "gnss2tec-verify-archive-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&root);
+        let day = root.join("2026").join("060");
+        fs::create_dir_all(&day).unwrap();
+        (root, day)
+    }
+
+    fn gzip(bytes: &[u8]) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(bytes).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    // One archived hour the way `convert` leaves it: gzip and zstd products, their
+    // `.sha256` sidecars, and the day manifest listing them.
+    fn archive_hour(day: &Path) -> (PathBuf, PathBuf) {
+        let gz = day.join("NJIT0600.26o.gz");
+        fs::write(&gz, gzip(&OBS.repeat(200))).unwrap();
+        let zst = day.join("NJIT0600.26n.zst");
+        fs::write(
+            &zst,
+            zstd::stream::encode_all(&OBS.repeat(200)[..], 0).unwrap(),
+        )
+        .unwrap();
+        let mut archived = vec![gz.clone(), zst.clone()];
+        archived.push(write_sha256_sidecar(&gz).unwrap());
+        archived.push(write_sha256_sidecar(&zst).unwrap());
+        let hour = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap();
+        record_archived_hour(day, hour, &archived, true).unwrap();
+        (gz, zst)
+    }
+
+    fn verify(day: &Path) -> Report {
+        let mut report = Report::default();
+        verify_day(day, &mut report).unwrap();
+        report
+    }
+
+    fn args(root: &Path) -> VerifyArchiveArgs {
+        VerifyArchiveArgs {
+            config: None,
+            archive_dir: root.to_path_buf(),
+            archive_station_prefix: false,
+            station: "NJIT".to_string(),
+        }
+    }
+
+    #[test]
+    fn clean_archive_passes() {
+        let (root, day) = scratch_day("clean");
+        archive_hour(&day);
+        let report = verify(&day);
+        assert_eq!(report.days, 1);
+        // Each product is claimed by the manifest and by its sidecar.
+        assert_eq!(report.files, 4);
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+        run_verify_archive(args(&root)).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn missing_resized_and_altered_products_fail() {
+        let (root, day) = scratch_day("damaged");
+        let (gz, zst) = archive_hour(&day);
+        fs::remove_file(&gz).unwrap();
+        // Same size, different bytes: only the digest can tell.
+        let mut bytes = fs::read(&zst).unwrap();
+        let middle = bytes.len() / 2;
+        bytes[middle] ^= 0xFF;
+        fs::write(&zst, &bytes).unwrap();
+
+        let report = verify(&day);
+        let kinds: Vec<(&str, &Path)> = report
+            .problems
+            .iter()
+            .map(|(kind, path)| (*kind, path.as_path()))
+            .collect();
+        assert!(kinds.contains(&("MISSING", gz.as_path())));
+        assert!(kinds.contains(&("CHECKSUM", zst.as_path())));
+        assert!(!kinds.iter().any(|(kind, _)| *kind == "SIZE"));
+
+        fs::write(&zst, b"short").unwrap();
+        let report = verify(&day);
+        assert!(report.problems.contains(&("SIZE", zst.clone())));
+        assert!(run_verify_archive(args(&root)).is_err());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn truncated_compressed_files_fail_to_decompress() {
+        let (root, day) = scratch_day("truncated");
+        let gz = day.join("NJIT0600.26o.gz");
+        let full = gzip(&OBS.repeat(200));
+        fs::write(&gz, &full[..full.len() / 2]).unwrap();
+        let zst = day.join("NJIT0600.26n.zst");
+        let full = zstd::stream::encode_all(&OBS.repeat(200)[..], 0).unwrap();
+        fs::write(&zst, &full[..full.len() / 2]).unwrap();
+
+        // No manifest or sidecar: the decompression pass alone catches them.
+        let report = verify(&day);
+        assert_eq!(report.days, 1);
+        assert_eq!(
+            report.problems,
+            [("CORRUPT", zst.clone()), ("CORRUPT", gz.clone())]
+        );
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn corrupt_manifest_and_sidecar_are_reported() {
+        let (root, day) = scratch_day("corrupt-metadata");
+        archive_hour(&day);
+        let manifest = day.join(DAY_MANIFEST_FILE_NAME);
+        fs::write(&manifest, "{\"hours\": [").unwrap();
+        let sidecar = day.join("NJIT0600.26o.gz.sha256");
+        fs::write(&sidecar, "not-a-digest  NJIT0600.26o.gz\n").unwrap();
+
+        let report = verify(&day);
+        assert!(report.problems.contains(&("CORRUPT", manifest)));
+        assert!(report.problems.contains(&("CORRUPT", sidecar)));
+        // The intact zstd sidecar is still checked.
+        assert_eq!(report.files, 1);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
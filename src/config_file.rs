@@ -0,0 +1,503 @@
+use crate::args::Cli;
+use anyhow::{Context, Result, bail};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgAction, ArgMatches, Command, CommandFactory, FromArgMatches};
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+// Option ids that never come from (or go into) a config file.
+const SKIPPED_IDS: [&str; 4] = ["config", "env_file", "help", "version"];
+
+// Parse the command line, layering `--config <file.toml>` values underneath it.
+// Precedence: explicit flags, then GNSS2TEC_* env vars, then the file, then built-in defaults.
+// File keys are option field names (`serial_port`, `archive_dir`, ...); a `[log]`, `[convert]`,
+// `[run]`, `[doctor]`, or `[upload]` table overrides top-level keys for that subcommand.
+pub fn parse_cli() -> Result<(Cli, ArgMatches)> {
+    parse_cli_from(std::env::args_os().collect())
+}
+
+fn parse_cli_from(argv: Vec<OsString>) -> Result<(Cli, ArgMatches)> {
+    // Env-file variables must be in place before clap reads any `env = ...` binding.
+    crate::env_file::apply_env_file(&argv)?;
+
+    // Probe without failing on missing required options, which the file may still provide.
+    let Ok(probe) = Cli::command()
+        .ignore_errors(true)
+        .try_get_matches_from(&argv)
+    else {
+        return finish_parse(argv);
+    };
+
+    let command = Cli::command();
+    let (leaf_name, leaf_command, leaf_matches) = leaf_subcommand(&command, &probe);
+    let Some(config_path) = leaf_matches
+        .try_get_one::<std::path::PathBuf>("config")
+        .ok()
+        .flatten()
+        .cloned()
+    else {
+        return finish_parse(argv);
+    };
+
+    let values = load_config_values(&command, &config_path, &leaf_name)?;
+    // Global options (`quiet`, `verbose`) are accepted after the subcommand as well.
+    let globals = command
+        .get_arguments()
+        .filter(|arg| arg.is_global_set() && !SKIPPED_IDS.contains(&arg.get_id().as_str()));
+    let overrides = config_overrides(
+        leaf_command.get_arguments().chain(globals),
+        &[leaf_matches, &probe],
+        &values,
+    )
+    .with_context(|| format!("applying config file failed: {}", config_path.display()))?;
+
+    // File values go right after the leaf subcommand token, so a trailing `--` or positional
+    // arguments on the command line keep their meaning.
+    let mut full_argv = argv;
+    let tail = full_argv.split_off(leaf_insert_index(&command, &probe, &full_argv));
+    full_argv.extend(overrides);
+    full_argv.extend(tail);
+    finish_parse(full_argv)
+}
+
+fn finish_parse(argv: Vec<OsString>) -> Result<(Cli, ArgMatches)> {
+    let matches = Cli::command().get_matches_from(argv);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+    Ok((cli, matches))
+}
+
+// Innermost subcommand (e.g. `run`, or `run` under `print-config run`) with its matches.
+fn leaf_subcommand<'a>(
+    command: &'a Command,
+    matches: &'a ArgMatches,
+) -> (String, &'a Command, &'a ArgMatches) {
+    let mut name = command.get_name().to_string();
+    let mut command = command;
+    let mut matches = matches;
+    while let Some((sub_name, sub_matches)) = matches.subcommand() {
+        let Some(sub_command) = command.find_subcommand(sub_name) else {
+            break;
+        };
+        name = sub_name.to_string();
+        command = sub_command;
+        matches = sub_matches;
+    }
+    (name, command, matches)
+}
+
+// Index just past the leaf subcommand token in `argv`. Options before each subcommand token
+// are skipped along with their values, so `--env-file run run` still finds the real `run`.
+fn leaf_insert_index(root: &Command, probe: &ArgMatches, argv: &[OsString]) -> usize {
+    let mut command = root;
+    let mut matches = probe;
+    let mut index = 1;
+    while let Some((sub_name, sub_matches)) = matches.subcommand() {
+        let Some(sub_command) = command.find_subcommand(sub_name) else {
+            break;
+        };
+        let mut found = None;
+        let mut at = index;
+        while at < argv.len() {
+            let token = argv[at].to_string_lossy();
+            if token == "--" {
+                break;
+            }
+            if token == sub_name || sub_command.get_all_aliases().any(|alias| token == alias) {
+                found = Some(at);
+                break;
+            }
+            if option_takes_separate_value(command, &token) {
+                at += 1;
+            }
+            at += 1;
+        }
+        let Some(position) = found else {
+            return argv.len();
+        };
+        index = position + 1;
+        command = sub_command;
+        matches = sub_matches;
+    }
+    index
+}
+
+// True for `--long` / `-s` options of `command` whose value is the next token.
+fn option_takes_separate_value(command: &Command, token: &str) -> bool {
+    let arg = if let Some(long) = token.strip_prefix("--") {
+        if long.contains('=') {
+            return false;
+        }
+        command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(long))
+    } else if let Some(shorts) = token.strip_prefix('-') {
+        // Only a value-taking short at the end of a cluster (`-qx <value>`) reads the next token.
+        let mut chars = shorts.chars();
+        let last = chars.next_back();
+        if chars.any(|short| {
+            command
+                .get_arguments()
+                .any(|arg| arg.get_short() == Some(short) && arg.get_action().takes_values())
+        }) {
+            return false;
+        }
+        last.and_then(|short| {
+            command
+                .get_arguments()
+                .find(|arg| arg.get_short() == Some(short))
+        })
+    } else {
+        None
+    };
+    arg.is_some_and(|arg| arg.get_action().takes_values())
+}
+
+// Read the file and merge the top-level keys with the table for `subcommand`.
+fn load_config_values(root: &Command, path: &Path, subcommand: &str) -> Result<Table> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("reading config file failed: {}", path.display()))?;
+    let table: Table = text
+        .parse()
+        .with_context(|| format!("parsing config file failed: {}", path.display()))?;
+
+    let sections: Vec<&Command> = root
+        .get_subcommands()
+        .filter(|sub| sub.get_subcommands().next().is_none())
+        .collect();
+    // Global options (`quiet`, `verbose`) are valid at the top level and in every section.
+    let known_ids = |command: &Command| -> BTreeSet<String> {
+        command
+            .get_arguments()
+            .chain(root.get_arguments().filter(|arg| arg.is_global_set()))
+            .map(|arg| arg.get_id().to_string())
+            .filter(|id| !SKIPPED_IDS.contains(&id.as_str()))
+            .collect()
+    };
+    let all_ids: BTreeSet<String> = sections.iter().flat_map(|sub| known_ids(sub)).collect();
+
+    let mut merged = Table::new();
+    let mut section_values = None;
+    for (key, value) in table {
+        if let Value::Table(section) = value {
+            let Some(section_command) = sections.iter().find(|sub| sub.get_name() == key) else {
+                bail!("unknown section [{key}] in {}", path.display());
+            };
+            let section_ids = known_ids(section_command);
+            let mut normalized = Table::new();
+            for (section_key, section_value) in section {
+                let id = normalize_key(&section_key);
+                if !section_ids.contains(&id) {
+                    bail!("unknown key {section_key} in [{key}] of {}", path.display());
+                }
+                normalized.insert(id, section_value);
+            }
+            if key == subcommand {
+                section_values = Some(normalized);
+            }
+            continue;
+        }
+
+        let id = normalize_key(&key);
+        if !all_ids.contains(&id) {
+            bail!("unknown key {key} in {}", path.display());
+        }
+        merged.insert(id, value);
+    }
+    merged.extend(section_values.unwrap_or_default());
+    Ok(merged)
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim().replace('-', "_")
+}
+
+// Synthesized `--long=value` flags for options not already set on the command line or via env
+// (in any of `matches`, since global options may be given at either level).
+fn config_overrides<'a>(
+    arguments: impl Iterator<Item = &'a Arg>,
+    matches: &[&ArgMatches],
+    values: &Table,
+) -> Result<Vec<OsString>> {
+    let mut overrides = Vec::new();
+    for arg in arguments {
+        let id = arg.get_id().as_str();
+        let Some(value) = values.get(id) else {
+            continue;
+        };
+        // `value_source` panics on ids unknown to that level, so check membership first.
+        if matches.iter().any(|matches| {
+            matches.try_contains_id(id).is_ok()
+                && matches!(
+                    matches.value_source(id),
+                    Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+                )
+        }) {
+            continue;
+        }
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+
+        match arg.get_action() {
+            ArgAction::SetTrue | ArgAction::SetFalse => {
+                let Value::Boolean(enabled) = value else {
+                    bail!("{id} expects true or false");
+                };
+                // `SetFalse` options (e.g. `--no-convert-on-start` for `convert_on_start`) take
+                // the flag to turn the field off.
+                let wants_flag = *enabled == matches!(arg.get_action(), ArgAction::SetTrue);
+                if wants_flag {
+                    overrides.push(OsString::from(format!("--{long}")));
+                }
+            }
+            ArgAction::Count => {
+                let Value::Integer(count) = value else {
+                    bail!("{id} expects a count");
+                };
+                for _ in 0..*count {
+                    overrides.push(OsString::from(format!("--{long}")));
+                }
+            }
+            ArgAction::Append => {
+                let items = match value {
+                    Value::Array(items) => items.iter().collect(),
+                    other => vec![other],
+                };
+                for item in items {
+                    overrides.push(OsString::from(format!("--{long}={}", scalar(id, item)?)));
+                }
+            }
+            _ => overrides.push(OsString::from(format!("--{long}={}", scalar(id, value)?))),
+        }
+    }
+    Ok(overrides)
+}
+
+fn scalar(id: &str, value: &Value) -> Result<String> {
+    Ok(match value {
+        Value::String(text) => text.clone(),
+        Value::Integer(number) => number.to_string(),
+        Value::Float(number) => number.to_string(),
+        Value::Boolean(flag) => flag.to_string(),
+        Value::Datetime(datetime) => datetime.to_string(),
+        Value::Array(_) | Value::Table(_) => bail!("{id} expects a single value"),
+    })
+}
+
+// Dump the effective values of the `print-config` target as a TOML config file.
+pub fn print_config(matches: &ArgMatches) -> Result<()> {
+    let (name, table) = effective_config(matches);
+    let rendered = toml::to_string(&table).context("rendering effective config failed")?;
+    println!("# Effective configuration for `gnss2tec-logger {name}`");
+    print!("{rendered}");
+    Ok(())
+}
+
+// Leaf subcommand name and its effective option values, keyed like a config file.
+fn effective_config(matches: &ArgMatches) -> (String, Table) {
+    let command = Cli::command();
+    let (name, leaf_command, leaf_matches) = leaf_subcommand(&command, matches);
+
+    let mut table = Table::new();
+    for arg in leaf_command.get_arguments() {
+        let id = arg.get_id().as_str();
+        if SKIPPED_IDS.contains(&id) {
+            continue;
+        }
+        if matches!(arg.get_action(), ArgAction::SetTrue | ArgAction::SetFalse) {
+            table.insert(id.to_string(), Value::Boolean(leaf_matches.get_flag(id)));
+            continue;
+        }
+        let Some(raw) = leaf_matches.get_raw(id) else {
+            continue;
+        };
+        let mut items: Vec<Value> = raw
+            .map(|item| typed_value(&item.to_string_lossy()))
+            .collect();
+        let value = if items.len() == 1 && !matches!(arg.get_action(), ArgAction::Append) {
+            items.remove(0)
+        } else {
+            Value::Array(items)
+        };
+        table.insert(id.to_string(), value);
+    }
+    (name, table)
+}
+
+// Render numbers as TOML integers; everything else stays a string (both load back identically).
+fn typed_value(raw: &str) -> Value {
+    match raw.parse::<i64>() {
+        Ok(number) => Value::Integer(number),
+        Err(_) => Value::String(raw.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{AppCommand, ConvertArgs};
+    use std::path::PathBuf;
+
+    fn write_config(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "gnss2tec-config-{name}-{}.toml",
+            std::process::id()
+        ));
+        fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn argv(tokens: &[&str]) -> Vec<OsString> {
+        tokens.iter().map(OsString::from).collect()
+    }
+
+    fn convert_args(cli: Cli) -> ConvertArgs {
+        match cli.command {
+            AppCommand::Convert(args) => args,
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn file_values_fill_options_and_flags_win() {
+        let path = write_config(
+            "precedence",
+            "quiet = true\nstation = \"FILE\"\nmax_days_back = 7\n\n[convert]\ncountry = \"DEU\"\n\n[run]\ncountry = \"XXX\"\n",
+        );
+        let (cli, _) = parse_cli_from(argv(&[
+            "gnss2tec-logger",
+            "convert",
+            "--config",
+            path.to_str().unwrap(),
+            "--station",
+            "CLI",
+        ]))
+        .unwrap();
+        assert!(cli.quiet);
+        let args = convert_args(cli);
+        assert_eq!(args.station, "CLI");
+        assert_eq!(args.max_days_back, 7);
+        assert_eq!(args.country, "DEU");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn env_vars_win_over_file_values() {
+        let path = write_config("env", "max_days_back = 7\n");
+        // SAFETY: no other test reads or writes this variable.
+        unsafe { std::env::set_var("GNSS2TEC_MAX_DAYS_BACK", "9") };
+        let (cli, _) = parse_cli_from(argv(&[
+            "gnss2tec-logger",
+            "run",
+            "--config",
+            path.to_str().unwrap(),
+        ]))
+        .unwrap();
+        unsafe { std::env::remove_var("GNSS2TEC_MAX_DAYS_BACK") };
+        let AppCommand::Run(args) = cli.command else {
+            panic!("expected run");
+        };
+        assert_eq!(args.max_days_back, 9);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn file_values_go_before_a_trailing_double_dash() {
+        let path = write_config("trailing", "station = \"FILE\"\n");
+        let (cli, _) = parse_cli_from(argv(&[
+            "gnss2tec-logger",
+            "convert",
+            "--config",
+            path.to_str().unwrap(),
+            "--",
+        ]))
+        .unwrap();
+        assert_eq!(convert_args(cli).station, "FILE");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn leaf_insert_index_skips_global_option_values() {
+        let argv = argv(&[
+            "gnss2tec-logger",
+            "--env-file",
+            "run",
+            "print-config",
+            "run",
+            "--station",
+            "X",
+        ]);
+        let command = Cli::command();
+        let probe = Cli::command()
+            .ignore_errors(true)
+            .try_get_matches_from(&argv)
+            .unwrap();
+        assert_eq!(leaf_insert_index(&command, &probe, &argv), 5);
+    }
+
+    #[test]
+    fn effective_config_round_trips_through_a_file() {
+        let path = write_config(
+            "round-trip",
+            "station = \"FILE\"\nmax_days_back = 5\nkeep_hourly = true\n",
+        );
+        let (_, matches) = parse_cli_from(argv(&[
+            "gnss2tec-logger",
+            "convert",
+            "--config",
+            path.to_str().unwrap(),
+            "--shift-hours",
+            "2",
+        ]))
+        .unwrap();
+        let (name, table) = effective_config(&matches);
+        assert_eq!(name, "convert");
+        let first = convert_args(Cli::from_arg_matches(&matches).unwrap());
+
+        let dumped = write_config("round-trip-dump", &toml::to_string(&table).unwrap());
+        let (cli, _) = parse_cli_from(argv(&[
+            "gnss2tec-logger",
+            "convert",
+            "--config",
+            dumped.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let mut second = convert_args(cli);
+        assert_eq!(second.shift_hours, 2);
+        assert!(second.keep_hourly);
+        second.config = first.config.clone();
+        assert_eq!(format!("{first:?}"), format!("{second:?}"));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&dumped).unwrap();
+    }
+
+    #[test]
+    fn no_device_lock_env_var_turns_the_lock_off() {
+        // SAFETY: no other test reads or writes this variable.
+        unsafe { std::env::set_var("GNSS2TEC_NO_DEVICE_LOCK", "true") };
+        let (cli, _) = parse_cli_from(argv(&["gnss2tec-logger", "run"])).unwrap();
+        unsafe { std::env::remove_var("GNSS2TEC_NO_DEVICE_LOCK") };
+        let AppCommand::Run(args) = cli.command else {
+            panic!("expected run");
+        };
+        assert!(args.no_device_lock);
+        assert!(args.to_log_args().no_device_lock);
+
+        let path = write_config("device-lock", "no_device_lock = true\n");
+        let (cli, _) = parse_cli_from(argv(&[
+            "gnss2tec-logger",
+            "log",
+            "--config",
+            path.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let AppCommand::Log(args) = cli.command else {
+            panic!("expected log");
+        };
+        assert!(args.no_device_lock);
+        fs::remove_file(&path).unwrap();
+    }
+}
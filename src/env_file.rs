@@ -0,0 +1,210 @@
+use anyhow::{Context, Result, bail};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ENV_FILE_FLAG: &str = "--env-file";
+
+// Load `--env-file <path>` into the process environment before clap resolves any `env = ...`
+// binding, so a `.env`-style file works like the systemd EnvironmentFile outside systemd.
+// Variables already set in the environment win over the file.
+pub fn apply_env_file(argv: &[OsString]) -> Result<()> {
+    let Some(path) = env_file_arg(argv) else {
+        return Ok(());
+    };
+    let text = fs::read_to_string(&path)
+        .with_context(|| format!("reading env file failed: {}", path.display()))?;
+    for (key, value) in parse_env_file(&text, &path)? {
+        if std::env::var_os(&key).is_none() {
+            // SAFETY: runs at the top of `main`, before any other thread is started.
+            unsafe { std::env::set_var(&key, value) };
+        }
+    }
+    Ok(())
+}
+
+// The `--env-file` value, as `--env-file <path>` or `--env-file=<path>`, before any `--`.
+fn env_file_arg(argv: &[OsString]) -> Option<PathBuf> {
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        let arg = arg.to_str()?;
+        if arg == "--" {
+            return None;
+        }
+        if arg == ENV_FILE_FLAG {
+            return args.next().map(PathBuf::from);
+        }
+        if let Some(value) = arg
+            .strip_prefix(ENV_FILE_FLAG)
+            .and_then(|rest| rest.strip_prefix('='))
+        {
+            return Some(PathBuf::from(value));
+        }
+    }
+    None
+}
+
+// `KEY=VALUE` lines with `#` comments, an optional `export ` prefix, and single- or
+// double-quoted values (double quotes understand `\"`, `\\`, and `\n`).
+fn parse_env_file(text: &str, path: &Path) -> Result<Vec<(String, String)>> {
+    let mut out = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let parsed = line
+            .split_once('=')
+            .context("expected KEY=VALUE")
+            .and_then(|(key, value)| {
+                let key = key.trim();
+                if !is_valid_key(key) {
+                    bail!("invalid variable name {key:?}");
+                }
+                Ok((key.to_string(), parse_value(value.trim())?))
+            })
+            .with_context(|| format!("{}:{}: invalid env file line", path.display(), index + 1))?;
+        out.push(parsed);
+    }
+    Ok(out)
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_value(raw: &str) -> Result<String> {
+    let (value, rest) = if let Some(quoted) = raw.strip_prefix('\'') {
+        let (value, rest) = quoted
+            .split_once('\'')
+            .context("unterminated single-quoted value")?;
+        (value.to_string(), rest)
+    } else if let Some(quoted) = raw.strip_prefix('"') {
+        let mut value = String::new();
+        let mut chars = quoted.char_indices();
+        let rest = loop {
+            match chars.next() {
+                Some((_, '\\')) => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, escaped)) => value.push(escaped),
+                    None => bail!("unterminated double-quoted value"),
+                },
+                Some((at, '"')) => break &quoted[at + 1..],
+                Some((_, c)) => value.push(c),
+                None => bail!("unterminated double-quoted value"),
+            }
+        };
+        (value, rest)
+    } else {
+        // Unquoted: a `#` after whitespace starts a comment.
+        let end = raw
+            .char_indices()
+            .find(|&(at, c)| c == '#' && raw[..at].ends_with(char::is_whitespace))
+            .map_or(raw.len(), |(at, _)| at);
+        return Ok(raw[..end].trim_end().to_string());
+    };
+    let rest = rest.trim_start();
+    if !rest.is_empty() && !rest.starts_with('#') {
+        bail!("unexpected text after quoted value: {rest:?}");
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{AppCommand, Cli};
+    use clap::Parser;
+
+    #[test]
+    fn file_values_reach_run_args_and_real_env_wins() {
+        let path = std::env::temp_dir().join(format!("gnss2tec-env-{}.env", std::process::id()));
+        fs::write(
+            &path,
+            "# receiver hooks\n\
+             export GNSS2TEC_POST_CLOSE_COMMAND=\"echo \\\"closed\\\" # not a comment\"\n\
+             GNSS2TEC_PRE_OPEN_COMMAND='stty -F /dev/ttyACM0 raw'\n\
+             GNSS2TEC_RECONNECT_DELAY_MS=750 # trailing comment\n",
+        )
+        .unwrap();
+        // SAFETY: no other test reads or writes these variables.
+        unsafe {
+            std::env::remove_var("GNSS2TEC_POST_CLOSE_COMMAND");
+            std::env::remove_var("GNSS2TEC_PRE_OPEN_COMMAND");
+            std::env::set_var("GNSS2TEC_RECONNECT_DELAY_MS", "125");
+        }
+
+        let argv: Vec<OsString> = [
+            "gnss2tec-logger",
+            "--env-file",
+            path.to_str().unwrap(),
+            "run",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+        apply_env_file(&argv).unwrap();
+        let AppCommand::Run(args) = Cli::try_parse_from(&argv).unwrap().command else {
+            panic!("expected the run subcommand");
+        };
+        assert_eq!(
+            args.post_close_command.as_deref(),
+            Some("echo \"closed\" # not a comment")
+        );
+        assert_eq!(
+            args.pre_open_command.as_deref(),
+            Some("stty -F /dev/ttyACM0 raw")
+        );
+        assert_eq!(args.reconnect_delay_ms, 125);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn env_file_flag_is_found_in_both_spellings() {
+        let argv = |tokens: &[&str]| tokens.iter().map(OsString::from).collect::<Vec<_>>();
+        assert_eq!(
+            env_file_arg(&argv(&["gnss2tec-logger", "run", "--env-file", "a.env"])),
+            Some(PathBuf::from("a.env"))
+        );
+        assert_eq!(
+            env_file_arg(&argv(&["gnss2tec-logger", "--env-file=b.env", "run"])),
+            Some(PathBuf::from("b.env"))
+        );
+        assert_eq!(
+            env_file_arg(&argv(&[
+                "gnss2tec-logger",
+                "run",
+                "--",
+                "--env-file",
+                "c.env"
+            ])),
+            None
+        );
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected_with_their_line_number() {
+        let path = Path::new("test.env");
+        for bad in [
+            "NO_EQUALS",
+            "1BAD=x",
+            "OPEN='unterminated",
+            "Q=\"a\" trailing",
+        ] {
+            let err = parse_env_file(&format!("# ok\n{bad}\n"), path).unwrap_err();
+            assert!(format!("{err:#}").contains("test.env:2"), "{bad}: {err:#}");
+        }
+        assert_eq!(
+            parse_env_file("EMPTY=\nURL=http://x/#frag\n", path).unwrap(),
+            vec![
+                ("EMPTY".to_string(), String::new()),
+                ("URL".to_string(), "http://x/#frag".to_string()),
+            ]
+        );
+    }
+}
@@ -1,19 +1,34 @@
 mod args;
 mod commands;
+mod config_file;
+mod env_file;
 mod shared;
 
 use anyhow::Result;
-use clap::Parser;
 
-use args::{AppCommand, Cli};
-use commands::{run_convert, run_log, run_mode};
+use args::AppCommand;
+use commands::{
+    run_check_config, run_convert, run_doctor, run_list_commands, run_log, run_mode, run_reprocess,
+    run_status, run_upload, run_verify_archive,
+};
 
-// Top-level entrypoint: parse CLI args and dispatch to a concrete command module.
+// Top-level entrypoint: parse CLI args (plus any `--config` file) and dispatch to a concrete
+// command module. Typed `PipelineError`s from the commands become anyhow errors only here.
 fn main() -> Result<()> {
-    let cli = Cli::parse();
+    let (cli, matches) = config_file::parse_cli()?;
+    shared::event::set_log_format(cli.command.log_format());
+    shared::event::set_verbosity(cli.quiet, cli.verbose);
     match cli.command {
-        AppCommand::Log(args) => run_log(args),
-        AppCommand::Convert(args) => run_convert(args),
+        AppCommand::Log(args) => Ok(run_log(args)?),
+        AppCommand::Convert(args) => Ok(run_convert(args)?),
         AppCommand::Run(args) => run_mode(args),
+        AppCommand::Doctor(args) => run_doctor(args),
+        AppCommand::Upload(args) => run_upload(args),
+        AppCommand::Status(args) => run_status(args),
+        AppCommand::PrintConfig(_) => config_file::print_config(&matches),
+        AppCommand::ListCommands => run_list_commands(),
+        AppCommand::CheckConfig(args) => run_check_config(args),
+        AppCommand::Reprocess(args) => run_reprocess(args),
+        AppCommand::VerifyArchive(args) => run_verify_archive(args),
     }
 }
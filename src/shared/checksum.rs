@@ -0,0 +1,94 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+// Hex-encoded SHA-256 of a file's contents, streamed so large products are not loaded in memory.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let file = File::open(path)
+        .with_context(|| format!("opening file for checksum failed: {}", path.display()))?;
+    sha256_reader(file).with_context(|| format!("hashing file failed: {}", path.display()))
+}
+
+// Hex-encoded SHA-256 of everything readable from `reader`.
+pub fn sha256_reader(mut reader: impl Read) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = [0_u8; 64 * 1024];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(to_hex(&hasher.finalize()))
+}
+
+// Write `<file>.sha256` next to `path` in coreutils `sha256sum` format ("<hex>  <name>").
+pub fn write_sha256_sidecar(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("missing file name for {}", path.display()))?;
+    let digest = sha256_file(path)?;
+
+    let mut sidecar_name = file_name.to_os_string();
+    sidecar_name.push(".sha256");
+    let sidecar = path.with_file_name(sidecar_name);
+    fs::write(
+        &sidecar,
+        format!("{digest}  {}\n", file_name.to_string_lossy()),
+    )
+    .with_context(|| format!("writing checksum sidecar failed: {}", sidecar.display()))?;
+    Ok(sidecar)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sidecar_matches_known_sha256_vectors() {
+        let dir = std::env::temp_dir().join(format!("gnss2tec-checksum-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        // FIPS 180-2 "abc" and empty-input vectors.
+        for (name, contents, expected) in [
+            (
+                "abc.rnx.gz",
+                &b"abc"[..],
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                "empty.rnx.gz",
+                &b""[..],
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+        ] {
+            let product = dir.join(name);
+            fs::write(&product, contents).unwrap();
+            let sidecar = write_sha256_sidecar(&product).unwrap();
+            assert_eq!(sidecar, dir.join(format!("{name}.sha256")));
+            assert_eq!(
+                fs::read_to_string(&sidecar).unwrap(),
+                format!("{expected}  {name}\n")
+            );
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn streamed_digest_spans_buffer_boundaries() {
+        // One million `a`s (FIPS 180-2) crosses the 64 KiB read buffer many times.
+        let input = vec![b'a'; 1_000_000];
+        assert_eq!(
+            sha256_reader(&input[..]).unwrap(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+}
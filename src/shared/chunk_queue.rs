@@ -0,0 +1,235 @@
+use crate::args::OverflowPolicy;
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::Duration;
+
+// Bounded byte-chunk hand-off between the input reader thread and the file writer.
+// When the writer falls behind and `capacity_bytes` are queued, `--overflow` decides whether
+// the oldest queued chunks, the incoming chunk, or the reader itself gives way.
+pub struct ChunkQueue {
+    state: Mutex<QueueState>,
+    changed: Condvar,
+    capacity_bytes: usize,
+    policy: OverflowPolicy,
+}
+
+struct QueueState {
+    chunks: VecDeque<Vec<u8>>,
+    queued_bytes: usize,
+    closed: bool,
+    dropped: DropCounts,
+}
+
+// Chunks discarded on overflow since the last `take_dropped` call.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct DropCounts {
+    pub chunks: u64,
+    pub bytes: u64,
+}
+
+impl DropCounts {
+    pub fn add(&mut self, other: DropCounts) {
+        self.chunks += other.chunks;
+        self.bytes += other.bytes;
+    }
+
+    fn record(&mut self, chunk: &[u8]) {
+        self.chunks += 1;
+        self.bytes += chunk.len() as u64;
+    }
+}
+
+impl ChunkQueue {
+    pub fn new(capacity_bytes: usize, policy: OverflowPolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                chunks: VecDeque::new(),
+                queued_bytes: 0,
+                closed: false,
+                dropped: DropCounts::default(),
+            }),
+            changed: Condvar::new(),
+            capacity_bytes: capacity_bytes.max(1),
+            policy,
+        }
+    }
+
+    // Queue one chunk. An empty queue always accepts it, so oversized chunks still get through.
+    // Returns false once the queue is closed; the chunk is discarded.
+    pub fn push(&self, chunk: Vec<u8>) -> bool {
+        let mut state = self.lock();
+        loop {
+            if state.closed {
+                return false;
+            }
+            if state.chunks.is_empty() || state.queued_bytes + chunk.len() <= self.capacity_bytes {
+                break;
+            }
+            match self.policy {
+                OverflowPolicy::DropOld => {
+                    while !state.chunks.is_empty()
+                        && state.queued_bytes + chunk.len() > self.capacity_bytes
+                    {
+                        if let Some(old) = state.chunks.pop_front() {
+                            state.queued_bytes -= old.len();
+                            state.dropped.record(&old);
+                        }
+                    }
+                }
+                OverflowPolicy::DropNew => {
+                    state.dropped.record(&chunk);
+                    return true;
+                }
+                OverflowPolicy::Block => {
+                    state = self
+                        .changed
+                        .wait(state)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner());
+                }
+            }
+        }
+        state.queued_bytes += chunk.len();
+        state.chunks.push_back(chunk);
+        self.changed.notify_all();
+        true
+    }
+
+    // Next chunk, waiting up to `timeout`; None on timeout or once closed and drained.
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<Vec<u8>> {
+        let state = self.lock();
+        let (mut state, _) = self
+            .changed
+            .wait_timeout_while(state, timeout, |state| {
+                state.chunks.is_empty() && !state.closed
+            })
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let chunk = state.chunks.pop_front()?;
+        state.queued_bytes -= chunk.len();
+        self.changed.notify_all();
+        Some(chunk)
+    }
+
+    // No more chunks will arrive: wakes a blocked reader and lets the writer drain and stop.
+    pub fn close(&self) {
+        self.lock().closed = true;
+        self.changed.notify_all();
+    }
+
+    // Bytes and chunks waiting for the writer.
+    pub fn depth(&self) -> (usize, usize) {
+        let state = self.lock();
+        (state.queued_bytes, state.chunks.len())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.lock().closed
+    }
+
+    pub fn is_drained(&self) -> bool {
+        let state = self.lock();
+        state.closed && state.chunks.is_empty()
+    }
+
+    pub fn take_dropped(&self) -> DropCounts {
+        std::mem::take(&mut self.lock().dropped)
+    }
+
+    // Closes the queue when dropped, so an early error return on either side releases the other.
+    pub fn close_on_drop(&self) -> CloseOnDrop<'_> {
+        CloseOnDrop(self)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub struct CloseOnDrop<'a>(&'a ChunkQueue);
+
+impl Drop for CloseOnDrop<'_> {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Instant;
+
+    const WRITER_DELAY: Duration = Duration::from_millis(200);
+
+    // Push five 4-byte chunks into a 10-byte queue while a writer that starts late and sleeps
+    // between writes drains it; returns the written chunk ids and the drop counts.
+    fn run_with_slow_writer(policy: OverflowPolicy) -> (Vec<u8>, DropCounts, Duration) {
+        let queue = ChunkQueue::new(10, policy);
+        thread::scope(|scope| {
+            let writer = scope.spawn(|| {
+                thread::sleep(WRITER_DELAY);
+                let mut written = Vec::new();
+                while !queue.is_drained() {
+                    if let Some(chunk) = queue.pop_timeout(Duration::from_millis(50)) {
+                        written.push(chunk[0]);
+                        thread::sleep(Duration::from_millis(20));
+                    }
+                }
+                written
+            });
+            let started = Instant::now();
+            for id in 0..5_u8 {
+                assert!(queue.push(vec![id; 4]));
+            }
+            let push_time = started.elapsed();
+            queue.close();
+            (writer.join().unwrap(), queue.take_dropped(), push_time)
+        })
+    }
+
+    #[test]
+    fn drop_old_keeps_the_newest_chunks() {
+        let (written, dropped, _) = run_with_slow_writer(OverflowPolicy::DropOld);
+        assert_eq!(written, vec![3, 4]);
+        assert_eq!(
+            dropped,
+            DropCounts {
+                chunks: 3,
+                bytes: 12
+            }
+        );
+    }
+
+    #[test]
+    fn drop_new_keeps_the_oldest_chunks() {
+        let (written, dropped, _) = run_with_slow_writer(OverflowPolicy::DropNew);
+        assert_eq!(written, vec![0, 1]);
+        assert_eq!(
+            dropped,
+            DropCounts {
+                chunks: 3,
+                bytes: 12
+            }
+        );
+    }
+
+    #[test]
+    fn block_waits_for_the_writer_and_loses_nothing() {
+        let (written, dropped, push_time) = run_with_slow_writer(OverflowPolicy::Block);
+        assert_eq!(written, vec![0, 1, 2, 3, 4]);
+        assert_eq!(dropped, DropCounts::default());
+        assert!(push_time >= WRITER_DELAY);
+    }
+
+    #[test]
+    fn oversized_chunk_passes_an_empty_queue_and_close_rejects_pushes() {
+        let queue = ChunkQueue::new(4, OverflowPolicy::DropNew);
+        assert!(queue.push(vec![7; 16]));
+        assert_eq!(queue.depth(), (16, 1));
+        queue.close();
+        assert!(!queue.push(vec![8]));
+        assert_eq!(queue.pop_timeout(Duration::ZERO), Some(vec![7; 16]));
+        assert!(queue.is_drained());
+    }
+}
@@ -0,0 +1,194 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::nmea::{NmeaSentenceCollector, parse_nmea_utc};
+use crate::shared::time_drift::{CLASS_NAV, ID_NAV_TIMEUTC, parse_nav_timeutc};
+use crate::shared::ubx::{Frame, FrameScanner};
+use chrono::{DateTime, Utc};
+use std::time::{Duration, Instant};
+
+// How often a held rotation repeats its error while the skew persists.
+const HOLD_REPORT_INTERVAL: Duration = Duration::from_secs(60);
+
+// Host-vs-GNSS clock guard for `--max-clock-skew-secs`.
+// Every valid NAV-TIMEUTC, RMC (status A), or ZDA time is compared with `Utc::now()`. While
+// the latest offset exceeds the limit, hour rotation is held: the current UBX file keeps
+// growing and nothing is queued for conversion or archived under a wrong hour name.
+// `--allow-clock-skew` keeps the measurement and the errors but never holds rotation.
+pub struct ClockSkewGuard {
+    max_skew: Option<Duration>,
+    allow_skew: bool,
+    scanner: FrameScanner,
+    frames: Vec<Frame>,
+    collector: NmeaSentenceCollector,
+    sentences: Vec<String>,
+    skew_secs: Option<f64>,
+    last_report: Option<Instant>,
+}
+
+impl ClockSkewGuard {
+    // A zero limit disables the guard and skips parsing.
+    pub fn new(max_skew_secs: u64, allow_skew: bool) -> Self {
+        Self {
+            max_skew: (max_skew_secs > 0).then(|| Duration::from_secs(max_skew_secs)),
+            allow_skew,
+            scanner: FrameScanner::new(),
+            frames: Vec::new(),
+            collector: NmeaSentenceCollector::new(),
+            sentences: Vec::new(),
+            skew_secs: None,
+            last_report: None,
+        }
+    }
+
+    pub fn ingest(&mut self, bytes: &[u8]) {
+        if self.max_skew.is_none() {
+            return;
+        }
+        let now = Utc::now();
+        let mut latest = None;
+        self.scanner.push_bytes(bytes, &mut self.frames);
+        for frame in self.frames.drain(..) {
+            if (frame.class, frame.id) == (CLASS_NAV, ID_NAV_TIMEUTC)
+                && let Some(utc) = parse_nav_timeutc(&frame.payload)
+                && utc.valid_utc
+            {
+                latest = Some(utc.time);
+            }
+        }
+        self.collector.push_bytes(bytes, &mut self.sentences);
+        for sentence in self.sentences.drain(..) {
+            if let Some(time) = parse_nmea_utc(&sentence) {
+                latest = Some(time);
+            }
+        }
+        if let Some(gnss) = latest {
+            self.observe(now, gnss);
+        }
+    }
+
+    // True while host time disagrees with GNSS time beyond the limit and no override is
+    // given. Called at each due rotation, so the error repeats at most once a minute.
+    pub fn holds_rotation(&mut self) -> bool {
+        if self.allow_skew || !self.is_skewed() {
+            return false;
+        }
+        if self
+            .last_report
+            .is_none_or(|at| at.elapsed() >= HOLD_REPORT_INTERVAL)
+        {
+            self.last_report = Some(Instant::now());
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "[TIME] ERROR holding hour rotation: host clock is {:+.1} s off GNSS time (limit {} s); fix NTP/chrony or pass --allow-clock-skew",
+                    self.skew_secs.unwrap_or_default(),
+                    self.max_skew.unwrap_or_default().as_secs()
+                ),
+            );
+        }
+        true
+    }
+
+    fn is_skewed(&self) -> bool {
+        match (self.max_skew, self.skew_secs) {
+            (Some(limit), Some(skew)) => skew.abs() > limit.as_secs_f64(),
+            _ => false,
+        }
+    }
+
+    fn observe(&mut self, now: DateTime<Utc>, gnss: DateTime<Utc>) {
+        let first = self.skew_secs.is_none();
+        let was_skewed = self.is_skewed();
+        self.skew_secs = Some((now - gnss).num_milliseconds() as f64 / 1000.0);
+        let skewed = self.is_skewed();
+        let skew = self.skew_secs.unwrap_or_default();
+        if skewed && !was_skewed {
+            self.last_report = Some(Instant::now());
+            let consequence = if self.allow_skew {
+                "rotating anyway (--allow-clock-skew)"
+            } else {
+                "hour rotation and archiving are held until it agrees"
+            };
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "[TIME] ERROR host clock is {skew:+.1} s off GNSS time (limit {} s); {consequence}",
+                    self.max_skew.unwrap_or_default().as_secs()
+                ),
+            );
+        } else if was_skewed && !skewed {
+            emit_event(
+                EventKind::TimeDrift,
+                &format!(
+                    "[TIME] host clock back within the skew limit ({skew:+.1} s off GNSS time)"
+                ),
+            );
+        } else if first {
+            emit_event(
+                EventKind::TimeDrift,
+                &format!("[TIME] host clock is {skew:+.1} s off GNSS time at startup"),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::nmea::tests::nmea_sentence;
+    use chrono::TimeDelta;
+
+    // A valid RMC fix from 2001, far from any plausible host clock.
+    fn stale_fix() -> String {
+        let sentence = nmea_sentence("GPRMC,120000.00,A,4807.038,N,01131.000,E,0.0,0.0,010101,,,A");
+        format!("{sentence}\r\n")
+    }
+
+    #[test]
+    fn skew_within_the_limit_does_not_hold_rotation() {
+        let mut guard = ClockSkewGuard::new(5, false);
+        let now = Utc::now();
+        guard.observe(now, now - TimeDelta::seconds(3));
+        assert!(!guard.holds_rotation());
+    }
+
+    #[test]
+    fn skew_beyond_the_limit_holds_rotation_until_synced() {
+        let mut guard = ClockSkewGuard::new(5, false);
+        let now = Utc::now();
+        guard.observe(now, now + TimeDelta::seconds(30));
+        assert!(guard.holds_rotation());
+        // Still held at the next due rotation; only the report is throttled.
+        assert!(guard.holds_rotation());
+
+        guard.observe(now, now - TimeDelta::milliseconds(200));
+        assert!(!guard.holds_rotation());
+    }
+
+    #[test]
+    fn allow_clock_skew_reports_but_never_holds() {
+        let mut guard = ClockSkewGuard::new(5, true);
+        let now = Utc::now();
+        guard.observe(now, now - TimeDelta::hours(1));
+        assert!(guard.is_skewed());
+        assert!(!guard.holds_rotation());
+    }
+
+    #[test]
+    fn ingested_gnss_time_drives_the_decision() {
+        let mut guard = ClockSkewGuard::new(5, false);
+        // A void fix carries no usable time.
+        let void = nmea_sentence("GPRMC,120000.00,V,,,,,,,010101,,,N");
+        guard.ingest(format!("{void}\r\n").as_bytes());
+        assert!(!guard.holds_rotation());
+
+        guard.ingest(stale_fix().as_bytes());
+        assert!(guard.holds_rotation());
+    }
+
+    #[test]
+    fn zero_limit_disables_the_guard() {
+        let mut guard = ClockSkewGuard::new(0, false);
+        guard.ingest(stale_fix().as_bytes());
+        assert!(!guard.holds_rotation());
+    }
+}
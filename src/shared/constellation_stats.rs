@@ -0,0 +1,222 @@
+use crate::shared::ubx::{Frame, FrameScanner};
+use std::collections::{BTreeMap, BTreeSet};
+
+const CLASS_RXM: u8 = 0x02;
+const ID_RXM_SFRBX: u8 = 0x13;
+const ID_RXM_RAWX: u8 = 0x15;
+
+// UBX-RXM-RAWX layout: 16-byte header (numMeas at offset 11), then 32-byte measurement
+// blocks with gnssId at +20 and svId at +21.
+const RAWX_HEADER_LEN: usize = 16;
+const RAWX_MEAS_LEN: usize = 32;
+const RAWX_NUM_MEAS_OFFSET: usize = 11;
+const RAWX_GNSS_ID_OFFSET: usize = 20;
+const RAWX_SV_ID_OFFSET: usize = 21;
+
+// Per-constellation counts from RXM-RAWX/SFRBX frames over one stats window.
+// Lets operators see which systems are actually tracked, e.g. `sats GPS=11 GAL=8`.
+pub struct ConstellationStats {
+    enabled: bool,
+    scanner: FrameScanner,
+    frames: Vec<Frame>,
+    satellites: BTreeMap<u8, BTreeSet<u8>>,
+    epochs: BTreeMap<u8, u64>,
+    subframes: BTreeMap<u8, u64>,
+    frame_count: u64,
+}
+
+impl ConstellationStats {
+    // Disabled stats skip frame parsing entirely (stats interval of zero).
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            scanner: FrameScanner::new(),
+            frames: Vec::new(),
+            satellites: BTreeMap::new(),
+            epochs: BTreeMap::new(),
+            subframes: BTreeMap::new(),
+            frame_count: 0,
+        }
+    }
+
+    pub fn ingest(&mut self, bytes: &[u8]) {
+        if !self.enabled {
+            return;
+        }
+
+        self.scanner.push_bytes(bytes, &mut self.frames);
+        self.frame_count += self.frames.len() as u64;
+        for frame in std::mem::take(&mut self.frames) {
+            match (frame.class, frame.id) {
+                (CLASS_RXM, ID_RXM_RAWX) => self.record_rawx(&frame.payload),
+                (CLASS_RXM, ID_RXM_SFRBX) => {
+                    if let Some(&gnss_id) = frame.payload.first() {
+                        *self.subframes.entry(gnss_id).or_default() += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Each RAWX frame is one measurement epoch; count it once per constellation present.
+    fn record_rawx(&mut self, payload: &[u8]) {
+        let Some(&num_meas) = payload.get(RAWX_NUM_MEAS_OFFSET) else {
+            return;
+        };
+        let mut seen = BTreeSet::new();
+        for idx in 0..usize::from(num_meas) {
+            let base = RAWX_HEADER_LEN + idx * RAWX_MEAS_LEN;
+            let (Some(&gnss_id), Some(&sv_id)) = (
+                payload.get(base + RAWX_GNSS_ID_OFFSET),
+                payload.get(base + RAWX_SV_ID_OFFSET),
+            ) else {
+                break;
+            };
+            self.satellites.entry(gnss_id).or_default().insert(sv_id);
+            seen.insert(gnss_id);
+        }
+        for gnss_id in seen {
+            *self.epochs.entry(gnss_id).or_default() += 1;
+        }
+    }
+
+    // Valid UBX frames and checksum failures in the current window, then reset.
+    pub fn take_frame_counts(&mut self) -> (u64, u64) {
+        (
+            std::mem::take(&mut self.frame_count),
+            self.scanner.take_bad_checksums(),
+        )
+    }
+
+    // Summary for the current window, then reset; None when nothing was counted.
+    pub fn take_summary(&mut self) -> Option<String> {
+        if self.satellites.is_empty() && self.subframes.is_empty() {
+            return None;
+        }
+
+        let sats = format_counts(
+            self.satellites
+                .iter()
+                .map(|(gnss_id, svs)| (*gnss_id, svs.len() as u64)),
+        );
+        let epochs = format_counts(self.epochs.iter().map(|(id, count)| (*id, *count)));
+        let subframes = format_counts(self.subframes.iter().map(|(id, count)| (*id, *count)));
+        self.satellites.clear();
+        self.epochs.clear();
+        self.subframes.clear();
+        Some(format!("sats {sats} | epochs {epochs} | sfrbx {subframes}"))
+    }
+}
+
+fn format_counts(counts: impl Iterator<Item = (u8, u64)>) -> String {
+    let parts: Vec<String> = counts
+        .map(|(gnss_id, count)| format!("{}={count}", gnss_name(gnss_id)))
+        .collect();
+    if parts.is_empty() {
+        "-".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+// u-blox gnssId numbering.
+fn gnss_name(gnss_id: u8) -> String {
+    match gnss_id {
+        0 => "GPS".to_string(),
+        1 => "SBAS".to_string(),
+        2 => "GAL".to_string(),
+        3 => "BDS".to_string(),
+        4 => "IMES".to_string(),
+        5 => "QZSS".to_string(),
+        6 => "GLO".to_string(),
+        7 => "NAVIC".to_string(),
+        other => format!("GNSS{other}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::ubx::encode_packet;
+
+    // RXM-RAWX frame with one measurement block per (gnssId, svId).
+    fn rawx_frame(measurements: &[(u8, u8)]) -> Vec<u8> {
+        let mut payload = vec![0_u8; RAWX_HEADER_LEN + measurements.len() * RAWX_MEAS_LEN];
+        payload[RAWX_NUM_MEAS_OFFSET] = measurements.len() as u8;
+        for (idx, (gnss_id, sv_id)) in measurements.iter().enumerate() {
+            let base = RAWX_HEADER_LEN + idx * RAWX_MEAS_LEN;
+            payload[base + RAWX_GNSS_ID_OFFSET] = *gnss_id;
+            payload[base + RAWX_SV_ID_OFFSET] = *sv_id;
+        }
+        encode_packet(CLASS_RXM, ID_RXM_RAWX, &payload)
+    }
+
+    fn sfrbx_frame(gnss_id: u8) -> Vec<u8> {
+        encode_packet(CLASS_RXM, ID_RXM_SFRBX, &[gnss_id, 1, 0, 0, 0, 0, 0, 0])
+    }
+
+    #[test]
+    fn rawx_counts_satellites_and_epochs_per_constellation() {
+        let mut stats = ConstellationStats::new(true);
+        // Two epochs: GPS in both (one satellite repeated), Galileo and GLONASS in one each.
+        let first = rawx_frame(&[(0, 5), (0, 12), (2, 3), (0, 12)]);
+        let second = rawx_frame(&[(0, 5), (0, 29), (6, 17)]);
+        let stream = [
+            first,
+            sfrbx_frame(0),
+            sfrbx_frame(0),
+            sfrbx_frame(2),
+            second,
+        ]
+        .concat();
+        // Split mid-frame, as serial reads do.
+        stats.ingest(&stream[..50]);
+        stats.ingest(&stream[50..]);
+
+        assert_eq!(
+            stats.take_summary().as_deref(),
+            Some("sats GPS=3 GAL=1 GLO=1 | epochs GPS=2 GAL=1 GLO=1 | sfrbx GPS=2 GAL=1")
+        );
+        assert_eq!(stats.take_frame_counts(), (5, 0));
+        // Both windows reset after being taken.
+        assert_eq!(stats.take_summary(), None);
+        assert_eq!(stats.take_frame_counts(), (0, 0));
+    }
+
+    #[test]
+    fn truncated_rawx_counts_only_complete_measurements() {
+        let mut stats = ConstellationStats::new(true);
+        let mut frame = rawx_frame(&[(3, 8), (5, 1)]);
+        // Claim a third measurement the payload does not carry.
+        frame[6 + RAWX_NUM_MEAS_OFFSET] = 3;
+        let payload_end = frame.len() - 2;
+        let (ck_a, ck_b) = crate::shared::ubx::checksum(&frame[2..payload_end]);
+        frame[payload_end] = ck_a;
+        frame[payload_end + 1] = ck_b;
+        stats.ingest(&frame);
+        assert_eq!(
+            stats.take_summary().as_deref(),
+            Some("sats BDS=1 QZSS=1 | epochs BDS=1 QZSS=1 | sfrbx -")
+        );
+    }
+
+    #[test]
+    fn corrupt_frames_are_counted_not_parsed() {
+        let mut stats = ConstellationStats::new(true);
+        let mut frame = rawx_frame(&[(0, 5)]);
+        let last = frame.len() - 1;
+        frame[last] ^= 0xFF;
+        stats.ingest(&frame);
+        assert_eq!(stats.take_summary(), None);
+        assert_eq!(stats.take_frame_counts(), (0, 1));
+    }
+
+    #[test]
+    fn disabled_stats_ignore_input() {
+        let mut stats = ConstellationStats::new(false);
+        stats.ingest(&rawx_frame(&[(0, 5)]));
+        assert_eq!(stats.take_summary(), None);
+        assert_eq!(stats.take_frame_counts(), (0, 0));
+    }
+}
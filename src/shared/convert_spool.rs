@@ -0,0 +1,150 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::lock::LockGuard;
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::BTreeSet;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const SPOOL_FILE_NAME: &str = "convert-queue";
+const SPOOL_LOCK_NAME: &str = ".convert-queue.lock";
+const HOUR_KEY_FORMAT: &str = "%Y%m%d_%H";
+
+// On-disk mirror of the run-mode conversion channel: one `YYYYMMDD_HH` line per queued hour.
+// Hours are appended before they are sent to the worker and removed once finished, so hours
+// still listed after a crash or restart are enqueued again at startup.
+#[derive(Clone)]
+pub struct ConvertSpool {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl ConvertSpool {
+    pub fn new(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join(SPOOL_FILE_NAME),
+            lock_path: data_dir.join(SPOOL_LOCK_NAME),
+        }
+    }
+
+    pub fn push(&self, hour: DateTime<Utc>) -> Result<()> {
+        let _lock = LockGuard::acquire_wait(&self.lock_path)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("opening conversion spool failed: {}", self.path.display()))?;
+        writeln!(file, "{}", hour.format(HOUR_KEY_FORMAT))
+            .with_context(|| format!("writing conversion spool failed: {}", self.path.display()))
+    }
+
+    // Drop every entry for `hour` (the same hour may have been queued more than once).
+    pub fn remove(&self, hour: DateTime<Utc>) -> Result<()> {
+        let _lock = LockGuard::acquire_wait(&self.lock_path)?;
+        let key = hour.format(HOUR_KEY_FORMAT).to_string();
+        let kept: Vec<String> = self
+            .read_lines()?
+            .into_iter()
+            .filter(|line| *line != key)
+            .collect();
+
+        let tmp_path = self.path.with_extension("tmp");
+        let mut contents = kept.join("\n");
+        if !contents.is_empty() {
+            contents.push('\n');
+        }
+        fs::write(&tmp_path, contents)
+            .with_context(|| format!("writing {} failed", tmp_path.display()))?;
+        fs::rename(&tmp_path, &self.path).with_context(|| {
+            format!(
+                "replacing conversion spool failed: {} -> {}",
+                tmp_path.display(),
+                self.path.display()
+            )
+        })
+    }
+
+    // Distinct spooled hours, oldest first. Unparseable lines are reported and skipped.
+    pub fn load(&self) -> Result<Vec<DateTime<Utc>>> {
+        let _lock = LockGuard::acquire_wait(&self.lock_path)?;
+        let mut hours = BTreeSet::new();
+        for line in self.read_lines()? {
+            match NaiveDateTime::parse_from_str(&format!("{line}0000"), "%Y%m%d_%H%M%S") {
+                Ok(hour) => {
+                    hours.insert(hour.and_utc());
+                }
+                Err(_) => emit_event(
+                    EventKind::Warning,
+                    &format!(
+                        "Ignoring invalid conversion spool entry in {}: {line}",
+                        self.path.display()
+                    ),
+                ),
+            }
+        }
+        Ok(hours.into_iter().collect())
+    }
+
+    fn read_lines(&self) -> Result<Vec<String>> {
+        match fs::read_to_string(&self.path) {
+            Ok(text) => Ok(text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(err) => Err(err).with_context(|| {
+                format!("reading conversion spool failed: {}", self.path.display())
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn hour(day: u32, h: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, day, h, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn pending_hours_survive_a_restart_oldest_first() {
+        let dir = std::env::temp_dir().join(format!("gnss2tec-spool-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let spool = ConvertSpool::new(&dir);
+        for queued in [hour(2, 5), hour(1, 23), hour(2, 4), hour(1, 23), hour(2, 6)] {
+            spool.push(queued).unwrap();
+        }
+        // The worker finishes 02T04 and 01T23 (queued twice), then the process stops.
+        spool.remove(hour(2, 4)).unwrap();
+        spool.remove(hour(1, 23)).unwrap();
+        drop(spool);
+
+        let mut raw = fs::read_to_string(dir.join(SPOOL_FILE_NAME)).unwrap();
+        raw.push_str("not-an-hour\n");
+        fs::write(dir.join(SPOOL_FILE_NAME), raw).unwrap();
+
+        let restarted = ConvertSpool::new(&dir);
+        assert_eq!(restarted.load().unwrap(), vec![hour(2, 5), hour(2, 6)]);
+
+        restarted.remove(hour(2, 5)).unwrap();
+        restarted.remove(hour(2, 6)).unwrap();
+        assert!(restarted.load().unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn missing_spool_loads_empty() {
+        let dir = std::env::temp_dir().join(format!("gnss2tec-spool-empty-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        assert!(ConvertSpool::new(&dir).load().unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,298 @@
+use crate::shared::checksum::sha256_file;
+use crate::shared::event::{EventKind, emit_event};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+pub const DAY_MANIFEST_FILE_NAME: &str = "manifest.json";
+
+// Serializes read-modify-write cycles between `convert --jobs` workers in this process; the
+// convert lock already keeps other processes out of the archive.
+static MANIFEST_UPDATE: Mutex<()> = Mutex::new(());
+
+// Add (or replace) one archived hour in `<day_dir>/manifest.json` for `--day-manifest`.
+// Hours are keyed by their UTC start, so a bucket shifted by --archive-timezone stays
+// unambiguous. `.sha256` sidecars are folded into their product's `sha256` field.
+pub fn record_archived_hour(
+    day_dir: &Path,
+    hour: DateTime<Utc>,
+    archived: &[PathBuf],
+    with_checksums: bool,
+) -> Result<()> {
+    let mut files = Vec::with_capacity(archived.len());
+    for path in archived {
+        if path.extension().is_some_and(|ext| ext == "sha256") {
+            continue;
+        }
+        let size = fs::metadata(path)
+            .with_context(|| format!("reading metadata for {}", path.display()))?
+            .len();
+        let name = path.strip_prefix(day_dir).unwrap_or(path);
+        let mut entry = json!({
+            "name": name.to_string_lossy(),
+            "size": size,
+        });
+        if with_checksums {
+            entry["sha256"] = Value::String(sha256_file(path)?);
+        }
+        files.push(entry);
+    }
+
+    let _guard = MANIFEST_UPDATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = day_dir.join(DAY_MANIFEST_FILE_NAME);
+    let mut hours = load_manifest_hours(&path);
+    hours.insert(
+        hour_key(hour),
+        json!({
+            "archived_at": Utc::now().to_rfc3339(),
+            "files": files,
+        }),
+    );
+    write_manifest(day_dir, &path, hours)
+}
+
+// Drop one hour from `<day_dir>/manifest.json` once its files have left the archive
+// (`--rsync-delete-after-upload`). Nothing is written when the hour is not listed.
+pub fn remove_archived_hour(day_dir: &Path, hour: DateTime<Utc>) -> Result<()> {
+    let _guard = MANIFEST_UPDATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = day_dir.join(DAY_MANIFEST_FILE_NAME);
+    if !path.is_file() {
+        return Ok(());
+    }
+    let mut hours = load_manifest_hours(&path);
+    if hours.remove(&hour_key(hour)).is_none() {
+        return Ok(());
+    }
+    write_manifest(day_dir, &path, hours)
+}
+
+// Drop single files from one hour of `<day_dir>/manifest.json` once they left the archive
+// (hourly OBS merged by `--daily`); the hour goes too when none of its files remain.
+pub fn remove_archived_files(
+    day_dir: &Path,
+    hour: DateTime<Utc>,
+    removed: &[PathBuf],
+) -> Result<()> {
+    let names: Vec<String> = removed
+        .iter()
+        .map(|path| {
+            path.strip_prefix(day_dir)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .collect();
+
+    let _guard = MANIFEST_UPDATE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let path = day_dir.join(DAY_MANIFEST_FILE_NAME);
+    if !path.is_file() {
+        return Ok(());
+    }
+    let mut hours = load_manifest_hours(&path);
+    let key = hour_key(hour);
+    let Some(files) = hours
+        .get_mut(&key)
+        .and_then(|entry| entry.get_mut("files"))
+        .and_then(Value::as_array_mut)
+    else {
+        return Ok(());
+    };
+    let listed = files.len();
+    files.retain(|file| {
+        !file["name"]
+            .as_str()
+            .is_some_and(|name| names.iter().any(|removed| removed == name))
+    });
+    if files.len() == listed {
+        return Ok(());
+    }
+    if files.is_empty() {
+        hours.remove(&key);
+    }
+    write_manifest(day_dir, &path, hours)
+}
+
+fn hour_key(hour: DateTime<Utc>) -> String {
+    hour.format("%Y-%m-%dT%H:00:00Z").to_string()
+}
+
+fn write_manifest(day_dir: &Path, path: &Path, hours: Map<String, Value>) -> Result<()> {
+    let manifest = json!({
+        "updated": Utc::now().to_rfc3339(),
+        "hour_count": hours.len(),
+        "hours": hours,
+    });
+
+    // Replace atomically so a crash mid-write never leaves a truncated manifest.
+    let tmp_path = day_dir.join(format!(".{DAY_MANIFEST_FILE_NAME}.tmp"));
+    let mut contents =
+        serde_json::to_vec_pretty(&manifest).context("encoding day manifest failed")?;
+    contents.push(b'\n');
+    fs::write(&tmp_path, contents)
+        .with_context(|| format!("writing day manifest failed: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("replacing day manifest failed: {}", path.display()))?;
+    Ok(())
+}
+
+// Hours already recorded, or none for a new day. A corrupt manifest is reported and rebuilt
+// from the hours archived from now on; the `.ready` markers still describe the older ones.
+fn load_manifest_hours(path: &Path) -> Map<String, Value> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Map::new(),
+        Err(err) => {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "Reading day manifest failed; starting a new one: {}: {err}",
+                    path.display()
+                ),
+            );
+            return Map::new();
+        }
+    };
+    let hours = serde_json::from_str::<Value>(&text)
+        .ok()
+        .and_then(|value| value.get("hours").and_then(Value::as_object).cloned());
+    hours.unwrap_or_else(|| {
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "Ignoring corrupt day manifest; starting a new one: {}",
+                path.display()
+            ),
+        );
+        Map::new()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::checksum::write_sha256_sidecar;
+    use chrono::TimeZone;
+
+    fn scratch_day(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "gnss2tec-day-manifest-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read_manifest(day: &Path) -> Value {
+        serde_json::from_str(&fs::read_to_string(day.join(DAY_MANIFEST_FILE_NAME)).unwrap())
+            .unwrap()
+    }
+
+    #[test]
+    fn manifest_accumulates_archived_hours() {
+        let day = scratch_day("accumulate");
+        for hour in [0, 1, 5] {
+            let obs = day.join(format!("NJIT_{hour:02}_MO.rnx.gz"));
+            fs::write(&obs, vec![b'o'; 10 + hour]).unwrap();
+            let nav = day.join(format!("NJIT_{hour:02}_MN.rnx.gz"));
+            fs::write(&nav, b"abc").unwrap();
+            let sidecar = write_sha256_sidecar(&nav).unwrap();
+            let dt = Utc.with_ymd_and_hms(2026, 3, 1, hour as u32, 0, 0).unwrap();
+            record_archived_hour(&day, dt, &[obs, nav, sidecar], true).unwrap();
+        }
+
+        let manifest = read_manifest(&day);
+        assert_eq!(manifest["hour_count"], 3);
+        let hours = manifest["hours"].as_object().unwrap();
+        assert_eq!(
+            hours.keys().collect::<Vec<_>>(),
+            [
+                "2026-03-01T00:00:00Z",
+                "2026-03-01T01:00:00Z",
+                "2026-03-01T05:00:00Z"
+            ]
+        );
+        // The sidecar is folded into its product rather than listed.
+        let files = hours["2026-03-01T05:00:00Z"]["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0]["name"], "NJIT_05_MO.rnx.gz");
+        assert_eq!(files[0]["size"], 15);
+        assert_eq!(files[1]["name"], "NJIT_05_MN.rnx.gz");
+        assert_eq!(
+            files[1]["sha256"],
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert!(!day.join(format!(".{DAY_MANIFEST_FILE_NAME}.tmp")).exists());
+        fs::remove_dir_all(&day).unwrap();
+    }
+
+    #[test]
+    fn rearchived_hour_is_replaced_and_removed_hour_dropped() {
+        let day = scratch_day("replace");
+        let obs = day.join("obs.rnx");
+        let dt = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        fs::write(&obs, b"first").unwrap();
+        record_archived_hour(&day, dt, std::slice::from_ref(&obs), false).unwrap();
+        fs::write(&obs, b"second run").unwrap();
+        record_archived_hour(&day, dt, std::slice::from_ref(&obs), false).unwrap();
+
+        let manifest = read_manifest(&day);
+        assert_eq!(manifest["hour_count"], 1);
+        let files = &manifest["hours"]["2026-03-01T12:00:00Z"]["files"];
+        assert_eq!(files[0]["size"], 10);
+        assert!(files[0].get("sha256").is_none());
+
+        remove_archived_hour(&day, dt).unwrap();
+        assert_eq!(read_manifest(&day)["hour_count"], 0);
+        fs::remove_dir_all(&day).unwrap();
+    }
+
+    #[test]
+    fn corrupt_manifest_is_rebuilt() {
+        let day = scratch_day("corrupt");
+        fs::write(day.join(DAY_MANIFEST_FILE_NAME), b"{\"hours\": [").unwrap();
+        let obs = day.join("obs.rnx");
+        fs::write(&obs, b"obs").unwrap();
+        let dt = Utc.with_ymd_and_hms(2026, 3, 1, 3, 0, 0).unwrap();
+        record_archived_hour(&day, dt, &[obs], false).unwrap();
+
+        let manifest = read_manifest(&day);
+        assert_eq!(manifest["hour_count"], 1);
+        assert!(manifest["hours"]["2026-03-01T03:00:00Z"].is_object());
+        fs::remove_dir_all(&day).unwrap();
+    }
+
+    #[test]
+    fn removed_files_leave_the_rest_of_their_hour() {
+        let day = scratch_day("remove-files");
+        let obs = day.join("obs").join("NJIT_12_MO.rnx.gz");
+        fs::create_dir_all(obs.parent().unwrap()).unwrap();
+        fs::write(&obs, b"obs").unwrap();
+        let nav = day.join("NJIT_12_MN.rnx.gz");
+        fs::write(&nav, b"nav").unwrap();
+        let dt = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        record_archived_hour(&day, dt, &[obs.clone(), nav.clone()], false).unwrap();
+
+        remove_archived_files(&day, dt, std::slice::from_ref(&obs)).unwrap();
+        let manifest = read_manifest(&day);
+        let files = manifest["hours"]["2026-03-01T12:00:00Z"]["files"]
+            .as_array()
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0]["name"], "NJIT_12_MN.rnx.gz");
+
+        remove_archived_files(&day, dt, &[nav]).unwrap();
+        assert_eq!(read_manifest(&day)["hour_count"], 0);
+        fs::remove_dir_all(&day).unwrap();
+    }
+}
@@ -0,0 +1,286 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::logfile::SyncData;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const SPACE_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+const WARNING_INTERVAL: Duration = Duration::from_secs(60);
+// Headroom required before resuming after a write failed for lack of space.
+const RESUME_MIN_FREE_BYTES: u64 = 16 * 1024 * 1024;
+
+// ENOSPC / EDQUOT on Linux; some filesystems surface a short write instead.
+const ENOSPC: i32 = 28;
+const EDQUOT: i32 = 122;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SpaceEvent {
+    // Writes stopped: the disk filled up or free space dropped below `--min-free-bytes`.
+    Paused,
+    // Enough space is available again; callers should start a fresh output file.
+    Resumed,
+}
+
+// Disk-full state machine for the logging loops.
+// While paused, incoming chunks are dropped (the caller keeps draining the receiver),
+// a throttled warning is logged, and free space is re-checked until writes can resume.
+pub struct DiskSpaceGuard {
+    data_dir: PathBuf,
+    min_free_bytes: u64,
+    paused: bool,
+    pending: Option<SpaceEvent>,
+    dropped_bytes: u64,
+    last_check: Option<Instant>,
+    last_warning: Option<Instant>,
+}
+
+impl DiskSpaceGuard {
+    pub fn new(data_dir: &Path, min_free_bytes: u64) -> Self {
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            min_free_bytes,
+            paused: false,
+            pending: None,
+            dropped_bytes: 0,
+            last_check: None,
+            last_warning: None,
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // Write one chunk unless paused. Out-of-space errors pause writing instead of failing.
+    pub fn write_chunk<W: Write + ?Sized>(
+        &mut self,
+        writer: &mut W,
+        chunk: &[u8],
+    ) -> io::Result<()> {
+        if self.paused {
+            self.dropped_bytes += chunk.len() as u64;
+            self.maybe_warn();
+            return Ok(());
+        }
+
+        match writer.write_all(chunk) {
+            Ok(()) => Ok(()),
+            Err(err) if is_out_of_space(&err) => {
+                self.dropped_bytes += chunk.len() as u64;
+                self.pause(&format!("write failed: {err}"));
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    // Flush unless paused, treating out-of-space like `write_chunk` does.
+    pub fn flush<W: Write + ?Sized>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.paused {
+            return Ok(());
+        }
+
+        match writer.flush() {
+            Ok(()) => Ok(()),
+            Err(err) if is_out_of_space(&err) => {
+                self.pause(&format!("flush failed: {err}"));
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    // Flush and `sync_data` unless paused; delayed-allocation filesystems may report a full
+    // disk only here.
+    pub fn sync_data<W: SyncData + ?Sized>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.paused {
+            return Ok(());
+        }
+
+        match writer.sync_data() {
+            Ok(()) => Ok(()),
+            Err(err) if is_out_of_space(&err) => {
+                self.pause(&format!("sync failed: {err}"));
+                Ok(())
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    // Periodic free-space check; returns a pause/resume transition at most once.
+    pub fn check(&mut self) -> Option<SpaceEvent> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+        if !self.paused && self.min_free_bytes == 0 {
+            return None;
+        }
+        if self
+            .last_check
+            .is_some_and(|at| at.elapsed() < SPACE_CHECK_INTERVAL)
+        {
+            return None;
+        }
+        self.last_check = Some(Instant::now());
+
+        let available = match fs2::available_space(&self.data_dir) {
+            Ok(available) => available,
+            Err(err) => {
+                emit_event(
+                    EventKind::DiskSpace,
+                    &format!(
+                        "Checking free space on {} failed: {err}",
+                        self.data_dir.display()
+                    ),
+                );
+                return None;
+            }
+        };
+
+        if self.paused {
+            if available < self.min_free_bytes.max(RESUME_MIN_FREE_BYTES) {
+                self.maybe_warn();
+                return None;
+            }
+            emit_event(
+                EventKind::DiskSpace,
+                &format!(
+                    "Free space recovered on {} ({} bytes available); resuming UBX logging after dropping {} bytes",
+                    self.data_dir.display(),
+                    available,
+                    self.dropped_bytes
+                ),
+            );
+            self.paused = false;
+            self.dropped_bytes = 0;
+            self.last_warning = None;
+            return Some(SpaceEvent::Resumed);
+        }
+
+        if available < self.min_free_bytes {
+            self.pause(&format!(
+                "{} bytes available is below --min-free-bytes {}",
+                available, self.min_free_bytes
+            ));
+            return self.pending.take();
+        }
+        None
+    }
+
+    fn pause(&mut self, reason: &str) {
+        emit_event(
+            EventKind::DiskSpace,
+            &format!(
+                "WARNING: pausing UBX logging on {} ({reason}); still draining the receiver",
+                self.data_dir.display()
+            ),
+        );
+        self.paused = true;
+        self.pending = Some(SpaceEvent::Paused);
+        self.last_check = Some(Instant::now());
+        self.last_warning = Some(Instant::now());
+    }
+
+    fn maybe_warn(&mut self) {
+        if self
+            .last_warning
+            .is_some_and(|at| at.elapsed() < WARNING_INTERVAL)
+        {
+            return;
+        }
+        self.last_warning = Some(Instant::now());
+        emit_event(
+            EventKind::DiskSpace,
+            &format!(
+                "WARNING: UBX logging still paused for lack of disk space on {}; {} bytes dropped so far",
+                self.data_dir.display(),
+                self.dropped_bytes
+            ),
+        );
+    }
+}
+
+// True for errors meaning the filesystem (or quota) is full.
+pub fn is_out_of_space(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::StorageFull | io::ErrorKind::WriteZero
+    ) || matches!(err.raw_os_error(), Some(ENOSPC) | Some(EDQUOT))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sink that fails with ENOSPC while `full` is set, like a data partition that filled up.
+    struct FillingDisk {
+        full: bool,
+        written: Vec<u8>,
+    }
+
+    impl Write for FillingDisk {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.full {
+                return Err(io::Error::from_raw_os_error(ENOSPC));
+            }
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn full_disk_pauses_then_resumes_once_space_frees() {
+        let mut disk = FillingDisk {
+            full: true,
+            written: Vec::new(),
+        };
+        let mut guard = DiskSpaceGuard::new(&std::env::temp_dir(), 0);
+
+        guard.write_chunk(&mut disk, b"lost").unwrap();
+        assert!(guard.is_paused());
+        assert_eq!(guard.check(), Some(SpaceEvent::Paused));
+        // Paused: chunks are dropped without touching the writer.
+        disk.full = false;
+        guard.write_chunk(&mut disk, b"dropped").unwrap();
+        assert!(disk.written.is_empty());
+        assert_eq!(guard.dropped_bytes, 11);
+        // Within the check interval nothing changes.
+        assert_eq!(guard.check(), None);
+
+        guard.last_check = None;
+        assert_eq!(guard.check(), Some(SpaceEvent::Resumed));
+        assert!(!guard.is_paused());
+        guard.write_chunk(&mut disk, b"kept").unwrap();
+        assert_eq!(disk.written, b"kept");
+        assert_eq!(guard.check(), None);
+    }
+
+    #[test]
+    fn min_free_bytes_pauses_before_writes_fail() {
+        let mut guard = DiskSpaceGuard::new(&std::env::temp_dir(), u64::MAX);
+        assert_eq!(guard.check(), Some(SpaceEvent::Paused));
+        guard.last_check = None;
+        assert_eq!(guard.check(), None);
+        assert!(guard.is_paused());
+    }
+
+    #[test]
+    fn other_write_errors_still_fail() {
+        struct Broken;
+        impl Write for Broken {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::from(io::ErrorKind::BrokenPipe))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let mut guard = DiskSpaceGuard::new(&std::env::temp_dir(), 0);
+        assert!(guard.write_chunk(&mut Broken, b"data").is_err());
+        assert!(!guard.is_paused());
+    }
+}
@@ -0,0 +1,262 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::ubx::{SYNC_CHAR_1, SYNC_CHAR_2, checksum};
+use std::time::{Duration, Instant};
+
+// A measurement epoch starts with its RXM-RAWX frame and ends with NAV-EOE when enabled.
+const CLASS_RXM: u8 = 0x02;
+const ID_RXM_RAWX: u8 = 0x15;
+const CLASS_NAV: u8 = 0x01;
+const ID_NAV_EOE: u8 = 0x61;
+// Longer length fields are false syncs, as in `FrameScanner`.
+const MAX_FRAME_PAYLOAD_LEN: usize = 8 * 1024;
+
+// Hour rotation for `--rotate-on-epoch`: once the wall-clock hour has changed, logged bytes
+// are held back until the first epoch boundary (the start of an RXM-RAWX frame, or the end
+// of a NAV-EOE frame) so every epoch lands whole in one file. Bytes before the boundary close
+// the old hour and the rest open the new one. Without a boundary within the grace period the
+// rotation happens anyway, exactly as wall-clock rotation would have done it.
+pub struct EpochRotation {
+    grace: Option<Duration>,
+    pending: Option<PendingRotation>,
+}
+
+struct PendingRotation {
+    since: Instant,
+    bytes: Vec<u8>,
+    scanned: usize,
+    boundary: Option<usize>,
+}
+
+impl EpochRotation {
+    // None rotates on the wall clock alone.
+    pub fn new(grace: Option<Duration>) -> Self {
+        Self {
+            grace,
+            pending: None,
+        }
+    }
+
+    // Bytes to write to the open file now; while a rotation waits for an epoch, none.
+    pub fn pass<'a>(&'a mut self, chunk: &'a [u8]) -> &'a [u8] {
+        let Some(pending) = self.pending.as_mut() else {
+            return chunk;
+        };
+        pending.bytes.extend_from_slice(chunk);
+        if pending.boundary.is_none() {
+            pending.find_boundary();
+        }
+        &[]
+    }
+
+    // Called while the wall-clock hour has changed: true once the rotation may happen.
+    pub fn rotation_due(&mut self) -> bool {
+        self.rotation_due_at(Instant::now())
+    }
+
+    fn rotation_due_at(&mut self, now: Instant) -> bool {
+        let Some(grace) = self.grace else {
+            return true;
+        };
+        let Some(pending) = &self.pending else {
+            self.pending = Some(PendingRotation {
+                since: now,
+                bytes: Vec::new(),
+                scanned: 0,
+                boundary: None,
+            });
+            return false;
+        };
+        if pending.boundary.is_some() {
+            return true;
+        }
+        if now.saturating_duration_since(pending.since) >= grace {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "No UBX epoch boundary within {}s of the hour change; rotating on the wall clock",
+                    grace.as_secs()
+                ),
+            );
+            return true;
+        }
+        false
+    }
+
+    // After `rotation_due`: the held bytes for the closing file and for the new one.
+    pub fn take_split(&mut self) -> (Vec<u8>, Vec<u8>) {
+        let Some(mut pending) = self.pending.take() else {
+            return (Vec::new(), Vec::new());
+        };
+        let tail = pending.bytes.split_off(pending.boundary.unwrap_or(0));
+        (pending.bytes, tail)
+    }
+
+    // Abandon a waiting rotation (the file was replaced some other way, or logging stops)
+    // and return everything held so it can still be written.
+    pub fn release(&mut self) -> Vec<u8> {
+        self.pending
+            .take()
+            .map(|pending| pending.bytes)
+            .unwrap_or_default()
+    }
+}
+
+impl PendingRotation {
+    // Look for the first checksum-valid boundary frame; `scanned` skips sync positions that
+    // were already ruled out, so a partial frame is retried once more bytes arrive.
+    fn find_boundary(&mut self) {
+        let bytes = &self.bytes;
+        let mut at = self.scanned;
+        loop {
+            let Some(offset) = bytes[at..]
+                .windows(2)
+                .position(|pair| pair == [SYNC_CHAR_1, SYNC_CHAR_2])
+            else {
+                // Keep a trailing first sync byte; its partner may arrive with the next chunk.
+                at = at.max(bytes.len().saturating_sub(1));
+                break;
+            };
+            at += offset;
+            let frame = &bytes[at..];
+            if frame.len() < 6 {
+                break;
+            }
+            let len = usize::from(u16::from_le_bytes([frame[4], frame[5]]));
+            if len > MAX_FRAME_PAYLOAD_LEN {
+                at += 1;
+                continue;
+            }
+            if frame.len() < len + 8 {
+                break;
+            }
+            let valid = checksum(&frame[2..6 + len]) == (frame[6 + len], frame[7 + len]);
+            match (frame[2], frame[3]) {
+                (CLASS_RXM, ID_RXM_RAWX) if valid => {
+                    self.boundary = Some(at);
+                    return;
+                }
+                (CLASS_NAV, ID_NAV_EOE) if valid => {
+                    self.boundary = Some(at + len + 8);
+                    return;
+                }
+                _ if valid => at += len + 8,
+                _ => at += 1,
+            }
+        }
+        self.scanned = at;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::ubx::encode_packet;
+
+    const GRACE: Duration = Duration::from_secs(5);
+
+    fn rawx(marker: u8) -> Vec<u8> {
+        encode_packet(CLASS_RXM, ID_RXM_RAWX, &[marker; 16])
+    }
+
+    fn sfrbx(marker: u8) -> Vec<u8> {
+        encode_packet(CLASS_RXM, 0x13, &[marker; 8])
+    }
+
+    fn eoe(marker: u8) -> Vec<u8> {
+        encode_packet(CLASS_NAV, ID_NAV_EOE, &[marker; 4])
+    }
+
+    #[test]
+    fn rollover_splits_at_the_next_rawx_not_the_host_hour_change() {
+        let mut rotation = EpochRotation::new(Some(GRACE));
+        let start = Instant::now();
+        let old_epoch = [rawx(1), sfrbx(1)].concat();
+        assert_eq!(rotation.pass(&old_epoch), old_epoch.as_slice());
+
+        // The host clock has crossed the hour while epoch 1 is still arriving.
+        assert!(!rotation.rotation_due_at(start));
+        let old_tail = sfrbx(2);
+        assert!(rotation.pass(&old_tail).is_empty());
+        assert!(!rotation.rotation_due_at(start + Duration::from_secs(1)));
+
+        let new_epoch = [rawx(3), sfrbx(3)].concat();
+        assert!(rotation.pass(&new_epoch).is_empty());
+        assert!(rotation.rotation_due_at(start + Duration::from_secs(1)));
+        assert_eq!(rotation.take_split(), (old_tail, new_epoch));
+
+        // Rotated: bytes pass straight through again.
+        let next = sfrbx(4);
+        assert_eq!(rotation.pass(&next), next.as_slice());
+    }
+
+    #[test]
+    fn nav_eoe_closes_the_epoch_in_the_old_file() {
+        let mut rotation = EpochRotation::new(Some(GRACE));
+        let start = Instant::now();
+        assert!(!rotation.rotation_due_at(start));
+        let old_tail = [sfrbx(1), eoe(1)].concat();
+        let new_head = sfrbx(2);
+        assert!(
+            rotation
+                .pass(&[old_tail.clone(), new_head.clone()].concat())
+                .is_empty()
+        );
+        assert!(rotation.rotation_due_at(start));
+        assert_eq!(rotation.take_split(), (old_tail, new_head));
+    }
+
+    #[test]
+    fn boundary_frame_split_across_chunks_is_found() {
+        let mut rotation = EpochRotation::new(Some(GRACE));
+        let start = Instant::now();
+        assert!(!rotation.rotation_due_at(start));
+        let old_tail = sfrbx(1);
+        let new_epoch = rawx(2);
+        let stream = [old_tail.clone(), new_epoch.clone()].concat();
+        // Cut inside the sync pair, then inside the payload.
+        let cuts = [old_tail.len() + 1, old_tail.len() + 10];
+        assert!(rotation.pass(&stream[..cuts[0]]).is_empty());
+        assert!(!rotation.rotation_due_at(start));
+        assert!(rotation.pass(&stream[cuts[0]..cuts[1]]).is_empty());
+        assert!(!rotation.rotation_due_at(start));
+        assert!(rotation.pass(&stream[cuts[1]..]).is_empty());
+        assert!(rotation.rotation_due_at(start));
+        assert_eq!(rotation.take_split(), (old_tail, new_epoch));
+    }
+
+    #[test]
+    fn corrupt_rawx_is_not_a_boundary() {
+        let mut rotation = EpochRotation::new(Some(GRACE));
+        let start = Instant::now();
+        assert!(!rotation.rotation_due_at(start));
+        let mut corrupt = rawx(1);
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0xFF;
+        let held = [corrupt, sfrbx(1)].concat();
+        assert!(rotation.pass(&held).is_empty());
+        assert!(!rotation.rotation_due_at(start + Duration::from_secs(1)));
+        assert_eq!(rotation.release(), held);
+    }
+
+    #[test]
+    fn without_a_boundary_the_grace_period_falls_back_to_the_wall_clock() {
+        let mut rotation = EpochRotation::new(Some(GRACE));
+        let start = Instant::now();
+        assert!(!rotation.rotation_due_at(start));
+        let held = sfrbx(1);
+        assert!(rotation.pass(&held).is_empty());
+        assert!(!rotation.rotation_due_at(start + GRACE - Duration::from_millis(1)));
+        assert!(rotation.rotation_due_at(start + GRACE));
+        // Everything since the hour change goes to the new file, as wall-clock rotation does.
+        assert_eq!(rotation.take_split(), (Vec::new(), held));
+    }
+
+    #[test]
+    fn no_grace_rotates_on_the_wall_clock() {
+        let mut rotation = EpochRotation::new(None);
+        assert!(rotation.rotation_due());
+        let chunk = sfrbx(1);
+        assert_eq!(rotation.pass(&chunk), chunk.as_slice());
+        assert_eq!(rotation.take_split(), (Vec::new(), Vec::new()));
+    }
+}
@@ -0,0 +1,49 @@
+use std::error::Error as StdError;
+use thiserror::Error;
+
+// Detailed cause of a pipeline failure, usually an anyhow chain with file paths and stderr.
+pub type ErrorSource = Box<dyn StdError + Send + Sync + 'static>;
+
+// Failure categories returned by the `log` and `convert` entrypoints, so embedding code can
+// react to the kind of failure without matching message text. Each variant keeps the
+// detailed context chain as its source; `main` prints both.
+#[derive(Debug, Error)]
+pub enum PipelineError {
+    #[error("installing signal handlers failed")]
+    Signal(#[source] ErrorSource),
+    #[error("invalid configuration")]
+    InvalidConfig(#[source] ErrorSource),
+    #[error("parsing UBX configuration failed")]
+    ConfigParse(#[source] ErrorSource),
+    #[error("lock unavailable")]
+    Lock(#[source] ErrorSource),
+    #[error("preparing data or archive storage failed")]
+    Storage(#[source] ErrorSource),
+    #[error("opening GNSS input failed")]
+    SerialOpen(#[source] ErrorSource),
+    #[error("reading GNSS input failed")]
+    Input(#[source] ErrorSource),
+    #[error("writing UBX log failed")]
+    LogWrite(#[source] ErrorSource),
+    #[error("converter unavailable or misconfigured")]
+    ConverterMissing(#[source] ErrorSource),
+    #[error("conversion failed for {hours}")]
+    ConversionFailed {
+        hours: String,
+        #[source]
+        source: ErrorSource,
+    },
+    #[error("archive maintenance failed")]
+    Archive(#[source] ErrorSource),
+}
+
+// Tag an anyhow result with its pipeline category: `.or_pipeline(PipelineError::Lock)?`.
+pub trait PipelineResultExt<T> {
+    fn or_pipeline(self, kind: fn(ErrorSource) -> PipelineError) -> Result<T, PipelineError>;
+}
+
+impl<T> PipelineResultExt<T> for anyhow::Result<T> {
+    fn or_pipeline(self, kind: fn(ErrorSource) -> PipelineError) -> Result<T, PipelineError> {
+        self.map_err(|err| kind(err.into()))
+    }
+}
@@ -0,0 +1,195 @@
+use crate::args::LogFormat;
+use chrono::{SecondsFormat, Utc};
+use serde_json::json;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+
+// Set once at startup from `--log-format`; every status message goes through `emit_event`.
+static JSON_EVENTS: AtomicBool = AtomicBool::new(false);
+// Most detailed level still printed, set once at startup from `-q`/`-v`.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(Level::Info as u8);
+
+pub fn set_log_format(format: LogFormat) {
+    JSON_EVENTS.store(format == LogFormat::Json, Ordering::SeqCst);
+}
+
+// Message importance, most important first. Warnings cannot be silenced; fatal errors are
+// printed by `main` regardless of verbosity.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Level {
+    Warn,
+    Info,
+    Verbose,
+    Debug,
+}
+
+// `-q` keeps warnings only, `-v` adds verbose detail, `-vv` (or more) adds debug output such as
+// every converter command line.
+pub fn set_verbosity(quiet: bool, verbose: u8) {
+    MAX_LEVEL.store(verbosity_level(quiet, verbose) as u8, Ordering::SeqCst);
+}
+
+fn verbosity_level(quiet: bool, verbose: u8) -> Level {
+    match (quiet, verbose) {
+        (true, _) => Level::Warn,
+        (false, 0) => Level::Info,
+        (false, 1) => Level::Verbose,
+        (false, _) => Level::Debug,
+    }
+}
+
+fn is_enabled(level: Level) -> bool {
+    level as u8 <= MAX_LEVEL.load(Ordering::Relaxed)
+}
+
+// Event categories written as the `event` field of JSON status lines.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EventKind {
+    Startup,
+    Shutdown,
+    Config,
+    Rotation,
+    Stats,
+    Nmea,
+    Stall,
+    Position,
+    TimeDrift,
+    Reconnect,
+    DiskSpace,
+    ConvertStart,
+    ConvertFinish,
+    ConvertFailed,
+    Upload,
+    Archive,
+    Warning,
+    Info,
+}
+
+impl EventKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Startup => "startup",
+            Self::Shutdown => "shutdown",
+            Self::Config => "config",
+            Self::Rotation => "rotation",
+            Self::Stats => "stats",
+            Self::Nmea => "nmea",
+            Self::Stall => "stall",
+            Self::Position => "position",
+            Self::TimeDrift => "time_drift",
+            Self::Reconnect => "reconnect",
+            Self::DiskSpace => "disk_space",
+            Self::ConvertStart => "convert_start",
+            Self::ConvertFinish => "convert_finish",
+            Self::ConvertFailed => "convert_failed",
+            Self::Upload => "upload",
+            Self::Archive => "archive",
+            Self::Warning => "warning",
+            Self::Info => "info",
+        }
+    }
+
+    // Level `emit_event` uses: alerts (and their recovery messages) stay visible under `-q`.
+    fn level(self) -> Level {
+        match self {
+            Self::Warning
+            | Self::ConvertFailed
+            | Self::Stall
+            | Self::Position
+            | Self::Reconnect
+            | Self::DiskSpace => Level::Warn,
+            _ => Level::Info,
+        }
+    }
+}
+
+// Write one status message to stderr: the plain text, or
+// `{"ts": "<UTC RFC 3339>", "event": "<kind>", "message": "<text>"}` in JSON mode.
+pub fn emit_event(kind: EventKind, message: &str) {
+    log_at(kind.level(), kind, message);
+}
+
+// `emit_event` at an explicit level; dropped when the level is above the `-q`/`-v` setting.
+pub fn log_at(level: Level, kind: EventKind, message: &str) {
+    if !is_enabled(level) {
+        return;
+    }
+    if JSON_EVENTS.load(Ordering::Relaxed) {
+        eprintln!("{}", event_json_line(kind, message));
+    } else {
+        eprintln!("{message}");
+    }
+}
+
+fn event_json_line(kind: EventKind, message: &str) -> String {
+    json!({
+        "ts": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "event": kind.as_str(),
+        "message": message,
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Whether a message at `level` prints under the given `-q`/`-v` flags.
+    fn printed(level: Level, quiet: bool, verbose: u8) -> bool {
+        level <= verbosity_level(quiet, verbose)
+    }
+
+    #[test]
+    fn quiet_keeps_only_warnings() {
+        assert!(printed(Level::Warn, true, 0));
+        assert!(!printed(Level::Info, true, 0));
+        // `-q` wins over any `-v`.
+        assert!(!printed(Level::Debug, true, 2));
+    }
+
+    #[test]
+    fn each_verbose_flag_adds_one_level() {
+        assert!(printed(Level::Info, false, 0));
+        assert!(!printed(Level::Verbose, false, 0));
+        assert!(printed(Level::Verbose, false, 1));
+        assert!(!printed(Level::Debug, false, 1));
+        assert!(printed(Level::Debug, false, 2));
+        assert!(printed(Level::Debug, false, 5));
+    }
+
+    #[test]
+    fn alerts_and_recoveries_survive_quiet_mode() {
+        for kind in [
+            EventKind::Warning,
+            EventKind::ConvertFailed,
+            EventKind::Stall,
+            EventKind::Position,
+            EventKind::Reconnect,
+            EventKind::DiskSpace,
+        ] {
+            assert!(printed(kind.level(), true, 0), "{kind:?}");
+        }
+        for kind in [
+            EventKind::Stats,
+            EventKind::Rotation,
+            EventKind::ConvertFinish,
+        ] {
+            assert!(!printed(kind.level(), true, 0), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn json_event_is_one_object_on_one_line() {
+        let message = "Converter said:\n  \"bad\" header\r\n\tat line 3";
+        let line = event_json_line(EventKind::ConvertFailed, message);
+        assert_eq!(line.lines().count(), 1);
+
+        let event: serde_json::Value = serde_json::from_str(&line).unwrap();
+        let object = event.as_object().unwrap();
+        assert_eq!(object.len(), 3);
+        assert_eq!(object["event"], "convert_failed");
+        assert_eq!(object["message"], message);
+        let ts = object["ts"].as_str().unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(ts).is_ok());
+        assert!(ts.ends_with('Z'));
+    }
+}
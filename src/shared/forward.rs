@@ -0,0 +1,225 @@
+use crate::args::OverflowPolicy;
+use crate::shared::chunk_queue::ChunkQueue;
+use crate::shared::event::{EventKind, emit_event};
+use anyhow::{Context, Result, bail};
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const TCP_SCHEME: &str = "tcp://";
+const FORWARD_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// A consumer that accepts nothing for this long is dropped and reconnected.
+const FORWARD_WRITE_TIMEOUT: Duration = Duration::from_secs(5);
+const FORWARD_POLL: Duration = Duration::from_millis(250);
+const FORWARD_RETRY_MIN: Duration = Duration::from_secs(1);
+const FORWARD_RETRY_MAX: Duration = Duration::from_secs(60);
+
+// Live copy of the logged stream for `--forward tcp://host:port`.
+// The logging loop only queues chunks; a separate thread owns the connection and reconnects
+// with backoff, so a slow or missing consumer never delays the file. The queue holds at most
+// `--forward-buffer-bytes` and drops the oldest bytes beyond that.
+pub struct Forwarder {
+    queue: Arc<ChunkQueue>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Forwarder {
+    pub fn start(target: &str, buffer_bytes: usize) -> Result<Self> {
+        let Some(address) = target.trim().strip_prefix(TCP_SCHEME) else {
+            bail!("--forward expects tcp://host:port, got {target}");
+        };
+        if address.is_empty() || !address.contains(':') {
+            bail!("--forward expects tcp://host:port, got {target}");
+        }
+        let queue = Arc::new(ChunkQueue::new(buffer_bytes, OverflowPolicy::DropOld));
+        let worker_queue = Arc::clone(&queue);
+        let address = address.to_string();
+        let handle = thread::Builder::new()
+            .name("gnss-forward".to_string())
+            .spawn(move || forward_loop(&address, &worker_queue))
+            .context("starting forward thread failed")?;
+        Ok(Self {
+            queue,
+            handle: Some(handle),
+        })
+    }
+
+    // Queue bytes for the consumer; never blocks.
+    pub fn send(&self, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            self.queue.push(bytes.to_vec());
+        }
+    }
+}
+
+impl Drop for Forwarder {
+    // Let the thread hand over what is queued (it gives up on a stuck consumer after the
+    // write timeout), then stop.
+    fn drop(&mut self) {
+        self.queue.close();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn forward_loop(address: &str, queue: &ChunkQueue) {
+    let label = format!("{TCP_SCHEME}{address}");
+    let mut stream: Option<TcpStream> = None;
+    let mut retry_at = Instant::now();
+    let mut retry_delay = FORWARD_RETRY_MIN;
+    loop {
+        let Some(conn) = stream.as_mut() else {
+            if queue.is_closed() {
+                return;
+            }
+            if Instant::now() < retry_at {
+                thread::sleep(FORWARD_POLL);
+                continue;
+            }
+            match connect(address) {
+                Ok(conn) => {
+                    emit_event(
+                        EventKind::Reconnect,
+                        &format!("Forwarding logged stream to {label}"),
+                    );
+                    retry_delay = FORWARD_RETRY_MIN;
+                    stream = Some(conn);
+                }
+                Err(err) => {
+                    emit_event(
+                        EventKind::Warning,
+                        &format!(
+                            "Forward target unavailable; retrying in {}s: {err:#}",
+                            retry_delay.as_secs()
+                        ),
+                    );
+                    retry_at = Instant::now() + retry_delay;
+                    retry_delay = (retry_delay * 2).min(FORWARD_RETRY_MAX);
+                }
+            }
+            continue;
+        };
+
+        let dropped = queue.take_dropped();
+        if dropped.bytes > 0 {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "Forward buffer overflowed; dropped {} byte(s) for {label}",
+                    dropped.bytes
+                ),
+            );
+        }
+        let Some(chunk) = queue.pop_timeout(FORWARD_POLL) else {
+            if queue.is_drained() {
+                return;
+            }
+            continue;
+        };
+        if let Err(err) = conn.write_all(&chunk) {
+            emit_event(
+                EventKind::Reconnect,
+                &format!("Forward connection to {label} lost: {err}"),
+            );
+            stream = None;
+            retry_at = Instant::now();
+        }
+    }
+}
+
+fn connect(address: &str) -> Result<TcpStream> {
+    let addrs: Vec<_> = address
+        .to_socket_addrs()
+        .with_context(|| format!("resolving forward address failed: {address}"))?
+        .collect();
+    let mut last_err = None;
+    for addr in &addrs {
+        match TcpStream::connect_timeout(addr, FORWARD_CONNECT_TIMEOUT) {
+            Ok(stream) => {
+                stream
+                    .set_write_timeout(Some(FORWARD_WRITE_TIMEOUT))
+                    .with_context(|| format!("setting forward write timeout failed: {address}"))?;
+                let _ = stream.set_nodelay(true);
+                return Ok(stream);
+            }
+            Err(err) => last_err = Some(err),
+        }
+    }
+    match last_err {
+        Some(err) => {
+            Err(err).with_context(|| format!("connecting forward target failed: {address}"))
+        }
+        None => bail!("forward address resolved to nothing: {address}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+
+    #[test]
+    fn forwarded_bytes_match_what_was_logged() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = format!("tcp://{}", listener.local_addr().unwrap());
+        let chunks: Vec<Vec<u8>> = (0..50_u8).map(|i| vec![i; 1_000]).collect();
+
+        let forwarder = Forwarder::start(&target, 1 << 20).unwrap();
+        let (mut client, _) = listener.accept().unwrap();
+        for chunk in &chunks {
+            forwarder.send(chunk);
+        }
+        // Dropping hands over everything queued, then closes the connection.
+        drop(forwarder);
+
+        let mut received = Vec::new();
+        client.read_to_end(&mut received).unwrap();
+        assert_eq!(received, chunks.concat());
+    }
+
+    #[test]
+    fn absent_consumer_does_not_block_logging() {
+        // Bind and release a port so nothing is listening on it.
+        let target = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            format!("tcp://{}", listener.local_addr().unwrap())
+        };
+        let forwarder = Forwarder::start(&target, 64 * 1024).unwrap();
+        let started = Instant::now();
+        for _ in 0..1_000 {
+            forwarder.send(&[0xB5; 4_096]);
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+        drop(forwarder);
+    }
+
+    #[test]
+    fn stalled_consumer_does_not_block_logging() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let target = format!("tcp://{}", listener.local_addr().unwrap());
+        let forwarder = Forwarder::start(&target, 64 * 1024).unwrap();
+        // Accepted but never read, so the socket buffers fill and the forward thread blocks.
+        let (client, _) = listener.accept().unwrap();
+
+        let started = Instant::now();
+        for _ in 0..10_000 {
+            forwarder.send(&[0xB5; 4_096]);
+        }
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Closing the consumer fails the blocked write, so the drop does not wait it out.
+        drop(client);
+        drop(forwarder);
+    }
+
+    #[test]
+    fn rejects_targets_without_tcp_host_and_port() {
+        assert!(Forwarder::start("udp://127.0.0.1:9000", 1024).is_err());
+        assert!(Forwarder::start("tcp://", 1024).is_err());
+        assert!(Forwarder::start("tcp://localhost", 1024).is_err());
+    }
+}
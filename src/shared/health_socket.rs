@@ -0,0 +1,294 @@
+use crate::shared::chunk_queue::ChunkQueue;
+use crate::shared::event::{EventKind, emit_event};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::thread::JoinHandle;
+
+// Values the logging threads publish for `--health-socket`. Updates are a relaxed atomic store
+// (or a short lock on rotation), so they stay cheap when no socket is configured.
+static LAST_READ_MS: AtomicU64 = AtomicU64::new(0);
+static TOTAL_BYTES: AtomicU64 = AtomicU64::new(0);
+static BITS_PER_SEC: AtomicU64 = AtomicU64::new(u64::MAX);
+// 0 = not checked yet, 1 = available, 2 = unavailable.
+static CONVERTER_AVAILABLE: AtomicU8 = AtomicU8::new(0);
+static CURRENT_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+// The input reader got data.
+pub fn record_read() {
+    LAST_READ_MS.store(
+        Utc::now().timestamp_millis().max(1) as u64,
+        Ordering::Relaxed,
+    );
+}
+
+pub fn record_total_bytes(total_bytes: u64) {
+    TOTAL_BYTES.store(total_bytes, Ordering::Relaxed);
+}
+
+// Bit rate of the last stats interval.
+pub fn record_bits_per_sec(bits_per_sec: u64) {
+    BITS_PER_SEC.store(bits_per_sec, Ordering::Relaxed);
+}
+
+pub fn record_converter_available(available: bool) {
+    CONVERTER_AVAILABLE.store(if available { 1 } else { 2 }, Ordering::Relaxed);
+}
+
+pub fn record_current_file(path: &Path) {
+    *CURRENT_FILE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(path.to_path_buf());
+}
+
+// One JSON object per connection; fields the logger has not published yet are null.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn health_snapshot(queue: &ChunkQueue) -> Value {
+    let now = Utc::now();
+    let last_read = match LAST_READ_MS.load(Ordering::Relaxed) {
+        0 => None,
+        millis => DateTime::<Utc>::from_timestamp_millis(millis as i64),
+    };
+    let bits_per_sec = match BITS_PER_SEC.load(Ordering::Relaxed) {
+        u64::MAX => None,
+        bps => Some(bps),
+    };
+    let converter_available = match CONVERTER_AVAILABLE.load(Ordering::Relaxed) {
+        1 => Some(true),
+        2 => Some(false),
+        _ => None,
+    };
+    let current_file = CURRENT_FILE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .as_ref()
+        .map(|path| path.to_string_lossy().into_owned());
+    let (queued_bytes, queued_chunks) = queue.depth();
+    json!({
+        "time": now.to_rfc3339(),
+        "pid": std::process::id(),
+        "last_read": last_read.map(|at| at.to_rfc3339()),
+        "last_read_age_secs": last_read.map(|at| (now - at).num_milliseconds() as f64 / 1000.0),
+        "bits_per_sec": bits_per_sec,
+        "total_bytes": TOTAL_BYTES.load(Ordering::Relaxed),
+        "current_file": current_file,
+        "converter_available": converter_available,
+        "queued_bytes": queued_bytes,
+        "queued_chunks": queued_chunks,
+    })
+}
+
+// `--health-socket` listener: every connection gets one JSON snapshot line and is closed.
+// The socket file is removed when this is dropped, i.e. when logging stops.
+pub struct HealthSocket {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl HealthSocket {
+    pub fn bind(path: &Path, queue: Arc<ChunkQueue>) -> Result<Self> {
+        let stop = Arc::new(AtomicBool::new(false));
+        let handle = unix_listener::spawn(path, queue, Arc::clone(&stop))?;
+        emit_event(
+            EventKind::Startup,
+            &format!("Serving health snapshots on {}", path.display()),
+        );
+        Ok(Self {
+            path: path.to_path_buf(),
+            stop,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl Drop for HealthSocket {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(unix)]
+mod unix_listener {
+    use super::health_snapshot;
+    use crate::shared::chunk_queue::ChunkQueue;
+    use crate::shared::event::{EventKind, emit_event};
+    use anyhow::{Context, Result, bail};
+    use std::fs;
+    use std::io::{self, Write};
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread::{self, JoinHandle};
+    use std::time::Duration;
+
+    const ACCEPT_POLL: Duration = Duration::from_millis(200);
+    const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+    pub(super) fn spawn(
+        path: &Path,
+        queue: Arc<ChunkQueue>,
+        stop: Arc<AtomicBool>,
+    ) -> Result<JoinHandle<()>> {
+        // A socket left by a crashed run would make bind fail; anything else is not ours.
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                fs::remove_file(path).with_context(|| {
+                    format!("removing stale health socket failed: {}", path.display())
+                })?;
+            }
+            Ok(_) => bail!(
+                "--health-socket path exists and is not a socket: {}",
+                path.display()
+            ),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => {
+                return Err(err)
+                    .with_context(|| format!("reading metadata for {}", path.display()));
+            }
+        }
+        let listener = UnixListener::bind(path)
+            .with_context(|| format!("binding health socket failed: {}", path.display()))?;
+        // Non-blocking accept so the thread notices shutdown without a wake-up connection.
+        listener
+            .set_nonblocking(true)
+            .with_context(|| format!("configuring health socket failed: {}", path.display()))?;
+
+        thread::Builder::new()
+            .name("health-socket".to_string())
+            .spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    match listener.accept() {
+                        Ok((stream, _)) => {
+                            if let Err(err) = answer(stream, &queue) {
+                                emit_event(
+                                    EventKind::Warning,
+                                    &format!("Answering health socket client failed: {err}"),
+                                );
+                            }
+                        }
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(ACCEPT_POLL);
+                        }
+                        Err(err) => {
+                            emit_event(
+                                EventKind::Warning,
+                                &format!("Accepting health socket client failed: {err}"),
+                            );
+                            thread::sleep(ACCEPT_POLL);
+                        }
+                    }
+                }
+            })
+            .context("starting health socket thread failed")
+    }
+
+    fn answer(mut stream: UnixStream, queue: &ChunkQueue) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        stream.write_all(format!("{}\n", health_snapshot(queue)).as_bytes())?;
+        stream.flush()
+    }
+}
+
+#[cfg(not(unix))]
+mod unix_listener {
+    use crate::shared::chunk_queue::ChunkQueue;
+    use anyhow::{Result, bail};
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::sync::atomic::AtomicBool;
+    use std::thread::JoinHandle;
+
+    pub(super) fn spawn(
+        _path: &Path,
+        _queue: Arc<ChunkQueue>,
+        _stop: Arc<AtomicBool>,
+    ) -> Result<JoinHandle<()>> {
+        bail!("--health-socket needs Unix domain sockets, which this platform does not support")
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::args::OverflowPolicy;
+    use std::io::Read;
+    use std::os::unix::net::UnixStream;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-health-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn read_snapshot(path: &Path) -> String {
+        let mut response = String::new();
+        UnixStream::connect(path)
+            .unwrap()
+            .read_to_string(&mut response)
+            .unwrap();
+        response
+    }
+
+    #[test]
+    fn each_connection_gets_one_json_snapshot() {
+        let dir = scratch_dir("snapshot");
+        let path = dir.join("health.sock");
+        let queue = Arc::new(ChunkQueue::new(1 << 20, OverflowPolicy::DropOld));
+        queue.push(vec![0xB5; 100]);
+        queue.push(vec![0x62; 50]);
+        let socket = HealthSocket::bind(&path, Arc::clone(&queue)).unwrap();
+
+        let response = read_snapshot(&path);
+        assert!(response.ends_with('\n'));
+        assert_eq!(response.lines().count(), 1);
+        let snapshot: Value = serde_json::from_str(&response).unwrap();
+        assert_eq!(snapshot["pid"], std::process::id());
+        assert_eq!(snapshot["queued_bytes"], 150);
+        assert_eq!(snapshot["queued_chunks"], 2);
+        assert!(DateTime::parse_from_rfc3339(snapshot["time"].as_str().unwrap()).is_ok());
+        assert!(snapshot["total_bytes"].is_u64());
+        for field in [
+            "last_read",
+            "last_read_age_secs",
+            "bits_per_sec",
+            "current_file",
+            "converter_available",
+        ] {
+            assert!(snapshot.get(field).is_some(), "missing {field}");
+        }
+
+        // A second client gets its own fresh snapshot.
+        assert!(queue.pop_timeout(std::time::Duration::ZERO).is_some());
+        let snapshot: Value = serde_json::from_str(&read_snapshot(&path)).unwrap();
+        assert_eq!(snapshot["queued_bytes"], 50);
+
+        drop(socket);
+        assert!(!path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_path_that_is_not_a_socket() {
+        let dir = scratch_dir("not-socket");
+        let path = dir.join("health.sock");
+        std::fs::write(&path, b"keep me").unwrap();
+        let queue = Arc::new(ChunkQueue::new(1024, OverflowPolicy::DropOld));
+        assert!(HealthSocket::bind(&path, queue).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"keep me");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::nmea::{NmeaSentenceCollector, has_valid_checksum};
+use crate::shared::ubx::FrameScanner;
+use std::time::{Duration, Instant};
+
+// First-run sanity check of the input link. At the wrong baud rate (or with the receiver
+// speaking another protocol) bytes still arrive, but not one UBX frame or NMEA sentence
+// decodes. The window starts with the first received byte; the warning is emitted at most
+// once and logging continues either way.
+pub struct LinkCheck {
+    window: Option<Duration>,
+    first_byte: Option<Instant>,
+    bytes: u64,
+    scanner: FrameScanner,
+    collector: NmeaSentenceCollector,
+    done: bool,
+}
+
+impl LinkCheck {
+    // A zero window disables the check.
+    pub fn new(window_secs: u64) -> Self {
+        Self {
+            window: (window_secs > 0).then(|| Duration::from_secs(window_secs)),
+            first_byte: None,
+            bytes: 0,
+            scanner: FrameScanner::new(),
+            collector: NmeaSentenceCollector::new(),
+            done: window_secs == 0,
+        }
+    }
+
+    // Feed received bytes until the stream proves decodable.
+    pub fn ingest(&mut self, bytes: &[u8]) {
+        self.ingest_at(Instant::now(), bytes);
+    }
+
+    fn ingest_at(&mut self, now: Instant, bytes: &[u8]) {
+        if self.done || bytes.is_empty() {
+            return;
+        }
+        self.first_byte.get_or_insert(now);
+        self.bytes += bytes.len() as u64;
+
+        let mut frames = Vec::new();
+        self.scanner.push_bytes(bytes, &mut frames);
+        let mut sentences = Vec::new();
+        self.collector.push_bytes(bytes, &mut sentences);
+        if !frames.is_empty() || sentences.iter().any(|s| has_valid_checksum(s)) {
+            self.done = true;
+        }
+    }
+
+    // Returns true (and warns) once when the window passes with nothing decodable.
+    pub fn check(&mut self, source_label: &str) -> bool {
+        self.check_at(Instant::now(), source_label)
+    }
+
+    fn check_at(&mut self, now: Instant, source_label: &str) -> bool {
+        let (Some(window), Some(first_byte)) = (self.window, self.first_byte) else {
+            return false;
+        };
+        if self.done || now.saturating_duration_since(first_byte) < window {
+            return false;
+        }
+        self.done = true;
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "WARNING: {} bytes from {} in the first {}s contained no valid UBX frame or NMEA sentence; \
+                 the baud rate (--baud-rate) or receiver output protocol is probably wrong",
+                self.bytes,
+                source_label,
+                window.as_secs()
+            ),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::nmea::tests::nmea_sentence;
+    use crate::shared::ubx::encode_packet;
+
+    const LABEL: &str = "/dev/ttyACM0";
+    const WINDOW: Duration = Duration::from_secs(10);
+
+    // Bytes a receiver produces when read at the wrong baud rate.
+    const GARBAGE: &[u8] = &[0xF8, 0x80, 0x00, 0x78, 0xE0, 0x1C, 0xFE, 0x06];
+
+    #[test]
+    fn undecodable_bytes_warn_once_after_the_window() {
+        let mut check = LinkCheck::new(10);
+        let start = Instant::now();
+        check.ingest_at(start, GARBAGE);
+        check.ingest_at(start + Duration::from_secs(5), GARBAGE);
+        assert!(!check.check_at(start + WINDOW - Duration::from_millis(1), LABEL));
+        assert!(check.check_at(start + WINDOW, LABEL));
+        assert!(!check.check_at(start + WINDOW * 2, LABEL));
+    }
+
+    #[test]
+    fn window_starts_with_the_first_byte() {
+        let mut check = LinkCheck::new(10);
+        let start = Instant::now();
+        // Nothing received yet: a silent port is the stall detector's business.
+        assert!(!check.check_at(start + WINDOW * 6, LABEL));
+        let first_byte = start + WINDOW * 6;
+        check.ingest_at(first_byte, GARBAGE);
+        assert!(!check.check_at(first_byte + WINDOW / 2, LABEL));
+        assert!(check.check_at(first_byte + WINDOW, LABEL));
+    }
+
+    #[test]
+    fn a_ubx_frame_or_valid_nmea_sentence_passes() {
+        let gga = format!(
+            "{}\r\n",
+            nmea_sentence("GPGGA,123519.00,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,")
+        );
+        let frame = encode_packet(0x01, 0x07, &[0; 92]);
+        for decodable in [gga.as_bytes(), frame.as_slice()] {
+            let mut check = LinkCheck::new(10);
+            let start = Instant::now();
+            check.ingest_at(start, GARBAGE);
+            check.ingest_at(start, decodable);
+            assert!(!check.check_at(start + WINDOW * 2, LABEL));
+        }
+    }
+
+    #[test]
+    fn nmea_with_a_bad_checksum_does_not_pass() {
+        let mut check = LinkCheck::new(10);
+        let start = Instant::now();
+        check.ingest_at(start, b"$GPGGA,123519.00,,,,,0,00,,,M,,M,,*00\r\n");
+        assert!(check.check_at(start + WINDOW, LABEL));
+    }
+
+    #[test]
+    fn zero_window_disables_the_check() {
+        let mut check = LinkCheck::new(0);
+        let start = Instant::now();
+        check.ingest_at(start, GARBAGE);
+        assert!(!check.check_at(start + Duration::from_secs(3_600), LABEL));
+    }
+}
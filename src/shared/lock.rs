@@ -1,7 +1,12 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use fs2::FileExt;
 use std::fs::{self, File, OpenOptions};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(200);
 
 // Process-level lock guard backed by an OS file lock.
 // This prevents duplicate logger/converter instances from stepping on each other.
@@ -12,26 +17,76 @@ pub struct LockGuard {
 impl LockGuard {
     // Acquire an exclusive lock on the given file path.
     pub fn acquire(path: &Path) -> Result<Self> {
-        if let Some(parent) = path.parent() {
-            if !parent.as_os_str().is_empty() {
-                fs::create_dir_all(parent).with_context(|| {
-                    format!("creating lock directory failed: {}", parent.display())
-                })?;
+        let file = Self::open_lock_file(path)?;
+        file.try_lock_exclusive()
+            .with_context(|| format!("another instance is already running: {}", path.display()))?;
+
+        Ok(Self { file })
+    }
+
+    // Like `acquire`, but keep retrying for up to `timeout` while another instance finishes
+    // (cron overlaps). A zero timeout fails fast; clearing `running` abandons the wait.
+    pub fn acquire_with_timeout(
+        path: &Path,
+        timeout: Duration,
+        running: &AtomicBool,
+    ) -> Result<Self> {
+        let file = Self::open_lock_file(path)?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => return Ok(Self { file }),
+                Err(err) if Instant::now() >= deadline => {
+                    return Err(err).with_context(|| {
+                        if timeout.is_zero() {
+                            format!("another instance is already running: {}", path.display())
+                        } else {
+                            format!(
+                                "another instance still held {} after waiting {}s",
+                                path.display(),
+                                timeout.as_secs()
+                            )
+                        }
+                    });
+                }
+                Err(_) => {}
+            }
+            if !running.load(Ordering::SeqCst) {
+                bail!(
+                    "shutdown requested while waiting for lock: {}",
+                    path.display()
+                );
             }
+            thread::sleep(
+                LOCK_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())),
+            );
         }
+    }
+
+    // Block until an exclusive lock on the given file path is available.
+    // Used for short critical sections (e.g. queue file edits) shared by cooperating processes.
+    pub fn acquire_wait(path: &Path) -> Result<Self> {
+        let file = Self::open_lock_file(path)?;
+        file.lock_exclusive()
+            .with_context(|| format!("waiting for lock failed: {}", path.display()))?;
+        Ok(Self { file })
+    }
 
-        let file = OpenOptions::new()
+    fn open_lock_file(path: &Path) -> Result<File> {
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating lock directory failed: {}", parent.display()))?;
+        }
+
+        OpenOptions::new()
             .create(true)
             .read(true)
             .write(true)
             .truncate(false)
             .open(path)
-            .with_context(|| format!("opening lock file failed: {}", path.display()))?;
-
-        file.try_lock_exclusive()
-            .with_context(|| format!("another instance is already running: {}", path.display()))?;
-
-        Ok(Self { file })
+            .with_context(|| format!("opening lock file failed: {}", path.display()))
     }
 }
 
@@ -41,3 +96,60 @@ impl Drop for LockGuard {
         let _ = self.file.unlock();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::mpsc;
+
+    fn lock_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gnss2tec-lock-{name}-{}.lock", std::process::id()))
+    }
+
+    #[test]
+    fn second_thread_waits_and_then_acquires() {
+        let path = lock_path("contend");
+        let running = AtomicBool::new(true);
+        let (locked_tx, locked_rx) = mpsc::channel();
+
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let _held = LockGuard::acquire(&path).unwrap();
+                locked_tx.send(()).unwrap();
+                thread::sleep(Duration::from_millis(500));
+            });
+            locked_rx.recv().unwrap();
+
+            let started = Instant::now();
+            assert!(LockGuard::acquire(&path).is_err());
+            let _lock =
+                LockGuard::acquire_with_timeout(&path, Duration::from_secs(10), &running).unwrap();
+            assert!(started.elapsed() >= Duration::from_millis(200));
+        });
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn zero_timeout_fails_fast_and_shutdown_abandons_the_wait() {
+        let path = lock_path("fail-fast");
+        let held = LockGuard::acquire(&path).unwrap();
+
+        let started = Instant::now();
+        let err = LockGuard::acquire_with_timeout(&path, Duration::ZERO, &AtomicBool::new(true))
+            .err()
+            .unwrap();
+        assert!(format!("{err:#}").contains("already running"), "{err:#}");
+        let err = LockGuard::acquire_with_timeout(
+            &path,
+            Duration::from_secs(30),
+            &AtomicBool::new(false),
+        )
+        .err()
+        .unwrap();
+        assert!(format!("{err:#}").contains("shutdown requested"), "{err:#}");
+        assert!(started.elapsed() < Duration::from_secs(5));
+        drop(held);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,215 @@
+use crate::args::DataLayout;
+use crate::shared::lock::LockGuard;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration as ChronoDuration, Timelike, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const UBX_SUFFIX: &str = ".ubx";
+const UBX_GZ_SUFFIX: &str = ".ubx.gz";
+
+// Longest the rotation check trusts the monotonic clock before re-reading the wall clock, which
+// bounds how late rotation follows a wall-clock step (NTP, manual `date`).
+const WALL_CLOCK_RECHECK: Duration = Duration::from_secs(1);
+
+// UTC hour the open log file belongs to, for hourly rotation.
+// The logging loop asks `crossed` on every iteration, but the wall clock is only read once the
+// monotonic deadline passes: at most every `WALL_CLOCK_RECHECK`, and exactly at the top of the
+// hour as it approaches. Leaving the hour in either direction counts, like comparing hour keys.
+pub struct HourBoundary {
+    hour_start: DateTime<Utc>,
+    next_hour: DateTime<Utc>,
+    recheck_at: Instant,
+}
+
+impl HourBoundary {
+    pub fn new(now: DateTime<Utc>, mono_now: Instant) -> Self {
+        let hour_start = now
+            .with_minute(0)
+            .and_then(|v| v.with_second(0))
+            .and_then(|v| v.with_nanosecond(0))
+            .expect("UTC floor-to-hour should always be valid");
+        let mut boundary = Self {
+            hour_start,
+            next_hour: hour_start + ChronoDuration::hours(1),
+            recheck_at: mono_now,
+        };
+        boundary.schedule(now, mono_now);
+        boundary
+    }
+
+    // Wall-clock time once it has left this hour, else None. `wall_now` is only called when the
+    // monotonic deadline has passed; callers replace the boundary after rotating.
+    pub fn crossed(
+        &mut self,
+        mono_now: Instant,
+        wall_now: impl FnOnce() -> DateTime<Utc>,
+    ) -> Option<DateTime<Utc>> {
+        if mono_now < self.recheck_at {
+            return None;
+        }
+        let now = wall_now();
+        if now < self.hour_start || now >= self.next_hour {
+            return Some(now);
+        }
+        self.schedule(now, mono_now);
+        None
+    }
+
+    fn schedule(&mut self, now: DateTime<Utc>, mono_now: Instant) {
+        let remaining = (self.next_hour - now).to_std().unwrap_or(Duration::ZERO);
+        self.recheck_at = mono_now + remaining.min(WALL_CLOCK_RECHECK);
+    }
+}
+
+// Active hourly UBX output, either a plain file or a gzip stream on top of it.
+// `flush` on the gzip variant performs a Z_SYNC_FLUSH, so periodic flushes keep a crash-truncated
+// file decodable up to the last flush point. Both sit on a `BufWriter` sized by
+// `--write-buffer-bytes`; with capacity 0 every write goes straight to the file.
+pub enum LogWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+impl LogWriter {
+    pub fn create(path: &Path, compress: bool, buffer_bytes: usize) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening log output failed: {}", path.display()))?;
+        let file = BufWriter::with_capacity(buffer_bytes, file);
+        if compress {
+            Ok(Self::Gzip(GzEncoder::new(file, Compression::default())))
+        } else {
+            Ok(Self::Plain(file))
+        }
+    }
+
+    // Flush remaining bytes (and the gzip trailer) and hand back the underlying file.
+    pub fn finish(self) -> io::Result<File> {
+        let buffered = match self {
+            Self::Plain(buffered) => buffered,
+            Self::Gzip(encoder) => encoder.finish()?,
+        };
+        buffered.into_inner().map_err(|err| err.into_error())
+    }
+}
+
+// Flush-to-stable-storage seam used for `--durability fsync`.
+pub trait SyncData: Write {
+    fn sync_data(&mut self) -> io::Result<()>;
+}
+
+// A closed log file, as `LogWriter::finish` returns it.
+impl SyncData for File {
+    fn sync_data(&mut self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+}
+
+impl SyncData for LogWriter {
+    // Push buffered (or gzip-pending) bytes to the file and then the file to disk.
+    fn sync_data(&mut self) -> io::Result<()> {
+        self.flush()?;
+        match self {
+            Self::Plain(buffered) => buffered.get_ref().sync_data(),
+            Self::Gzip(encoder) => encoder.get_ref().get_ref().sync_data(),
+        }
+    }
+}
+
+impl Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(buffered) => buffered.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(buffered) => buffered.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+        }
+    }
+}
+
+// Name token of the file a rotation opens at `now`: the start second, or only the hour with
+// `--single-file-per-hour` so a restart within the hour appends to the same file.
+pub fn log_file_token(now: DateTime<Utc>, single_file_per_hour: bool) -> String {
+    if single_file_per_hour {
+        now.format("%Y%m%d_%H").to_string()
+    } else {
+        now.format("%Y%m%d_%H%M%S").to_string()
+    }
+}
+
+// File name for a log file started at the given UTC timestamp token.
+pub fn log_file_name(stem: &str, compress: bool) -> String {
+    if compress {
+        format!("{stem}{UBX_GZ_SUFFIX}")
+    } else {
+        format!("{stem}{UBX_SUFFIX}")
+    }
+}
+
+// Return the timestamp stem of a logger output name (`<stem>.ubx` or `<stem>.ubx.gz`).
+pub fn log_file_stem(file_name: &str) -> Option<&str> {
+    file_name
+        .strip_suffix(UBX_GZ_SUFFIX)
+        .or_else(|| file_name.strip_suffix(UBX_SUFFIX))
+}
+
+pub fn is_compressed_log_name(file_name: &str) -> bool {
+    file_name.ends_with(UBX_GZ_SUFFIX)
+}
+
+// Directory holding the UBX files of the UTC day `day` (YYYYMMDD) under `--data-layout`.
+pub fn log_day_dir(data_dir: &Path, layout: DataLayout, day: &str) -> PathBuf {
+    match layout {
+        DataLayout::Flat => data_dir.to_path_buf(),
+        DataLayout::Daily => data_dir.join(day),
+    }
+}
+
+// Directory for a log file opened at `now`, created on first use with the daily layout.
+pub fn log_file_dir(data_dir: &Path, layout: DataLayout, now: DateTime<Utc>) -> Result<PathBuf> {
+    let dir = log_day_dir(data_dir, layout, &now.format("%Y%m%d").to_string());
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("creating log directory failed: {}", dir.display()))?;
+    Ok(dir)
+}
+
+// data_dir plus any `YYYYMMDD` day subdirectories, so scans find UBX files in either layout.
+pub fn log_file_dirs(data_dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = vec![data_dir.to_path_buf()];
+    for entry in fs::read_dir(data_dir)
+        .with_context(|| format!("reading data directory failed: {}", data_dir.display()))?
+    {
+        let entry = entry.with_context(|| format!("iterating {}", data_dir.display()))?;
+        let is_day_name = entry
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.len() == 8 && name.bytes().all(|b| b.is_ascii_digit()));
+        if is_day_name && entry.path().is_dir() {
+            dirs.push(entry.path());
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+// Held while logging with `--single-file-per-hour`: the hour file name no longer differs per
+// process, so a second logger on the same data directory must not append to it as well.
+pub fn acquire_hour_file_lock(data_dir: &Path) -> Result<LockGuard> {
+    LockGuard::acquire(&hour_file_lock_path(data_dir))
+}
+
+fn hour_file_lock_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(".hour-file.lock")
+}
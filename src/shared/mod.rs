@@ -1,4 +1,33 @@
 // Shared support used across command modules.
+pub mod checksum;
+pub mod chunk_queue;
+pub mod clock_skew;
+pub mod constellation_stats;
+pub mod convert_spool;
+pub mod day_manifest;
+pub mod diskspace;
+pub mod epoch_rotation;
+pub mod error;
+pub mod event;
+pub mod forward;
+pub mod health_socket;
+pub mod link_check;
 pub mod lock;
+pub mod logfile;
 pub mod nmea;
+pub mod read_cadence;
+pub mod rinex_obs;
+pub mod sd_notify;
+pub mod serial;
+pub mod shell;
 pub mod signal;
+pub mod source;
+pub mod stale;
+pub mod stall;
+pub mod state_file;
+pub mod stats_csv;
+pub mod status_file;
+pub mod time_drift;
+pub mod ubx;
+pub mod ubx_filter;
+pub mod upload_queue;
@@ -1,9 +1,12 @@
 use crate::args::NmeaLogFormat;
+use crate::shared::event::{EventKind, emit_event};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
 
 const WATCHED_MESSAGE_IDS: [&str; 6] = ["GSA", "GSV", "GNS", "RMC", "GBS", "GST"];
 const MAX_SENTENCE_LEN: usize = 160;
+const EARTH_RADIUS_M: f64 = 6_371_000.0;
 
 // Periodically emits the latest watched NMEA sentences found in the byte stream.
 pub struct NmeaMonitor {
@@ -13,6 +16,8 @@ pub struct NmeaMonitor {
     interval: Option<Duration>,
     format: NmeaLogFormat,
     last_emit: Instant,
+    position: Option<PositionWatch>,
+    per_talker: bool,
 }
 
 impl NmeaMonitor {
@@ -30,12 +35,29 @@ impl NmeaMonitor {
             interval,
             format,
             last_emit: Instant::now(),
+            position: None,
+            per_talker: false,
         }
     }
 
-    // Feed raw serial bytes; matching NMEA sentences are retained as latest snapshot by type.
+    // Track each talker's sentence separately (`GPGSV`, `GLGSV`, ...) instead of keeping only
+    // the latest sentence of each type, for `--nmea-per-talker`.
+    pub fn with_per_talker(mut self, per_talker: bool) -> Self {
+        self.per_talker = per_talker;
+        self
+    }
+
+    // Also alert when an RMC/GNS fix strays more than `alert_meters` from the running mean
+    // position (0 disables). A deviation lasting `reset_after` becomes the new baseline.
+    pub fn with_position_alert(mut self, alert_meters: f64, reset_after: Duration) -> Self {
+        self.position = (alert_meters > 0.0).then(|| PositionWatch::new(alert_meters, reset_after));
+        self
+    }
+
+    // Feed raw serial bytes; matching NMEA sentences are retained as latest snapshot by type
+    // (or by talker and type with `with_per_talker`).
     pub fn ingest(&mut self, bytes: &[u8]) {
-        if self.interval.is_none() {
+        if self.interval.is_none() && self.position.is_none() {
             return;
         }
 
@@ -43,15 +65,26 @@ impl NmeaMonitor {
         self.collector.push_bytes(bytes, &mut sentences);
 
         for sentence in sentences {
-            let Some(message_id) = parse_message_id(&sentence) else {
+            let Some(address) = parse_sentence_address(&sentence) else {
                 continue;
             };
-            if !is_watched_message(&message_id) {
+            let message_id = message_type(address);
+            if !is_watched_message(message_id) {
                 continue;
             }
+            if let Some(watch) = self.position.as_mut()
+                && let Some((lat, lon)) = parse_fix_position(message_id, &sentence)
+            {
+                watch.observe(lat, lon, Instant::now());
+            }
 
-            self.latest.insert(message_id.clone(), sentence);
-            self.updated_since_emit.insert(message_id, true);
+            let key = if self.per_talker {
+                address.to_string()
+            } else {
+                message_id.to_string()
+            };
+            self.updated_since_emit.insert(key.clone(), true);
+            self.latest.insert(key, sentence);
         }
     }
 
@@ -64,62 +97,205 @@ impl NmeaMonitor {
             return;
         }
 
+        // Keys are the bare type, or talker + type in per-talker mode; either ends in the type.
         for message_id in WATCHED_MESSAGE_IDS {
-            let Some(sentence) = self.latest.get(message_id).cloned() else {
-                continue;
-            };
-            if !self
-                .updated_since_emit
-                .get(message_id)
-                .copied()
-                .unwrap_or(false)
-            {
-                continue;
+            let due: Vec<(String, String)> = self
+                .latest
+                .iter()
+                .filter(|(key, _)| {
+                    key.ends_with(message_id)
+                        && self.updated_since_emit.get(*key).copied().unwrap_or(false)
+                })
+                .map(|(key, sentence)| (key.clone(), sentence.clone()))
+                .collect();
+            for (key, sentence) in due {
+                self.emit_sentence_logs(&key, message_id, &sentence);
+                self.updated_since_emit.insert(key, false);
             }
-
-            self.emit_sentence_logs(message_id, &sentence);
-            self.updated_since_emit
-                .insert(message_id.to_string(), false);
         }
 
         self.last_emit = Instant::now();
     }
 
-    fn emit_sentence_logs(&self, message_id: &str, sentence: &str) {
+    // `label` is the map key shown in the tag (`GSV`, or `GPGSV` per talker).
+    fn emit_sentence_logs(&self, label: &str, message_id: &str, sentence: &str) {
         match self.format {
             NmeaLogFormat::Raw => {
-                eprintln!("[NMEA:{}:RAW] {}", message_id, sentence);
+                emit_event(
+                    EventKind::Nmea,
+                    &format!("[NMEA:{}:RAW] {}", label, sentence),
+                );
             }
             NmeaLogFormat::Plain => {
                 let plain = summarize_nmea_plain(message_id, sentence)
                     .unwrap_or_else(|| "unable to parse sentence".to_string());
-                eprintln!("[NMEA:{}:PLAIN] {}", message_id, plain);
+                emit_event(
+                    EventKind::Nmea,
+                    &format!("[NMEA:{}:PLAIN] {}", label, plain),
+                );
             }
             NmeaLogFormat::Both => {
-                eprintln!("[NMEA:{}:RAW] {}", message_id, sentence);
+                emit_event(
+                    EventKind::Nmea,
+                    &format!("[NMEA:{}:RAW] {}", label, sentence),
+                );
                 let plain = summarize_nmea_plain(message_id, sentence)
                     .unwrap_or_else(|| "unable to parse sentence".to_string());
-                eprintln!("[NMEA:{}:PLAIN] {}", message_id, plain);
+                emit_event(
+                    EventKind::Nmea,
+                    &format!("[NMEA:{}:PLAIN] {}", label, plain),
+                );
+            }
+        }
+    }
+}
+
+// Running-mean reference position behind `--position-alert-meters`.
+// Fixes within the threshold refine the mean; fixes outside it raise one alert per excursion
+// and are kept out of the mean until the receiver settles back or the excursion outlasts
+// `reset_after`, at which point the current fix becomes the new baseline.
+struct PositionWatch {
+    alert_meters: f64,
+    reset_after: Duration,
+    mean: Option<(f64, f64)>,
+    fixes: u64,
+    deviating_since: Option<Instant>,
+}
+
+impl PositionWatch {
+    fn new(alert_meters: f64, reset_after: Duration) -> Self {
+        Self {
+            alert_meters,
+            reset_after,
+            mean: None,
+            fixes: 0,
+            deviating_since: None,
+        }
+    }
+
+    fn observe(&mut self, lat: f64, lon: f64, now: Instant) {
+        let Some((mean_lat, mean_lon)) = self.mean else {
+            self.reset_baseline(lat, lon);
+            return;
+        };
+        let distance = equirectangular_distance_m(mean_lat, mean_lon, lat, lon);
+        if distance <= self.alert_meters {
+            if self.deviating_since.take().is_some() {
+                emit_event(
+                    EventKind::Position,
+                    &format!(
+                        "[POSITION] fix back within {:.1} m of the mean position ({:.1} m)",
+                        self.alert_meters, distance
+                    ),
+                );
+            }
+            self.fixes += 1;
+            let weight = 1.0 / self.fixes as f64;
+            self.mean = Some((
+                mean_lat + (lat - mean_lat) * weight,
+                mean_lon + (lon - mean_lon) * weight,
+            ));
+            return;
+        }
+
+        match self.deviating_since {
+            None => {
+                self.deviating_since = Some(now);
+                emit_event(
+                    EventKind::Position,
+                    &format!(
+                        "[POSITION] WARNING: fix lat={lat:.7} lon={lon:.7} is {distance:.1} m from the mean position lat={mean_lat:.7} lon={mean_lon:.7} (alert threshold {:.1} m over {} fixes)",
+                        self.alert_meters, self.fixes
+                    ),
+                );
             }
+            Some(since) if now.duration_since(since) >= self.reset_after => {
+                emit_event(
+                    EventKind::Position,
+                    &format!(
+                        "[POSITION] deviation persisted for {}s; new baseline lat={lat:.7} lon={lon:.7}",
+                        self.reset_after.as_secs()
+                    ),
+                );
+                self.reset_baseline(lat, lon);
+            }
+            Some(_) => {}
         }
     }
+
+    fn reset_baseline(&mut self, lat: f64, lon: f64) {
+        self.mean = Some((lat, lon));
+        self.fixes = 1;
+        self.deviating_since = None;
+    }
+}
+
+// Position of a checksum-valid RMC (status A) or GNS (any mode but N) fix in decimal degrees.
+fn parse_fix_position(message_id: &str, sentence: &str) -> Option<(f64, f64)> {
+    if !has_valid_checksum(sentence) {
+        return None;
+    }
+    let fields = parse_nmea_fields(sentence)?;
+    let (lat_idx, valid) = match message_id {
+        "RMC" => (3, field(&fields, 2) == "A"),
+        "GNS" => (2, field(&fields, 6).chars().any(|mode| mode != 'N')),
+        _ => return None,
+    };
+    if !valid {
+        return None;
+    }
+    let lat = parse_lat(field(&fields, lat_idx), field(&fields, lat_idx + 1))?;
+    let lon = parse_lon(field(&fields, lat_idx + 2), field(&fields, lat_idx + 3))?;
+    Some((lat, lon))
+}
+
+// UTC date and time carried by a checksum-valid RMC (status `A` only) or ZDA sentence.
+pub(crate) fn parse_nmea_utc(sentence: &str) -> Option<DateTime<Utc>> {
+    if !has_valid_checksum(sentence) {
+        return None;
+    }
+    let message_id = message_type(parse_sentence_address(sentence)?);
+    let fields = parse_nmea_fields(sentence)?;
+    let date = match message_id {
+        // $xxRMC,hhmmss.ss,A,lat,N,lon,E,spd,cog,ddmmyy,...
+        "RMC" if field(&fields, 2) == "A" => {
+            NaiveDate::parse_from_str(field(&fields, 9), "%d%m%y").ok()?
+        }
+        // $xxZDA,hhmmss.ss,dd,mm,yyyy,zh,zm
+        "ZDA" => NaiveDate::from_ymd_opt(
+            field(&fields, 4).parse().ok()?,
+            field(&fields, 3).parse().ok()?,
+            field(&fields, 2).parse().ok()?,
+        )?,
+        _ => return None,
+    };
+    let time = NaiveTime::parse_from_str(field(&fields, 1), "%H%M%S%.f").ok()?;
+    Some(date.and_time(time).and_utc())
+}
+
+// Equirectangular approximation; plenty for deviations of metres to kilometres.
+fn equirectangular_distance_m(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let mean_lat = ((lat1 + lat2) / 2.0).to_radians();
+    let x = (lon2 - lon1).to_radians() * mean_lat.cos();
+    let y = (lat2 - lat1).to_radians();
+    (x * x + y * y).sqrt() * EARTH_RADIUS_M
 }
 
 // Extract complete NMEA sentences from arbitrary serial bytes.
-struct NmeaSentenceCollector {
+pub(crate) struct NmeaSentenceCollector {
     capturing: bool,
     buf: Vec<u8>,
 }
 
 impl NmeaSentenceCollector {
-    fn new() -> Self {
+    pub(crate) fn new() -> Self {
         Self {
             capturing: false,
             buf: Vec::with_capacity(MAX_SENTENCE_LEN),
         }
     }
 
-    fn push_bytes(&mut self, bytes: &[u8], out: &mut Vec<String>) {
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8], out: &mut Vec<String>) {
         for &byte in bytes {
             if !self.capturing {
                 if byte == b'$' {
@@ -301,6 +477,20 @@ fn summarize_gst(fields: &[&str]) -> Option<String> {
     ))
 }
 
+// True when the sentence carries a `*hh` checksum matching the XOR of the bytes after `$`.
+pub(crate) fn has_valid_checksum(sentence: &str) -> bool {
+    let Some((body, tail)) = sentence
+        .strip_prefix('$')
+        .and_then(|rest| rest.split_once('*'))
+    else {
+        return false;
+    };
+    let Ok(expected) = u8::from_str_radix(tail.get(..2).unwrap_or_default(), 16) else {
+        return false;
+    };
+    body.bytes().fold(0, |acc, byte| acc ^ byte) == expected
+}
+
 fn parse_nmea_fields(sentence: &str) -> Option<Vec<&str>> {
     let core = sentence
         .strip_prefix('$')?
@@ -366,19 +556,212 @@ fn is_allowed_nmea_byte(byte: u8) -> bool {
     byte == b'\r' || (0x20..=0x7E).contains(&byte)
 }
 
-fn parse_message_id(sentence: &str) -> Option<String> {
+// Address field of a sentence: talker plus message type, e.g. `GPGSV`.
+fn parse_sentence_address(sentence: &str) -> Option<&str> {
     let core = sentence
         .strip_prefix('$')?
         .split('*')
         .next()
         .unwrap_or_default();
     let talker_and_id = core.split(',').next().unwrap_or_default();
-    if talker_and_id.len() < 3 {
+    if talker_and_id.len() < 3 || !talker_and_id.is_ascii() {
         return None;
     }
-    Some(talker_and_id[talker_and_id.len() - 3..].to_string())
+    Some(talker_and_id)
+}
+
+// Message type (last three characters) of an address field.
+fn message_type(address: &str) -> &str {
+    &address[address.len() - 3..]
 }
 
 fn is_watched_message(message_id: &str) -> bool {
     WATCHED_MESSAGE_IDS.contains(&message_id)
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // `$<body>*hh` with the checksum filled in.
+    pub(crate) fn nmea_sentence(body: &str) -> String {
+        let checksum = body.bytes().fold(0, |acc, byte| acc ^ byte);
+        format!("${body}*{checksum:02X}")
+    }
+
+    #[test]
+    fn utc_comes_from_valid_rmc_and_zda() {
+        let rmc =
+            nmea_sentence("GPRMC,123519.00,A,4807.038,N,01131.000,E,022.4,084.4,150326,003.1,W,A");
+        assert_eq!(
+            parse_nmea_utc(&rmc),
+            Some(Utc.with_ymd_and_hms(2026, 3, 15, 12, 35, 19).unwrap())
+        );
+
+        let zda = nmea_sentence("GNZDA,201530.50,04,07,2026,00,00");
+        assert_eq!(
+            parse_nmea_utc(&zda),
+            Some(
+                Utc.with_ymd_and_hms(2026, 7, 4, 20, 15, 30).unwrap()
+                    + chrono::Duration::milliseconds(500)
+            )
+        );
+    }
+
+    #[test]
+    fn utc_ignores_void_rmc_and_bad_checksums() {
+        let void = nmea_sentence("GPRMC,123519.00,V,,,,,,,150326,,,N");
+        assert_eq!(parse_nmea_utc(&void), None);
+
+        let rmc =
+            nmea_sentence("GPRMC,123519.00,A,4807.038,N,01131.000,E,022.4,084.4,150326,003.1,W,A");
+        let corrupted = rmc.replace("123519", "123518");
+        assert!(!has_valid_checksum(&corrupted));
+        assert_eq!(parse_nmea_utc(&corrupted), None);
+
+        let gga = nmea_sentence("GPGGA,123519.00,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,");
+        assert_eq!(parse_nmea_utc(&gga), None);
+    }
+
+    fn rmc(lat: &str, status: &str) -> String {
+        nmea_sentence(&format!(
+            "GPRMC,123519.00,{status},{lat},N,01131.000,E,000.0,000.0,150326,,,A"
+        ))
+    }
+
+    fn position_monitor() -> NmeaMonitor {
+        NmeaMonitor::new(0, NmeaLogFormat::Plain)
+            .with_position_alert(10.0, Duration::from_secs(3600))
+    }
+
+    fn deviating(monitor: &NmeaMonitor) -> bool {
+        monitor.position.as_ref().unwrap().deviating_since.is_some()
+    }
+
+    #[test]
+    fn fix_position_comes_from_valid_rmc_and_gns() {
+        let (lat, lon) = parse_fix_position("RMC", &rmc("4807.038", "A")).unwrap();
+        assert!((lat - 48.1173).abs() < 1e-9);
+        assert!((lon - (11.0 + 31.0 / 60.0)).abs() < 1e-9);
+
+        let gns = nmea_sentence("GNGNS,123519.00,3342.600,S,15112.300,W,AN,12,0.8,45.0,,,");
+        let (lat, lon) = parse_fix_position("GNS", &gns).unwrap();
+        assert!((lat + 33.71).abs() < 1e-9);
+        assert!((lon + 151.205).abs() < 1e-9);
+    }
+
+    #[test]
+    fn fix_position_ignores_void_fixes_bad_checksums_and_gga() {
+        assert_eq!(parse_fix_position("RMC", &rmc("4807.038", "V")), None);
+
+        let no_fix = nmea_sentence("GNGNS,123519.00,3342.600,S,15112.300,W,NN,00,,,,,");
+        assert_eq!(parse_fix_position("GNS", &no_fix), None);
+
+        let corrupted = rmc("4807.038", "A").replace("4807.038", "4907.038");
+        assert!(!has_valid_checksum(&corrupted));
+        assert_eq!(parse_fix_position("RMC", &corrupted), None);
+
+        let gga = nmea_sentence("GPGGA,123519.00,4807.038,N,01131.000,E,1,08,0.9,545.4,M,46.9,M,,");
+        assert_eq!(parse_fix_position("GGA", &gga), None);
+    }
+
+    #[test]
+    fn distance_approximation_matches_an_arc_minute_of_latitude() {
+        let one_minute = equirectangular_distance_m(48.0, 11.0, 48.0 + 1.0 / 60.0, 11.0);
+        assert!((one_minute - 1853.2).abs() < 1.0, "{one_minute}");
+        assert_eq!(equirectangular_distance_m(48.0, 11.0, 48.0, 11.0), 0.0);
+    }
+
+    #[test]
+    fn far_off_fix_raises_a_deviation_until_it_returns() {
+        let mut monitor = position_monitor();
+        for lat in ["4807.0380", "4807.0381", "4807.0379"] {
+            monitor.ingest(format!("{}\r\n", rmc(lat, "A")).as_bytes());
+        }
+        assert!(!deviating(&monitor));
+        assert_eq!(monitor.position.as_ref().unwrap().fixes, 3);
+
+        // One arc minute north, roughly 1.85 km away.
+        monitor.ingest(format!("{}\r\n", rmc("4808.038", "A")).as_bytes());
+        assert!(deviating(&monitor));
+        assert_eq!(monitor.position.as_ref().unwrap().fixes, 3);
+
+        monitor.ingest(format!("{}\r\n", rmc("4807.038", "A")).as_bytes());
+        assert!(!deviating(&monitor));
+        assert_eq!(monitor.position.as_ref().unwrap().fixes, 4);
+    }
+
+    #[test]
+    fn corrupted_far_off_fix_is_not_a_deviation() {
+        let mut monitor = position_monitor();
+        monitor.ingest(format!("{}\r\n", rmc("4807.038", "A")).as_bytes());
+        let corrupted = rmc("4807.038", "A").replace("4807.038", "4808.038");
+        monitor.ingest(format!("{corrupted}\r\n").as_bytes());
+        assert!(!deviating(&monitor));
+        assert_eq!(monitor.position.as_ref().unwrap().fixes, 1);
+    }
+
+    #[test]
+    fn persistent_deviation_becomes_the_new_baseline() {
+        let mut watch = PositionWatch::new(10.0, Duration::from_secs(60));
+        let start = Instant::now();
+        watch.observe(48.0, 11.0, start);
+        watch.observe(48.1, 11.0, start + Duration::from_secs(1));
+        assert_eq!(watch.deviating_since, Some(start + Duration::from_secs(1)));
+        assert_eq!(watch.mean, Some((48.0, 11.0)));
+
+        watch.observe(48.1, 11.0, start + Duration::from_secs(61));
+        assert_eq!(watch.deviating_since, None);
+        assert_eq!(watch.mean, Some((48.1, 11.0)));
+        assert_eq!(watch.fixes, 1);
+    }
+
+    #[test]
+    fn per_talker_keeps_each_constellations_gsv() {
+        let gsv = format!(
+            "{}\r\n{}\r\n",
+            nmea_sentence("GPGSV,3,1,11,01,40,083,46,02,17,308,41,12,07,344,39,14,22,228,45"),
+            nmea_sentence("GLGSV,2,1,08,65,45,120,40,66,30,200,38,72,12,310,35,81,60,045,44")
+        );
+
+        let mut collapsed = NmeaMonitor::new(1, NmeaLogFormat::Plain);
+        collapsed.ingest(gsv.as_bytes());
+        assert_eq!(collapsed.latest.keys().collect::<Vec<_>>(), ["GSV"]);
+        assert!(collapsed.latest["GSV"].starts_with("$GLGSV"));
+
+        let mut per_talker = NmeaMonitor::new(1, NmeaLogFormat::Plain).with_per_talker(true);
+        per_talker.ingest(gsv.as_bytes());
+        assert_eq!(
+            per_talker.latest.keys().collect::<Vec<_>>(),
+            ["GLGSV", "GPGSV"]
+        );
+        assert!(
+            per_talker
+                .updated_since_emit
+                .values()
+                .all(|&updated| updated)
+        );
+    }
+
+    #[test]
+    fn plain_summaries_report_fix_status_and_quality() {
+        let rmc = rmc("4807.038", "A");
+        assert_eq!(
+            summarize_nmea_plain("RMC", &rmc).unwrap(),
+            "status=valid time=123519.00 date=150326 lat=48.117300 lon=11.516667 speed=0.00 kn/0.00 kmh course_deg=000.0"
+        );
+
+        let gsa = nmea_sentence("GNGSA,A,3,01,02,12,14,,,,,,,,,1.8,1.0,1.5");
+        assert_eq!(
+            summarize_nmea_plain("GSA", &gsa).unwrap(),
+            "mode=automatic fix=3D sats_used=4 pdop=1.8 hdop=1.0 vdop=1.5"
+        );
+
+        let gns = nmea_sentence("GNGNS,123519.00,3342.600,S,15112.300,W,AN,12,0.8,45.0,,,");
+        assert_eq!(
+            summarize_nmea_plain("GNS", &gns).unwrap(),
+            "time=123519.00 mode=AN sats_used=12 hdop=0.8 lat=-33.710000 lon=-151.205000 alt_m=45.0"
+        );
+    }
+}
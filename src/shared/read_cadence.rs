@@ -0,0 +1,171 @@
+use crate::shared::event::{EventKind, emit_event};
+use std::time::{Duration, Instant};
+
+// Largest read buffer the cadence monitor grows to.
+const MAX_READ_BUFFER_BYTES: usize = 1 << 20;
+// Late reads after the first warning are summarized at most this often.
+const WARNING_INTERVAL: Duration = Duration::from_secs(60);
+
+// Watches the gap between one input read returning and the next one starting (time spent
+// queueing chunks, resending config, or simply not scheduled). A long gap means the kernel's
+// serial/USB buffer had to hold the stream meanwhile and may have overflowed; the serialport
+// backend does not report overruns, so the gap is the only signal. Late reads warn and grow
+// the read buffer so each read drains more of the backlog.
+pub struct ReadCadence {
+    threshold: Option<Duration>,
+    last_read_end: Option<Instant>,
+    late_reads: u64,
+    unreported: u64,
+    worst_gap: Duration,
+    last_warning: Option<Instant>,
+}
+
+impl ReadCadence {
+    // A zero threshold disables monitoring.
+    pub fn new(threshold_ms: u64) -> Self {
+        Self {
+            threshold: (threshold_ms > 0).then(|| Duration::from_millis(threshold_ms)),
+            last_read_end: None,
+            late_reads: 0,
+            unreported: 0,
+            worst_gap: Duration::ZERO,
+            last_warning: None,
+        }
+    }
+
+    // Call right before each read with the read buffer; grows it (and warns) after a late read.
+    pub fn before_read(&mut self, now: Instant, buffer: &mut Vec<u8>, source_label: &str) {
+        let (Some(threshold), Some(last_end)) = (self.threshold, self.last_read_end) else {
+            return;
+        };
+        let gap = now.saturating_duration_since(last_end);
+        if gap < threshold {
+            return;
+        }
+
+        self.late_reads += 1;
+        self.unreported += 1;
+        self.worst_gap = self.worst_gap.max(gap);
+        let grown = buffer.len() < MAX_READ_BUFFER_BYTES;
+        if grown {
+            buffer.resize((buffer.len() * 2).min(MAX_READ_BUFFER_BYTES), 0);
+        }
+        if self
+            .last_warning
+            .is_some_and(|at| now.saturating_duration_since(at) < WARNING_INTERVAL)
+            && !grown
+        {
+            return;
+        }
+
+        let buffer_note = if grown {
+            format!("; read buffer now {} B", buffer.len())
+        } else {
+            String::new()
+        };
+        emit_event(
+            EventKind::Warning,
+            &format!(
+                "Input {} was not read for {} ms (threshold {} ms; {} late read(s), {} total, worst {} ms){}; the receiver buffer may have overflowed",
+                source_label,
+                gap.as_millis(),
+                threshold.as_millis(),
+                self.unreported,
+                self.late_reads,
+                self.worst_gap.as_millis(),
+                buffer_note
+            ),
+        );
+        self.unreported = 0;
+        self.last_warning = Some(now);
+    }
+
+    // Call right after each read returns, whatever its result.
+    pub fn after_read(&mut self, now: Instant) {
+        self.last_read_end = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABEL: &str = "/dev/ttyACM0";
+
+    // One instant read cycle at `at`, measured against where the previous one ended.
+    fn late_read(cadence: &mut ReadCadence, at: Instant, buffer: &mut Vec<u8>) {
+        cadence.before_read(at, buffer, LABEL);
+        cadence.after_read(at);
+    }
+
+    #[test]
+    fn gaps_below_the_threshold_leave_the_buffer_alone() {
+        let mut cadence = ReadCadence::new(100);
+        let mut buffer = vec![0_u8; 4_096];
+        let start = Instant::now();
+        // The first read has nothing to compare against.
+        cadence.before_read(start, &mut buffer, LABEL);
+        cadence.after_read(start);
+        cadence.before_read(start + Duration::from_millis(99), &mut buffer, LABEL);
+        assert_eq!(buffer.len(), 4_096);
+        assert_eq!(cadence.late_reads, 0);
+    }
+
+    #[test]
+    fn late_reads_double_the_buffer_up_to_the_cap() {
+        let mut cadence = ReadCadence::new(100);
+        let mut buffer = vec![0_u8; MAX_READ_BUFFER_BYTES / 4];
+        let mut at = Instant::now();
+        cadence.after_read(at);
+        for expected in [
+            MAX_READ_BUFFER_BYTES / 2,
+            MAX_READ_BUFFER_BYTES,
+            MAX_READ_BUFFER_BYTES,
+        ] {
+            at += Duration::from_millis(100);
+            late_read(&mut cadence, at, &mut buffer);
+            assert_eq!(buffer.len(), expected);
+        }
+        assert_eq!(cadence.late_reads, 3);
+        assert_eq!(cadence.worst_gap, Duration::from_millis(100));
+    }
+
+    #[test]
+    fn warnings_at_the_cap_are_summarized_once_a_minute() {
+        let mut cadence = ReadCadence::new(100);
+        let mut buffer = vec![0_u8; MAX_READ_BUFFER_BYTES];
+        let start = Instant::now();
+        cadence.after_read(start);
+
+        late_read(
+            &mut cadence,
+            start + Duration::from_millis(200),
+            &mut buffer,
+        );
+        assert_eq!(cadence.unreported, 0);
+        // Within the minute the late reads are only counted.
+        late_read(&mut cadence, start + Duration::from_secs(10), &mut buffer);
+        late_read(&mut cadence, start + Duration::from_secs(20), &mut buffer);
+        assert_eq!(cadence.unreported, 2);
+        assert_eq!(cadence.worst_gap, Duration::from_secs(10));
+        // A minute after the last warning they are reported together.
+        late_read(
+            &mut cadence,
+            start + Duration::from_millis(60_200),
+            &mut buffer,
+        );
+        assert_eq!(cadence.unreported, 0);
+        assert_eq!(cadence.late_reads, 4);
+    }
+
+    #[test]
+    fn zero_threshold_disables_monitoring() {
+        let mut cadence = ReadCadence::new(0);
+        let mut buffer = vec![0_u8; 1_024];
+        let start = Instant::now();
+        cadence.after_read(start);
+        cadence.before_read(start + Duration::from_secs(3_600), &mut buffer, LABEL);
+        assert_eq!(buffer.len(), 1_024);
+        assert_eq!(cadence.late_reads, 0);
+    }
+}
@@ -0,0 +1,466 @@
+use anyhow::{Context, Result, bail};
+use chrono::{Duration as ChronoDuration, NaiveDate, NaiveDateTime};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const END_OF_HEADER: &str = "END OF HEADER";
+const HEADER_LABEL_COLUMN: usize = 60;
+
+// Span with no observation epochs: `start` is the last epoch (or window start) before the gap,
+// `end` the first epoch (or window end) after it.
+#[derive(Clone, Copy, Debug)]
+pub struct EpochGap {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+}
+
+impl EpochGap {
+    pub fn duration(&self) -> ChronoDuration {
+        self.end - self.start
+    }
+}
+
+// Collect observation epoch timestamps from a RINEX 3 (or 2.11) OBS file.
+// Only epoch records with flag 0 (OK) or 1 (power failure) count; event records are skipped.
+pub fn scan_obs_epochs(path: &Path) -> Result<Vec<NaiveDateTime>> {
+    let file = File::open(path)
+        .with_context(|| format!("opening observation RINEX failed: {}", path.display()))?;
+    let mut epochs = Vec::new();
+    let mut in_header = true;
+    for line in BufReader::new(file).split(b'\n') {
+        let line =
+            line.with_context(|| format!("reading observation RINEX failed: {}", path.display()))?;
+        let line = String::from_utf8_lossy(&line);
+        if in_header {
+            if header_label(&line) == END_OF_HEADER {
+                in_header = false;
+            }
+            continue;
+        }
+        if let Some(epoch) = parse_epoch_record(&line).or_else(|| parse_rinex2_epoch_record(&line))
+        {
+            epochs.push(epoch);
+        }
+    }
+    Ok(epochs)
+}
+
+// `--qc` figures for one hour's observation product(s).
+// `observations_expected` counts one slot per satellite, epoch, and header observation type
+// of its system; `observations_present` the slots with a value.
+#[derive(Clone, Debug, Default)]
+pub struct ObsQc {
+    pub epochs: BTreeSet<NaiveDateTime>,
+    pub satellites: BTreeMap<char, BTreeSet<String>>,
+    pub observations_expected: u64,
+    pub observations_present: u64,
+}
+
+impl ObsQc {
+    // Fold in another product of the same hour (per-constellation output).
+    pub fn merge(&mut self, other: ObsQc) {
+        self.epochs.extend(other.epochs);
+        for (system, satellites) in other.satellites {
+            self.satellites
+                .entry(system)
+                .or_default()
+                .extend(satellites);
+        }
+        self.observations_expected += other.observations_expected;
+        self.observations_present += other.observations_present;
+    }
+
+    pub fn observation_completeness_percent(&self) -> f64 {
+        if self.observations_expected == 0 {
+            return 0.0;
+        }
+        self.observations_present as f64 * 100.0 / self.observations_expected as f64
+    }
+
+    fn record(&mut self, satellite: &str, expected: usize, present: usize) {
+        // RINEX 2 may leave the system blank for GPS and pad the PRN with a space.
+        let mut chars = satellite.chars();
+        let system = chars.next().filter(|c| !c.is_whitespace()).unwrap_or('G');
+        let prn = chars.as_str().trim();
+        if !prn.is_empty() {
+            self.satellites
+                .entry(system)
+                .or_default()
+                .insert(format!("{system}{prn:0>2}"));
+        }
+        self.observations_expected += expected as u64;
+        self.observations_present += present as u64;
+    }
+}
+
+// Scan a RINEX 3 (or 2.11) OBS file for `--qc`: epochs, satellites per system, and how many
+// of the header's observation types carry a value. Event records (flag > 1) are skipped.
+pub fn summarize_obs(path: &Path) -> Result<ObsQc> {
+    let file = File::open(path)
+        .with_context(|| format!("opening observation RINEX failed: {}", path.display()))?;
+    let mut lines = BufReader::new(file).split(b'\n').map(|line| {
+        line.map(|bytes| {
+            String::from_utf8_lossy(&bytes)
+                .trim_end_matches('\r')
+                .to_string()
+        })
+    });
+    let read_failed = || format!("reading observation RINEX failed: {}", path.display());
+
+    let mut rinex2 = false;
+    let mut v2_types = 0_usize;
+    let mut v3_types: BTreeMap<char, usize> = BTreeMap::new();
+    for line in lines.by_ref() {
+        let line = line.with_context(read_failed)?;
+        match header_label(&line) {
+            "RINEX VERSION / TYPE" => rinex2 = line.trim_start().starts_with('2'),
+            "SYS / # / OBS TYPES" => {
+                // Continuation records leave the system and count blank.
+                if let Some(system) = line.chars().next().filter(char::is_ascii_alphabetic)
+                    && let Some(count) = line.get(3..6).and_then(|v| v.trim().parse().ok())
+                {
+                    v3_types.insert(system, count);
+                }
+            }
+            "# / TYPES OF OBSERV" => {
+                if let Some(count) = line.get(0..6).and_then(|v| v.trim().parse().ok()) {
+                    v2_types = count;
+                }
+            }
+            END_OF_HEADER => break,
+            _ => {}
+        }
+    }
+
+    let mut qc = ObsQc::default();
+    while let Some(line) = lines.next() {
+        let line = line.with_context(read_failed)?;
+        let (epoch, flag, count) = if rinex2 {
+            if line.as_bytes().get(18) != Some(&b'.') {
+                continue;
+            }
+            let field = |range: std::ops::Range<usize>| line.get(range).map(str::trim);
+            let (Some(flag), Some(count)) = (
+                field(28..29).and_then(|v| v.parse::<u8>().ok()),
+                field(29..32).and_then(|v| v.parse::<usize>().ok()),
+            ) else {
+                continue;
+            };
+            (parse_rinex2_epoch_record(&line), flag, count)
+        } else {
+            let Some(rest) = line.strip_prefix('>') else {
+                continue;
+            };
+            let mut fields = rest.split_whitespace().skip(6);
+            let (Some(flag), Some(count)) = (
+                fields.next().and_then(|v| v.parse::<u8>().ok()),
+                fields.next().and_then(|v| v.parse::<usize>().ok()),
+            ) else {
+                continue;
+            };
+            (parse_epoch_record(&line), flag, count)
+        };
+        if flag > 1 {
+            // Event records: `count` header-style lines follow.
+            for skipped in lines.by_ref().take(count) {
+                skipped.with_context(read_failed)?;
+            }
+            continue;
+        }
+        if let Some(epoch) = epoch {
+            qc.epochs.insert(epoch);
+        }
+
+        if rinex2 {
+            // Up to 12 satellite IDs per line from column 33, continued on following lines.
+            let mut satellites = Vec::with_capacity(count);
+            let mut id_line = line;
+            loop {
+                for slot in 0..12 {
+                    if satellites.len() == count {
+                        break;
+                    }
+                    let start = 32 + 3 * slot;
+                    satellites.push(id_line.get(start..start + 3).unwrap_or("").to_string());
+                }
+                if satellites.len() == count {
+                    break;
+                }
+                match lines.next() {
+                    Some(next) => id_line = next.with_context(read_failed)?,
+                    None => break,
+                }
+            }
+            // Five 16-column fields per line; each satellite starts a new line.
+            let lines_per_satellite = v2_types.div_ceil(5);
+            for satellite in satellites {
+                let mut present = 0;
+                for index in 0..lines_per_satellite {
+                    let Some(data) = lines.next() else {
+                        break;
+                    };
+                    let data = data.with_context(read_failed)?;
+                    let fields = (v2_types - index * 5).min(5);
+                    present += count_present_fields(&data, fields);
+                }
+                qc.record(&satellite, v2_types, present);
+            }
+        } else {
+            for data in lines.by_ref().take(count) {
+                let data = data.with_context(read_failed)?;
+                let satellite = data.get(0..3).unwrap_or("").to_string();
+                let types = satellite
+                    .chars()
+                    .next()
+                    .and_then(|system| v3_types.get(&system).copied())
+                    .unwrap_or(0);
+                let present = count_present_fields(data.get(3..).unwrap_or(""), types);
+                qc.record(&satellite, types, present);
+            }
+        }
+    }
+    Ok(qc)
+}
+
+// Observation fields are F14.3 plus LLI and signal-strength columns; blank means missing.
+fn count_present_fields(data: &str, fields: usize) -> usize {
+    (0..fields)
+        .filter(|index| {
+            let start = index * 16;
+            data.get(start..(start + 14).min(data.len()))
+                .is_some_and(|value| !value.trim().is_empty())
+        })
+        .count()
+}
+
+// Find spacing above `threshold` between consecutive epochs and at the edges of the expected window.
+pub fn find_epoch_gaps(
+    epochs: &[NaiveDateTime],
+    window_start: NaiveDateTime,
+    window_end: NaiveDateTime,
+    threshold: ChronoDuration,
+) -> Vec<EpochGap> {
+    let mut gaps = Vec::new();
+    let (Some(&first), Some(&last)) = (epochs.first(), epochs.last()) else {
+        gaps.push(EpochGap {
+            start: window_start,
+            end: window_end,
+        });
+        return gaps;
+    };
+
+    if first - window_start > threshold {
+        gaps.push(EpochGap {
+            start: window_start,
+            end: first,
+        });
+    }
+    for pair in epochs.windows(2) {
+        if pair[1] - pair[0] > threshold {
+            gaps.push(EpochGap {
+                start: pair[0],
+                end: pair[1],
+            });
+        }
+    }
+    if window_end - last > threshold {
+        gaps.push(EpochGap {
+            start: last,
+            end: window_end,
+        });
+    }
+    gaps
+}
+
+// Insert COMMENT records immediately before END OF HEADER, rewriting the file in place.
+pub fn insert_header_comments(path: &Path, comments: &[String]) -> Result<()> {
+    if comments.is_empty() {
+        return Ok(());
+    }
+
+    let tmp_path = path.with_extension("rnx.tmp");
+    let input = File::open(path)
+        .with_context(|| format!("opening observation RINEX failed: {}", path.display()))?;
+    let output = File::create(&tmp_path)
+        .with_context(|| format!("creating temporary RINEX failed: {}", tmp_path.display()))?;
+    let mut writer = BufWriter::new(output);
+    let mut reader = BufReader::new(input);
+    let mut inserted = false;
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = reader
+            .read_until(b'\n', &mut line)
+            .with_context(|| format!("reading observation RINEX failed: {}", path.display()))?;
+        if read == 0 {
+            break;
+        }
+        if !inserted && header_label(&String::from_utf8_lossy(&line)) == END_OF_HEADER {
+            for comment in comments {
+                writeln!(writer, "{}", comment_record(comment))
+                    .with_context(|| format!("writing {}", tmp_path.display()))?;
+            }
+            inserted = true;
+        }
+        writer
+            .write_all(&line)
+            .with_context(|| format!("writing {}", tmp_path.display()))?;
+    }
+    writer
+        .flush()
+        .with_context(|| format!("flushing {}", tmp_path.display()))?;
+    drop(writer);
+
+    if !inserted {
+        let _ = fs::remove_file(&tmp_path);
+        bail!("no END OF HEADER record found in {}", path.display());
+    }
+    fs::rename(&tmp_path, path).with_context(|| {
+        format!(
+            "replacing observation RINEX failed: {} -> {}",
+            tmp_path.display(),
+            path.display()
+        )
+    })
+}
+
+// Header label occupies columns 61-80 of a RINEX header line.
+fn header_label(line: &str) -> &str {
+    line.get(HEADER_LABEL_COLUMN..).unwrap_or("").trim()
+}
+
+// Format free text as a RINEX COMMENT header record (60 columns of text + label).
+fn comment_record(text: &str) -> String {
+    let text: String = text.chars().take(HEADER_LABEL_COLUMN).collect();
+    format!("{text:<60}COMMENT")
+}
+
+// Parse `> YYYY MM DD HH MM SS.SSSSSSS  F NN` epoch records.
+fn parse_epoch_record(line: &str) -> Option<NaiveDateTime> {
+    let rest = line.strip_prefix('>')?;
+    let mut fields = rest.split_whitespace();
+    let year: i32 = fields.next()?.parse().ok()?;
+    let month: u32 = fields.next()?.parse().ok()?;
+    let day: u32 = fields.next()?.parse().ok()?;
+    let hour: u32 = fields.next()?.parse().ok()?;
+    let minute: u32 = fields.next()?.parse().ok()?;
+    let seconds: f64 = fields.next()?.parse().ok()?;
+    let flag: u8 = fields.next()?.parse().ok()?;
+    if flag > 1 || !(0.0..61.0).contains(&seconds) {
+        return None;
+    }
+
+    epoch_time(year, month, day, hour, minute, seconds)
+}
+
+// Parse RINEX 2 ` YY MM DD HH MM SS.SSSSSSS  F NN...` epoch records (fixed columns). The
+// seconds decimal point at column 19 tells them apart from observation data lines.
+fn parse_rinex2_epoch_record(line: &str) -> Option<NaiveDateTime> {
+    if line.as_bytes().get(18) != Some(&b'.') {
+        return None;
+    }
+    let field = |range: std::ops::Range<usize>| line.get(range).map(str::trim);
+    let yy: i32 = field(1..3)?.parse().ok()?;
+    let month: u32 = field(4..6)?.parse().ok()?;
+    let day: u32 = field(7..9)?.parse().ok()?;
+    let hour: u32 = field(10..12)?.parse().ok()?;
+    let minute: u32 = field(13..15)?.parse().ok()?;
+    let seconds: f64 = field(15..26)?.parse().ok()?;
+    let flag: u8 = field(28..29)?.parse().ok()?;
+    if flag > 1 || !(0.0..61.0).contains(&seconds) {
+        return None;
+    }
+    // RINEX 2 two-digit years: 80-99 are 19xx, 00-79 are 20xx.
+    let year = if yy >= 80 { 1900 + yy } else { 2000 + yy };
+    epoch_time(year, month, day, hour, minute, seconds)
+}
+
+fn epoch_time(
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    seconds: f64,
+) -> Option<NaiveDateTime> {
+    let whole = seconds.trunc() as u32;
+    let micros = ((seconds - seconds.trunc()) * 1_000_000.0).round() as u32;
+    NaiveDate::from_ymd_opt(year, month, day)?
+        .and_hms_opt(hour, minute, 0)
+        .map(|t| t + ChronoDuration::seconds(i64::from(whole)))
+        .map(|t| t + ChronoDuration::microseconds(i64::from(micros)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_obs(name: &str, lines: &[String]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "gnss2tec-rinex-obs-{name}-{}.rnx",
+            std::process::id()
+        ));
+        fs::write(&path, lines.join("\n") + "\n").unwrap();
+        path
+    }
+
+    fn header(text: &str, label: &str) -> String {
+        format!("{text:<60}{label}")
+    }
+
+    fn at(secs: i64) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+            + ChronoDuration::seconds(secs)
+    }
+
+    #[test]
+    fn counts_epoch_records_of_a_stub_rinex() {
+        let path = write_obs(
+            "stub",
+            &[
+                header(
+                    "     3.04           OBSERVATION DATA    M",
+                    "RINEX VERSION / TYPE",
+                ),
+                header("> 2026 03 01 11 00  0.0000000  0  1", "COMMENT"),
+                header("", END_OF_HEADER),
+                "> 2026 03 01 12 00  0.0000000  0  1".to_string(),
+                "G01  20000000.000   105000000.000".to_string(),
+                "> 2026 03 01 12 00  1.0000000  0  1".to_string(),
+                "G01  20000001.000   105000005.000".to_string(),
+                "> 2026 03 01 12 00  1.5000000  4  1".to_string(),
+                header("receiver event", "COMMENT"),
+                "> 2026 03 01 12 00  2.0000000  1  1".to_string(),
+                "G01  20000002.000   105000010.000".to_string(),
+            ],
+        );
+        // The event record (flag 4) and the epoch-like header comment are not epochs.
+        assert_eq!(scan_obs_epochs(&path).unwrap(), vec![at(0), at(1), at(2)]);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn counts_rinex2_epoch_records() {
+        let path = write_obs(
+            "rinex2",
+            &[
+                header(
+                    "     2.11           OBSERVATION DATA    M",
+                    "RINEX VERSION / TYPE",
+                ),
+                header("", END_OF_HEADER),
+                " 26  3  1 12  0  0.0000000  0  1G01".to_string(),
+                "  20000000.000".to_string(),
+                " 26  3  1 12  0 30.0000000  0  1G01".to_string(),
+                "  20000001.000".to_string(),
+            ],
+        );
+        assert_eq!(scan_obs_epochs(&path).unwrap(), vec![at(0), at(30)]);
+        fs::remove_file(&path).unwrap();
+    }
+}
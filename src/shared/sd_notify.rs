@@ -0,0 +1,259 @@
+use crate::shared::event::{EventKind, emit_event};
+use std::io;
+use std::time::{Duration, Instant};
+
+// Destination for sd_notify state strings such as "READY=1". A trait so the watchdog cadence
+// does not depend on a real systemd socket.
+pub trait NotifySink: Send {
+    fn send(&mut self, state: &str) -> io::Result<()>;
+}
+
+// systemd readiness and watchdog notifications. Active only when systemd passed
+// `NOTIFY_SOCKET`; otherwise every call is a no-op. Watchdog pings are throttled to half of
+// `WATCHDOG_USEC`, and are skipped entirely when no watchdog is configured.
+pub struct Watchdog {
+    sink: Option<Box<dyn NotifySink>>,
+    ping_interval: Option<Duration>,
+    last_ping: Option<Instant>,
+}
+
+impl Watchdog {
+    // Notifier for the environment systemd set up for this process.
+    pub fn from_env() -> Self {
+        let ping_interval = watchdog_interval_from_env().map(|interval| interval / 2);
+        match socket_sink_from_env() {
+            Ok(Some(sink)) => Self::with_sink(sink, ping_interval),
+            Ok(None) => Self::disabled(),
+            Err(err) => {
+                emit_event(
+                    EventKind::Warning,
+                    &format!(
+                        "systemd notify socket unavailable; not sending READY/WATCHDOG: {err}"
+                    ),
+                );
+                Self::disabled()
+            }
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            sink: None,
+            ping_interval: None,
+            last_ping: None,
+        }
+    }
+
+    pub fn with_sink(sink: Box<dyn NotifySink>, ping_interval: Option<Duration>) -> Self {
+        Self {
+            sink: Some(sink),
+            ping_interval,
+            last_ping: None,
+        }
+    }
+
+    // Tell systemd start-up finished (the receiver is open and configured).
+    pub fn ready(&mut self) {
+        self.notify("READY=1");
+    }
+
+    // Record a successful read; pings the watchdog at most once per half interval.
+    pub fn ping(&mut self) {
+        self.ping_at(Instant::now());
+    }
+
+    fn ping_at(&mut self, now: Instant) {
+        let Some(interval) = self.ping_interval else {
+            return;
+        };
+        if self
+            .last_ping
+            .is_some_and(|last| now.saturating_duration_since(last) < interval)
+        {
+            return;
+        }
+        self.last_ping = Some(now);
+        self.notify("WATCHDOG=1");
+    }
+
+    // A failed send is reported but never stops logging.
+    fn notify(&mut self, state: &str) {
+        let Some(sink) = self.sink.as_mut() else {
+            return;
+        };
+        if let Err(err) = sink.send(state) {
+            emit_event(
+                EventKind::Warning,
+                &format!("sending {state} to systemd failed: {err}"),
+            );
+        }
+    }
+}
+
+// The input reader owns the notifier, so it is dropped exactly when logging stops.
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.notify("STOPPING=1");
+    }
+}
+
+// `WATCHDOG_USEC`, honoured only when `WATCHDOG_PID` is absent or names this process.
+fn watchdog_interval_from_env() -> Option<Duration> {
+    if let Ok(pid) = std::env::var("WATCHDOG_PID")
+        && pid.trim().parse::<u32>().ok() != Some(std::process::id())
+    {
+        return None;
+    }
+    let usec = std::env::var("WATCHDOG_USEC")
+        .ok()?
+        .trim()
+        .parse::<u64>()
+        .ok()?;
+    (usec > 0).then(|| Duration::from_micros(usec))
+}
+
+#[cfg(unix)]
+fn socket_sink_from_env() -> io::Result<Option<Box<dyn NotifySink>>> {
+    let Some(path) = std::env::var_os("NOTIFY_SOCKET").filter(|path| !path.is_empty()) else {
+        return Ok(None);
+    };
+    Ok(Some(Box::new(unix_socket::UnixNotifySink::connect(&path)?)))
+}
+
+// sd_notify is a systemd (Unix) protocol; elsewhere the notifier stays disabled.
+#[cfg(not(unix))]
+fn socket_sink_from_env() -> io::Result<Option<Box<dyn NotifySink>>> {
+    Ok(None)
+}
+
+#[cfg(unix)]
+mod unix_socket {
+    use super::NotifySink;
+    use std::ffi::OsStr;
+    use std::io;
+    use std::os::unix::ffi::OsStrExt;
+    use std::os::unix::net::UnixDatagram;
+
+    // Datagram socket connected to `NOTIFY_SOCKET`; a leading `@` names a Linux abstract socket.
+    pub struct UnixNotifySink {
+        socket: UnixDatagram,
+    }
+
+    impl UnixNotifySink {
+        pub fn connect(path: &OsStr) -> io::Result<Self> {
+            let socket = UnixDatagram::unbound()?;
+            match path.as_bytes().strip_prefix(b"@") {
+                Some(name) => connect_abstract(&socket, name)?,
+                None => socket.connect(path)?,
+            }
+            Ok(Self { socket })
+        }
+    }
+
+    impl NotifySink for UnixNotifySink {
+        fn send(&mut self, state: &str) -> io::Result<()> {
+            self.socket.send(state.as_bytes()).map(|_| ())
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn connect_abstract(socket: &UnixDatagram, name: &[u8]) -> io::Result<()> {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::SocketAddr;
+
+        socket.connect_addr(&SocketAddr::from_abstract_name(name)?)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn connect_abstract(_socket: &UnixDatagram, _name: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "abstract notify sockets are Linux-only",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    // Records every state string instead of talking to systemd.
+    #[derive(Clone, Default)]
+    struct RecordingSink {
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl NotifySink for RecordingSink {
+        fn send(&mut self, state: &str) -> io::Result<()> {
+            self.sent.lock().unwrap().push(state.to_string());
+            Ok(())
+        }
+    }
+
+    impl RecordingSink {
+        fn sent(&self) -> Vec<String> {
+            self.sent.lock().unwrap().clone()
+        }
+    }
+
+    #[test]
+    fn ready_is_sent_once_on_request() {
+        let sink = RecordingSink::default();
+        let mut watchdog = Watchdog::with_sink(Box::new(sink.clone()), None);
+        watchdog.ready();
+        assert_eq!(sink.sent(), ["READY=1"]);
+    }
+
+    #[test]
+    fn pings_are_throttled_to_the_interval() {
+        let sink = RecordingSink::default();
+        let interval = Duration::from_secs(5);
+        let mut watchdog = Watchdog::with_sink(Box::new(sink.clone()), Some(interval));
+        let start = Instant::now();
+
+        watchdog.ping_at(start);
+        watchdog.ping_at(start + Duration::from_secs(1));
+        watchdog.ping_at(start + Duration::from_millis(4_999));
+        assert_eq!(sink.sent(), ["WATCHDOG=1"]);
+
+        watchdog.ping_at(start + interval);
+        watchdog.ping_at(start + Duration::from_secs(7));
+        watchdog.ping_at(start + Duration::from_secs(10));
+        assert_eq!(sink.sent(), ["WATCHDOG=1"; 3]);
+    }
+
+    #[test]
+    fn no_watchdog_interval_sends_no_pings() {
+        let sink = RecordingSink::default();
+        let mut watchdog = Watchdog::with_sink(Box::new(sink.clone()), None);
+        watchdog.ping();
+        watchdog.ping();
+        assert!(sink.sent().is_empty());
+    }
+
+    #[test]
+    fn stopping_is_sent_when_the_notifier_is_dropped() {
+        let sink = RecordingSink::default();
+        let mut watchdog =
+            Watchdog::with_sink(Box::new(sink.clone()), Some(Duration::from_secs(5)));
+        watchdog.ready();
+        watchdog.ping();
+        drop(watchdog);
+        assert_eq!(sink.sent(), ["READY=1", "WATCHDOG=1", "STOPPING=1"]);
+    }
+
+    #[test]
+    fn failed_sends_do_not_panic() {
+        struct BrokenSink;
+        impl NotifySink for BrokenSink {
+            fn send(&mut self, _state: &str) -> io::Result<()> {
+                Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+            }
+        }
+
+        let mut watchdog = Watchdog::with_sink(Box::new(BrokenSink), Some(Duration::ZERO));
+        watchdog.ready();
+        watchdog.ping();
+    }
+}
@@ -0,0 +1,379 @@
+use crate::args::{SerialFlowControl, SerialParity, SerialStopBits};
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::lock::LockGuard;
+use anyhow::{Context, Result, bail};
+use serialport::{
+    DataBits, FlowControl, Parity, SerialPort, SerialPortBuilder, SerialPortInfo, SerialPortType,
+    StopBits,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Baud rate and line framing for the receiver port (`--baud-rate`, `--data-bits`, `--parity`,
+// `--stop-bits`, `--flow-control`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SerialSettings {
+    pub baud_rate: u32,
+    data_bits: DataBits,
+    parity: Parity,
+    stop_bits: StopBits,
+    flow_control: FlowControl,
+}
+
+impl SerialSettings {
+    // Map the CLI options to serialport types. 5 and 6 data bits cannot carry NMEA or UBX and
+    // are rejected; 7 data bits and XON/XOFF flow control only suit NMEA-only receivers, since
+    // binary UBX frames need all 8 bits and contain the 0x11/0x13 XON/XOFF bytes.
+    pub fn new(
+        baud_rate: u32,
+        data_bits: u8,
+        parity: SerialParity,
+        stop_bits: SerialStopBits,
+        flow_control: SerialFlowControl,
+    ) -> Result<Self> {
+        let data_bits = match data_bits {
+            7 => DataBits::Seven,
+            8 => DataBits::Eight,
+            other => bail!("--data-bits {other} cannot carry NMEA or UBX; use 7 or 8"),
+        };
+        if data_bits == DataBits::Seven {
+            emit_event(
+                EventKind::Warning,
+                "--data-bits 7 truncates binary UBX frames; only NMEA output will be usable",
+            );
+        }
+        if flow_control == SerialFlowControl::Software {
+            emit_event(
+                EventKind::Warning,
+                "--flow-control software treats 0x11/0x13 bytes as XON/XOFF and will corrupt binary UBX frames",
+            );
+        }
+        Ok(Self {
+            baud_rate,
+            data_bits,
+            parity: match parity {
+                SerialParity::None => Parity::None,
+                SerialParity::Odd => Parity::Odd,
+                SerialParity::Even => Parity::Even,
+            },
+            stop_bits: match stop_bits {
+                SerialStopBits::One => StopBits::One,
+                SerialStopBits::Two => StopBits::Two,
+            },
+            flow_control: match flow_control {
+                SerialFlowControl::None => FlowControl::None,
+                SerialFlowControl::Software => FlowControl::Software,
+                SerialFlowControl::Hardware => FlowControl::Hardware,
+            },
+        })
+    }
+
+    // Conventional short form, e.g. `115200 8N1` or `9600 7E2 rtscts`.
+    pub fn describe(&self) -> String {
+        let parity = match self.parity {
+            Parity::None => 'N',
+            Parity::Odd => 'O',
+            Parity::Even => 'E',
+        };
+        let flow = match self.flow_control {
+            FlowControl::None => "",
+            FlowControl::Software => " xonxoff",
+            FlowControl::Hardware => " rtscts",
+        };
+        let data_bits = match self.data_bits {
+            DataBits::Five => 5,
+            DataBits::Six => 6,
+            DataBits::Seven => 7,
+            DataBits::Eight => 8,
+        };
+        let stop_bits = match self.stop_bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        };
+        format!("{} {data_bits}{parity}{stop_bits}{flow}", self.baud_rate)
+    }
+}
+
+// `--serial-port auto`: pick the port from the USB IDs below instead of a fixed device path.
+pub const AUTO_SERIAL_PORT: &str = "auto";
+
+// USB vendor/product IDs that `--serial-port auto` matches (`--usb-vid`, `--usb-pid`).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct UsbPortFilter {
+    pub vid: u16,
+    pub pid: Option<u16>,
+}
+
+impl UsbPortFilter {
+    fn describe(&self) -> String {
+        match self.pid {
+            Some(pid) => format!("USB {:04x}:{pid:04x}", self.vid),
+            None => format!("USB vendor {:04x}", self.vid),
+        }
+    }
+}
+
+// The device path to open: `serial_port` itself, or for `auto` the single enumerated USB serial
+// port matching `filter`. Detection runs once at startup; the chosen path is then used for
+// reconnects and the device lock like a configured one.
+pub fn resolve_serial_port(serial_port: &str, filter: UsbPortFilter) -> Result<String> {
+    if serial_port != AUTO_SERIAL_PORT {
+        return Ok(serial_port.to_string());
+    }
+    let ports = serialport::available_ports().context("enumerating serial ports failed")?;
+    let port = select_usb_port(&ports, filter)?.to_string();
+    emit_event(
+        EventKind::Info,
+        &format!(
+            "Auto-detected receiver serial port {port} ({})",
+            filter.describe()
+        ),
+    );
+    Ok(port)
+}
+
+// Exactly one USB port must match: none means the receiver is unplugged or the IDs are wrong,
+// several means auto-detection cannot tell the receivers apart.
+fn select_usb_port(ports: &[SerialPortInfo], filter: UsbPortFilter) -> Result<&str> {
+    let matches: Vec<&str> = ports
+        .iter()
+        .filter(|port| match &port.port_type {
+            SerialPortType::UsbPort(usb) => {
+                usb.vid == filter.vid && filter.pid.is_none_or(|pid| usb.pid == pid)
+            }
+            _ => false,
+        })
+        .map(|port| port.port_name.as_str())
+        .collect();
+    match matches.as_slice() {
+        [port] => Ok(port),
+        [] => bail!(
+            "--serial-port auto found no serial port for {} among {} port(s); check the receiver is connected or set --usb-vid/--usb-pid",
+            filter.describe(),
+            ports.len()
+        ),
+        several => bail!(
+            "--serial-port auto found {} serial ports for {} ({}); set --usb-pid or give the device path (e.g. /dev/serial/by-id/...)",
+            several.len(),
+            filter.describe(),
+            several.join(", ")
+        ),
+    }
+}
+
+// Open the receiver serial port with the configured line settings and read timeout.
+pub fn open_serial_port(
+    serial_port: &str,
+    settings: &SerialSettings,
+    read_timeout_ms: u64,
+) -> Result<Box<dyn SerialPort>> {
+    port_builder(serial_port, settings, read_timeout_ms)
+        .open()
+        .with_context(|| {
+            format!(
+                "opening serial port failed: {serial_port} @ {}",
+                settings.describe()
+            )
+        })
+}
+
+fn port_builder(
+    serial_port: &str,
+    settings: &SerialSettings,
+    read_timeout_ms: u64,
+) -> SerialPortBuilder {
+    serialport::new(serial_port, settings.baud_rate)
+        .data_bits(settings.data_bits)
+        .parity(settings.parity)
+        .stop_bits(settings.stop_bits)
+        .flow_control(settings.flow_control)
+        .timeout(Duration::from_millis(read_timeout_ms))
+}
+
+// Take an exclusive lock keyed by the resolved device path.
+// The data-dir lock only protects our output folder; this one stops two instances configured
+// with different data dirs (or different symlinks to the same device) from reading one receiver.
+pub fn acquire_device_lock(serial_port: &str, lock_dir: &Path) -> Result<LockGuard> {
+    let resolved = resolve_device_path(serial_port);
+    let lock_path = lock_dir.join(device_lock_file_name(&resolved));
+    LockGuard::acquire(&lock_path).with_context(|| {
+        format!(
+            "serial port {} ({}) is already in use by another gnss2tec-logger instance; device lock {}",
+            serial_port,
+            resolved.display(),
+            lock_path.display()
+        )
+    })
+}
+
+// Follow symlinks such as /dev/serial/by-id/* so aliases of one device share a lock.
+fn resolve_device_path(serial_port: &str) -> PathBuf {
+    fs::canonicalize(serial_port).unwrap_or_else(|_| PathBuf::from(serial_port))
+}
+
+// Flatten a device path into a single lock file name, e.g. /dev/ttyACM0 -> device-dev_ttyACM0.lock.
+fn device_lock_file_name(device: &Path) -> String {
+    let flattened: String = device
+        .to_string_lossy()
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("device-{flattened}.lock")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::args::{AppCommand, Cli};
+    use clap::Parser;
+
+    fn builder(
+        data_bits: u8,
+        parity: SerialParity,
+        stop_bits: SerialStopBits,
+        flow_control: SerialFlowControl,
+    ) -> SerialPortBuilder {
+        let settings =
+            SerialSettings::new(9_600, data_bits, parity, stop_bits, flow_control).unwrap();
+        port_builder("/dev/ttyTEST", &settings, 250)
+    }
+
+    fn expected() -> SerialPortBuilder {
+        serialport::new("/dev/ttyTEST", 9_600).timeout(Duration::from_millis(250))
+    }
+
+    #[test]
+    fn default_settings_are_8n1_without_flow_control() {
+        let settings = SerialSettings::new(
+            115_200,
+            8,
+            SerialParity::None,
+            SerialStopBits::One,
+            SerialFlowControl::None,
+        )
+        .unwrap();
+        assert_eq!(settings.describe(), "115200 8N1");
+        assert_eq!(
+            port_builder("/dev/ttyTEST", &settings, 250),
+            serialport::new("/dev/ttyTEST", 115_200)
+                .data_bits(DataBits::Eight)
+                .parity(Parity::None)
+                .stop_bits(StopBits::One)
+                .flow_control(FlowControl::None)
+                .timeout(Duration::from_millis(250))
+        );
+    }
+
+    #[test]
+    fn each_setting_maps_to_the_serialport_builder() {
+        let base = (
+            8,
+            SerialParity::None,
+            SerialStopBits::One,
+            SerialFlowControl::None,
+        );
+        assert_eq!(
+            builder(7, base.1, base.2, base.3),
+            expected().data_bits(DataBits::Seven)
+        );
+        assert_eq!(
+            builder(base.0, SerialParity::Odd, base.2, base.3),
+            expected().parity(Parity::Odd)
+        );
+        assert_eq!(
+            builder(base.0, SerialParity::Even, base.2, base.3),
+            expected().parity(Parity::Even)
+        );
+        assert_eq!(
+            builder(base.0, base.1, SerialStopBits::Two, base.3),
+            expected().stop_bits(StopBits::Two)
+        );
+        assert_eq!(
+            builder(base.0, base.1, base.2, SerialFlowControl::Software),
+            expected().flow_control(FlowControl::Software)
+        );
+        assert_eq!(
+            builder(base.0, base.1, base.2, SerialFlowControl::Hardware),
+            expected().flow_control(FlowControl::Hardware)
+        );
+    }
+
+    #[test]
+    fn data_bits_outside_7_or_8_are_rejected_at_parse_time() {
+        for bits in ["5", "6", "9"] {
+            assert!(Cli::try_parse_from(["gnss2tec-logger", "log", "--data-bits", bits]).is_err());
+        }
+        let cli = Cli::try_parse_from(["gnss2tec-logger", "log", "--data-bits", "7"]).unwrap();
+        let AppCommand::Log(args) = cli.command else {
+            panic!("expected log");
+        };
+        assert_eq!(args.data_bits, 7);
+    }
+
+    fn usb_port(name: &str, vid: u16, pid: u16) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::UsbPort(serialport::UsbPortInfo {
+                vid,
+                pid,
+                serial_number: None,
+                manufacturer: None,
+                product: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn auto_port_selects_the_single_usb_match() {
+        let ublox = UsbPortFilter {
+            vid: 0x1546,
+            pid: None,
+        };
+        let mut ports = vec![
+            SerialPortInfo {
+                port_name: "/dev/ttyS0".to_string(),
+                port_type: SerialPortType::Unknown,
+            },
+            usb_port("/dev/ttyUSB0", 0x0403, 0x6001),
+            usb_port("/dev/ttyACM0", 0x1546, 0x01A9),
+        ];
+        assert_eq!(select_usb_port(&ports, ublox).unwrap(), "/dev/ttyACM0");
+
+        // A second u-blox receiver makes the vendor alone ambiguous; the product ID settles it.
+        ports.push(usb_port("/dev/ttyACM1", 0x1546, 0x01A8));
+        let err = select_usb_port(&ports, ublox).unwrap_err().to_string();
+        assert!(
+            err.contains("found 2 serial ports") && err.contains("/dev/ttyACM0, /dev/ttyACM1"),
+            "{err}"
+        );
+        let f9p = UsbPortFilter {
+            pid: Some(0x01A8),
+            ..ublox
+        };
+        assert_eq!(select_usb_port(&ports, f9p).unwrap(), "/dev/ttyACM1");
+
+        let other = UsbPortFilter {
+            vid: 0x1234,
+            pid: None,
+        };
+        let err = select_usb_port(&ports, other).unwrap_err().to_string();
+        assert!(
+            err.contains("found no serial port for USB vendor 1234 among 4 port(s)"),
+            "{err}"
+        );
+
+        // A configured path is used as given, without enumerating.
+        assert_eq!(
+            resolve_serial_port("/dev/ttyACM3", ublox).unwrap(),
+            "/dev/ttyACM3"
+        );
+    }
+}
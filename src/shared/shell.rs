@@ -0,0 +1,66 @@
+use anyhow::{Result, bail};
+
+// Split a command line into arguments with POSIX-shell-like quoting.
+// Supports single quotes (literal), double quotes (with `\"` and `\\` escapes), and backslash
+// escapes outside quotes. No variable expansion, globbing, or command substitution is performed.
+pub fn split_command_line(input: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_token = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => current.push(ch),
+                        None => bail!("unterminated single quote in: {input}"),
+                    }
+                }
+            }
+            '"' => {
+                in_token = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(ch @ ('"' | '\\')) => current.push(ch),
+                            Some(ch) => {
+                                current.push('\\');
+                                current.push(ch);
+                            }
+                            None => bail!("unterminated double quote in: {input}"),
+                        },
+                        Some(ch) => current.push(ch),
+                        None => bail!("unterminated double quote in: {input}"),
+                    }
+                }
+            }
+            '\\' => {
+                in_token = true;
+                match chars.next() {
+                    Some(ch) => current.push(ch),
+                    None => bail!("trailing backslash in: {input}"),
+                }
+            }
+            ch if ch.is_whitespace() => {
+                if in_token {
+                    args.push(std::mem::take(&mut current));
+                    in_token = false;
+                }
+            }
+            ch => {
+                in_token = true;
+                current.push(ch);
+            }
+        }
+    }
+
+    if in_token {
+        args.push(current);
+    }
+    Ok(args)
+}
@@ -3,7 +3,8 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 
 // Install Ctrl-C handler and return a shared run flag.
-// Commands poll this flag to stop cleanly without abrupt termination.
+// Commands poll this flag to stop cleanly without abrupt termination. On Unix SIGTERM clears
+// it too, since that is what `systemctl stop` delivers.
 pub fn install_ctrlc_handler() -> Result<Arc<AtomicBool>> {
     let running = Arc::new(AtomicBool::new(true));
     let running_for_signal = Arc::clone(&running);
@@ -11,5 +12,77 @@ pub fn install_ctrlc_handler() -> Result<Arc<AtomicBool>> {
         running_for_signal.store(false, Ordering::SeqCst);
     })
     .context("installing Ctrl-C handler failed")?;
+    install_term_handler(Arc::clone(&running))?;
     Ok(running)
 }
+
+// Watch for SIGTERM on a helper thread and clear the run flag, so the logger's final flush and
+// the conversion worker's queue drain run exactly as for Ctrl-C.
+#[cfg(unix)]
+fn install_term_handler(running: Arc<AtomicBool>) -> Result<()> {
+    let mut signals = signal_hook::iterator::Signals::new([signal_hook::consts::SIGTERM])
+        .context("installing SIGTERM handler failed")?;
+    std::thread::Builder::new()
+        .name("sigterm".to_string())
+        .spawn(move || {
+            for _ in signals.forever() {
+                running.store(false, Ordering::SeqCst);
+            }
+        })
+        .context("spawning SIGTERM watcher failed")?;
+    Ok(())
+}
+
+// Windows has no SIGTERM; Ctrl-C (and console close) go through `ctrlc` as before.
+#[cfg(not(unix))]
+fn install_term_handler(_running: Arc<AtomicBool>) -> Result<()> {
+    Ok(())
+}
+
+// Install a SIGHUP handler and return a flag that is set on every hangup.
+// The logging loops poll and clear it to reload `ubx.dat` without restarting.
+#[cfg(unix)]
+pub fn install_reload_handler() -> Result<Arc<AtomicBool>> {
+    let reload = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGHUP, Arc::clone(&reload))
+        .context("installing SIGHUP handler failed")?;
+    Ok(reload)
+}
+
+// SIGHUP does not exist here; the flag simply never fires.
+#[cfg(not(unix))]
+pub fn install_reload_handler() -> Result<Arc<AtomicBool>> {
+    Ok(Arc::new(AtomicBool::new(false)))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn cleared_within(flag: &AtomicBool, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        while flag.load(Ordering::SeqCst) {
+            if Instant::now() >= deadline {
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+
+    // The only test that installs the process-wide handlers, so the raised signals cannot
+    // reach a process without them.
+    #[test]
+    fn sigint_and_sigterm_both_clear_the_run_flag() {
+        let running = install_ctrlc_handler().unwrap();
+        for signal in [signal_hook::consts::SIGINT, signal_hook::consts::SIGTERM] {
+            running.store(true, Ordering::SeqCst);
+            signal_hook::low_level::raise(signal).unwrap();
+            assert!(
+                cleared_within(&running, Duration::from_secs(5)),
+                "signal {signal} did not clear the run flag"
+            );
+        }
+    }
+}
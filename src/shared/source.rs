@@ -0,0 +1,651 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::serial::{SerialSettings, UsbPortFilter, open_serial_port, resolve_serial_port};
+use anyhow::{Context, Result, anyhow, bail};
+use serialport::SerialPort;
+use std::fs::{self, File};
+use std::hash::{BuildHasher, RandomState};
+use std::io::{self, BufReader, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TCP_SCHEME: &str = "tcp://";
+const TCP_CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const RECONNECT_POLL: Duration = Duration::from_millis(250);
+// A connection that keeps delivering data this long resets the reconnect backoff.
+const SUSTAINED_CONNECTION: Duration = Duration::from_secs(30);
+// Reconnect delays vary by up to this fraction either way.
+const RECONNECT_JITTER: f64 = 0.1;
+
+// Byte stream the logger reads GNSS data from (and writes UBX config commands to).
+// Reads follow the serial convention: `ErrorKind::TimedOut` means "no data yet", not failure.
+pub trait ByteSource: Read + Write + Send {
+    fn label(&self) -> &str;
+}
+
+// Where the GNSS stream comes from: `--input-file` replay, `--input tcp://host:port`, otherwise
+// `--serial-port`.
+#[derive(Clone, Debug)]
+pub enum InputSpec {
+    Serial {
+        path: String,
+        settings: SerialSettings,
+    },
+    Tcp {
+        address: String,
+    },
+    File {
+        path: PathBuf,
+        realtime: bool,
+    },
+}
+
+impl InputSpec {
+    pub fn from_args(
+        input: Option<&str>,
+        serial_port: &str,
+        settings: SerialSettings,
+        usb: UsbPortFilter,
+    ) -> Result<Self> {
+        let Some(input) = input.map(str::trim).filter(|value| !value.is_empty()) else {
+            return Ok(Self::Serial {
+                path: resolve_serial_port(serial_port, usb)?,
+                settings,
+            });
+        };
+
+        if let Some(address) = input.strip_prefix(TCP_SCHEME) {
+            if address.is_empty() || !address.contains(':') {
+                bail!("--input expects tcp://host:port, got {input}");
+            }
+            return Ok(Self::Tcp {
+                address: address.to_string(),
+            });
+        }
+        if input.contains("://") {
+            bail!("unsupported --input scheme (only tcp:// is supported): {input}");
+        }
+        Ok(Self::Serial {
+            path: resolve_serial_port(input, usb)?,
+            settings,
+        })
+    }
+
+    // Serial device path, used to key the per-device lock.
+    pub fn serial_path(&self) -> Option<&str> {
+        match self {
+            Self::Serial { path, .. } => Some(path),
+            Self::Tcp { .. } | Self::File { .. } => None,
+        }
+    }
+
+    // A replayed file ends instead of dropping out: EOF stops logging rather than reconnecting.
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Self::File { .. })
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Self::Serial { path, .. } => path.clone(),
+            Self::Tcp { address } => format!("{TCP_SCHEME}{address}"),
+            Self::File { path, .. } => path.display().to_string(),
+        }
+    }
+
+    pub fn open(&self, read_timeout: Duration) -> Result<Box<dyn ByteSource>> {
+        match self {
+            Self::Serial { path, settings } => {
+                let port = open_serial_port(path, settings, read_timeout.as_millis() as u64)?;
+                Ok(Box::new(SerialSource {
+                    label: path.clone(),
+                    port,
+                }))
+            }
+            Self::Tcp { address } => Ok(Box::new(TcpSource::connect(address, read_timeout)?)),
+            Self::File { path, realtime } => {
+                Ok(Box::new(FileSource::open(path, *realtime, read_timeout)?))
+            }
+        }
+    }
+}
+
+struct SerialSource {
+    label: String,
+    port: Box<dyn SerialPort>,
+}
+
+impl Read for SerialSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.port.read(buf)
+    }
+}
+
+impl Write for SerialSource {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.port.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.port.flush()
+    }
+}
+
+impl ByteSource for SerialSource {
+    fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+// TCP client for ser2net-style bridges. Timeouts map to `TimedOut` and a peer close to
+// `UnexpectedEof` so the logging loop sees the same semantics as a serial port.
+struct TcpSource {
+    label: String,
+    stream: TcpStream,
+}
+
+impl TcpSource {
+    fn connect(address: &str, read_timeout: Duration) -> Result<Self> {
+        let addrs: Vec<_> = address
+            .to_socket_addrs()
+            .with_context(|| format!("resolving TCP input address failed: {address}"))?
+            .collect();
+        let mut last_err = None;
+        for addr in &addrs {
+            match TcpStream::connect_timeout(addr, TCP_CONNECT_TIMEOUT) {
+                Ok(stream) => {
+                    stream
+                        .set_read_timeout(Some(read_timeout.max(Duration::from_millis(1))))
+                        .with_context(|| format!("setting TCP read timeout failed: {address}"))?;
+                    let _ = stream.set_nodelay(true);
+                    return Ok(Self {
+                        label: format!("{TCP_SCHEME}{address}"),
+                        stream,
+                    });
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        match last_err {
+            Some(err) => {
+                Err(err).with_context(|| format!("connecting TCP input failed: {address}"))
+            }
+            None => bail!("TCP input address resolved to nothing: {address}"),
+        }
+    }
+}
+
+impl Read for TcpSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.stream.read(buf) {
+            Ok(0) if !buf.is_empty() => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "TCP input closed by peer",
+            )),
+            Ok(size) => Ok(size),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                Err(io::Error::from(io::ErrorKind::TimedOut))
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl Write for TcpSource {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl ByteSource for TcpSource {
+    fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+// `--input-file` replay of a captured stream. Reads return the file's bytes as fast as the
+// writer takes them and `UnexpectedEof` at the end; UBX config writes are discarded.
+// With `--replay-realtime` and a `<file>.times` sidecar, bytes are held back until the capture
+// time of their mark, so rotation, stall, and NMEA timing behave as they did in the field.
+struct FileSource {
+    label: String,
+    reader: BufReader<File>,
+    position: u64,
+    marks: Vec<ReplayMark>,
+    next_mark: usize,
+    started: Instant,
+    read_timeout: Duration,
+}
+
+// The first `offset` bytes of the capture had arrived `at` after it started.
+#[derive(Clone, Copy, Debug)]
+struct ReplayMark {
+    at: Duration,
+    offset: u64,
+}
+
+impl FileSource {
+    fn open(path: &Path, realtime: bool, read_timeout: Duration) -> Result<Self> {
+        let file = File::open(path)
+            .with_context(|| format!("opening replay input failed: {}", path.display()))?;
+        let marks = if realtime {
+            load_replay_marks(path)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            label: path.display().to_string(),
+            reader: BufReader::new(file),
+            position: 0,
+            marks,
+            next_mark: 0,
+            started: Instant::now(),
+            read_timeout: read_timeout.max(Duration::from_millis(1)),
+        })
+    }
+}
+
+// `<file>.times`: one `<seconds> <byte offset>` pair per line, both increasing, e.g. `12.5 40960`.
+// Without the sidecar `--replay-realtime` warns and replays at full speed.
+fn load_replay_marks(path: &Path) -> Result<Vec<ReplayMark>> {
+    let mut sidecar = path.as_os_str().to_os_string();
+    sidecar.push(".times");
+    let sidecar = PathBuf::from(sidecar);
+    let text = match fs::read_to_string(&sidecar) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "--replay-realtime has no timestamp sidecar {}; replaying at full speed",
+                    sidecar.display()
+                ),
+            );
+            return Ok(Vec::new());
+        }
+        Err(err) => {
+            return Err(err).with_context(|| {
+                format!("reading replay timestamps failed: {}", sidecar.display())
+            });
+        }
+    };
+
+    let mut marks: Vec<ReplayMark> = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || {
+            anyhow!(
+                "invalid replay timestamp on line {} of {} (expected `<seconds> <byte offset>`): {line}",
+                index + 1,
+                sidecar.display()
+            )
+        };
+        let mut fields = line.split_whitespace();
+        let (Some(seconds), Some(offset), None) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(invalid());
+        };
+        let at = seconds
+            .parse::<f64>()
+            .ok()
+            .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+            .ok_or_else(invalid)?;
+        let offset = offset.parse::<u64>().map_err(|_| invalid())?;
+        if marks
+            .last()
+            .is_some_and(|last| at < last.at || offset < last.offset)
+        {
+            return Err(invalid());
+        }
+        marks.push(ReplayMark { at, offset });
+    }
+    Ok(marks)
+}
+
+impl Read for FileSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self
+            .marks
+            .get(self.next_mark)
+            .is_some_and(|mark| mark.offset <= self.position)
+        {
+            self.next_mark += 1;
+        }
+        let mut limit = buf.len();
+        if let Some(mark) = self.marks.get(self.next_mark) {
+            let elapsed = self.started.elapsed();
+            if elapsed < mark.at {
+                thread::sleep((mark.at - elapsed).min(self.read_timeout));
+                if self.started.elapsed() < mark.at {
+                    return Err(io::Error::from(io::ErrorKind::TimedOut));
+                }
+            }
+            limit = limit.min((mark.offset - self.position) as usize);
+        }
+
+        match self.reader.read(&mut buf[..limit])? {
+            0 if limit > 0 => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "end of replay input",
+            )),
+            size => {
+                self.position += size as u64;
+                Ok(size)
+            }
+        }
+    }
+}
+
+impl Write for FileSource {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ByteSource for FileSource {
+    fn label(&self) -> &str {
+        &self.label
+    }
+}
+
+// Why the connect hook runs: a freshly opened input, or a resend on the live connection
+// (SIGHUP reload, stall recovery) whose bytes must keep flowing into the log.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConnectKind {
+    Opened,
+    Resend,
+}
+
+// Runs on every (re)connect, e.g. to push the receiver UBX configuration again.
+pub type OnConnect = Box<dyn FnMut(&mut dyn ByteSource, ConnectKind) -> Result<()> + Send>;
+
+// Reconnect delay schedule: starts at `--reconnect-delay-ms`, doubles after every failed attempt
+// up to `--reconnect-max-backoff-secs`, and carries a little jitter so several stations on one
+// host do not retry in lockstep.
+pub struct ReconnectBackoff {
+    initial: Duration,
+    max: Duration,
+    next: Duration,
+    jitter: Box<dyn FnMut() -> f64 + Send>,
+}
+
+impl ReconnectBackoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max: max.max(initial),
+            next: initial,
+            jitter: Box::new(jitter_unit),
+        }
+    }
+
+    // Replace the jitter source; it must return values in [0, 1), 0.5 meaning no jitter.
+    pub fn with_jitter(mut self, jitter: impl FnMut() -> f64 + Send + 'static) -> Self {
+        self.jitter = Box::new(jitter);
+        self
+    }
+
+    // Zero initial delay: reconnecting is off and read errors stay fatal.
+    fn is_disabled(&self) -> bool {
+        self.initial.is_zero()
+    }
+
+    // Delay before the next attempt; each call doubles the following one up to the cap.
+    fn next_delay(&mut self) -> Duration {
+        let base = self.next;
+        self.next = self.next.saturating_mul(2).min(self.max);
+        base.mul_f64(1.0 + RECONNECT_JITTER * (2.0 * (self.jitter)() - 1.0))
+    }
+
+    // Data arrived on a connection that has been up for `connected_for`. Only one that keeps
+    // delivering earns a fresh backoff; one that drops right after reconnecting keeps backing
+    // off. Returns true when the backoff was reset.
+    fn reset_if_sustained(&mut self, connected_for: Duration) -> bool {
+        if connected_for < SUSTAINED_CONNECTION {
+            return false;
+        }
+        self.next = self.initial;
+        true
+    }
+}
+// Uniform-ish value in [0, 1) from the std hasher's random keys; good enough to spread retries.
+fn jitter_unit() -> f64 {
+    let bits = RandomState::new().hash_one(Instant::now());
+    (bits >> 11) as f64 / (1_u64 << 53) as f64
+}
+
+// Input wrapper that reopens the source after connection loss.
+// While disconnected, reads report `TimedOut` after a short pause so the caller keeps flushing,
+// rotating, and polling its shutdown flag; a zero reconnect delay keeps errors fatal instead.
+pub struct ReconnectingSource {
+    spec: InputSpec,
+    label: String,
+    read_timeout: Duration,
+    backoff: ReconnectBackoff,
+    retry_delay: Duration,
+    inner: Option<Box<dyn ByteSource>>,
+    last_attempt: Instant,
+    connected_since: Option<Instant>,
+    on_connect: OnConnect,
+}
+
+impl ReconnectingSource {
+    // Open the source for the first time; failures here are returned to the caller.
+    pub fn connect(
+        spec: InputSpec,
+        read_timeout: Duration,
+        backoff: ReconnectBackoff,
+        mut on_connect: OnConnect,
+    ) -> Result<Self> {
+        let mut inner = spec.open(read_timeout)?;
+        on_connect(inner.as_mut(), ConnectKind::Opened)?;
+        Ok(Self {
+            label: spec.label(),
+            spec,
+            read_timeout,
+            retry_delay: backoff.initial,
+            backoff,
+            inner: Some(inner),
+            last_attempt: Instant::now(),
+            connected_since: Some(Instant::now()),
+            on_connect,
+        })
+    }
+
+    pub fn label(&self) -> &str {
+        &self.label
+    }
+
+    pub fn is_replay(&self) -> bool {
+        self.spec.is_replay()
+    }
+
+    // Run the connect hook again on the live connection (e.g. to resend UBX config).
+    // Does nothing while disconnected; the next reconnect runs the hook anyway.
+    pub fn rerun_on_connect(&mut self) -> Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => (self.on_connect)(inner.as_mut(), ConnectKind::Resend),
+            None => Ok(()),
+        }
+    }
+
+    fn try_reconnect(&mut self) {
+        let since_last = self.last_attempt.elapsed();
+        if since_last < self.retry_delay {
+            thread::sleep((self.retry_delay - since_last).min(RECONNECT_POLL));
+            return;
+        }
+
+        self.last_attempt = Instant::now();
+        let result = self.spec.open(self.read_timeout).and_then(|mut inner| {
+            (self.on_connect)(inner.as_mut(), ConnectKind::Opened)?;
+            Ok(inner)
+        });
+        match result {
+            Ok(inner) => {
+                emit_event(
+                    EventKind::Reconnect,
+                    &format!("Reconnected GNSS input {}", self.label),
+                );
+                self.inner = Some(inner);
+                self.connected_since = Some(Instant::now());
+            }
+            Err(err) => {
+                self.retry_delay = self.backoff.next_delay();
+                emit_event(
+                    EventKind::Reconnect,
+                    &format!(
+                        "Reconnect to GNSS input {} failed: {err:#}; retrying in {} ms",
+                        self.label,
+                        self.retry_delay.as_millis()
+                    ),
+                );
+            }
+        }
+    }
+}
+
+impl Read for ReconnectingSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(inner) = self.inner.as_mut() else {
+            self.try_reconnect();
+            return Err(io::Error::from(io::ErrorKind::TimedOut));
+        };
+
+        match inner.read(buf) {
+            Ok(size) => {
+                if size > 0
+                    && let Some(since) = self.connected_since
+                    && self.backoff.reset_if_sustained(since.elapsed())
+                {
+                    self.connected_since = None;
+                }
+                Ok(size)
+            }
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => Err(err),
+            Err(err) if self.backoff.is_disabled() || self.spec.is_replay() => Err(err),
+            Err(err) => {
+                self.retry_delay = self.backoff.next_delay();
+                emit_event(
+                    EventKind::Reconnect,
+                    &format!(
+                        "GNSS input {} lost ({err}); reconnecting in {} ms (backoff up to {} s)",
+                        self.label,
+                        self.retry_delay.as_millis(),
+                        self.backoff.max.as_secs()
+                    ),
+                );
+                self.inner = None;
+                self.connected_since = None;
+                self.last_attempt = Instant::now();
+                Err(io::Error::from(io::ErrorKind::TimedOut))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn connect_hook_tells_a_resend_from_an_opened_input() {
+        let path =
+            std::env::temp_dir().join(format!("gnss2tec-connect-kind-{}.ubx", std::process::id()));
+        fs::write(&path, b"data").unwrap();
+        let kinds = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&kinds);
+        let mut source = ReconnectingSource::connect(
+            InputSpec::File {
+                path: path.clone(),
+                realtime: false,
+            },
+            Duration::from_millis(10),
+            ReconnectBackoff::new(Duration::from_millis(10), Duration::from_millis(10)),
+            Box::new(move |_source: &mut dyn ByteSource, kind| {
+                recorded.lock().unwrap().push(kind);
+                Ok(())
+            }),
+        )
+        .unwrap();
+        source.rerun_on_connect().unwrap();
+
+        assert_eq!(
+            *kinds.lock().unwrap(),
+            [ConnectKind::Opened, ConnectKind::Resend]
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn secs(value: u64) -> Duration {
+        Duration::from_secs(value)
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let mut backoff = ReconnectBackoff::new(secs(2), secs(60)).with_jitter(|| 0.5);
+        let delays: Vec<_> = (0..7).map(|_| backoff.next_delay()).collect();
+        assert_eq!(
+            delays,
+            [
+                secs(2),
+                secs(4),
+                secs(8),
+                secs(16),
+                secs(32),
+                secs(60),
+                secs(60)
+            ]
+        );
+    }
+
+    #[test]
+    fn cap_below_the_initial_delay_keeps_the_initial_delay() {
+        let mut backoff = ReconnectBackoff::new(secs(5), secs(1)).with_jitter(|| 0.5);
+        assert_eq!(backoff.next_delay(), secs(5));
+        assert_eq!(backoff.next_delay(), secs(5));
+    }
+
+    #[test]
+    fn jitter_stays_within_ten_percent() {
+        let base = secs(10);
+        let mut low = ReconnectBackoff::new(base, base).with_jitter(|| 0.0);
+        assert_eq!(low.next_delay(), secs(9));
+        let mut high = ReconnectBackoff::new(base, base).with_jitter(|| 0.999_999);
+        let delay = high.next_delay();
+        assert!(delay > secs(10) && delay < secs(11), "{delay:?}");
+
+        let mut random = ReconnectBackoff::new(base, base);
+        for _ in 0..1_000 {
+            let delay = random.next_delay();
+            assert!(delay >= secs(9) && delay < secs(11), "{delay:?}");
+        }
+    }
+
+    #[test]
+    fn only_a_sustained_connection_resets_the_backoff() {
+        let mut backoff = ReconnectBackoff::new(secs(1), secs(60)).with_jitter(|| 0.5);
+        for _ in 0..3 {
+            backoff.next_delay();
+        }
+        // A connection that drops soon after reconnecting keeps backing off.
+        assert!(!backoff.reset_if_sustained(secs(5)));
+        assert_eq!(backoff.next_delay(), secs(8));
+
+        assert!(backoff.reset_if_sustained(SUSTAINED_CONNECTION));
+        assert_eq!(backoff.next_delay(), secs(1));
+        assert_eq!(backoff.next_delay(), secs(2));
+    }
+}
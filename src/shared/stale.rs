@@ -0,0 +1,122 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::logfile::{log_file_dirs, log_file_stem};
+use anyhow::{Context, Result};
+use chrono::{Duration as ChronoDuration, NaiveDateTime, Utc};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const QUARANTINE_DIR_NAME: &str = ".quarantine";
+
+// Startup guard against UBX files left behind by a previous station or session.
+// Files that are older than the threshold or not named like logger output are reported,
+// and optionally moved aside so the converter never picks them up.
+pub fn check_stale_ubx_files(data_dir: &Path, max_age_hours: u32, quarantine: bool) -> Result<()> {
+    if max_age_hours == 0 {
+        return Ok(());
+    }
+
+    let cutoff = Utc::now().naive_utc() - ChronoDuration::hours(i64::from(max_age_hours));
+    let mut stale = Vec::new();
+    for dir in log_file_dirs(data_dir)? {
+        for entry in fs::read_dir(&dir)
+            .with_context(|| format!("reading directory failed: {}", dir.display()))?
+        {
+            let entry = entry.with_context(|| format!("iterating {}", dir.display()))?;
+            if !entry
+                .file_type()
+                .with_context(|| format!("reading metadata for {}", entry.path().display()))?
+                .is_file()
+            {
+                continue;
+            }
+
+            let path = entry.path();
+            let Some(file_name) = path.file_name().map(|n| n.to_string_lossy()) else {
+                continue;
+            };
+            let Some(stem) = log_file_stem(&file_name) else {
+                continue;
+            };
+
+            match parse_log_file_time(stem) {
+                Some(started) if started >= cutoff => {}
+                Some(_) => stale.push((path, "older than threshold")),
+                None => stale.push((path, "unexpected file name")),
+            }
+        }
+    }
+
+    if stale.is_empty() {
+        return Ok(());
+    }
+
+    stale.sort();
+    emit_event(
+        EventKind::Warning,
+        &format!(
+            "WARNING: found {} stale UBX file(s) in {} (threshold {} hour(s)); they may belong to a previous station",
+            stale.len(),
+            data_dir.display(),
+            max_age_hours
+        ),
+    );
+    for (path, reason) in &stale {
+        emit_event(
+            EventKind::Warning,
+            &format!("WARNING:   {} ({})", path.display(), reason),
+        );
+    }
+
+    if !quarantine {
+        emit_event(
+            EventKind::Warning,
+            "WARNING: pass --quarantine-stale to move these files aside before conversion",
+        );
+        return Ok(());
+    }
+
+    let quarantine_dir = data_dir
+        .join(QUARANTINE_DIR_NAME)
+        .join(Utc::now().format("%Y%m%d_%H%M%S").to_string());
+    fs::create_dir_all(&quarantine_dir).with_context(|| {
+        format!(
+            "creating quarantine directory failed: {}",
+            quarantine_dir.display()
+        )
+    })?;
+    for (path, _) in &stale {
+        let destination = quarantine_destination(&quarantine_dir, path)?;
+        fs::rename(path, &destination).with_context(|| {
+            format!(
+                "moving stale UBX file failed: {} -> {}",
+                path.display(),
+                destination.display()
+            )
+        })?;
+    }
+    emit_event(
+        EventKind::Warning,
+        &format!(
+            "Quarantined {} stale UBX file(s) into {}",
+            stale.len(),
+            quarantine_dir.display()
+        ),
+    );
+    Ok(())
+}
+
+// Logger output is named `<YYYYMMDD_HHMMSS>.ubx[.gz]` in UTC, or `<YYYYMMDD_HH>.ubx` with
+// `--single-file-per-hour`.
+pub fn parse_log_file_time(stem: &str) -> Option<NaiveDateTime> {
+    if stem.len() == "YYYYMMDD_HH".len() {
+        return NaiveDateTime::parse_from_str(&format!("{stem}0000"), "%Y%m%d_%H%M%S").ok();
+    }
+    NaiveDateTime::parse_from_str(stem, "%Y%m%d_%H%M%S").ok()
+}
+
+fn quarantine_destination(quarantine_dir: &Path, path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("missing file name for {}", path.display()))?;
+    Ok(quarantine_dir.join(file_name))
+}
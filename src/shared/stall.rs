@@ -0,0 +1,118 @@
+use crate::shared::event::{EventKind, emit_event};
+use std::time::{Duration, Instant};
+
+// Tracks time since the last nonzero read so a silent receiver is reported instead of
+// quietly producing empty hours. Each stall is reported once; any data resets it.
+pub struct StallDetector {
+    timeout: Option<Duration>,
+    last_data: Instant,
+    stalled: bool,
+}
+
+impl StallDetector {
+    // A zero timeout disables detection.
+    pub fn new(timeout_secs: u64) -> Self {
+        Self {
+            timeout: (timeout_secs > 0).then(|| Duration::from_secs(timeout_secs)),
+            last_data: Instant::now(),
+            stalled: false,
+        }
+    }
+
+    // Call for every nonzero read.
+    pub fn record_data(&mut self, source_label: &str) {
+        self.record_data_at(Instant::now(), source_label);
+    }
+
+    fn record_data_at(&mut self, now: Instant, source_label: &str) {
+        if self.stalled {
+            emit_event(
+                EventKind::Stall,
+                &format!(
+                    "[STALL] data from {} resumed after {}s of silence",
+                    source_label,
+                    now.saturating_duration_since(self.last_data).as_secs()
+                ),
+            );
+            self.stalled = false;
+        }
+        self.last_data = now;
+    }
+
+    // Returns true exactly once when the current silence crosses the timeout.
+    // The caller resends the UBX configuration on that one true when `--stall-resend-config`
+    // is set.
+    pub fn check(&mut self, source_label: &str) -> bool {
+        self.check_at(Instant::now(), source_label)
+    }
+
+    fn check_at(&mut self, now: Instant, source_label: &str) -> bool {
+        let Some(timeout) = self.timeout else {
+            return false;
+        };
+        let silence = now.saturating_duration_since(self.last_data);
+        if self.stalled || silence < timeout {
+            return false;
+        }
+
+        self.stalled = true;
+        emit_event(
+            EventKind::Stall,
+            &format!(
+                "[STALL] WARNING: no bytes from {} for {}s (antenna, cable, or receiver firmware?)",
+                source_label,
+                silence.as_secs()
+            ),
+        );
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABEL: &str = "/dev/ttyACM0";
+
+    #[test]
+    fn silence_past_the_timeout_triggers_one_resend() {
+        let mut detector = StallDetector::new(30);
+        let start = detector.last_data;
+        assert!(!detector.check_at(start + Duration::from_secs(29), LABEL));
+        assert!(detector.check_at(start + Duration::from_secs(30), LABEL));
+        // Still silent: already reported, so no second resend.
+        assert!(!detector.check_at(start + Duration::from_secs(31), LABEL));
+        assert!(!detector.check_at(start + Duration::from_secs(600), LABEL));
+    }
+
+    #[test]
+    fn data_resets_the_stall_and_a_new_silence_resends_again() {
+        let mut detector = StallDetector::new(30);
+        let start = detector.last_data;
+        assert!(detector.check_at(start + Duration::from_secs(40), LABEL));
+
+        let resumed = start + Duration::from_secs(45);
+        detector.record_data_at(resumed, LABEL);
+        assert!(!detector.stalled);
+        assert!(!detector.check_at(resumed + Duration::from_secs(29), LABEL));
+        assert!(detector.check_at(resumed + Duration::from_secs(30), LABEL));
+    }
+
+    #[test]
+    fn steady_data_never_stalls() {
+        let mut detector = StallDetector::new(30);
+        let start = detector.last_data;
+        for second in (10..600).step_by(10) {
+            let now = start + Duration::from_secs(second);
+            detector.record_data_at(now, LABEL);
+            assert!(!detector.check_at(now + Duration::from_secs(5), LABEL));
+        }
+    }
+
+    #[test]
+    fn zero_timeout_disables_detection() {
+        let mut detector = StallDetector::new(0);
+        let start = detector.last_data;
+        assert!(!detector.check_at(start + Duration::from_secs(86_400), LABEL));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::shared::event::{EventKind, emit_event};
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::{Value, json};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const STATE_FILE_NAME: &str = ".logger-state.json";
+
+// Counters carried across logger restarts so long-term throughput stays continuous.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct LoggerState {
+    pub total_bytes: u64,
+    pub last_hour_key: Option<String>,
+}
+
+pub fn state_file_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(STATE_FILE_NAME)
+}
+
+// Persisted state, or zeroed counters when the file is missing or unreadable.
+// A corrupt file is reported and then ignored; it is overwritten by the next save.
+pub fn load_logger_state(data_dir: &Path) -> LoggerState {
+    let path = state_file_path(data_dir);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return LoggerState::default(),
+        Err(err) => {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "Reading logger state failed; byte counters start at zero: {}: {err}",
+                    path.display()
+                ),
+            );
+            return LoggerState::default();
+        }
+    };
+    match parse_logger_state(&text) {
+        Some(state) => state,
+        None => {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "Ignoring corrupt logger state; byte counters start at zero: {}",
+                    path.display()
+                ),
+            );
+            LoggerState::default()
+        }
+    }
+}
+
+fn parse_logger_state(text: &str) -> Option<LoggerState> {
+    let value: Value = serde_json::from_str(text).ok()?;
+    Some(LoggerState {
+        total_bytes: value.get("total_bytes")?.as_u64()?,
+        last_hour_key: value
+            .get("last_hour_key")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+    })
+}
+
+// Replace the state file atomically so a crash mid-write never leaves a truncated file.
+pub fn save_logger_state(data_dir: &Path, state: &LoggerState) -> Result<()> {
+    let path = state_file_path(data_dir);
+    let tmp_path = data_dir.join(format!("{STATE_FILE_NAME}.tmp"));
+    let snapshot = json!({
+        "updated": Utc::now().to_rfc3339(),
+        "total_bytes": state.total_bytes,
+        "last_hour_key": state.last_hour_key,
+    });
+    fs::write(&tmp_path, format!("{snapshot}\n"))
+        .with_context(|| format!("writing logger state failed: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("replacing logger state failed: {}", path.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("gnss2tec-state-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn counters_continue_across_save_and_load() {
+        let dir = scratch_dir("round-trip");
+        assert_eq!(load_logger_state(&dir), LoggerState::default());
+
+        let first = LoggerState {
+            total_bytes: 4096,
+            last_hour_key: Some("20260301_12".to_string()),
+        };
+        save_logger_state(&dir, &first).unwrap();
+        assert_eq!(load_logger_state(&dir), first);
+
+        // The next run picks up the counter and keeps adding to it.
+        let mut next = load_logger_state(&dir);
+        next.total_bytes += 1024;
+        next.last_hour_key = Some("20260301_13".to_string());
+        save_logger_state(&dir, &next).unwrap();
+        assert_eq!(load_logger_state(&dir).total_bytes, 5120);
+        assert!(!dir.join(format!("{STATE_FILE_NAME}.tmp")).exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn corrupt_state_falls_back_to_zero() {
+        assert_eq!(parse_logger_state("not json"), None);
+        assert_eq!(parse_logger_state("{\"total_bytes\": \"many\"}"), None);
+        assert_eq!(
+            parse_logger_state("{\"total_bytes\": 7, \"last_hour_key\""),
+            None
+        );
+        assert_eq!(
+            parse_logger_state("{\"total_bytes\": 7}"),
+            Some(LoggerState {
+                total_bytes: 7,
+                last_hour_key: None,
+            })
+        );
+
+        let dir = scratch_dir("corrupt");
+        fs::write(state_file_path(&dir), b"\x00\x01garbage").unwrap();
+        assert_eq!(load_logger_state(&dir), LoggerState::default());
+        // The next save replaces the corrupt file.
+        let state = LoggerState {
+            total_bytes: 1,
+            last_hour_key: None,
+        };
+        save_logger_state(&dir, &state).unwrap();
+        assert_eq!(load_logger_state(&dir), state);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const HEADER: &str = "timestamp,total_bytes,bps,frames,bad_frames,port";
+
+// One `--stats-file` row per stats interval, next to the `[STAT]` line on stderr.
+pub struct StatsRow<'a> {
+    pub timestamp: DateTime<Utc>,
+    pub total_bytes: u64,
+    pub bits_per_sec: u64,
+    pub frames: u64,
+    pub bad_frames: u64,
+    pub port: &'a str,
+}
+
+// Append-only CSV of link health for plotting. Every row is flushed as it is written, and a
+// new (or empty) file gets the header first.
+pub struct StatsCsv {
+    path: PathBuf,
+    file: File,
+}
+
+impl StatsCsv {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("opening stats file failed: {}", path.display()))?;
+        let is_new = file
+            .metadata()
+            .with_context(|| format!("reading metadata for {}", path.display()))?
+            .len()
+            == 0;
+        if is_new {
+            writeln!(file, "{HEADER}")
+                .and_then(|()| file.flush())
+                .with_context(|| format!("writing stats file header failed: {}", path.display()))?;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    pub fn append(&mut self, row: &StatsRow) -> Result<()> {
+        writeln!(
+            self.file,
+            "{},{},{},{},{},{}",
+            row.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+            row.total_bytes,
+            row.bits_per_sec,
+            row.frames,
+            row.bad_frames,
+            csv_field(row.port)
+        )
+        .and_then(|()| self.file.flush())
+        .with_context(|| format!("appending to stats file failed: {}", self.path.display()))
+    }
+}
+
+// Quote a field containing a separator, quote, or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::fs;
+
+    fn row(second: u32, total_bytes: u64, port: &str) -> StatsRow<'_> {
+        StatsRow {
+            timestamp: Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, second).unwrap(),
+            total_bytes,
+            bits_per_sec: 9_600,
+            frames: 42,
+            bad_frames: 1,
+            port,
+        }
+    }
+
+    #[test]
+    fn header_once_then_rows_appended_across_restarts() {
+        let path =
+            std::env::temp_dir().join(format!("gnss2tec-stats-csv-{}.csv", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut stats = StatsCsv::open(&path).unwrap();
+        stats.append(&row(0, 1_200, "/dev/ttyACM0")).unwrap();
+        drop(stats);
+        // A restart appends below the existing rows without repeating the header.
+        let mut stats = StatsCsv::open(&path).unwrap();
+        stats.append(&row(10, 2_400, "/dev/ttyACM0")).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "timestamp,total_bytes,bps,frames,bad_frames,port\n\
+             2026-03-01T12:00:00Z,1200,9600,42,1,/dev/ttyACM0\n\
+             2026-03-01T12:00:10Z,2400,9600,42,1,/dev/ttyACM0\n"
+        );
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty_existing_file_gets_the_header() {
+        let path = std::env::temp_dir().join(format!(
+            "gnss2tec-stats-csv-empty-{}.csv",
+            std::process::id()
+        ));
+        fs::write(&path, "").unwrap();
+        drop(StatsCsv::open(&path).unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), format!("{HEADER}\n"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn port_labels_with_separators_are_quoted() {
+        assert_eq!(csv_field("/dev/ttyACM0"), "/dev/ttyACM0");
+        assert_eq!(csv_field("tcp://host:2101,b"), "\"tcp://host:2101,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
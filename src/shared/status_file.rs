@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const STATUS_FILE_NAME: &str = ".logger-status.json";
+
+// Snapshot of a running logger, refreshed every stats interval for `gnss2tec-logger status`.
+pub struct LoggerStatus<'a> {
+    pub input: &'a str,
+    pub current_file: &'a Path,
+    pub last_rotation: DateTime<Utc>,
+    pub total_bytes: u64,
+    pub bits_per_sec: u64,
+}
+
+pub fn status_file_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(STATUS_FILE_NAME)
+}
+
+// Replace the status file atomically so readers never see a half-written snapshot.
+pub fn write_logger_status(data_dir: &Path, status: &LoggerStatus) -> Result<()> {
+    let path = status_file_path(data_dir);
+    let tmp_path = data_dir.join(format!("{STATUS_FILE_NAME}.tmp"));
+    let snapshot = json!({
+        "updated": Utc::now().to_rfc3339(),
+        "pid": std::process::id(),
+        "input": status.input,
+        "current_file": status.current_file.to_string_lossy(),
+        "last_rotation": status.last_rotation.to_rfc3339(),
+        "total_bytes": status.total_bytes,
+        "bits_per_sec": status.bits_per_sec,
+    });
+    fs::write(&tmp_path, format!("{snapshot}\n"))
+        .with_context(|| format!("writing status file failed: {}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .with_context(|| format!("replacing status file failed: {}", path.display()))?;
+    Ok(())
+}
+
+// Last snapshot, or None when no logger has written one into `data_dir`.
+pub fn read_logger_status(data_dir: &Path) -> Result<Option<Value>> {
+    let path = status_file_path(data_dir);
+    let text = match fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => {
+            return Err(err)
+                .with_context(|| format!("reading status file failed: {}", path.display()));
+        }
+    };
+    let value = serde_json::from_str(&text)
+        .with_context(|| format!("parsing status file failed: {}", path.display()))?;
+    Ok(Some(value))
+}
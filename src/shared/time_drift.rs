@@ -0,0 +1,238 @@
+use crate::shared::event::{EventKind, emit_event};
+use crate::shared::ubx::{Frame, FrameScanner};
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use std::time::{Duration, Instant};
+
+pub(crate) const CLASS_NAV: u8 = 0x01;
+pub(crate) const ID_NAV_TIMEUTC: u8 = 0x21;
+
+// UBX-NAV-TIMEUTC layout: iTOW u4, tAcc u4 (ns), nano i4 (ns), year u2, month, day, hour,
+// min, sec u1, then the validity flags (bit 2 validUTC).
+const TIMEUTC_LEN: usize = 20;
+const TIMEUTC_VALID_UTC: u8 = 0x04;
+
+// Host clocks this far from receiver UTC are almost certainly not synchronised at all.
+const UNSYNCED_HOST_OFFSET: Duration = Duration::from_secs(1);
+
+// One decoded NAV-TIMEUTC solution.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReceiverUtc {
+    pub time: DateTime<Utc>,
+    pub accuracy_ns: u32,
+    pub valid_utc: bool,
+}
+
+// Host-vs-receiver clock monitor for `--log-time-drift-secs`: every interval it prints the
+// offset of `Utc::now()` from the receiver's NAV-TIMEUTC solution. Offsets include the
+// USB/serial and ring-buffer latency, so a few tens of milliseconds is normal.
+pub struct TimeDriftMonitor {
+    interval: Option<Duration>,
+    scanner: FrameScanner,
+    frames: Vec<Frame>,
+    offsets_ms: Vec<f64>,
+    invalid: u64,
+    last_accuracy_ns: Option<u32>,
+    last_emit: Instant,
+}
+
+impl TimeDriftMonitor {
+    // A zero interval disables the monitor and skips frame parsing.
+    pub fn new(interval_secs: u64) -> Self {
+        Self {
+            interval: (interval_secs > 0).then(|| Duration::from_secs(interval_secs)),
+            scanner: FrameScanner::new(),
+            frames: Vec::new(),
+            offsets_ms: Vec::new(),
+            invalid: 0,
+            last_accuracy_ns: None,
+            last_emit: Instant::now(),
+        }
+    }
+
+    pub fn ingest(&mut self, bytes: &[u8]) {
+        self.ingest_at(bytes, Utc::now());
+    }
+
+    // `now` is the host time the bytes arrived.
+    fn ingest_at(&mut self, bytes: &[u8], now: DateTime<Utc>) {
+        if self.interval.is_none() {
+            return;
+        }
+        self.scanner.push_bytes(bytes, &mut self.frames);
+        for frame in std::mem::take(&mut self.frames) {
+            if (frame.class, frame.id) != (CLASS_NAV, ID_NAV_TIMEUTC) {
+                continue;
+            }
+            match parse_nav_timeutc(&frame.payload) {
+                Some(utc) if utc.valid_utc => {
+                    let offset = now - utc.time;
+                    self.offsets_ms
+                        .push(offset.num_microseconds().unwrap_or(i64::MAX) as f64 / 1000.0);
+                    self.last_accuracy_ns = Some(utc.accuracy_ns);
+                }
+                _ => self.invalid += 1,
+            }
+        }
+    }
+
+    // Emit one `[TIME]` line per interval, plus a warning when the host clock looks unsynced.
+    pub fn maybe_emit_logs(&mut self) {
+        let Some(interval) = self.interval else {
+            return;
+        };
+        if self.last_emit.elapsed() < interval {
+            return;
+        }
+        self.last_emit = Instant::now();
+
+        let invalid = std::mem::take(&mut self.invalid);
+        let offsets = std::mem::take(&mut self.offsets_ms);
+        if offsets.is_empty() {
+            emit_event(
+                EventKind::TimeDrift,
+                &format!(
+                    "[TIME] no valid NAV-TIMEUTC in the last {}s ({invalid} without valid UTC); enable it with `!UBX CFG-MSG 1 33 ...`",
+                    interval.as_secs()
+                ),
+            );
+            return;
+        }
+
+        let mean = offsets.iter().sum::<f64>() / offsets.len() as f64;
+        let min = offsets.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = offsets.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        emit_event(
+            EventKind::TimeDrift,
+            &format!(
+                "[TIME] host - receiver UTC {mean:+.1} ms (min {min:+.1}, max {max:+.1}, {} sample(s), tAcc {} ns)",
+                offsets.len(),
+                self.last_accuracy_ns.unwrap_or_default()
+            ),
+        );
+        if host_clock_unsynced(mean) {
+            emit_event(
+                EventKind::Warning,
+                &format!(
+                    "[TIME] WARNING host clock is {:+.3} s off receiver UTC; check NTP/chrony (log file names and hour rotation follow the host clock)",
+                    mean / 1000.0
+                ),
+            );
+        }
+    }
+}
+
+fn host_clock_unsynced(mean_offset_ms: f64) -> bool {
+    mean_offset_ms.abs() >= UNSYNCED_HOST_OFFSET.as_secs_f64() * 1000.0
+}
+
+// Decode a NAV-TIMEUTC payload; None when it is short or the date fields are out of range.
+pub fn parse_nav_timeutc(payload: &[u8]) -> Option<ReceiverUtc> {
+    if payload.len() < TIMEUTC_LEN {
+        return None;
+    }
+    let accuracy_ns = u32::from_le_bytes(payload[4..8].try_into().ok()?);
+    let nano = i32::from_le_bytes(payload[8..12].try_into().ok()?);
+    let year = u16::from_le_bytes(payload[12..14].try_into().ok()?);
+    let valid = payload[19];
+    // `nano` may be negative (-1e9..1e9) and is added to the whole-second time; a leap
+    // second (sec 60) is clamped to :59.
+    let time = NaiveDate::from_ymd_opt(
+        i32::from(year),
+        u32::from(payload[14]),
+        u32::from(payload[15]),
+    )?
+    .and_hms_opt(
+        u32::from(payload[16]),
+        u32::from(payload[17]),
+        u32::from(payload[18]).min(59),
+    )?
+    .and_utc()
+        + TimeDelta::nanoseconds(i64::from(nano));
+    Some(ReceiverUtc {
+        time,
+        accuracy_ns,
+        valid_utc: valid & TIMEUTC_VALID_UTC != 0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::ubx::encode_packet;
+    use chrono::TimeZone;
+
+    // NAV-TIMEUTC payload for 2026-03-01 12:00:`sec` plus `nano`.
+    fn timeutc_payload(sec: u8, nano: i32, valid: u8) -> Vec<u8> {
+        let mut payload = vec![0_u8; TIMEUTC_LEN];
+        payload[4..8].copy_from_slice(&25_u32.to_le_bytes());
+        payload[8..12].copy_from_slice(&nano.to_le_bytes());
+        payload[12..14].copy_from_slice(&2026_u16.to_le_bytes());
+        payload[14..19].copy_from_slice(&[3, 1, 12, 0, sec]);
+        payload[19] = valid;
+        payload
+    }
+
+    fn at(sec: u32, millis: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, sec).unwrap() + TimeDelta::milliseconds(millis)
+    }
+
+    #[test]
+    fn parses_time_accuracy_and_validity() {
+        let utc = parse_nav_timeutc(&timeutc_payload(30, 250_000_000, 0x07)).unwrap();
+        assert_eq!(utc.time, at(30, 250));
+        assert_eq!(utc.accuracy_ns, 25);
+        assert!(utc.valid_utc);
+        assert!(
+            !parse_nav_timeutc(&timeutc_payload(30, 0, 0x03))
+                .unwrap()
+                .valid_utc
+        );
+    }
+
+    #[test]
+    fn negative_nano_and_leap_seconds_stay_in_range() {
+        let utc = parse_nav_timeutc(&timeutc_payload(30, -100_000_000, 0x07)).unwrap();
+        assert_eq!(utc.time, at(29, 900));
+        let leap = parse_nav_timeutc(&timeutc_payload(60, 0, 0x07)).unwrap();
+        assert_eq!(leap.time, at(59, 0));
+    }
+
+    #[test]
+    fn short_or_out_of_range_payloads_are_rejected() {
+        let payload = timeutc_payload(0, 0, 0x07);
+        assert!(parse_nav_timeutc(&payload[..TIMEUTC_LEN - 1]).is_none());
+        let mut bad_month = payload;
+        bad_month[14] = 13;
+        assert!(parse_nav_timeutc(&bad_month).is_none());
+    }
+
+    #[test]
+    fn offsets_are_host_minus_receiver_and_skip_invalid_utc() {
+        let mut monitor = TimeDriftMonitor::new(60);
+        let valid = encode_packet(CLASS_NAV, ID_NAV_TIMEUTC, &timeutc_payload(0, 0, 0x07));
+        let invalid = encode_packet(CLASS_NAV, ID_NAV_TIMEUTC, &timeutc_payload(0, 0, 0x03));
+        monitor.ingest_at(&valid, at(0, 40));
+        monitor.ingest_at(&invalid, at(1, 0));
+        monitor.ingest_at(&valid, at(0, -15));
+        assert_eq!(monitor.offsets_ms, [40.0, -15.0]);
+        assert_eq!(monitor.invalid, 1);
+        assert_eq!(monitor.last_accuracy_ns, Some(25));
+    }
+
+    #[test]
+    fn unsynced_warning_starts_at_one_second_either_way() {
+        assert!(!host_clock_unsynced(0.0));
+        assert!(!host_clock_unsynced(999.9));
+        assert!(!host_clock_unsynced(-999.9));
+        assert!(host_clock_unsynced(1_000.0));
+        assert!(host_clock_unsynced(-1_000.0));
+    }
+
+    #[test]
+    fn zero_interval_ignores_input() {
+        let mut monitor = TimeDriftMonitor::new(0);
+        let valid = encode_packet(CLASS_NAV, ID_NAV_TIMEUTC, &timeutc_payload(0, 0, 0x07));
+        monitor.ingest_at(&valid, at(0, 0));
+        assert!(monitor.offsets_ms.is_empty());
+    }
+}
@@ -0,0 +1,271 @@
+// Minimal UBX framing helpers for messages the `ublox` crate does not build for us.
+
+use anyhow::{Context, Result, bail};
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+pub const SYNC_CHAR_1: u8 = 0xB5;
+pub const SYNC_CHAR_2: u8 = 0x62;
+
+pub const CLASS_CFG: u8 = 0x06;
+pub const ID_CFG_RST: u8 = 0x04;
+pub const ID_CFG_RATE: u8 = 0x08;
+pub const ID_CFG_NAV5: u8 = 0x24;
+pub const ID_CFG_TMODE3: u8 = 0x71;
+pub const ID_CFG_VALSET: u8 = 0x8A;
+pub const CLASS_MON: u8 = 0x0A;
+pub const ID_MON_VER: u8 = 0x04;
+
+// Encode one complete UBX frame: sync chars, class, id, little-endian length, payload, checksum.
+pub fn encode_packet(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+    let len = payload.len() as u16;
+    let mut packet = Vec::with_capacity(payload.len() + 8);
+    packet.push(SYNC_CHAR_1);
+    packet.push(SYNC_CHAR_2);
+    packet.push(class);
+    packet.push(id);
+    packet.extend_from_slice(&len.to_le_bytes());
+    packet.extend_from_slice(payload);
+    let (ck_a, ck_b) = checksum(&packet[2..]);
+    packet.push(ck_a);
+    packet.push(ck_b);
+    packet
+}
+
+// 8-bit Fletcher checksum over class, id, length, and payload bytes.
+pub fn checksum(bytes: &[u8]) -> (u8, u8) {
+    let mut ck_a: u8 = 0;
+    let mut ck_b: u8 = 0;
+    for &byte in bytes {
+        ck_a = ck_a.wrapping_add(byte);
+        ck_b = ck_b.wrapping_add(ck_a);
+    }
+    (ck_a, ck_b)
+}
+
+// Return (class, id) for an encoded UBX frame.
+pub fn packet_class_id(packet: &[u8]) -> Option<(u8, u8)> {
+    if packet.len() < 8 || packet[0] != SYNC_CHAR_1 || packet[1] != SYNC_CHAR_2 {
+        return None;
+    }
+    Some((packet[2], packet[3]))
+}
+
+// Largest payload accepted while scanning; anything longer is treated as a false sync.
+const MAX_SCAN_PAYLOAD_LEN: usize = 8 * 1024;
+
+// One checksum-verified UBX frame pulled out of the receiver byte stream.
+pub struct Frame {
+    pub class: u8,
+    pub id: u8,
+    pub payload: Vec<u8>,
+}
+
+// Incremental UBX frame scanner for serial chunks that split frames at arbitrary points.
+// Non-UBX bytes (NMEA, RTCM) and frames with bad checksums are skipped.
+#[derive(Default)]
+pub struct FrameScanner {
+    buffer: Vec<u8>,
+    bad_checksums: u64,
+}
+
+impl FrameScanner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Append bytes and collect every frame completed by them.
+    pub fn push_bytes(&mut self, bytes: &[u8], out: &mut Vec<Frame>) {
+        self.buffer.extend_from_slice(bytes);
+
+        let mut start = 0;
+        loop {
+            let Some(offset) = self.buffer[start..]
+                .windows(2)
+                .position(|pair| pair == [SYNC_CHAR_1, SYNC_CHAR_2])
+            else {
+                // Keep a trailing first sync byte; its partner may arrive in the next chunk.
+                start = if self.buffer.last() == Some(&SYNC_CHAR_1) {
+                    self.buffer.len() - 1
+                } else {
+                    self.buffer.len()
+                };
+                break;
+            };
+            start += offset;
+
+            let header = &self.buffer[start..];
+            if header.len() < 6 {
+                break;
+            }
+            let len = usize::from(u16::from_le_bytes([header[4], header[5]]));
+            if len > MAX_SCAN_PAYLOAD_LEN {
+                start += 1;
+                continue;
+            }
+            if header.len() < len + 8 {
+                break;
+            }
+
+            let body = &header[2..6 + len];
+            if checksum(body) == (header[6 + len], header[7 + len]) {
+                out.push(Frame {
+                    class: header[2],
+                    id: header[3],
+                    payload: header[6..6 + len].to_vec(),
+                });
+                start += len + 8;
+            } else {
+                self.bad_checksums += 1;
+                start += 1;
+            }
+        }
+        self.buffer.drain(..start);
+    }
+
+    // Sync-framed candidates rejected for a bad checksum since the last call.
+    pub fn take_bad_checksums(&mut self) -> u64 {
+        std::mem::take(&mut self.bad_checksums)
+    }
+}
+
+// Read from `source` until a frame with the given class/id arrives or `timeout` passes.
+// Other traffic (periodic messages, NMEA) is skipped; `TimedOut` reads mean "no data yet".
+pub fn read_response<R: Read + ?Sized>(
+    source: &mut R,
+    class: u8,
+    id: u8,
+    timeout: Duration,
+) -> Result<Frame> {
+    let deadline = Instant::now() + timeout;
+    let mut scanner = FrameScanner::new();
+    let mut frames = Vec::new();
+    let mut buf = [0_u8; 1024];
+    while Instant::now() < deadline {
+        match source.read(&mut buf) {
+            Ok(size) => scanner.push_bytes(&buf[..size], &mut frames),
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {}
+            Err(err) => return Err(err).context("reading UBX response failed"),
+        }
+        if let Some(index) = frames
+            .iter()
+            .position(|frame| frame.class == class && frame.id == id)
+        {
+            return Ok(frames.swap_remove(index));
+        }
+        frames.clear();
+    }
+    bail!(
+        "no UBX response to class 0x{class:02X} id 0x{id:02X} within {:.1}s",
+        timeout.as_secs_f64()
+    )
+}
+
+// Read until one checksum-valid UBX frame of any kind arrives, failing after `window`.
+// The failure tells silence (wrong port or baud rate) apart from data without UBX (UBX
+// output disabled, or a baud mismatch producing noise). Bytes read here are discarded, so it
+// only belongs right after the input is opened, never on a live connection being logged.
+pub fn await_ubx_frame<R: Read + ?Sized>(source: &mut R, window: Duration) -> Result<()> {
+    let deadline = Instant::now() + window;
+    let mut scanner = FrameScanner::new();
+    let mut frames = Vec::new();
+    let mut buf = [0_u8; 1024];
+    let mut received = 0_usize;
+    while Instant::now() < deadline {
+        match source.read(&mut buf) {
+            Ok(size) => {
+                received += size;
+                scanner.push_bytes(&buf[..size], &mut frames);
+            }
+            Err(err) if err.kind() == io::ErrorKind::TimedOut => {}
+            Err(err) => return Err(err).context("reading receiver output failed"),
+        }
+        if !frames.is_empty() {
+            return Ok(());
+        }
+    }
+    if received == 0 {
+        bail!(
+            "no data at all within {:.1}s after sending the config (check the port and baud rate)",
+            window.as_secs_f64()
+        );
+    }
+    bail!(
+        "{received} byte(s) but no valid UBX frame within {:.1}s after sending the config (receiver is likely sending NMEA/RTCM only; enable UBX output, or check the baud rate)",
+        window.as_secs_f64()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::thread;
+
+    const WINDOW: Duration = Duration::from_millis(100);
+
+    // Receiver stand-in: hands out the queued chunks, then times out like an idle serial port.
+    struct MockPort {
+        chunks: VecDeque<Vec<u8>>,
+    }
+
+    impl MockPort {
+        fn new(chunks: &[&[u8]]) -> Self {
+            Self {
+                chunks: chunks.iter().map(|chunk| chunk.to_vec()).collect(),
+            }
+        }
+    }
+
+    impl Read for MockPort {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some(chunk) = self.chunks.pop_front() else {
+                thread::sleep(Duration::from_millis(5));
+                return Err(io::Error::from(io::ErrorKind::TimedOut));
+            };
+            buf[..chunk.len()].copy_from_slice(&chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    const GGA: &[u8] =
+        b"$GPGGA,120000.00,4044.1234,N,07410.5678,W,1,08,1.0,10.0,M,-34.0,M,,*5C\r\n";
+
+    #[test]
+    fn ubx_frame_passes_the_check() {
+        // NAV-EOE split across two reads, after some NMEA.
+        let frame = encode_packet(0x01, 0x61, &[0x00, 0x10, 0x00, 0x00]);
+        let (head, tail) = frame.split_at(5);
+        let mut port = MockPort::new(&[GGA, head, tail]);
+        await_ubx_frame(&mut port, WINDOW).unwrap();
+    }
+
+    #[test]
+    fn nmea_only_output_fails_the_check() {
+        let mut port = MockPort::new(&[GGA, GGA, GGA]);
+        let err = await_ubx_frame(&mut port, WINDOW).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("no valid UBX frame"), "{message}");
+        assert!(
+            message.starts_with(&format!("{} byte(s)", GGA.len() * 3)),
+            "{message}"
+        );
+    }
+
+    #[test]
+    fn silent_port_fails_the_check() {
+        let mut port = MockPort::new(&[]);
+        let err = await_ubx_frame(&mut port, WINDOW).unwrap_err();
+        assert!(err.to_string().contains("no data at all"), "{err}");
+    }
+
+    #[test]
+    fn corrupted_ubx_frame_does_not_count() {
+        let mut frame = encode_packet(0x01, 0x61, &[0x00, 0x10, 0x00, 0x00]);
+        let last = frame.len() - 1;
+        frame[last] ^= 0xFF;
+        let mut port = MockPort::new(&[&frame]);
+        let err = await_ubx_frame(&mut port, WINDOW).unwrap_err();
+        assert!(err.to_string().contains("no valid UBX frame"), "{err}");
+    }
+}
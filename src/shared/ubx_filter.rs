@@ -0,0 +1,139 @@
+use crate::shared::ubx::{Frame, FrameScanner, encode_packet};
+use anyhow::{Context, Result, bail};
+
+// Message names accepted by `--log-classes`; anything else is given as hex `0xCC-0xII`.
+const KNOWN_MESSAGES: &[(&str, u8, u8)] = &[
+    ("NAV-CLOCK", 0x01, 0x22),
+    ("NAV-DOP", 0x01, 0x04),
+    ("NAV-PVT", 0x01, 0x07),
+    ("NAV-SAT", 0x01, 0x35),
+    ("NAV-SIG", 0x01, 0x43),
+    ("NAV-STATUS", 0x01, 0x03),
+    ("NAV-TIMEGPS", 0x01, 0x20),
+    ("NAV-TIMEUTC", 0x01, 0x21),
+    ("RXM-MEASX", 0x02, 0x14),
+    ("RXM-RAWX", 0x02, 0x15),
+    ("RXM-SFRBX", 0x02, 0x13),
+    ("MON-HW", 0x0A, 0x09),
+    ("MON-RF", 0x0A, 0x38),
+    ("MON-VER", 0x0A, 0x04),
+    ("TIM-TM2", 0x0D, 0x03),
+    ("TIM-TP", 0x0D, 0x01),
+];
+
+// Logging-stage UBX message filter for `--log-classes`.
+// Without a filter every byte is written unchanged. With one, only complete checksum-valid
+// frames whose class/id is listed reach the file; NMEA, RTCM, and stray bytes are dropped.
+pub struct LogClassFilter {
+    allowed: Option<Vec<(u8, u8)>>,
+    scanner: FrameScanner,
+    frames: Vec<Frame>,
+    kept: Vec<u8>,
+}
+
+impl LogClassFilter {
+    // Build from a comma-separated list such as "RXM-RAWX,RXM-SFRBX,0x01-0x07".
+    pub fn from_spec(spec: Option<&str>) -> Result<Self> {
+        let allowed = match spec.map(str::trim).filter(|spec| !spec.is_empty()) {
+            None => None,
+            Some(spec) => Some(
+                spec.split(',')
+                    .map(|entry| parse_message(entry.trim()))
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(|| format!("invalid --log-classes list: {spec:?}"))?,
+            ),
+        };
+        Ok(Self {
+            allowed,
+            scanner: FrameScanner::new(),
+            frames: Vec::new(),
+            kept: Vec::new(),
+        })
+    }
+
+    // Bytes of `chunk` to log. Frames split across chunks are held until they complete.
+    pub fn apply<'a>(&'a mut self, chunk: &'a [u8]) -> &'a [u8] {
+        let Some(allowed) = &self.allowed else {
+            return chunk;
+        };
+        self.frames.clear();
+        self.kept.clear();
+        self.scanner.push_bytes(chunk, &mut self.frames);
+        for frame in &self.frames {
+            if allowed.contains(&(frame.class, frame.id)) {
+                self.kept
+                    .extend_from_slice(&encode_packet(frame.class, frame.id, &frame.payload));
+            }
+        }
+        &self.kept
+    }
+}
+
+fn parse_message(entry: &str) -> Result<(u8, u8)> {
+    if let Some(&(_, class, id)) = KNOWN_MESSAGES
+        .iter()
+        .find(|(name, _, _)| name.eq_ignore_ascii_case(entry))
+    {
+        return Ok((class, id));
+    }
+    let Some((class, id)) = entry.split_once('-') else {
+        bail!("unknown UBX message {entry:?} (use a name such as RXM-RAWX or hex 0x02-0x15)");
+    };
+    Ok((parse_hex_byte(class, entry)?, parse_hex_byte(id, entry)?))
+}
+
+fn parse_hex_byte(value: &str, entry: &str) -> Result<u8> {
+    let digits = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .with_context(|| {
+            format!("unknown UBX message {entry:?} (use a name such as RXM-RAWX or hex 0x02-0x15)")
+        })?;
+    u8::from_str_radix(digits, 16)
+        .with_context(|| format!("invalid hex byte {value:?} in UBX message {entry:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(class: u8, id: u8, payload: &[u8]) -> Vec<u8> {
+        encode_packet(class, id, payload)
+    }
+
+    #[test]
+    fn without_a_filter_bytes_pass_unchanged() {
+        let mut filter = LogClassFilter::from_spec(None).unwrap();
+        let chunk = b"$GPGGA,noise\r\n\xB5\x62";
+        assert_eq!(filter.apply(chunk), chunk);
+    }
+
+    #[test]
+    fn only_allowed_messages_reach_the_output() {
+        let rawx = frame(0x02, 0x15, &[1, 2, 3, 4]);
+        let sfrbx = frame(0x02, 0x13, &[5, 6]);
+        let pvt = frame(0x01, 0x07, &[7; 12]);
+        let stream = [
+            b"$GNRMC,garbage*00\r\n".to_vec(),
+            rawx.clone(),
+            pvt,
+            // A RAWX-sized frame with a bad checksum is dropped too.
+            vec![0xB5, 0x62, 0x02, 0x15, 0x02, 0x00, 0xAA, 0xBB, 0x00, 0x00],
+            sfrbx.clone(),
+        ]
+        .concat();
+
+        let mut filter = LogClassFilter::from_spec(Some("RXM-RAWX, 0x02-0x13")).unwrap();
+        // Split mid-frame: the first RAWX half is held until the rest arrives.
+        let (first, second) = stream.split_at(25);
+        let mut logged = filter.apply(first).to_vec();
+        logged.extend_from_slice(filter.apply(second));
+        assert_eq!(logged, [rawx, sfrbx].concat());
+    }
+
+    #[test]
+    fn unknown_message_names_are_rejected() {
+        assert!(LogClassFilter::from_spec(Some("RXM-NOPE")).is_err());
+        assert!(LogClassFilter::from_spec(Some("0x02-0xZZ")).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use crate::shared::checksum::sha256_file;
+use crate::shared::lock::LockGuard;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use serde_json::{Value, json};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const UPLOAD_QUEUE_FILE_NAME: &str = "upload-queue.jsonl";
+const UPLOAD_QUEUE_LOCK_NAME: &str = ".upload-queue.lock";
+
+pub const STATUS_PENDING: &str = "pending";
+pub const STATUS_DONE: &str = "done";
+pub const STATUS_CORRUPT: &str = "corrupt";
+
+// Archive -> remote handoff ledger: one JSON object per archived product, keyed by its
+// archive-relative path, carrying sha256 and upload status so an uploader can resume.
+pub struct UploadQueue {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl UploadQueue {
+    pub fn new(archive_dir: &Path) -> Self {
+        Self {
+            path: archive_dir.join(UPLOAD_QUEUE_FILE_NAME),
+            lock_path: archive_dir.join(UPLOAD_QUEUE_LOCK_NAME),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Append `pending` entries for freshly archived files.
+    pub fn append_pending(&self, archive_dir: &Path, files: &[PathBuf]) -> Result<()> {
+        let mut lines = Vec::with_capacity(files.len());
+        for path in files {
+            let relative = path.strip_prefix(archive_dir).unwrap_or(path);
+            let size = fs::metadata(path)
+                .with_context(|| format!("reading metadata for {}", path.display()))?
+                .len();
+            lines.push(json!({
+                "path": relative.to_string_lossy(),
+                "sha256": sha256_file(path)?,
+                "size": size,
+                "status": STATUS_PENDING,
+                "queued_at": Utc::now().to_rfc3339(),
+            }));
+        }
+
+        let _lock = LockGuard::acquire_wait(&self.lock_path)?;
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("opening upload queue failed: {}", self.path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for line in &lines {
+            serde_json::to_writer(&mut writer, line)
+                .with_context(|| format!("writing upload queue failed: {}", self.path.display()))?;
+            writer
+                .write_all(b"\n")
+                .with_context(|| format!("writing upload queue failed: {}", self.path.display()))?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("flushing upload queue failed: {}", self.path.display()))
+    }
+
+    // Snapshot all entries currently in the queue.
+    pub fn load(&self) -> Result<Vec<Value>> {
+        let _lock = LockGuard::acquire_wait(&self.lock_path)?;
+        self.read_entries()
+    }
+
+    // Merge fields into every entry with the given path and sha256, then rewrite the queue.
+    // Re-reading under the lock keeps entries appended by a concurrent conversion.
+    pub fn update(&self, entry_path: &str, sha256: &str, fields: Value) -> Result<()> {
+        let _lock = LockGuard::acquire_wait(&self.lock_path)?;
+        let mut entries = self.read_entries()?;
+        for entry in &mut entries {
+            if entry["path"] == entry_path
+                && entry["sha256"] == sha256
+                && let (Some(target), Some(updates)) = (entry.as_object_mut(), fields.as_object())
+            {
+                for (key, value) in updates {
+                    target.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        self.write_entries(&entries)
+    }
+
+    // Drop every entry for files that left the archive before upload (hourly OBS products
+    // merged by `--daily`). Other entries keep their order.
+    pub fn remove(&self, archive_dir: &Path, files: &[PathBuf]) -> Result<()> {
+        let relative: Vec<String> = files
+            .iter()
+            .map(|path| {
+                path.strip_prefix(archive_dir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+
+        let _lock = LockGuard::acquire_wait(&self.lock_path)?;
+        let mut entries = self.read_entries()?;
+        let queued = entries.len();
+        entries.retain(|entry| {
+            !entry["path"]
+                .as_str()
+                .is_some_and(|path| relative.iter().any(|removed| removed == path))
+        });
+        if entries.len() == queued {
+            return Ok(());
+        }
+        self.write_entries(&entries)
+    }
+
+    fn read_entries(&self) -> Result<Vec<Value>> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("opening upload queue failed: {}", self.path.display())
+                });
+            }
+        };
+
+        let mut entries = Vec::new();
+        for (idx, line) in BufReader::new(file).lines().enumerate() {
+            let line = line
+                .with_context(|| format!("reading upload queue failed: {}", self.path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Value = serde_json::from_str(&line).with_context(|| {
+                format!(
+                    "invalid upload queue entry at {}:{}",
+                    self.path.display(),
+                    idx + 1
+                )
+            })?;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    fn write_entries(&self, entries: &[Value]) -> Result<()> {
+        let tmp_path = self.path.with_extension("jsonl.tmp");
+        let file =
+            File::create(&tmp_path).with_context(|| format!("creating {}", tmp_path.display()))?;
+        let mut writer = BufWriter::new(file);
+        for entry in entries {
+            serde_json::to_writer(&mut writer, entry)
+                .with_context(|| format!("writing {}", tmp_path.display()))?;
+            writer
+                .write_all(b"\n")
+                .with_context(|| format!("writing {}", tmp_path.display()))?;
+        }
+        writer
+            .flush()
+            .with_context(|| format!("flushing {}", tmp_path.display()))?;
+        drop(writer);
+        fs::rename(&tmp_path, &self.path).with_context(|| {
+            format!(
+                "replacing upload queue failed: {} -> {}",
+                tmp_path.display(),
+                self.path.display()
+            )
+        })
+    }
+}